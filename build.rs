@@ -4,6 +4,10 @@ use std::{env, error::Error, fs::File, path::Path};
 static OPENGL_EXTENSIONS: &[&str] = &[];
 
 fn main() -> Result<(), Box<dyn Error>> {
+    // Only the renderer uses OpenGL
+    if env::var_os("CARGO_FEATURE_WINDOW").is_none() {
+        return Ok(())
+    }
     let out = env::var("OUT_DIR")?;
     let mut bindings = File::create(&Path::new(&out).join("gl_bindings.rs"))?;
     Registry::new(Api::Gl, (3, 3), Profile::Core, Fallbacks::All, &OPENGL_EXTENSIONS)
@@ -2,14 +2,19 @@ use crate::{
     game::Game,
     gml::{
         self,
-        compiler::{mappings, Compiler},
-        runtime::{Instruction, Node},
-        Context, Value,
+        compiler,
+        decompiler::{self, Decompiler},
+        runtime::{Instruction, Node, ReturnType},
+        vm, Context, Value,
     },
 };
-use gm8exe::asset::etc::CodeAction;
 use std::rc::Rc;
 
+#[cfg(feature = "gm8exe")]
+use crate::gml::compiler::{mappings, Compiler, Diagnostics};
+#[cfg(feature = "gm8exe")]
+use gm8exe::asset::etc::CodeAction;
+
 /// Consts which match those used in GM8
 pub mod kind {
     pub const NORMAL: u32 = 0;
@@ -57,7 +62,7 @@ pub struct Tree(Vec<Action>);
 pub enum Body {
     Normal {
         /// The arguments to be passed to the function or code body
-        args: Box<[Node]>,
+        args: Box<[Expression]>,
 
         /// The body of this action to be executed
         body: GmlBody,
@@ -67,7 +72,7 @@ pub enum Body {
     },
     Repeat {
         /// The expression giving the number of times to repeat.
-        count: Node,
+        count: Expression,
 
         /// The tree of actions to repeat.
         body: Box<[Action]>,
@@ -76,35 +81,67 @@ pub enum Body {
 }
 
 pub enum GmlBody {
-    Function(fn(&mut Game, &mut Context, &[Value]) -> gml::Result<Value>),
-    Code(Rc<[Instruction]>),
+    Function(fn(&mut Game, &mut Context, &[Value]) -> gml::Result<Value>, usize),
+    Code(Rc<[Instruction]>, vm::Code),
 }
 
 impl std::fmt::Debug for GmlBody {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match self {
-            GmlBody::Function(_) => write!(f, "Body::Function(..)"),
-            GmlBody::Code(c) => write!(f, "Body::Code({:?})", c),
+            GmlBody::Function(..) => write!(f, "Body::Function(..)"),
+            GmlBody::Code(c, _) => write!(f, "Body::Code({:?})", c),
         }
     }
 }
 
+impl GmlBody {
+    /// Wraps compiled code along with its bytecode.
+    pub fn code(instructions: Rc<[Instruction]>) -> Self {
+        let bytecode = vm::Code::new(&instructions);
+        GmlBody::Code(instructions, bytecode)
+    }
+}
+
+/// A compiled expression, such as an action's argument, along with its bytecode.
+#[derive(Debug)]
+pub struct Expression {
+    pub node: Node,
+    pub bytecode: vm::Code,
+}
+
+impl Expression {
+    pub fn new(node: Node) -> Self {
+        let bytecode = vm::Code::expression(&node);
+        Self { node, bytecode }
+    }
+
+    /// Folds the constant function calls in the expression, rebuilding its bytecode if any were.
+    fn fold_functions(&mut self, call: &mut dyn FnMut(usize, &[Value]) -> gml::Result<Value>) {
+        if compiler::fold_expression(&mut self.node, call) {
+            self.bytecode = vm::Code::expression(&self.node);
+        }
+    }
+}
+
+#[cfg(feature = "gm8exe")]
 impl Tree {
     /// Turn a list of gm8exe CodeActions into an Action tree.
-    pub fn from_list(list: &[CodeAction], compiler: &mut Compiler) -> Result<Self, String> {
+    /// Actions which fail to compile are reported, and raise their compile error if they're run.
+    pub fn from_list(list: &[CodeAction], compiler: &mut Compiler, diagnostics: &mut Diagnostics) -> Self {
         let mut iter = list.iter().enumerate().peekable();
         let mut output = Vec::new();
-        Self::from_iter(&mut iter, compiler, false, &mut output)?;
-        Ok(Self(output))
+        Self::from_iter(&mut iter, compiler, diagnostics, false, &mut output);
+        diagnostics.action = None;
+        Self(output)
     }
 
     fn from_iter<'a, T>(
         iter: &mut std::iter::Peekable<T>,
         compiler: &mut Compiler,
+        diagnostics: &mut Diagnostics,
         single_group: bool,
         output: &mut Vec<Action>,
-    ) -> Result<(), String>
-    where
+    ) where
         T: Iterator<Item = (usize, &'a CodeAction)>,
     {
         // If we're only iterating a single group of actions, and the first is not a BEGIN_GROUP action,
@@ -116,30 +153,32 @@ impl Tree {
         };
 
         while let Some((i, action)) = iter.next() {
+            diagnostics.action = Some(i + 1);
             match action.action_kind {
                 kind::NORMAL => {
                     // If the action we got is a condition then immediately parse its if/else bodies from the iterator
                     let if_else = if action.is_condition {
                         let mut if_body = Vec::new();
-                        Self::from_iter(iter, compiler, true, &mut if_body)?;
+                        Self::from_iter(iter, compiler, diagnostics, true, &mut if_body);
                         let mut else_body = Vec::new();
                         if let Some((_, CodeAction { action_kind: kind::ELSE, .. })) = iter.peek() {
-                            Self::from_iter(iter, compiler, true, &mut else_body)?;
+                            Self::from_iter(iter, compiler, diagnostics, true, &mut else_body);
                         }
                         Some((if_body.into_boxed_slice(), else_body.into_boxed_slice()))
                     } else {
                         None
                     };
+                    diagnostics.action = Some(i + 1); // the bodies are other actions
 
                     match action.execution_type {
                         // Execution type NONE does nothing, so don't compile anything
                         execution_type::NONE => (),
 
                         // For the FUNCTION execution type, a kernel function name is provided in the action's fn_name.
-                        // This is compiled to a function pointer.
+                        // This is compiled to a function pointer, along with its index so its name can be found again.
                         execution_type::FUNCTION => {
-                            if let Some((_, f_ptr, _)) =
-                                mappings::FUNCTIONS.iter().find(|(n, _, _)| n == &action.fn_name)
+                            if let Some((index, (_, f_ptr, _))) =
+                                mappings::FUNCTIONS.iter().enumerate().find(|(_, (n, _, _))| n == &action.fn_name)
                             {
                                 output.push(Action {
                                     index: i,
@@ -147,18 +186,27 @@ impl Tree {
                                     relative: action.is_relative,
                                     invert_condition: action.invert_condition,
                                     body: Body::Normal {
-                                        args: Self::compile_params(
-                                            compiler,
-                                            &action.param_strings,
-                                            &action.param_types,
-                                            action.param_count,
-                                        )?,
-                                        body: GmlBody::Function(*f_ptr),
+                                        args: Self::compile_params(compiler, diagnostics, action),
+                                        body: GmlBody::Function(*f_ptr, index),
                                         if_else,
                                     },
                                 });
                             } else {
-                                return Err(format!("Unknown function: {} in action {}", action.fn_name, i))
+                                let message = format!("Unknown function: {}", action.fn_name);
+                                diagnostics.report(&message);
+                                output.push(Action {
+                                    index: i,
+                                    target: None,
+                                    relative: action.is_relative,
+                                    invert_condition: action.invert_condition,
+                                    body: Body::Normal {
+                                        args: Box::new([]),
+                                        body: GmlBody::code(Rc::new([Instruction::RuntimeError {
+                                            error: gml::Error::CompileError(message),
+                                        }])),
+                                        if_else,
+                                    },
+                                });
                             }
                         },
 
@@ -171,13 +219,8 @@ impl Tree {
                                 relative: action.is_relative,
                                 invert_condition: action.invert_condition,
                                 body: Body::Normal {
-                                    args: Self::compile_params(
-                                        compiler,
-                                        &action.param_strings,
-                                        &action.param_types,
-                                        action.param_count,
-                                    )?,
-                                    body: GmlBody::Code(compiler.compile(&action.fn_code).map_err(|e| e.message)?),
+                                    args: Self::compile_params(compiler, diagnostics, action),
+                                    body: GmlBody::code(compiler.compile_reported(&action.fn_code, diagnostics)),
                                     if_else,
                                 },
                             });
@@ -186,7 +229,7 @@ impl Tree {
                 },
 
                 kind::BEGIN_GROUP => {
-                    Self::from_iter(iter, compiler, true, output)?;
+                    Self::from_iter(iter, compiler, diagnostics, true, output);
                 },
 
                 kind::EXIT => {
@@ -201,14 +244,16 @@ impl Tree {
 
                 kind::REPEAT => {
                     let mut body = Vec::new();
-                    Self::from_iter(iter, compiler, true, &mut body)?;
+                    Self::from_iter(iter, compiler, diagnostics, true, &mut body);
                     output.push(Action {
                         index: i,
                         target: if action.applies_to_something { Some(action.applies_to) } else { None },
                         relative: action.is_relative,
                         invert_condition: action.invert_condition,
                         body: Body::Repeat {
-                            count: compiler.compile_expression(&action.param_strings[0]).map_err(|e| e.message)?,
+                            count: Expression::new(
+                                compiler.compile_expression_reported(&action.param_strings[0], diagnostics),
+                            ),
                             body: body.into_boxed_slice(),
                         },
                     });
@@ -225,7 +270,7 @@ impl Tree {
                         invert_condition: action.invert_condition,
                         body: Body::Normal {
                             args: Box::new([]),
-                            body: GmlBody::Code(compiler.compile(&code).map_err(|e| e.message)?),
+                            body: GmlBody::code(compiler.compile_reported(&code, diagnostics)),
                             if_else: None,
                         },
                     });
@@ -239,7 +284,7 @@ impl Tree {
                         invert_condition: action.invert_condition,
                         body: Body::Normal {
                             args: Box::new([]),
-                            body: GmlBody::Code(compiler.compile(&action.param_strings[0]).map_err(|e| e.message)?),
+                            body: GmlBody::code(compiler.compile_reported(&action.param_strings[0], diagnostics)),
                             if_else: None,
                         },
                     });
@@ -253,29 +298,215 @@ impl Tree {
                 break
             }
         }
-
-        Ok(())
     }
 
     fn compile_params(
         compiler: &mut Compiler,
-        params: &[String],
-        types: &[u32],
-        count: usize,
-    ) -> Result<Box<[Node]>, String> {
-        Ok(params
+        diagnostics: &mut Diagnostics,
+        action: &CodeAction,
+    ) -> Box<[Expression]> {
+        action
+            .param_strings
             .iter()
-            .zip(types.iter())
-            .take(count)
+            .zip(action.param_types.iter())
+            .take(action.param_count)
             .map(|(param, t)| {
-                if *t == 2 {
-                    Ok(Node::Literal { value: Value::Str(param.as_str().into()) })
+                Expression::new(if *t == 2 {
+                    Node::Literal { value: Value::Str(param.as_str().into()) }
                 } else {
-                    compiler.compile_expression(param)
-                }
+                    compiler.compile_expression_reported(param, diagnostics)
+                })
             })
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| e.message)?
-            .into_boxed_slice())
+            .collect::<Vec<_>>()
+            .into_boxed_slice()
+    }
+}
+
+impl Tree {
+    /// Writes the tree out as GML, for debugging. Function actions are written as calls to their functions,
+    /// and actions which apply to something other than self are put in with statements.
+    pub fn decompile(&self, decompiler: &mut Decompiler) {
+        Self::decompile_actions(&self.0, decompiler)
+    }
+
+    fn decompile_actions(actions: &[Action], decompiler: &mut Decompiler) {
+        for action in actions {
+            let target = action.target.filter(|&target| target != gml::SELF);
+            if let Some(target) = target {
+                decompiler.open(&format!("with ({}) {{", target));
+            }
+            match &action.body {
+                Body::Normal { args, body: GmlBody::Function(_, index), if_else } => {
+                    let args = args.iter().map(|arg| decompiler.expression(&arg.node)).collect::<Vec<_>>();
+                    let name = decompiler::function_name(*index);
+                    let not = if action.invert_condition { "!" } else { "" };
+                    let call = format!("{}{}({})", not, name, args.join(", "));
+                    match if_else {
+                        Some((if_body, else_body)) => Self::decompile_if_else(&call, if_body, else_body, decompiler),
+                        None => decompiler.line(&format!("{};", call)),
+                    }
+                },
+                Body::Normal { args, body: GmlBody::Code(instructions, _), if_else } => {
+                    // Arguments and conditions don't have a GML equivalent for code, so they're noted in comments
+                    if !args.is_empty() {
+                        let args = args.iter().map(|arg| decompiler.expression(&arg.node)).collect::<Vec<_>>();
+                        decompiler.line(&format!("// arguments: {}", args.join(", ")));
+                    }
+                    decompiler.open("{");
+                    decompiler.code(instructions);
+                    decompiler.close();
+                    if let Some((if_body, else_body)) = if_else {
+                        let condition = if action.invert_condition { "!<return value>" } else { "<return value>" };
+                        Self::decompile_if_else(condition, if_body, else_body, decompiler);
+                    }
+                },
+                Body::Repeat { count, body } => {
+                    decompiler.open(&format!("repeat ({}) {{", decompiler.expression(&count.node)));
+                    Self::decompile_actions(body, decompiler);
+                    decompiler.close();
+                },
+                Body::Exit => decompiler.line("exit;"),
+            }
+            if target.is_some() {
+                decompiler.close();
+            }
+        }
+    }
+
+    fn decompile_if_else(condition: &str, if_body: &[Action], else_body: &[Action], decompiler: &mut Decompiler) {
+        decompiler.open(&format!("if ({}) {{", condition));
+        Self::decompile_actions(if_body, decompiler);
+        if !else_body.is_empty() {
+            decompiler.close_open("} else {");
+            Self::decompile_actions(else_body, decompiler);
+        }
+        decompiler.close();
+    }
+
+    /// Folds the constant function calls in the tree's code and arguments, rebuilding the bytecode of any code
+    /// which changed. See compiler::fold_functions.
+    pub fn fold_functions(&mut self, call: &mut dyn FnMut(usize, &[Value]) -> gml::Result<Value>) {
+        Self::fold_actions(&mut self.0, call)
+    }
+
+    fn fold_actions(actions: &mut [Action], call: &mut dyn FnMut(usize, &[Value]) -> gml::Result<Value>) {
+        for action in actions {
+            match &mut action.body {
+                Body::Normal { args, body, if_else } => {
+                    args.iter_mut().for_each(|arg| arg.fold_functions(call));
+                    if let GmlBody::Code(instructions, bytecode) = body {
+                        if let Some(code) = Rc::get_mut(instructions) {
+                            if compiler::fold_functions(code, call) {
+                                *bytecode = vm::Code::new(instructions);
+                            }
+                        }
+                    }
+                    if let Some((if_body, else_body)) = if_else {
+                        Self::fold_actions(if_body, call);
+                        Self::fold_actions(else_body, call);
+                    }
+                },
+                Body::Repeat { count, body } => {
+                    count.fold_functions(call);
+                    Self::fold_actions(body, call);
+                },
+                Body::Exit => (),
+            }
+        }
+    }
+}
+
+impl Game {
+    /// Executes a tree of drag-n-drop actions in the given context.
+    pub fn execute_tree(&mut self, tree: &Tree, context: &mut Context) -> gml::Result<ReturnType> {
+        self.execute_actions(&tree.0, context)
+    }
+
+    fn execute_actions(&mut self, actions: &[Action], context: &mut Context) -> gml::Result<ReturnType> {
+        for action in actions.iter() {
+            context.event_action = action.index;
+            context.relative = action.relative;
+            match &action.body {
+                Body::Normal { args, body, if_else } => {
+                    // An action which raises an error stops there, but unless the game aborts, the event carries on
+                    let result = match self.execute_action(action, args, body, context) {
+                        Ok(result) => result,
+                        Err(e) => {
+                            self.handle_error(e, context)?;
+                            false
+                        },
+                    };
+
+                    if let Some((if_body, else_body)) = if_else {
+                        let return_type =
+                            self.execute_actions(if result { if_body } else { else_body }, context)?;
+                        if return_type != ReturnType::Normal {
+                            return Ok(return_type)
+                        }
+                    }
+                },
+                Body::Repeat { count, body } => {
+                    let mut count = self.eval(&count.bytecode, context)?.round();
+                    while count > 0 {
+                        let return_type = self.execute_actions(body, context)?;
+                        if return_type != ReturnType::Normal {
+                            return Ok(return_type)
+                        }
+                        count -= 1;
+                    }
+                },
+                Body::Exit => return Ok(ReturnType::Exit),
+            }
+        }
+        Ok(ReturnType::Normal)
+    }
+
+    /// Runs a normal action on each instance it applies to, returning whether it was true for all of them.
+    fn execute_action(
+        &mut self,
+        action: &Action,
+        args: &[Expression],
+        body: &GmlBody,
+        context: &mut Context,
+    ) -> gml::Result<bool> {
+        let targets = match action.target {
+            Some(target) => self.find_instances(target, context),
+            None => vec![context.this],
+        };
+
+        // Instances targeted with "other" or an object see the current instance as their other
+        let other = match action.target {
+            None | Some(gml::SELF) => context.other,
+            Some(_) => context.this,
+        };
+
+        // A question which applies to several instances is only true if it's true for all of them
+        let mut result = true;
+        for target in targets {
+            if !self.instance_list.exists(target) {
+                continue
+            }
+            let mut new_context = context.nested(target, other);
+
+            // Arguments are evaluated straight into the action's own context
+            for (i, arg) in args.iter().enumerate() {
+                let value = self.eval(&arg.bytecode, &mut new_context)?;
+                new_context.arguments[i] = value;
+                new_context.argument_count = i + 1;
+            }
+            let value = match body {
+                GmlBody::Function(function, _) => {
+                    let arguments = std::mem::take(&mut new_context.arguments);
+                    function(self, &mut new_context, &arguments[..args.len()])?
+                },
+                GmlBody::Code(_, bytecode) => {
+                    // Exiting from a code action only stops that action, not the whole event
+                    self.run(bytecode, &mut new_context)?;
+                    new_context.return_value
+                },
+            };
+            result &= value.is_true() != action.invert_condition;
+        }
+        Ok(result)
     }
 }
@@ -1,6 +1,7 @@
 pub mod background;
 pub mod font;
 pub mod object;
+pub mod path;
 pub mod room;
 pub mod script;
 pub mod sprite;
@@ -9,6 +10,7 @@ pub mod timeline;
 pub use background::Background;
 pub use font::Font;
 pub use object::Object;
+pub use path::Path;
 pub use room::Room;
 pub use script::Script;
 pub use sprite::Sprite;
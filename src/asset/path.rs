@@ -0,0 +1,262 @@
+use crate::util;
+
+/// A control point of a path, as placed in the editor or added with path_add_point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub speed: f64,
+}
+
+/// A point along the generated path, along with how far along the path it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Node {
+    point: Point,
+    distance: f64,
+}
+
+#[derive(Clone)]
+pub struct Path {
+    pub name: String,
+    pub points: Vec<Point>,
+    pub smooth: bool,
+    pub closed: bool,
+    pub precision: u32,
+
+    /// The path generated from the control points, which must be regenerated with update() after any change
+    nodes: Vec<Node>,
+}
+
+impl Point {
+    fn midpoint(&self, other: &Point) -> Point {
+        Point { x: (self.x + other.x) / 2.0, y: (self.y + other.y) / 2.0, speed: (self.speed + other.speed) / 2.0 }
+    }
+
+    fn lerp(&self, other: &Point, t: f64) -> Point {
+        Point {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            speed: self.speed + (other.speed - self.speed) * t,
+        }
+    }
+}
+
+impl Path {
+    /// Creates an empty path with the same settings as path_add.
+    pub fn new(name: String) -> Self {
+        Self { name, points: Vec::new(), smooth: false, closed: true, precision: 4, nodes: Vec::new() }
+    }
+
+    /// Regenerates the path from its control points. This must be called after they or any settings are changed.
+    pub fn update(&mut self) {
+        let mut nodes: Vec<Point> = Vec::new();
+        let count = self.points.len();
+        if self.smooth && count >= 3 {
+            // Smooth paths are quadratic B-splines: each control point pulls the curve between the midpoints of
+            // the lines either side of it. Open paths still start and end exactly on their first and last points.
+            let segments = if self.closed { count } else { count - 2 };
+            for i in 0..segments {
+                let p1 = &self.points[i % count];
+                let p2 = &self.points[(i + 1) % count];
+                let p3 = &self.points[(i + 2) % count];
+                let start = if !self.closed && i == 0 { *p1 } else { p1.midpoint(p2) };
+                let end = if !self.closed && i == segments - 1 { *p3 } else { p2.midpoint(p3) };
+                nodes.push(start);
+                subdivide(&mut nodes, start, *p2, end, self.precision.min(8));
+            }
+            nodes.push(if self.closed { nodes[0] } else { self.points[count - 1] });
+        } else {
+            nodes.extend_from_slice(&self.points);
+            if self.closed && count > 0 {
+                nodes.push(self.points[0]);
+            }
+        }
+
+        let mut distance = 0.0;
+        let mut previous: Option<Point> = None;
+        self.nodes = nodes
+            .into_iter()
+            .map(|point| {
+                if let Some(previous) = previous {
+                    distance += (point.x - previous.x).hypot(point.y - previous.y);
+                }
+                previous = Some(point);
+                Node { point, distance }
+            })
+            .collect();
+    }
+
+    /// The total length of the path.
+    pub fn length(&self) -> f64 {
+        self.nodes.last().map_or(0.0, |node| node.distance)
+    }
+
+    /// The first point of the path, or the origin if it has no points.
+    pub fn start(&self) -> Point {
+        self.nodes.first().map_or(Point { x: 0.0, y: 0.0, speed: 100.0 }, |node| node.point)
+    }
+
+    /// Gets the point at a position along the path, from 0 (the start) to 1 (the end).
+    pub fn get_point(&self, position: f64) -> Point {
+        let target = position.clamp(0.0, 1.0) * self.length();
+        for pair in self.nodes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            if target <= to.distance {
+                let span = to.distance - from.distance;
+                return if span > 0.0 { from.point.lerp(&to.point, (target - from.distance) / span) } else { to.point }
+            }
+        }
+        self.nodes.last().map_or(self.start(), |node| node.point)
+    }
+
+    /// The centre of the box around all the control points, which the path transformations work around.
+    fn centre(&self) -> (f64, f64) {
+        let left = self.points.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+        let right = self.points.iter().map(|p| p.x).fold(f64::NEG_INFINITY, f64::max);
+        let top = self.points.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+        let bottom = self.points.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+        ((left + right) / 2.0, (top + bottom) / 2.0)
+    }
+
+    /// Reverses the direction of the path.
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+        self.update();
+    }
+
+    /// Mirrors the path horizontally around its centre.
+    pub fn mirror(&mut self) {
+        let (centre_x, _) = self.centre();
+        self.points.iter_mut().for_each(|p| p.x = centre_x * 2.0 - p.x);
+        self.update();
+    }
+
+    /// Flips the path vertically around its centre.
+    pub fn flip(&mut self) {
+        let (_, centre_y) = self.centre();
+        self.points.iter_mut().for_each(|p| p.y = centre_y * 2.0 - p.y);
+        self.update();
+    }
+
+    /// Rotates the path anticlockwise around its centre by an angle in degrees.
+    pub fn rotate(&mut self, angle: f64) {
+        let (centre_x, centre_y) = self.centre();
+        let (sin, cos) = util::degtorad(angle).sin_cos();
+        for p in self.points.iter_mut() {
+            let (dx, dy) = (p.x - centre_x, p.y - centre_y);
+            p.x = centre_x + dx * cos + dy * sin;
+            p.y = centre_y - dx * sin + dy * cos;
+        }
+        self.update();
+    }
+
+    /// Scales the path around its centre.
+    pub fn scale(&mut self, xscale: f64, yscale: f64) {
+        let (centre_x, centre_y) = self.centre();
+        for p in self.points.iter_mut() {
+            p.x = centre_x + (p.x - centre_x) * xscale;
+            p.y = centre_y + (p.y - centre_y) * yscale;
+        }
+        self.update();
+    }
+
+    /// Moves the path by the given amount.
+    pub fn shift(&mut self, xshift: f64, yshift: f64) {
+        for p in self.points.iter_mut() {
+            p.x += xshift;
+            p.y += yshift;
+        }
+        self.update();
+    }
+}
+
+/// Recursively splits a quadratic curve in half, adding the points between its two ends to the list.
+fn subdivide(nodes: &mut Vec<Point>, start: Point, control: Point, end: Point, depth: u32) {
+    if depth == 0 {
+        return
+    }
+    let left = start.midpoint(&control);
+    let right = control.midpoint(&end);
+    let middle = left.midpoint(&right);
+    subdivide(nodes, start, left, middle, depth - 1);
+    nodes.push(middle);
+    subdivide(nodes, middle, right, end, depth - 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(points: &[(f64, f64)], smooth: bool, closed: bool) -> Path {
+        let mut path = Path::new("path0".into());
+        path.points = points.iter().map(|&(x, y)| Point { x, y, speed: 100.0 }).collect();
+        path.smooth = smooth;
+        path.closed = closed;
+        path.update();
+        path
+    }
+
+    #[test]
+    fn straight() {
+        let open = path(&[(0.0, 0.0), (30.0, 0.0), (30.0, 40.0)], false, false);
+        assert_eq!(open.length(), 70.0);
+        assert_eq!(open.get_point(0.0), Point { x: 0.0, y: 0.0, speed: 100.0 });
+        assert_eq!(open.get_point(0.5), Point { x: 30.0, y: 5.0, speed: 100.0 });
+        assert_eq!(open.get_point(1.0), Point { x: 30.0, y: 40.0, speed: 100.0 });
+        assert_eq!(open.get_point(2.0), open.get_point(1.0));
+
+        let closed = path(&[(0.0, 0.0), (30.0, 0.0), (30.0, 40.0)], false, true);
+        assert_eq!(closed.length(), 120.0);
+        assert_eq!(closed.get_point(1.0), Point { x: 0.0, y: 0.0, speed: 100.0 });
+    }
+
+    #[test]
+    fn speed_interpolation() {
+        let mut p = path(&[(0.0, 0.0), (10.0, 0.0)], false, false);
+        p.points[1].speed = 50.0;
+        p.update();
+        assert_eq!(p.get_point(0.5).speed, 75.0);
+    }
+
+    #[test]
+    fn smooth() {
+        let open = path(&[(0.0, 0.0), (50.0, 50.0), (100.0, 0.0)], true, false);
+        assert_eq!(open.get_point(0.0), Point { x: 0.0, y: 0.0, speed: 100.0 });
+        assert_eq!(open.get_point(1.0), Point { x: 100.0, y: 0.0, speed: 100.0 });
+        let middle = open.get_point(0.5);
+        assert!((middle.x - 50.0).abs() < 1e-9 && (middle.y - 25.0).abs() < 1e-9, "{:?}", middle);
+        assert!(open.length() > 100.0 && open.length() < 50.0 * 2f64.sqrt() * 2.0);
+
+        // A closed smooth path doesn't pass through any of its control points
+        let closed = path(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)], true, true);
+        assert_eq!(closed.get_point(0.0), closed.get_point(1.0));
+        assert_eq!(closed.get_point(0.0), Point { x: 50.0, y: 0.0, speed: 100.0 });
+    }
+
+    #[test]
+    fn empty() {
+        let p = path(&[], true, true);
+        assert_eq!(p.length(), 0.0);
+        assert_eq!(p.get_point(0.5), Point { x: 0.0, y: 0.0, speed: 100.0 });
+    }
+
+    #[test]
+    fn transform() {
+        let mut p = path(&[(0.0, 0.0), (20.0, 10.0)], false, false);
+        p.mirror();
+        assert_eq!(p.points[0], Point { x: 20.0, y: 0.0, speed: 100.0 });
+        p.flip();
+        assert_eq!(p.points[0], Point { x: 20.0, y: 10.0, speed: 100.0 });
+        p.shift(5.0, -5.0);
+        assert_eq!(p.points[1], Point { x: 5.0, y: -5.0, speed: 100.0 });
+        p.scale(2.0, 1.0);
+        assert_eq!(p.points[1], Point { x: -5.0, y: -5.0, speed: 100.0 });
+        p.reverse();
+        assert_eq!(p.get_point(0.0), Point { x: -5.0, y: -5.0, speed: 100.0 });
+
+        let mut p = path(&[(0.0, 0.0), (10.0, 0.0)], false, false);
+        p.rotate(90.0);
+        assert!((p.points[0].x - 5.0).abs() < 1e-9 && (p.points[0].y - 5.0).abs() < 1e-9, "{:?}", p.points[0]);
+        assert!((p.points[1].x - 5.0).abs() < 1e-9 && (p.points[1].y + 5.0).abs() < 1e-9, "{:?}", p.points[1]);
+    }
+}
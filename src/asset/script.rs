@@ -1,8 +1,7 @@
-use crate::gml::runtime::Instruction;
-use std::rc::Rc;
+use crate::gml::vm;
 
 pub struct Script {
     pub name: String,
     pub source: String,
-    pub compiled: Rc<[Instruction]>,
+    pub bytecode: vm::Code,
 }
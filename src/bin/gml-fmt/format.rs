@@ -0,0 +1,668 @@
+use gm8emulator::gml::compiler::{
+    ast::{self, BinaryExpr, Expr, IfExpr, AST},
+    lexer::{Lexer, Position},
+    token::{Operator, Separator, Token},
+};
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    slice,
+};
+
+/// Where the brace opening a block goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BraceStyle {
+    /// At the end of the line the block belongs to: `if (a) {`
+    SameLine,
+
+    /// On a line of its own, below the one the block belongs to.
+    NextLine,
+}
+
+pub struct Config {
+    pub braces: BraceStyle,
+
+    /// What each level of indentation is made of.
+    pub indent: String,
+
+    /// Whether to put spaces around binary operators and after commas.
+    pub spaces: bool,
+
+    /// Whether to end statements with semicolons. Some keep them regardless, where leaving them out
+    /// would change what the code means.
+    pub semicolons: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { braces: BraceStyle::SameLine, indent: "    ".into(), spaces: true, semicolons: true }
+    }
+}
+
+/// Formats GML source code, keeping its comments.
+pub fn format(source: &str, config: &Config) -> Result<String, ast::Error> {
+    let ast = AST::new(source)?;
+    let mut formatter = Formatter::new(source, config);
+    for expr in ast.iter() {
+        formatter.statement(expr, 0)?;
+    }
+    formatter.comments_before(Position { line: usize::MAX, column: 0 }, 0);
+    Ok(formatter.finish())
+}
+
+#[derive(Clone, Copy)]
+struct Comment<'a> {
+    position: Position,
+    text: &'a str,
+
+    /// Whether the comment follows some code on the same line, rather than starting its own.
+    trailing: bool,
+
+    /// How many names and literals come before the comment. These are in the same order in the AST, so a
+    /// comment inside a statement is written next to the same ones as in the source.
+    leaves: usize,
+
+    /// Whether the comment is a block comment straight after a name or literal and before more of the same
+    /// statement, so it goes after that rather than in front of the next one.
+    suffix: bool,
+}
+
+/// A line of output. A line holding only a comment has no code.
+struct Line {
+    indent: usize,
+    code: String,
+    comment: Option<String>,
+}
+
+impl Line {
+    fn is_blank(&self) -> bool {
+        self.code.is_empty() && self.comment.is_none()
+    }
+}
+
+struct Formatter<'a, 'c> {
+    config: &'c Config,
+
+    /// Every comment in the source, and how many of them have been written out so far.
+    comments: Vec<Comment<'a>>,
+    next_comment: usize,
+
+    /// Tokens (as line and column) with at least one blank line before them in the source.
+    gaps: HashSet<(usize, usize)>,
+
+    /// The closing brace matching each opening brace.
+    closing: HashMap<(usize, usize), Position>,
+
+    /// Every real literal in the source along with how it was written, in order. The AST only keeps the value,
+    /// and writing that back out wouldn't always read back in as the same number.
+    reals: VecDeque<(f64, &'a str)>,
+
+    lines: Vec<Line>,
+
+    /// The last line, if it's a statement left without a semicolon.
+    unterminated: Option<usize>,
+
+    /// How many names and literals have been written so far.
+    leaves: usize,
+
+    /// The indentation of the statement being written.
+    indent: usize,
+}
+
+fn key(position: Position) -> (usize, usize) {
+    (position.line, position.column)
+}
+
+/// Whether an expression has to be wrapped in parentheses to have a field or array index read from it.
+fn needs_parens_as_owner(expr: &Expr) -> bool {
+    match expr {
+        Expr::LiteralIdentifier(_) | Expr::Function(_) => false,
+        Expr::Binary(binary) => !is_accessor(binary.op),
+        _ => true,
+    }
+}
+
+fn is_accessor(op: Operator) -> bool {
+    op == Operator::Deref || op == Operator::Index
+}
+
+fn precedence(expr: &Expr) -> Option<u8> {
+    match expr {
+        Expr::Binary(binary) => AST::get_op_precedence(&binary.op),
+        _ => None,
+    }
+}
+
+impl<'a, 'c> Formatter<'a, 'c> {
+    fn new(source: &'a str, config: &'c Config) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.bytes().enumerate().filter(|(_, ch)| *ch == b'\n').map(|(i, _)| i + 1))
+            .collect::<Vec<_>>();
+        let mut comments = Vec::new();
+        let mut gaps = HashSet::new();
+        let mut closing = HashMap::new();
+        let mut reals = VecDeque::new();
+
+        let mut lex = Lexer::with_comments(source);
+        let mut opened = Vec::new();
+        let mut last_line = None;
+        let mut leaves = 0;
+        let mut after_leaf = false;
+        let mut pending = 0;
+        while let Some(token) = lex.next() {
+            let position = lex.position();
+            if last_line.map(|line| position.line > line + 1).unwrap_or(false) {
+                gaps.insert(key(position));
+            }
+            let mut end_line = position.line;
+            match token {
+                Token::Comment(text) => {
+                    end_line += text.matches('\n').count();
+                    let trailing = last_line == Some(position.line);
+                    let suffix = after_leaf && !text.starts_with("//");
+                    comments.push(Comment { position, text, trailing, leaves, suffix });
+                },
+                Token::String(string) => end_line += string.matches('\n').count(),
+                Token::Real(value) => {
+                    let rest = &source[line_starts[position.line - 1] + position.column - 1..];
+                    let length = match rest.strip_prefix('$') {
+                        Some(hex) => 1 + hex.bytes().take_while(u8::is_ascii_hexdigit).count(),
+                        None => rest.bytes().take_while(|ch| ch.is_ascii_digit() || *ch == b'.').count(),
+                    };
+                    reals.push_back((value, &rest[..length]));
+                },
+                Token::Separator(Separator::BraceLeft) => opened.push(position),
+                Token::Separator(Separator::BraceRight) => {
+                    if let Some(open) = opened.pop() {
+                        closing.insert(key(open), position);
+                    }
+                },
+                _ => (),
+            }
+            if !matches!(token, Token::Comment(_)) {
+                let continues = match token {
+                    Token::Operator(_) => true,
+                    Token::Separator(separator) => matches!(
+                        separator,
+                        Separator::ParenRight | Separator::BracketRight | Separator::Comma | Separator::Semicolon
+                    ),
+                    _ => false,
+                };
+                for comment in &mut comments[pending..] {
+                    comment.suffix &= continues;
+                }
+                pending = comments.len();
+                after_leaf = matches!(token, Token::Identifier(_) | Token::Real(_) | Token::String(_));
+                if after_leaf {
+                    leaves += 1;
+                }
+            }
+            last_line = Some(end_line);
+        }
+        // Nothing follows these, so they go after the last statement
+        for comment in &mut comments[pending..] {
+            comment.suffix = false;
+        }
+
+        Formatter {
+            config,
+            comments,
+            next_comment: 0,
+            gaps,
+            closing,
+            reals,
+            lines: Vec::new(),
+            unterminated: None,
+            leaves: 0,
+            indent: 0,
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut output = String::new();
+        for line in &self.lines {
+            if !line.is_blank() {
+                output += &self.config.indent.repeat(line.indent);
+                output += &line.code;
+                if let Some(comment) = &line.comment {
+                    if !line.code.is_empty() {
+                        output.push(' ');
+                    }
+                    output += comment;
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Writes a line of code.
+    fn push(&mut self, code: String, indent: usize) {
+        self.lines.push(Line { indent, code, comment: None });
+        self.unterminated = None;
+    }
+
+    /// Writes a statement which can end in a semicolon, adding one if the config asks for it.
+    fn push_statement(&mut self, mut code: String, indent: usize, always_terminate: bool) {
+        // Without a semicolon, a statement starting with a parenthesis would be read as part of the one before it
+        if code.starts_with('(') {
+            if let Some(line) = self.unterminated {
+                self.lines[line].code.push(';');
+            }
+        }
+        if self.config.semicolons || always_terminate {
+            code.push(';');
+            self.push(code, indent);
+        } else {
+            self.push(code, indent);
+            self.unterminated = Some(self.lines.len() - 1);
+        }
+    }
+
+    /// Opens a block, attaching its first line to the last one written if `join` is set and braces go on the same
+    /// line, so `else` can follow `}`.
+    fn open(&mut self, header: &str, empty: bool, indent: usize, join: bool) {
+        let same_line = self.config.braces == BraceStyle::SameLine;
+        let brace = if empty { "{}" } else { "{" };
+        let code = if header.is_empty() {
+            brace.to_string()
+        } else if empty || same_line {
+            format!("{} {}", header, brace)
+        } else {
+            header.to_string()
+        };
+        match self.lines.last_mut() {
+            Some(line) if join && same_line && line.code.ends_with('}') => {
+                line.code.push(' ');
+                line.code += &code;
+                self.unterminated = None;
+            },
+            _ => self.push(code, indent),
+        }
+        if !empty && !same_line && !header.is_empty() {
+            self.push("{".into(), indent);
+        }
+    }
+
+    /// Writes out every comment that comes before the given position in the source.
+    fn comments_before(&mut self, position: Position, indent: usize) {
+        while let Some(&comment) = self.comments.get(self.next_comment) {
+            if key(comment.position) >= key(position) {
+                break
+            }
+            self.next_comment += 1;
+            match self.lines.last_mut() {
+                Some(line) if comment.trailing && !line.is_blank() => match &mut line.comment {
+                    Some(text) => {
+                        text.push(' ');
+                        text.push_str(comment.text);
+                    },
+                    None => line.comment = Some(comment.text.into()),
+                },
+                _ => {
+                    self.blank_line(comment.position);
+                    self.lines.push(Line { indent, code: String::new(), comment: Some(comment.text.into()) });
+                },
+            }
+        }
+    }
+
+    /// Writes a name or literal along with any comments written around it inside its statement. A line comment
+    /// ends the line, so the rest of the statement carries on on the next one.
+    fn leaf(&mut self, text: &str) -> String {
+        let mut output = String::new();
+        while let Some(&comment) = self.comments.get(self.next_comment) {
+            if comment.leaves != self.leaves {
+                break
+            }
+            self.next_comment += 1;
+            output += comment.text;
+            if comment.text.starts_with("//") {
+                output.push('\n');
+                output += &self.config.indent.repeat(self.indent + 1);
+            } else {
+                output.push(' ');
+            }
+        }
+        output += text;
+        self.leaves += 1;
+        while let Some(&comment) = self.comments.get(self.next_comment) {
+            if comment.leaves != self.leaves || !comment.suffix {
+                break
+            }
+            self.next_comment += 1;
+            output.push(' ');
+            output += comment.text;
+        }
+        output
+    }
+
+    /// Keeps a blank line from the source before the given position, unless it's at the start of a block.
+    fn blank_line(&mut self, position: Position) {
+        if self.gaps.contains(&key(position)) {
+            if let Some(line) = self.lines.last() {
+                if !line.is_blank() && !line.code.ends_with('{') {
+                    self.lines.push(Line { indent: 0, code: String::new(), comment: None });
+                }
+            }
+        }
+    }
+
+    fn statement(&mut self, expr: &Expr, indent: usize) -> Result<(), ast::Error> {
+        let (position, expr) = match expr {
+            Expr::Located(located) => (Some(located.position), &located.expr),
+            expr => (None, expr),
+        };
+        if let Some(position) = position {
+            self.comments_before(position, indent);
+            self.blank_line(position);
+        }
+        self.indent = indent;
+
+        match expr {
+            Expr::Group(body) => self.block("", body, position, indent, false, false)?,
+            Expr::If(if_ex) => self.if_statement("", if_ex, indent, false)?,
+            Expr::For(for_ex) => {
+                let start = self.inline(&for_ex.start)?;
+                let cond = self.expression(&for_ex.cond)?;
+                let step = self.inline(&for_ex.step)?;
+                self.body(&format!("for ({}; {}; {})", start, cond, step), &for_ex.body, indent, false)?;
+            },
+            Expr::While(while_ex) => {
+                let header = format!("while ({})", self.expression(&while_ex.cond)?);
+                self.body(&header, &while_ex.body, indent, false)?;
+            },
+            Expr::Repeat(repeat) => {
+                let header = format!("repeat ({})", self.expression(&repeat.count)?);
+                self.body(&header, &repeat.body, indent, false)?;
+            },
+            Expr::With(with) => {
+                let header = format!("with ({})", self.expression(&with.target)?);
+                self.body(&header, &with.body, indent, false)?;
+            },
+            Expr::Switch(switch) => {
+                let header = format!("switch ({})", self.expression(&switch.input)?);
+                match &switch.body {
+                    Expr::Located(located) => match &located.expr {
+                        Expr::Group(body) => self.block(&header, body, Some(located.position), indent, false, true)?,
+                        _ => self.body(&header, &switch.body, indent, false)?,
+                    },
+                    body => self.body(&header, body, indent, false)?,
+                }
+            },
+            Expr::DoUntil(dountil) => {
+                self.body("do", &dountil.body, indent, false)?;
+                self.indent = indent;
+                let until = format!("until ({})", self.expression(&dountil.cond)?);
+                self.push_statement(until, indent, false);
+                if self.config.braces == BraceStyle::SameLine {
+                    // Move it up onto the closing brace's line
+                    let until = self.lines.pop().unwrap();
+                    let line = self.lines.last_mut().unwrap();
+                    line.code.push(' ');
+                    line.code += &until.code;
+                    if self.unterminated.is_some() {
+                        self.unterminated = Some(self.lines.len() - 1);
+                    }
+                }
+            },
+            Expr::Case(value) => {
+                let code = format!("case {}:", self.expression(value)?);
+                self.push(code, indent);
+            },
+            Expr::Default => self.push("default:".into(), indent),
+            expr => {
+                let code = self.inline(expr)?;
+                let always_terminate = matches!(expr, Expr::Var(_) | Expr::GlobalVar(_));
+                self.push_statement(code, indent, always_terminate);
+            },
+        }
+        Ok(())
+    }
+
+    fn if_statement(&mut self, prefix: &str, if_ex: &IfExpr, indent: usize, join: bool) -> Result<(), ast::Error> {
+        self.indent = indent;
+        let header = format!("{}if ({})", prefix, self.expression(&if_ex.cond)?);
+        self.body(&header, &if_ex.body, indent, join)?;
+        match &if_ex.else_body {
+            Some(else_body) => match else_body.unlocated() {
+                Expr::If(else_if) => self.if_statement("else ", else_if, indent, true),
+                _ => self.body("else", else_body, indent, true),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the body of a statement, adding braces around it if it doesn't have any.
+    fn body(&mut self, header: &str, body: &Expr, indent: usize, join: bool) -> Result<(), ast::Error> {
+        match body {
+            Expr::Located(located) => match &located.expr {
+                Expr::Group(group) => self.block(header, group, Some(located.position), indent, join, false),
+                _ => self.block(header, slice::from_ref(body), None, indent, join, false),
+            },
+            _ => self.block(header, slice::from_ref(body), None, indent, join, false),
+        }
+    }
+
+    /// Writes a block of statements, where `position` is that of its opening brace if it had one in the source.
+    /// In switch blocks, statements are indented an extra level past the labels before them.
+    fn block(
+        &mut self,
+        header: &str,
+        body: &[Expr],
+        position: Option<Position>,
+        indent: usize,
+        join: bool,
+        switch: bool,
+    ) -> Result<(), ast::Error> {
+        let close = position.and_then(|position| self.closing.get(&key(position)).copied());
+        let has_comments = match (close, self.comments.get(self.next_comment)) {
+            (Some(close), Some(comment)) => key(comment.position) < key(close),
+            _ => false,
+        };
+        let empty = body.is_empty() && !has_comments;
+        self.open(header, empty, indent, join);
+        if empty {
+            return Ok(())
+        }
+
+        let mut inner = indent + 1;
+        for expr in body {
+            match expr.unlocated() {
+                Expr::Case(_) | Expr::Default if switch => {
+                    self.statement(expr, indent + 1)?;
+                    inner = indent + 2;
+                },
+                _ => self.statement(expr, inner)?,
+            }
+        }
+        if let Some(close) = close {
+            self.comments_before(close, inner);
+        }
+        self.push("}".into(), indent);
+        Ok(())
+    }
+
+    /// Formats a statement which fits on one line, without its semicolon.
+    fn inline(&mut self, expr: &Expr) -> Result<String, ast::Error> {
+        Ok(match expr.unlocated() {
+            Expr::Binary(binary) if AST::get_op_precedence(&binary.op).is_none() => {
+                let target = match &binary.left {
+                    Expr::Binary(target) if is_accessor(target.op) => self.accessor(target, true)?,
+                    target => self.expression(target)?,
+                };
+                let value = self.expression(&binary.right)?;
+                self.operator(target, binary.op, value)
+            },
+            expr @ Expr::Function(_) => self.expression(expr)?,
+            Expr::Var(var) if var.vars.is_empty() => "var".into(),
+            Expr::Var(var) => format!("var {}", self.names(&var.vars)),
+            Expr::GlobalVar(var) if var.vars.is_empty() => "globalvar".into(),
+            Expr::GlobalVar(var) => format!("globalvar {}", self.names(&var.vars)),
+            Expr::Return(value) => format!("return {}", self.expression(value)?),
+            Expr::Exit => "exit".into(),
+            Expr::Break => "break".into(),
+            Expr::Continue => "continue".into(),
+            expr => return Err(ast::Error::new(format!("Can't format {} on one line", expr))),
+        })
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<String, ast::Error> {
+        Ok(match expr {
+            Expr::LiteralIdentifier(id) => self.leaf(id),
+            Expr::LiteralReal(value) => match self.reals.front() {
+                Some(&(real, text)) if real.to_bits() == value.to_bits() => {
+                    self.reals.pop_front();
+                    self.leaf(text)
+                },
+                _ => self.leaf(&value.to_string()),
+            },
+            // GML strings have no escapes, so one can't contain both kinds of quote
+            Expr::LiteralString(string) if string.contains('"') => self.leaf(&format!("'{}'", string)),
+            Expr::LiteralString(string) => self.leaf(&format!("\"{}\"", string)),
+
+            Expr::Unary(unary) => {
+                let child = self.expression(&unary.child)?;
+                if precedence(&unary.child).is_some() {
+                    format!("{}({})", unary.op, child)
+                } else {
+                    format!("{}{}", unary.op, child)
+                }
+            },
+            Expr::Binary(binary) => match binary.op {
+                op if is_accessor(op) => self.accessor(binary, false)?,
+                op => {
+                    let mut left = self.expression(&binary.left)?;
+                    let mut right = self.expression(&binary.right)?;
+                    // Operators of the same precedence are read left to right, so only the right needs parentheses
+                    if let Some(op_precedence) = AST::get_op_precedence(&op) {
+                        if precedence(&binary.left).map(|p| p < op_precedence).unwrap_or(false) {
+                            left = format!("({})", left);
+                        }
+                        if precedence(&binary.right).map(|p| p <= op_precedence).unwrap_or(false) {
+                            right = format!("({})", right);
+                        }
+                    }
+                    self.operator(left, op, right)
+                },
+            },
+            Expr::Function(call) => {
+                let name = self.leaf(call.name);
+                format!("{}({})", name, self.list(&call.params)?)
+            },
+            expr => return Err(ast::Error::new(format!("Can't format {} as an expression", expr))),
+        })
+    }
+
+    /// Formats a field or array access. At the start of a statement, a function call has to be wrapped in
+    /// parentheses to be read from, as otherwise it would be read as a statement of its own.
+    fn accessor(&mut self, binary: &BinaryExpr, start: bool) -> Result<String, ast::Error> {
+        let owner = match &binary.left {
+            Expr::Binary(owner) if is_accessor(owner.op) => self.accessor(owner, start)?,
+            Expr::Function(_) if start => format!("({})", self.expression(&binary.left)?),
+            owner if needs_parens_as_owner(owner) => format!("({})", self.expression(owner)?),
+            owner => self.expression(owner)?,
+        };
+        Ok(match &binary.right {
+            Expr::Group(dimensions) => format!("{}[{}]", owner, self.list(dimensions)?),
+            field => format!("{}.{}", owner, self.expression(field)?),
+        })
+    }
+
+    fn operator(&self, left: String, op: Operator, right: String) -> String {
+        if self.config.spaces || op == Operator::Modulo || op == Operator::IntDivide {
+            format!("{} {} {}", left, op, right)
+        } else {
+            format!("{}{}{}", left, op, right)
+        }
+    }
+
+    fn list(&mut self, exprs: &[Expr]) -> Result<String, ast::Error> {
+        let items = exprs.iter().map(|expr| self.expression(expr)).collect::<Result<Vec<_>, _>>()?;
+        Ok(items.join(self.comma()))
+    }
+
+    fn names(&mut self, names: &[&str]) -> String {
+        let names = names.iter().map(|name| self.leaf(name)).collect::<Vec<_>>();
+        names.join(self.comma())
+    }
+
+    fn comma(&self) -> &'static str {
+        if self.config.spaces { ", " } else { "," }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the formatted code, and that formatting it again leaves it as it is.
+    fn assert_format(config: &Config, input: &str, expected: &str) {
+        let output = format(input, config).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(format(&output, config).unwrap(), output, "formatting isn't idempotent");
+    }
+
+    #[test]
+    fn statements() {
+        assert_format(
+            &Config::default(),
+            "var a,b;a:=1 if a=1 then b=2 else if a<>2 begin c+=1 end else exit\n\
+             for(i=0;i<10;i+=1)f(i,'x') repeat 3 do{a-=1}until a<0\n\
+             while a b() with other{x=1}switch a{case 1:case 2:b=1 break;default:return -a}",
+            "var a, b;\n\
+             a = 1;\n\
+             if (a == 1) {\n    b = 2;\n} else if (a != 2) {\n    c += 1;\n} else {\n    exit;\n}\n\
+             for (i = 0; i < 10; i += 1) {\n    f(i, \"x\");\n}\n\
+             repeat (3) {\n    do {\n        a -= 1;\n    } until (a < 0);\n}\n\
+             while (a) {\n    b();\n}\n\
+             with (other) {\n    x = 1;\n}\n\
+             switch (a) {\n    case 1:\n    case 2:\n        b = 1;\n        break;\n\
+             \x20   default:\n        return -a;\n}\n",
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        assert_format(
+            &Config::default(),
+            "a = (b + c) * d - (e - f) + -(g * h) + !i.j[2, 3];\n(5).x = $FF + 0.3 + .5 + (a.b).c;\n\
+             c = \"'\" + '\"'; (f()).x[1] = 2; a = f().x;",
+            "a = (b + c) * d - (e - f) + -(g * h) + !i.j[2, 3];\n(5).x = $FF + 0.3 + .5 + a.b.c;\n\
+             c = \"'\" + '\"';\n(f()).x[1] = 2;\na = f().x;\n",
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_format(
+            &Config::default(),
+            "// header\n\n\na = 1; // one\n/* block\n   comment */ if a { // open\n\n  b = 2 /* two */\n\
+             \x20 // last\n}\n\n\
+             if b {\n  // only a comment\n}\n// end",
+            "// header\n\na = 1; // one\n/* block\n   comment */\nif (a) { // open\n    b = 2; /* two */\n    \
+             // last\n}\n\nif (b) {\n    // only a comment\n}\n// end\n",
+        );
+    }
+
+    #[test]
+    fn inline_comments() {
+        assert_format(
+            &Config::default(),
+            "a = 1 + /* one */ 2\nif (a /* check */) {\n  b = f(a, // first\n  c)\n}\nwhile (a /* w */) {}\n\
+             c = a /* end */; d = /* d */ e /* trailing */\nvar x /* x */, y; do {} until (x // done\n)",
+            "a = 1 + /* one */ 2;\nif (a /* check */) {\n    b = f(a, // first\n        c);\n}\nwhile (a /* w */) {}\n\
+             c = a /* end */;\nd = /* d */ e; /* trailing */\nvar x /* x */, y;\ndo {} until (x); // done\n",
+        );
+    }
+
+    #[test]
+    fn styles() {
+        let config = Config { braces: BraceStyle::NextLine, indent: "\t".into(), spaces: false, semicolons: false };
+        assert_format(
+            &config,
+            "if a { b = c + d; } else { f(x, y mod 2) }; var e; do {} until e; a = 1; (5).c = 2; {}",
+            "if (a)\n{\n\tb=c+d\n}\nelse\n{\n\tf(x,y mod 2)\n}\nvar e;\ndo {}\nuntil (e)\na=1;\n(5).c=2\n{}\n",
+        );
+    }
+}
@@ -0,0 +1,150 @@
+mod format;
+
+use format::{BraceStyle, Config};
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::Path,
+    process,
+};
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+
+fn help(argv0: &str, opts: getopts::Options) {
+    let name = match Path::new(argv0).file_name() {
+        Some(file) => file.to_str().unwrap_or(argv0),
+        None => argv0,
+    };
+    print!("{}", opts.usage(&format!("Usage: {} [FILE...] [options]\nWith no files, formats standard input.", name)));
+}
+
+fn main() {
+    process::exit(xmain());
+}
+
+fn xmain() -> i32 {
+    let args: Vec<String> = env::args().collect();
+    let process = args[0].clone();
+
+    let mut opts = getopts::Options::new();
+    opts.optflag("h", "help", "prints this help message");
+    opts.optopt("", "braces", "where opening braces go (default same-line)", "same-line|next-line");
+    opts.optflag("c", "check", "lists the files which aren't formatted, without changing them");
+    opts.optflag("", "compact", "leaves out the spaces around operators and after commas");
+    opts.optopt("i", "indent", "how many spaces to indent by (default 4)", "N");
+    opts.optflag("", "no-semicolons", "leaves out semicolons wherever the code means the same without them");
+    opts.optflag("t", "tabs", "indents with tabs instead of spaces");
+    opts.optflag("w", "write", "writes the formatted code back to each file instead of printing it");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(matches) => matches,
+        Err(fail) => {
+            use getopts::Fail::*;
+            match fail {
+                ArgumentMissing(arg) => eprintln!("missing argument {}", arg),
+                UnrecognizedOption(opt) => eprintln!("unrecognized option {}", opt),
+                OptionMissing(opt) => eprintln!("missing option {}", opt),
+                OptionDuplicated(opt) => eprintln!("duplicated option {}", opt),
+                UnexpectedArgument(arg) => eprintln!("unexpected argument {}", arg),
+            }
+            return EXIT_FAILURE
+        },
+    };
+
+    if matches.opt_present("h") {
+        help(&process, opts);
+        return EXIT_SUCCESS
+    }
+
+    let check = matches.opt_present("c");
+    let write = matches.opt_present("w");
+    let braces = match matches.opt_str("braces").as_deref() {
+        Some("same-line") | None => BraceStyle::SameLine,
+        Some("next-line") => BraceStyle::NextLine,
+        Some(style) => {
+            eprintln!("unknown brace style {}, expected same-line or next-line", style);
+            return EXIT_FAILURE
+        },
+    };
+    let indent = match matches.opt_str("i").map(|n| n.parse::<usize>()) {
+        Some(Ok(n)) => n,
+        Some(Err(_)) => {
+            eprintln!("indent must be a number of spaces");
+            return EXIT_FAILURE
+        },
+        None => 4,
+    };
+    let config = Config {
+        braces,
+        indent: if matches.opt_present("t") { "\t".into() } else { " ".repeat(indent) },
+        spaces: !matches.opt_present("compact"),
+        semicolons: !matches.opt_present("no-semicolons"),
+    };
+
+    if matches.free.is_empty() {
+        if write {
+            eprintln!("--write needs files to write to, it can't be used on standard input");
+            return EXIT_FAILURE
+        }
+        let mut source = String::new();
+        if let Err(err) = io::stdin().read_to_string(&mut source) {
+            eprintln!("failed to read input: {}", err);
+            return EXIT_FAILURE
+        }
+        return match format::format(&source, &config) {
+            Ok(formatted) if check => {
+                if formatted == source {
+                    EXIT_SUCCESS
+                } else {
+                    println!("<stdin>");
+                    EXIT_FAILURE
+                }
+            },
+            Ok(formatted) => {
+                print!("{}", formatted);
+                EXIT_SUCCESS
+            },
+            Err(err) => {
+                eprintln!("<stdin>: {}", err);
+                EXIT_FAILURE
+            },
+        }
+    }
+
+    let mut status = EXIT_SUCCESS;
+    for path in &matches.free {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("failed to open '{}': {}", path, err);
+                status = EXIT_FAILURE;
+                continue
+            },
+        };
+        let formatted = match format::format(&source, &config) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                status = EXIT_FAILURE;
+                continue
+            },
+        };
+        if check {
+            if formatted != source {
+                println!("{}", path);
+                status = EXIT_FAILURE;
+            }
+        } else if write {
+            if formatted != source {
+                if let Err(err) = fs::write(path, formatted) {
+                    eprintln!("failed to write '{}': {}", path, err);
+                    status = EXIT_FAILURE;
+                }
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    status
+}
@@ -0,0 +1,96 @@
+mod protocol;
+mod server;
+
+#[cfg(feature = "gm8exe")]
+use gm8emulator::game::Game;
+use gm8emulator::gml::compiler::Compiler;
+use server::{Project, Server};
+#[cfg(feature = "gm8exe")]
+use std::fs;
+use std::{env, io, path::Path, process};
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+
+fn help(argv0: &str, opts: getopts::Options) {
+    let name = match Path::new(argv0).file_name() {
+        Some(file) => file.to_str().unwrap_or(argv0),
+        None => argv0,
+    };
+    print!("{}", opts.usage(&format!("Usage: {} [options]\nServes the language server protocol over stdio.", name)));
+}
+
+fn main() {
+    process::exit(xmain());
+}
+
+fn xmain() -> i32 {
+    let args: Vec<String> = env::args().collect();
+    let process = args[0].clone();
+
+    let mut opts = getopts::Options::new();
+    opts.optflag("h", "help", "prints this help message");
+    #[cfg(feature = "gm8exe")]
+    opts.optopt("g", "game", "reads asset and script names from a game", "FILE");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(matches) => matches,
+        Err(fail) => {
+            use getopts::Fail::*;
+            match fail {
+                ArgumentMissing(arg) => eprintln!("missing argument {}", arg),
+                UnrecognizedOption(opt) => eprintln!("unrecognized option {}", opt),
+                OptionMissing(opt) => eprintln!("missing option {}", opt),
+                OptionDuplicated(opt) => eprintln!("duplicated option {}", opt),
+                UnexpectedArgument(arg) => eprintln!("unexpected argument {}", arg),
+            }
+            return EXIT_FAILURE
+        },
+    };
+
+    if matches.opt_present("h") {
+        help(&process, opts);
+        return EXIT_SUCCESS
+    }
+    if let Some(arg) = matches.free.first() {
+        eprintln!("unexpected argument {}", arg);
+        return EXIT_FAILURE
+    }
+
+    #[cfg(feature = "gm8exe")]
+    let project = match matches.opt_str("g") {
+        Some(input) => {
+            let mut file = match fs::read(&input) {
+                Ok(data) => data,
+                Err(err) => {
+                    eprintln!("failed to open '{}': {}", input, err);
+                    return EXIT_FAILURE
+                },
+            };
+            let assets = match gm8exe::reader::from_exe(&mut file, None::<fn(&str)>, false, true) {
+                Ok(assets) => assets,
+                Err(err) => {
+                    eprintln!("failed to load '{}' - {}", input, err);
+                    return EXIT_FAILURE
+                },
+            };
+            let mut project = Project::new(Game::make_compiler(&assets));
+            for script in assets.scripts.iter().flatten() {
+                project.add_script(&script.name, script.source.to_string(), None);
+            }
+            project
+        },
+        None => Project::new(Compiler::new()),
+    };
+    #[cfg(not(feature = "gm8exe"))]
+    let project = Project::new(Compiler::new());
+
+    let stdin = io::stdin();
+    match Server::new(project).run(&mut stdin.lock(), &mut io::stdout()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("{}", err);
+            EXIT_FAILURE
+        },
+    }
+}
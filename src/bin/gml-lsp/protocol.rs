@@ -0,0 +1,154 @@
+use gm8emulator::gml::compiler::lexer::Position;
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+/// Reads one message from the client, or None once the input has ended.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None)
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    serde_json::from_slice(&content).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Sends one message to the client.
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    output.flush()
+}
+
+/// A source file open in the client.
+pub struct Document {
+    pub text: String,
+
+    /// Offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl Document {
+    pub fn new(text: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.bytes().enumerate().filter(|(_, ch)| *ch == b'\n').map(|(i, _)| i + 1))
+            .collect();
+        Document { text, line_starts }
+    }
+
+    /// Converts a byte offset to an LSP position, where lines start at 0 and characters are counted in UTF-16.
+    pub fn position(&self, offset: usize) -> Value {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.iter().rposition(|&start| start <= offset).unwrap_or(0);
+        let character = self.text[self.line_starts[line]..offset].encode_utf16().count();
+        json!({ "line": line, "character": character })
+    }
+
+    /// Converts an LSP position to a byte offset, clamping it to the end of its line.
+    pub fn offset(&self, position: &Value) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let mut character = position["character"].as_u64().unwrap_or(0) as usize;
+        let start = match self.line_starts.get(line) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+        let end = self.line_starts.get(line + 1).map(|&end| end - 1).unwrap_or(self.text.len());
+        for (i, ch) in self.text[start..end].char_indices() {
+            if character < ch.len_utf16() {
+                return start + i
+            }
+            character -= ch.len_utf16();
+        }
+        end
+    }
+
+    /// Converts a position from the lexer to a byte offset.
+    pub fn offset_of(&self, position: Position) -> usize {
+        match self.line_starts.get(position.line.saturating_sub(1)) {
+            Some(&start) => (start + position.column.saturating_sub(1)).min(self.text.len()),
+            None => self.text.len(),
+        }
+    }
+
+    /// Makes an LSP range covering the given byte offsets.
+    pub fn range(&self, start: usize, end: usize) -> Value {
+        json!({ "start": self.position(start), "end": self.position(end) })
+    }
+}
+
+/// Gets the path a file:// URI refers to.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(ch) = iter.next() {
+        if ch == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(ch);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // file:///C:/dir is C:/dir on Windows
+    match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => Some(PathBuf::from(&path[1..])),
+        _ => Some(PathBuf::from(path)),
+    }
+}
+
+/// Makes a file:// URI for a path.
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from(if path.starts_with('/') { "file://" } else { "file:///" });
+    for ch in path.bytes() {
+        match ch {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => uri.push(ch as char),
+            _ => uri += &format!("%{:02X}", ch),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        let mut output = Vec::new();
+        write_message(&mut output, &json!({ "id": 1, "result": "ü" })).unwrap();
+        assert!(output.starts_with(b"Content-Length: 22\r\n\r\n"));
+        let mut input = io::Cursor::new(output);
+        assert_eq!(read_message(&mut input).unwrap(), Some(json!({ "id": 1, "result": "ü" })));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn positions() {
+        let document = Document::new("a = 1;\nb = \"😀\" + c;".into());
+        let offset = document.text.find('+').unwrap();
+        assert_eq!(document.position(offset), json!({ "line": 1, "character": 9 }));
+        assert_eq!(document.offset(&json!({ "line": 1, "character": 9 })), offset);
+        assert_eq!(document.offset(&json!({ "line": 0, "character": 50 })), 6);
+        assert_eq!(document.offset_of(Position { line: 2, column: 1 }), 7);
+
+        let path = uri_to_path("file:///home/me/My%20Game/scripts/scr_a.gml").unwrap();
+        assert_eq!(path, PathBuf::from("/home/me/My Game/scripts/scr_a.gml"));
+        assert_eq!(path_to_uri(&path), "file:///home/me/My%20Game/scripts/scr_a.gml");
+    }
+}
@@ -0,0 +1,706 @@
+use crate::protocol::{self, Document};
+use gm8emulator::gml::{
+    compiler::{
+        ast::{Expr, AST},
+        lexer::{Lexer, Position},
+        mappings,
+        token::{Keyword, Separator, Token},
+        Compiler,
+    },
+    Value,
+};
+use serde_json::{json, Value as Json};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+// Numbers the protocol uses for the kinds of things the server reports
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_PROPERTY: u8 = 10;
+const COMPLETION_KEYWORD: u8 = 14;
+const COMPLETION_CONSTANT: u8 = 21;
+const SYMBOL_FIELD: u8 = 8;
+const SYMBOL_VARIABLE: u8 = 13;
+const LOG_ERROR: u8 = 1;
+
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Every word the lexer reads as something other than an identifier
+const KEYWORDS: [&str; 26] = [
+    "and", "begin", "break", "case", "continue", "default", "div", "do", "else", "end", "exit", "for", "globalvar",
+    "if", "mod", "not", "or", "repeat", "return", "switch", "then", "until", "var", "while", "with", "xor",
+];
+
+/// A script whose source the server has seen.
+pub struct Script {
+    pub source: String,
+
+    /// Where it can be found in the workspace, if it's there, as its file and the line it starts on.
+    pub location: Option<(PathBuf, usize)>,
+}
+
+/// What the server knows about the game being edited, besides GML's built-in names.
+pub struct Project {
+    /// Asset and script names, registered the same way as when the game is compiled.
+    pub compiler: Compiler,
+
+    pub scripts: HashMap<String, Script>,
+}
+
+impl Project {
+    pub fn new(compiler: Compiler) -> Self {
+        Project { compiler, scripts: HashMap::new() }
+    }
+
+    /// Adds or updates a script, registering its name if it's a new one.
+    pub fn add_script(&mut self, name: &str, source: String, location: Option<(PathBuf, usize)>) {
+        if !self.is_script(name) {
+            let index = self.compiler.scripts().map(|(_, index)| index + 1).max().unwrap_or(0);
+            self.compiler.register_script(name.into(), index);
+            self.compiler.register_constant(name.into(), index as f64);
+        }
+        let location = location.or_else(|| self.scripts.get(name).and_then(|script| script.location.clone()));
+        self.scripts.insert(name.into(), Script { source, location });
+    }
+
+    /// Adds the scripts in every GML file in a directory or any directory inside it, other than hidden ones.
+    pub fn load_workspace(&mut self, root: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(root)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                if !entry.file_name().to_string_lossy().starts_with('.') {
+                    self.load_workspace(&path)?;
+                }
+            } else if is_gml(&path) {
+                let text = fs::read_to_string(&path)?;
+                self.add_file(&path, &text);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds or updates the scripts in a GML file, if it has any.
+    pub fn add_file(&mut self, path: &Path, text: &str) {
+        for (name, source, line) in scripts_in_file(path, text) {
+            self.add_script(name, source, Some((path.to_path_buf(), line)));
+        }
+    }
+
+    fn is_script(&self, name: &str) -> bool {
+        self.compiler.scripts().any(|(script, _)| script == name)
+    }
+
+    /// Gets how to show a name when it's hovered over, as a line of code and what the name is.
+    fn describe(&self, name: &str) -> Option<(String, &'static str)> {
+        if self.is_script(name) {
+            let signature = match self.scripts.get(name) {
+                Some(script) => script_signature(name, &script.source),
+                None => format!("{}(...)", name),
+            };
+            Some((signature, "script"))
+        } else if let Some((_, value)) = self.compiler.constants().find(|(constant, _)| *constant == name) {
+            Some((format!("{} = {}", name, show_value(value)), "asset"))
+        } else if let Some((_, _, constant)) = mappings::FUNCTIONS.iter().find(|(function, ..)| *function == name) {
+            let description =
+                if *constant { "built-in function, which only depends on its arguments" } else { "built-in function" };
+            Some((format!("{}(...)", name), description))
+        } else if let Some((_, value)) = mappings::CONSTANTS.iter().find(|(constant, _)| *constant == name) {
+            Some((format!("{} = {}", name, value), "built-in constant"))
+        } else if mappings::INSTANCE_VARIABLES.iter().any(|(variable, _)| *variable == name) {
+            Some((name.to_string(), "built-in instance variable"))
+        } else {
+            None
+        }
+    }
+}
+
+fn show_value(value: &Value) -> String {
+    match value {
+        Value::Real(real) => real.to_string(),
+        Value::Str(string) => format!("\"{}\"", string),
+    }
+}
+
+fn is_gml(path: &Path) -> bool {
+    matches!(path.extension().and_then(|ext| ext.to_str()), Some(ext) if ext.eq_ignore_ascii_case("gml"))
+}
+
+/// Splits a GML file into the scripts it holds, as their names, sources and the lines they start on. GM8 exports
+/// scripts together in one file, each after a `#define NAME` line. Otherwise, a file in a directory called scripts
+/// (as --dump-code writes them) is one script named after the file, and any other file isn't a script.
+fn scripts_in_file<'a>(path: &'a Path, text: &'a str) -> Vec<(&'a str, String, usize)> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let defines = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("#define"), Some(name)) => Some((i, name)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    if defines.is_empty() {
+        let in_scripts = path.ancestors().skip(1).any(|dir| match dir.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.eq_ignore_ascii_case("scripts"),
+            None => false,
+        });
+        return match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) if in_scripts => vec![(name, text.to_string(), 0)],
+            _ => Vec::new(),
+        }
+    }
+    defines
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, name))| {
+            let end = defines.get(i + 1).map(|&(end, _)| end).unwrap_or(lines.len());
+            (name, lines[start + 1..end].join("\n"), start)
+        })
+        .collect()
+}
+
+/// Gets a script's signature from the `/// name(args)` comment GM8 scripts conventionally start with, or otherwise
+/// from the arguments it reads.
+fn script_signature(name: &str, source: &str) -> String {
+    if let Some(comment) = source.lines().next().and_then(|line| line.trim().strip_prefix("///")) {
+        if comment.trim().starts_with(name) {
+            return comment.trim().to_string()
+        }
+    }
+    let mut count = 0;
+    let mut lex = Lexer::new(source);
+    while let Some(token) = lex.next() {
+        match token {
+            Token::Identifier("argument") if lex.peek() == Some(&Token::Separator(Separator::BracketLeft)) => {
+                lex.next();
+                if let Some(Token::Real(index)) = lex.next() {
+                    count = count.max(index as usize + 1);
+                }
+            },
+            Token::Identifier(id) => {
+                if let Some(index) = id.strip_prefix("argument").and_then(|n| n.parse::<usize>().ok()) {
+                    count = count.max(index + 1);
+                }
+            },
+            _ => (),
+        }
+    }
+    let arguments = (0..count).map(|i| format!("argument{}", i)).collect::<Vec<_>>();
+    format!("{}({})", name, arguments.join(", "))
+}
+
+/// Finds the identifier at a byte offset in a document, along with where it starts.
+fn identifier_at(document: &Document, offset: usize) -> Option<(&str, usize)> {
+    let mut lex = Lexer::new(&document.text);
+    while let Some(token) = lex.next() {
+        let start = document.offset_of(lex.position());
+        if start > offset {
+            break
+        }
+        if let Token::Identifier(name) = token {
+            if offset <= start + name.len() {
+                return Some((name, start))
+            }
+        }
+    }
+    None
+}
+
+/// Finds every name declared with var or globalvar, along with where.
+fn declarations(source: &str) -> Vec<(Keyword, &str, Position)> {
+    let mut declarations = Vec::new();
+    let mut lex = Lexer::new(source);
+    let mut keyword = None;
+    while let Some(token) = lex.next() {
+        match (token, keyword) {
+            (Token::Keyword(k @ Keyword::Var), _) | (Token::Keyword(k @ Keyword::GlobalVar), _) => keyword = Some(k),
+            (Token::Identifier(name), Some(k)) => declarations.push((k, name, lex.position())),
+            (Token::Separator(Separator::Comma), Some(_)) => (),
+            _ => keyword = None,
+        }
+    }
+    declarations
+}
+
+pub struct Server {
+    project: Project,
+    documents: HashMap<String, Document>,
+
+    /// Whether the client has asked the server to shut down.
+    shutdown: bool,
+
+    /// Messages waiting to be sent to the client.
+    outgoing: Vec<Json>,
+}
+
+impl Server {
+    pub fn new(project: Project) -> Self {
+        Server { project, documents: HashMap::new(), shutdown: false, outgoing: Vec::new() }
+    }
+
+    /// Serves a client until it says to exit, returning the exit code to end with.
+    pub fn run(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<i32> {
+        while let Some(message) = protocol::read_message(input)? {
+            let exit = self.handle(&message);
+            for message in self.outgoing.drain(..) {
+                protocol::write_message(output, &message)?;
+            }
+            if let Some(code) = exit {
+                return Ok(code)
+            }
+        }
+        Ok(1)
+    }
+
+    /// Handles a message from the client, queueing up anything to send back. Returns an exit code once the client
+    /// says to exit.
+    pub fn handle(&mut self, message: &Json) -> Option<i32> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        match message.get("id") {
+            // The server never sends requests, so any responses from the client can be ignored
+            Some(_) if method.is_empty() => (),
+            Some(id) => {
+                let response = match self.request(method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => {
+                        json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+                    },
+                };
+                self.outgoing.push(response);
+            },
+            None => match method {
+                "exit" => return Some(if self.shutdown { 0 } else { 1 }),
+                "textDocument/didOpen" => {
+                    let document = &params["textDocument"];
+                    self.update(document["uri"].as_str().unwrap_or_default(), document["text"].as_str());
+                },
+                "textDocument/didChange" => {
+                    // Only whole documents are synced, so the last change holds all of it
+                    let text = params["contentChanges"].as_array().and_then(|changes| changes.last());
+                    self.update(
+                        params["textDocument"]["uri"].as_str().unwrap_or_default(),
+                        text.and_then(|change| change["text"].as_str()),
+                    );
+                },
+                "textDocument/didClose" => {
+                    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                    self.documents.remove(uri);
+                    self.publish_diagnostics(uri, Vec::new());
+                },
+                _ => (),
+            },
+        }
+        None
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        let document = || {
+            let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+            match self.documents.get(uri) {
+                Some(document) => Ok((document, document.offset(&params["position"]))),
+                None => Err((INVALID_PARAMS, format!("{} isn't open", uri))),
+            }
+        };
+        match method {
+            "initialize" => {
+                let root = match params["rootUri"].as_str() {
+                    Some(uri) => protocol::uri_to_path(uri),
+                    None => params["rootPath"].as_str().map(PathBuf::from),
+                };
+                if let Some(root) = root {
+                    match self.project.load_workspace(&root) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                            let message = format!("failed to load scripts from {}: {}", root.display(), e);
+                            self.log(LOG_ERROR, message);
+                        },
+                        _ => (),
+                    }
+                }
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "completionProvider": {},
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "documentSymbolProvider": true,
+                    },
+                    "serverInfo": { "name": "gml-lsp" },
+                }))
+            },
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            },
+            "textDocument/completion" => document().map(|(document, offset)| self.completion(document, offset)),
+            "textDocument/hover" => document().map(|(document, offset)| self.hover(document, offset)),
+            "textDocument/definition" => document().map(|(document, offset)| self.definition(document, offset)),
+            "textDocument/documentSymbol" => document().map(|(document, _)| self.symbols(document)),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        }
+    }
+
+    fn log(&mut self, level: u8, message: String) {
+        self.outgoing.push(json!({
+            "jsonrpc": "2.0",
+            "method": "window/logMessage",
+            "params": { "type": level, "message": message },
+        }));
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Json>) {
+        self.outgoing.push(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }));
+    }
+
+    /// Stores the new text of a document and checks it.
+    fn update(&mut self, uri: &str, text: Option<&str>) {
+        let text = match text {
+            Some(text) => text.to_string(),
+            None => return,
+        };
+        // Keep track of scripts being edited, so their latest signatures are shown
+        if let Some(path) = protocol::uri_to_path(uri) {
+            if is_gml(&path) {
+                self.project.add_file(&path, &text);
+            }
+        }
+        let document = Document::new(text);
+        let diagnostics = self.diagnostics(&document);
+        self.documents.insert(uri.into(), document);
+        self.publish_diagnostics(uri, diagnostics);
+    }
+
+    /// Reports every syntax error in a document, or if there are none, calls to anything that isn't a function or
+    /// one of the game's scripts. That can only be checked if the game's scripts are known.
+    fn diagnostics(&self, document: &Document) -> Vec<Json> {
+        let text = &document.text;
+        let errors = AST::errors(text);
+        if !errors.is_empty() {
+            return errors
+                .into_iter()
+                .map(|error| {
+                    let start = error.position.map(|position| document.offset_of(position)).unwrap_or(0);
+                    let end = text[start..].char_indices().nth(1).map(|(i, _)| start + i).unwrap_or(text.len());
+                    json!({
+                        "range": document.range(start, end),
+                        "severity": SEVERITY_ERROR,
+                        "source": "gml",
+                        "message": error.message,
+                    })
+                })
+                .collect()
+        }
+        if self.project.compiler.scripts().next().is_none() {
+            return Vec::new()
+        }
+
+        let mut diagnostics = Vec::new();
+        let mut lex = Lexer::new(text);
+        while let Some(token) = lex.next() {
+            if let Token::Identifier(name) = token {
+                let start = document.offset_of(lex.position());
+                if lex.peek() == Some(&Token::Separator(Separator::ParenLeft))
+                    && !self.project.is_script(name)
+                    && !mappings::FUNCTIONS.iter().any(|(function, ..)| *function == name)
+                {
+                    diagnostics.push(json!({
+                        "range": document.range(start, start + name.len()),
+                        "severity": SEVERITY_WARNING,
+                        "source": "gml",
+                        "message": format!("Unknown function or script {}", name),
+                    }));
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Lists every name starting with the part of one before the cursor. Scripts and assets come first, as they
+    /// hide any built-in names they share.
+    fn completion(&self, document: &Document, offset: usize) -> Json {
+        let text = &document.text;
+        let start = text[..offset].rfind(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_')).map(|i| i + 1);
+        let prefix = &text[start.unwrap_or(0)..offset];
+
+        let mut items = Vec::new();
+        let mut seen = HashSet::new();
+        let mut add = |name: &str, kind: u8, detail: String| {
+            if name.starts_with(prefix) && seen.insert(name.to_string()) {
+                items.push(json!({ "label": name, "kind": kind, "detail": detail }));
+            }
+        };
+        for (name, _) in self.project.compiler.scripts() {
+            add(name, COMPLETION_FUNCTION, "script".into());
+        }
+        for (name, value) in self.project.compiler.constants() {
+            add(name, COMPLETION_CONSTANT, show_value(value));
+        }
+        for (keyword, name, _) in declarations(text) {
+            add(name, COMPLETION_VARIABLE, keyword.to_string());
+        }
+        for (name, ..) in mappings::FUNCTIONS.iter() {
+            add(name, COMPLETION_FUNCTION, "function".into());
+        }
+        for (name, value) in mappings::CONSTANTS.iter() {
+            add(name, COMPLETION_CONSTANT, value.to_string());
+        }
+        for (name, _) in mappings::INSTANCE_VARIABLES.iter() {
+            add(name, COMPLETION_PROPERTY, "instance variable".into());
+        }
+        for keyword in KEYWORDS.iter() {
+            add(keyword, COMPLETION_KEYWORD, String::new());
+        }
+        json!({ "isIncomplete": false, "items": items })
+    }
+
+    fn hover(&self, document: &Document, offset: usize) -> Json {
+        match identifier_at(document, offset) {
+            Some((name, start)) => match self.project.describe(name) {
+                Some((code, description)) => json!({
+                    "contents": { "kind": "markdown", "value": format!("```gml\n{}\n```\n{}", code, description) },
+                    "range": document.range(start, start + name.len()),
+                }),
+                None => Json::Null,
+            },
+            None => Json::Null,
+        }
+    }
+
+    /// Finds where a script is, if it's in the workspace.
+    fn definition(&self, document: &Document, offset: usize) -> Json {
+        let location = identifier_at(document, offset)
+            .and_then(|(name, _)| self.project.scripts.get(name))
+            .and_then(|script| script.location.as_ref());
+        match location {
+            Some((path, line)) => json!({
+                "uri": protocol::path_to_uri(path),
+                "range": { "start": { "line": line, "character": 0 }, "end": { "line": line, "character": 0 } },
+            }),
+            None => Json::Null,
+        }
+    }
+
+    /// Lists the variables a document declares, then the fields it sets which aren't built-in.
+    fn symbols(&self, document: &Document) -> Json {
+        let mut symbols = Vec::new();
+        let mut seen = HashSet::new();
+        for (keyword, name, position) in declarations(&document.text) {
+            if seen.insert(name) {
+                let start = document.offset_of(position);
+                let range = document.range(start, start + name.len());
+                symbols.push(json!({
+                    "name": name,
+                    "detail": keyword.to_string(),
+                    "kind": SYMBOL_VARIABLE,
+                    "range": range,
+                    "selectionRange": range,
+                }));
+            }
+        }
+        if let Ok(ast) = AST::new(&document.text) {
+            for expr in ast.iter() {
+                field_symbols(document, expr, &mut seen, &mut symbols);
+            }
+        }
+        Json::Array(symbols)
+    }
+}
+
+/// Finds the first assignment to each field in a line of code and anything nested in it.
+fn field_symbols<'a>(document: &Document, expr: &Expr<'a>, seen: &mut HashSet<&'a str>, symbols: &mut Vec<Json>) {
+    match expr {
+        Expr::Located(located) => {
+            if let Expr::Binary(binary) = &located.expr {
+                if let Expr::LiteralIdentifier(name) = binary.left {
+                    if AST::get_op_precedence(&binary.op).is_none()
+                        && !mappings::INSTANCE_VARIABLES.iter().any(|(variable, _)| *variable == name)
+                        && seen.insert(name)
+                    {
+                        let start = document.offset_of(located.position);
+                        let range = document.range(start, start + name.len());
+                        symbols.push(json!({
+                            "name": name,
+                            "detail": "field",
+                            "kind": SYMBOL_FIELD,
+                            "range": range,
+                            "selectionRange": range,
+                        }));
+                    }
+                }
+            }
+            field_symbols(document, &located.expr, seen, symbols);
+        },
+        Expr::Group(group) => group.iter().for_each(|expr| field_symbols(document, expr, seen, symbols)),
+        Expr::If(if_ex) => {
+            field_symbols(document, &if_ex.body, seen, symbols);
+            if let Some(else_body) = &if_ex.else_body {
+                field_symbols(document, else_body, seen, symbols);
+            }
+        },
+        Expr::For(for_ex) => {
+            field_symbols(document, &for_ex.start, seen, symbols);
+            field_symbols(document, &for_ex.step, seen, symbols);
+            field_symbols(document, &for_ex.body, seen, symbols);
+        },
+        Expr::DoUntil(dountil) => field_symbols(document, &dountil.body, seen, symbols),
+        Expr::Repeat(repeat) => field_symbols(document, &repeat.body, seen, symbols),
+        Expr::Switch(switch) => field_symbols(document, &switch.body, seen, symbols),
+        Expr::While(while_ex) => field_symbols(document, &while_ex.body, seen, symbols),
+        Expr::With(with) => field_symbols(document, &with.body, seen, symbols),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///game/objects/obj_player/step.gml";
+
+    fn server() -> Server {
+        let mut compiler = Compiler::new();
+        compiler.register_constant("spr_player".into(), 0.0);
+        let mut project = Project::new(compiler);
+        let path = PathBuf::from("/game/scripts/scr_move.gml");
+        project.add_script("scr_move", "/// scr_move(dx, dy)\nx += argument0;".into(), Some((path, 0)));
+        project.add_script("scr_hurt", "hp -= argument[1];".into(), None);
+        Server::new(project)
+    }
+
+    /// Opens a document, returning the diagnostics published for it.
+    fn open(server: &mut Server, text: &str) -> Json {
+        let document = json!({ "uri": URI, "languageId": "gml", "version": 1, "text": text });
+        let message = json!({ "method": "textDocument/didOpen", "params": { "textDocument": document } });
+        assert_eq!(server.handle(&message), None);
+        let message = server.outgoing.pop().unwrap();
+        assert_eq!(message["method"], "textDocument/publishDiagnostics");
+        message["params"]["diagnostics"].clone()
+    }
+
+    fn request(server: &mut Server, method: &str, params: Json) -> Json {
+        server.handle(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }));
+        let response = server.outgoing.pop().unwrap();
+        assert_eq!(response["id"], 1);
+        response
+    }
+
+    fn at(line: usize, character: usize) -> Json {
+        json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut server = server();
+        let response = request(&mut server, "initialize", json!({ "rootUri": null }));
+        assert_eq!(response["result"]["capabilities"]["hoverProvider"], true);
+        assert_eq!(request(&mut server, "textDocument/rename", at(0, 0))["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(request(&mut server, "textDocument/hover", at(0, 0))["error"]["code"], INVALID_PARAMS);
+        assert_eq!(request(&mut server, "shutdown", Json::Null)["result"], Json::Null);
+        assert_eq!(server.handle(&json!({ "method": "exit" })), Some(0));
+        assert_eq!(Server::new(Project::new(Compiler::new())).handle(&json!({ "method": "exit" })), Some(1));
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut server = server();
+        let diagnostics = open(&mut server, "a = 1;\nb = (2 + ;\nc = 3 *;");
+        assert_eq!(diagnostics.as_array().unwrap().len(), 2);
+        assert_eq!(diagnostics[0]["severity"], SEVERITY_ERROR);
+        assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 1, "character": 9 }));
+        assert_eq!(diagnostics[1]["range"]["start"], json!({ "line": 2, "character": 7 }));
+
+        let diagnostics = open(&mut server, "scr_move(1, 2); scr_nope(); draw_sprite(spr_player, 0, x, y);");
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics[0]["severity"], SEVERITY_WARNING);
+        assert_eq!(diagnostics[0]["message"], "Unknown function or script scr_nope");
+        assert_eq!(diagnostics[0]["range"]["start"]["character"], 16);
+        assert_eq!(diagnostics[0]["range"]["end"]["character"], 24);
+
+        // Without the game's scripts, any call might be to one of them
+        let mut server = Server::new(Project::new(Compiler::new()));
+        assert_eq!(open(&mut server, "scr_nope();"), json!([]));
+    }
+
+    #[test]
+    fn completion() {
+        let mut server = server();
+        open(&mut server, "var speed_max;\nspr_\ndraw_spr\nspeed");
+        let labels = |response: Json| {
+            let items = response["result"]["items"].as_array().unwrap().clone();
+            items.iter().map(|item| item["label"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(labels(request(&mut server, "textDocument/completion", at(1, 4))), vec!["spr_player"]);
+        let functions = labels(request(&mut server, "textDocument/completion", at(2, 8)));
+        assert!(functions.contains(&"draw_sprite".to_string()));
+        assert!(functions.iter().all(|name| name.starts_with("draw_spr")));
+        let mut variables = labels(request(&mut server, "textDocument/completion", at(3, 5)));
+        variables.sort();
+        assert_eq!(variables, vec!["speed", "speed_max"]);
+    }
+
+    #[test]
+    fn hover_and_definition() {
+        let mut server = server();
+        open(&mut server, "scr_move(1, 2);\nscr_hurt(c_red, spr_player);");
+        let hover = |server: &mut Server, line, character| {
+            request(server, "textDocument/hover", at(line, character))["result"]["contents"]["value"].clone()
+        };
+        assert_eq!(hover(&mut server, 0, 3), "```gml\nscr_move(dx, dy)\n```\nscript");
+        assert_eq!(hover(&mut server, 1, 8), "```gml\nscr_hurt(argument0, argument1)\n```\nscript");
+        assert_eq!(hover(&mut server, 1, 10), "```gml\nc_red = 255\n```\nbuilt-in constant");
+        assert_eq!(hover(&mut server, 1, 20), "```gml\nspr_player = 0\n```\nasset");
+        assert_eq!(hover(&mut server, 0, 12), Json::Null);
+
+        let definition = request(&mut server, "textDocument/definition", at(0, 0))["result"].clone();
+        assert_eq!(definition["uri"], "file:///game/scripts/scr_move.gml");
+        assert_eq!(request(&mut server, "textDocument/definition", at(1, 0))["result"], Json::Null);
+
+        let path = PathBuf::from("/game/exported.gml");
+        let text = "#define scr_a\nreturn 1;\n\n#define   scr_hurt\n/// scr_hurt(amount)\nhp -= 1;";
+        server.project.add_file(&path, text);
+        let definition = request(&mut server, "textDocument/definition", at(1, 0))["result"].clone();
+        assert_eq!(definition["uri"], "file:///game/exported.gml");
+        assert_eq!(definition["range"]["start"], json!({ "line": 3, "character": 0 }));
+        assert_eq!(hover(&mut server, 1, 0), "```gml\nscr_hurt(amount)\n```\nscript");
+    }
+
+    #[test]
+    fn script_files() {
+        let scripts = |path: &str, text| {
+            let scripts = scripts_in_file(Path::new(path), text);
+            scripts.into_iter().map(|(name, source, line)| (name.to_string(), source, line)).collect::<Vec<_>>()
+        };
+        let script = |name: &str, source: &str, line| (name.to_string(), source.to_string(), line);
+        assert_eq!(scripts("/game/Scripts/movement/scr_move.gml", "x += 1;"), [script("scr_move", "x += 1;", 0)]);
+        assert!(scripts("/game/objects/obj_player/step.gml", "x += 1;").is_empty());
+        assert_eq!(
+            scripts("/game/all.gml", "// exported\n#define scr_a\na = 1;\n#define scr_b\nb = 2;\nc = 3;"),
+            [script("scr_a", "a = 1;", 1), script("scr_b", "b = 2;\nc = 3;", 3)]
+        );
+    }
+
+    #[test]
+    fn symbols() {
+        let mut server = server();
+        open(&mut server, "var a, b;\nglobalvar g;\nhp = 3;\na = 1;\nif hp { ready = true; speed = 2 }");
+        let symbols = request(&mut server, "textDocument/documentSymbol", at(0, 0))["result"].clone();
+        let names = symbols.as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "g", "hp", "ready"]);
+        assert_eq!(symbols[4]["kind"], SYMBOL_FIELD);
+        assert_eq!(symbols[4]["range"]["start"], json!({ "line": 4, "character": 8 }));
+    }
+}
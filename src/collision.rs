@@ -0,0 +1,139 @@
+//! Precise collision checking between instances and points, using the collision masks of their sprites.
+
+use crate::{
+    asset::sprite::{Collider, Sprite},
+    game::Game,
+    instance::Instance,
+    util,
+};
+
+/// Gets the collision mask an instance is currently using, along with the sprite it belongs to.
+/// This is the sprite given by mask_index, or the instance's own sprite if it doesn't have a mask.
+fn mask<'a>(game: &'a Game, instance: &Instance) -> Option<(&'a Sprite, &'a Collider)> {
+    let index = if instance.mask_index.get() >= 0 { instance.mask_index.get() } else { instance.sprite_index.get() };
+    let sprite = game.assets.sprites.get(index as usize)?.as_ref()?;
+    let collider = if sprite.per_frame_colliders {
+        sprite.colliders.get((instance.image_index.get().floor() as usize) % sprite.colliders.len().max(1))
+    } else {
+        sprite.colliders.first()
+    }?;
+    Some((sprite, collider))
+}
+
+/// Checks whether a point in the room is covered by a mask placed at an instance's position, scale and angle.
+fn mask_contains(instance: &Instance, sprite: &Sprite, collider: &Collider, x: f64, y: f64) -> bool {
+    // Undo the instance's rotation and scaling to find the corresponding pixel in the mask
+    let angle = util::degtorad(instance.image_angle.get());
+    let (sin, cos) = angle.sin_cos();
+    let dx = x - instance.x.get();
+    let dy = y - instance.y.get();
+    let local_x = ((dx * cos) - (dy * sin)) / instance.image_xscale.get() + f64::from(sprite.origin_x);
+    let local_y = ((dx * sin) + (dy * cos)) / instance.image_yscale.get() + f64::from(sprite.origin_y);
+    let (local_x, local_y) = (local_x.floor(), local_y.floor());
+    if local_x < 0.0 || local_y < 0.0 || local_x >= f64::from(collider.width) || local_y >= f64::from(collider.height) {
+        return false
+    }
+    collider.data.get(local_y as usize * collider.width as usize + local_x as usize).copied().unwrap_or(false)
+}
+
+impl Game {
+    /// Updates an instance's bounding box from its current mask, if it's stale.
+    pub fn update_instance_bbox(&self, instance: &Instance) {
+        instance.update_bbox(mask(self, instance).map(|(sprite, _)| sprite));
+    }
+
+    /// Gets an instance's bounding box as (left, top, right, bottom).
+    /// An instance without a collision mask is treated as a single point at its position.
+    pub fn instance_bbox(&self, instance: &Instance) -> (i32, i32, i32, i32) {
+        if mask(self, instance).is_some() {
+            self.update_instance_bbox(instance);
+            (instance.bbox_left.get(), instance.bbox_top.get(), instance.bbox_right.get(), instance.bbox_bottom.get())
+        } else {
+            let x = util::ieee_round(instance.x.get());
+            let y = util::ieee_round(instance.y.get());
+            (x, y, x, y)
+        }
+    }
+
+    /// Checks whether an instance's collision mask covers the given point.
+    pub fn check_collision_point(&self, idx: usize, x: i32, y: i32) -> bool {
+        let instance = match self.instance_list.get(idx) {
+            Some(instance) => instance,
+            None => return false,
+        };
+        let (sprite, collider) = match mask(self, instance) {
+            Some(mask) => mask,
+            None => return false,
+        };
+        self.update_instance_bbox(instance);
+        if x < instance.bbox_left.get()
+            || x > instance.bbox_right.get()
+            || y < instance.bbox_top.get()
+            || y > instance.bbox_bottom.get()
+        {
+            return false
+        }
+        mask_contains(instance, sprite, collider, f64::from(x), f64::from(y))
+    }
+
+    /// Checks whether the collision masks of two instances overlap.
+    pub fn check_collision(&self, idx1: usize, idx2: usize) -> bool {
+        let (inst1, inst2) = match (self.instance_list.get(idx1), self.instance_list.get(idx2)) {
+            (Some(inst1), Some(inst2)) => (inst1, inst2),
+            _ => return false,
+        };
+        let ((sprite1, collider1), (sprite2, collider2)) = match (mask(self, inst1), mask(self, inst2)) {
+            (Some(mask1), Some(mask2)) => (mask1, mask2),
+            _ => return false,
+        };
+        self.update_instance_bbox(inst1);
+        self.update_instance_bbox(inst2);
+
+        // Only the pixels inside both bounding boxes need to be checked
+        let left = inst1.bbox_left.get().max(inst2.bbox_left.get());
+        let right = inst1.bbox_right.get().min(inst2.bbox_right.get());
+        let top = inst1.bbox_top.get().max(inst2.bbox_top.get());
+        let bottom = inst1.bbox_bottom.get().min(inst2.bbox_bottom.get());
+        (top..=bottom).any(|y| {
+            (left..=right).any(|x| {
+                let (x, y) = (f64::from(x), f64::from(y));
+                mask_contains(inst1, sprite1, collider1, x, y) && mask_contains(inst2, sprite2, collider2, x, y)
+            })
+        })
+    }
+
+    /// Runs a check with an instance temporarily moved to another position, then puts it back.
+    pub fn at_position<T>(&self, idx: usize, x: f64, y: f64, check: impl FnOnce() -> T) -> T {
+        let instance = match self.instance_list.get(idx) {
+            Some(instance) => instance,
+            None => return check(),
+        };
+        let (old_x, old_y) = (instance.x.replace(x), instance.y.replace(y));
+        instance.bbox_is_stale.set(true);
+        let result = check();
+        instance.x.set(old_x);
+        instance.y.set(old_y);
+        instance.bbox_is_stale.set(true);
+        result
+    }
+
+    /// Checks whether an instance would collide with any other instance if it were at the given position.
+    /// If solid_only is set, only solid instances are considered, as with place_free.
+    pub fn check_collision_at(&self, idx: usize, x: f64, y: f64, solid_only: bool) -> bool {
+        self.at_position(idx, x, y, || {
+            let mut iter = self.instance_list.iter_inserted();
+            while let Some(other) = iter.next(&self.instance_list) {
+                if other == idx || !self.instance_list.exists(other) {
+                    continue
+                }
+                if solid_only && !matches!(self.instance_list.get(other), Some(instance) if instance.solid.get()) {
+                    continue
+                }
+                if self.check_collision(idx, other) {
+                    return true
+                }
+            }
+            false
+        })
+    }
+}
@@ -1,166 +1,358 @@
 use crate::{
     action::Tree,
-    asset::{
-        font::{Character, Font},
-        room::{self, Room},
-        sprite::{Collider, Frame, Sprite},
-        Background, Object, Script, Timeline,
+    asset::{sprite::Sprite, Background, Font, Object, Path, Room, Script, Timeline},
+    atlas::AtlasRef,
+    gml::{
+        self,
+        compiler::{self, mappings, Diagnostics},
+        datetime::Clock,
+        ev, ev_mouse, ev_other,
+        ini::IniFile,
+        mp,
+        rand::Random,
+        registry::{self, Registry},
+        runtime::{event_name, ErrorMode, Instruction},
+        vm, Compiler, Context, Value,
     },
-    atlas::AtlasBuilder,
-    background,
-    gml::{ev, rand::Random, Compiler},
+    input::{self, joystick, Input},
     instance::{DummyFieldHolder, Instance},
     instancelist::{InstanceList, TileList},
-    render::{opengl::OpenGLRenderer, Renderer, RendererOptions},
-    tile, view,
+    render::Renderer,
+    sandbox::Sandbox,
+    view,
 };
+#[cfg(feature = "gm8exe")]
 use gm8exe::GameAssets;
 use indexmap::IndexMap;
 use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
-    iter::repeat,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    mem,
+    panic::{self, AssertUnwindSafe},
     rc::Rc,
-    sync::mpsc::Receiver,
 };
 
+// Loading a game and opening its window
+#[cfg(feature = "window")]
+use crate::{
+    asset::{
+        font::Character,
+        path, room,
+        sprite::{Collider, Frame},
+    },
+    atlas::AtlasBuilder,
+    background,
+    gml::datetime::SystemClock,
+    render::{opengl::OpenGLRenderer, RendererOptions},
+    tile,
+};
+#[cfg(feature = "window")]
+use std::{collections::HashSet, fs, iter::repeat, path::PathBuf, sync::mpsc::Receiver};
+
 /// Structure which contains all the components of a game.
 pub struct Game {
     pub compiler: Compiler,
-    pub glfw: glfw::Glfw,
-    pub glfw_events: Receiver<(f64, glfw::WindowEvent)>,
     pub instance_list: InstanceList,
     pub tile_list: TileList,
     pub rand: Random,
     pub renderer: Box<dyn Renderer>,
     pub assets: Assets,
+
+    /// A single white pixel, for drawing plain rectangles with the sprite renderer
+    pub blank_texture: AtlasRef,
     pub event_holders: [IndexMap<u32, Rc<RefCell<Vec<i32>>>>; 12],
+    pub input: Input,
+    pub joystick_backend: Box<dyn joystick::Backend>,
+
+    pub last_instance_id: i32,
 
     pub room_id: i32,
     pub room_width: i32,
     pub room_height: i32,
+    pub views_enabled: bool,
+    pub views: Vec<view::View>,
     pub globals: DummyFieldHolder,
 
+    pub game_id: u32,
+    pub clock: Box<dyn Clock>,
+    pub sandbox: Sandbox,
+    pub open_ini: Option<IniFile>,
+    pub registry: Registry,
+    pub registry_root: registry::HKey,
+    pub mp_grids: Vec<Option<mp::Grid>>,
+    pub mp_potential: mp::PotentialSettings,
+
     pub uninit_fields_are_zero: bool,
     pub uninit_args_are_zero: bool,
+
+    /// Whether pressing Escape ends the game - if not, it's an ordinary key the game can use
+    pub esc_close_game: bool,
+
+    /// What to do when an action raises an error, along with the error_occurred and error_last variables
+    pub error_mode: ErrorMode,
+    pub error_occurred: Cell<bool>,
+    pub error_last: RefCell<String>,
+
+    /// Whether errors are also appended to game_errors.log, whatever the error mode is
+    pub error_log: bool,
+
+    /// The bytecode value stack, kept between runs so it doesn't need allocating every time
+    pub stack: Vec<gml::Value>,
+}
+
+/// The game window's event queue, which the front end empties at the start of every frame.
+#[cfg(feature = "window")]
+pub struct WindowEvents {
+    pub glfw: glfw::Glfw,
+    pub receiver: Receiver<(f64, glfw::WindowEvent)>,
+}
+
+/// All of a game's code, compiled before anything else is loaded. Each list is in the same order as the
+/// corresponding assets, with None where there's no asset.
+pub struct GameCode {
+    pub compiler: Compiler,
+    pub diagnostics: Diagnostics,
+    pub object_events: Vec<Option<ObjectEvents>>,
+    pub timeline_moments: Vec<Option<HashMap<u32, Tree>>>,
+    pub scripts: Vec<Option<Rc<[Instruction]>>>,
+    pub room_code: Vec<Option<RoomCode>>,
+}
+
+/// An object's events, by event type and then event number
+pub type ObjectEvents = [HashMap<u32, Rc<Tree>>; 12];
+
+/// The creation code of a room, along with that of each instance in it
+pub struct RoomCode {
+    pub creation_code: Rc<[Instruction]>,
+    pub instances: Vec<Rc<[Instruction]>>,
 }
 
 pub struct Assets {
     pub backgrounds: Vec<Option<Box<Background>>>,
     pub fonts: Vec<Option<Box<Font>>>,
     pub objects: Vec<Option<Box<Object>>>,
+    pub paths: Vec<Option<Box<Path>>>,
     pub rooms: Vec<Option<Box<Room>>>,
-    pub scripts: Vec<Option<Box<Script>>>,
+    /// Shared so running code can keep hold of the scripts it calls without borrowing the game
+    pub scripts: Rc<Vec<Option<Box<Script>>>>,
     pub sprites: Vec<Option<Box<Sprite>>>,
     pub timelines: Vec<Option<Box<Timeline>>>,
     // todo
 }
 
 impl Game {
-    pub fn launch(assets: GameAssets) -> Result<Self, Box<dyn std::error::Error>> {
-        // destructure assets
-        let GameAssets {
-            backgrounds,
-            constants,
-            fonts,
-            icon_data,
-            objects,
-            paths,
-            room_order,
-            rooms,
-            scripts,
-            settings,
-            sounds,
-            sprites,
-            timelines,
-            triggers,
-            ..
-        } = assets;
-
-        // If there are no rooms, you can't build a GM8 game. Fatal error.
-        // We need a lot of the initialization info from the first room,
-        // the window size, and title, etc. is based on it.
-        let room1_id = *room_order.first().ok_or("Room order is empty")?;
-        let room1 = match rooms.get(room1_id as usize) {
-            Some(Some(r)) => r,
-            _ => return Err("First room does not exist".into()),
-        };
-        let room1_width = room1.width;
-        let room1_height = room1.height;
-
-        // Set up a GML compiler
+    /// Sets up a GML compiler with the names of all of a game's assets and scripts.
+    #[cfg(feature = "gm8exe")]
+    pub fn make_compiler(assets: &GameAssets) -> Compiler {
         let mut compiler = Compiler::new();
-        compiler.reserve_scripts(scripts.iter().flatten().count());
+        compiler.reserve_scripts(assets.scripts.iter().flatten().count());
         compiler.reserve_constants(
-            backgrounds.iter().flatten().count()
-                + fonts.iter().flatten().count()
-                + objects.iter().flatten().count()
-                + paths.iter().flatten().count()
-                + rooms.iter().flatten().count()
-                + scripts.iter().flatten().count()
-                + sounds.iter().flatten().count()
-                + sprites.iter().flatten().count()
-                + timelines.iter().flatten().count()
-                + triggers.iter().flatten().count()
-                + constants.len(),
+            assets.backgrounds.iter().flatten().count()
+                + assets.fonts.iter().flatten().count()
+                + assets.objects.iter().flatten().count()
+                + assets.paths.iter().flatten().count()
+                + assets.rooms.iter().flatten().count()
+                + assets.scripts.iter().flatten().count()
+                + assets.sounds.iter().flatten().count()
+                + assets.sprites.iter().flatten().count()
+                + assets.timelines.iter().flatten().count()
+                + assets.triggers.iter().flatten().count()
+                + assets.constants.len(),
         );
-        backgrounds
+        assets
+            .backgrounds
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        fonts
+        assets
+            .fonts
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        objects
+        assets
+            .objects
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        paths
+        assets
+            .paths
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        rooms
+        assets
+            .rooms
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        scripts
+        assets
+            .scripts
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        sounds
+        assets
+            .sounds
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        sprites
+        assets
+            .sprites
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        timelines
+        assets
+            .timelines
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.name.clone(), i as f64));
-        triggers
+        assets
+            .triggers
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_constant(x.constant_name.clone(), i as f64));
 
-        scripts
+        assets
+            .scripts
             .iter()
             .enumerate()
             .filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
             .for_each(|(i, x)| compiler.register_script(x.name.clone(), i));
 
+        // globalvar applies to code compiled before the declaration, so every declaration needs to be known first
+        let lists = assets.objects.iter().flatten().flat_map(|o| o.events.iter().flatten().map(|(_, list)| list));
+        let lists = lists.chain(assets.timelines.iter().flatten().flat_map(|t| t.moments.iter().map(|(_, list)| list)));
+        let mut sources: Vec<&str> = lists
+            .flatten()
+            .flat_map(|action| std::iter::once(&action.fn_code).chain(action.param_strings.iter()))
+            .map(String::as_str)
+            .collect();
+        sources.extend(assets.scripts.iter().flatten().map(|s| s.source.as_str()));
+        sources.extend(assets.triggers.iter().flatten().map(|t| t.condition.as_str()));
+        for room in assets.rooms.iter().flatten() {
+            sources.push(&room.creation_code);
+            sources.extend(room.instances.iter().map(|i| i.creation_code.as_str()));
+        }
+        sources.iter().for_each(|source| compiler.declare_globalvars(source));
+        compiler
+    }
+
+    /// Compiles all of a game's code, collecting every compile error found in it.
+    #[cfg(feature = "gm8exe")]
+    pub fn compile_code(assets: &GameAssets) -> GameCode {
+        let mut compiler = Self::make_compiler(assets);
+        let mut diagnostics = Diagnostics::default();
+        let object_names = assets.objects.iter().map(|o| o.as_ref().map(|b| b.name.clone())).collect::<Vec<_>>();
+        let object_events = assets
+            .objects
+            .iter()
+            .map(|o| {
+                o.as_ref().map(|b| {
+                    let mut events: ObjectEvents = Default::default();
+                    for ((i, map), input) in events.iter_mut().enumerate().zip(b.events.iter()) {
+                        map.reserve(input.len());
+                        for (sub, actions) in input {
+                            diagnostics.context = event_context(&object_names, &b.name, i, *sub);
+                            map.insert(*sub, Rc::new(Tree::from_list(actions, &mut compiler, &mut diagnostics)));
+                        }
+                    }
+                    events
+                })
+            })
+            .collect();
+        let timeline_moments = assets
+            .timelines
+            .iter()
+            .map(|t| {
+                t.as_ref().map(|b| {
+                    let mut moments: HashMap<u32, Tree> = HashMap::with_capacity(b.moments.len());
+                    for (moment, actions) in b.moments.iter() {
+                        diagnostics.context = format!("moment {} of timeline {}", moment, b.name);
+                        moments.insert(*moment, Tree::from_list(actions, &mut compiler, &mut diagnostics));
+                    }
+                    moments
+                })
+            })
+            .collect();
+        let scripts = assets
+            .scripts
+            .iter()
+            .map(|s| {
+                s.as_ref().map(|b| {
+                    diagnostics.context = format!("script {}", b.name);
+                    compiler.compile_reported(&b.source, &mut diagnostics)
+                })
+            })
+            .collect();
+        let room_code = assets
+            .rooms
+            .iter()
+            .map(|r| {
+                r.as_ref().map(|b| {
+                    diagnostics.context = format!("creation code of room {}", b.name);
+                    let creation_code = compiler.compile_reported(&b.creation_code, &mut diagnostics);
+                    let instances = b
+                        .instances
+                        .iter()
+                        .map(|i| {
+                            diagnostics.context = format!("creation code of instance {}", i.id);
+                            compiler.compile_reported(&i.creation_code, &mut diagnostics)
+                        })
+                        .collect();
+                    RoomCode { creation_code, instances }
+                })
+            })
+            .collect();
+        GameCode { compiler, diagnostics, object_events, timeline_moments, scripts, room_code }
+    }
+
+    #[cfg(feature = "window")]
+    pub fn launch(
+        assets: GameAssets,
+        program_directory: PathBuf,
+        registry_import: Option<PathBuf>,
+    ) -> Result<(Self, WindowEvents), Box<dyn std::error::Error>> {
+        // Compile all the game's code
+        let GameCode { compiler, diagnostics, object_events, timeline_moments, scripts: compiled_scripts, room_code } =
+            Self::compile_code(&assets);
+
+        // destructure assets
+        let GameAssets {
+            backgrounds,
+            fonts,
+            game_id,
+            icon_data,
+            last_instance_id,
+            objects,
+            paths,
+            room_order,
+            rooms,
+            scripts,
+            settings,
+            sprites,
+            timelines,
+            ..
+        } = assets;
+
+        // If there are no rooms, you can't build a GM8 game. Fatal error.
+        // We need a lot of the initialization info from the first room,
+        // the window size, and title, etc. is based on it.
+        let room1_id = *room_order.first().ok_or("Room order is empty")?;
+        let room1 = match rooms.get(room1_id as usize) {
+            Some(Some(r)) => r,
+            _ => return Err("First room does not exist".into()),
+        };
+        let room1_width = room1.width;
+        let room1_height = room1.height;
+
         // Set up a Renderer
         let options = RendererOptions {
             title: &room1.caption,
@@ -311,28 +503,14 @@ impl Game {
             let mut object_parents: Vec<Option<i32>> = Vec::with_capacity(objects.len());
             let mut objects = objects
                 .into_iter()
-                .map(|o| {
+                .zip(object_events)
+                .map(|(o, events)| {
                     object_parents.push(match &o {
                         Some(b) => Some(b.parent_index),
                         None => None,
                     });
-                    o.map(|b| {
-                        let mut events: [HashMap<u32, Tree>; 12] = std::default::Default::default();
-                        for ((i, map), input) in events.iter_mut().enumerate().zip(b.events.iter()) {
-                            map.reserve(input.len());
-                            for (sub, actions) in input {
-                                map.insert(*sub, match Tree::from_list(actions, &mut compiler) {
-                                    Ok(t) => t,
-                                    Err(e) => {
-                                        return Err(format!(
-                                            "Compiler error in object {} event {},{}: {}",
-                                            b.name, i, sub, e
-                                        ))
-                                    },
-                                });
-                            }
-                        }
-                        Ok(Box::new(Object {
+                    o.zip(events).map(|(b, events)| {
+                        Box::new(Object {
                             name: b.name,
                             solid: b.solid,
                             visible: b.visible,
@@ -344,11 +522,10 @@ impl Game {
                             events,
                             identities: Rc::new(RefCell::new(HashSet::new())),
                             children: Rc::new(RefCell::new(HashSet::new())),
-                        }))
+                        })
                     })
-                    .transpose()
                 })
-                .collect::<Result<Vec<_>, _>>()?;
+                .collect::<Vec<_>>();
 
             // Populate identity lists
             for (i, object) in objects.iter_mut().enumerate().filter_map(|(i, x)| x.as_mut().map(|x| (i, x))) {
@@ -390,48 +567,42 @@ impl Game {
 
         let timelines = timelines
             .into_iter()
-            .map(|t| {
-                t.map(|b| {
-                    let mut moments: HashMap<u32, Tree> = HashMap::with_capacity(b.moments.len());
-                    for (moment, actions) in b.moments.iter() {
-                        match Tree::from_list(actions, &mut compiler) {
-                            Ok(t) => {
-                                moments.insert(*moment, t);
-                            },
-                            Err(e) => {
-                                return Err(format!("Compiler error in timeline {} moment {}: {}", b.name, moment, e))
-                            },
-                        };
-                    }
-                    Ok(Box::new(Timeline { name: b.name, moments }))
+            .zip(timeline_moments)
+            .map(|(t, moments)| t.zip(moments).map(|(b, moments)| Box::new(Timeline { name: b.name, moments })))
+            .collect();
+
+        let paths = paths
+            .into_iter()
+            .map(|p| {
+                p.map(|b| {
+                    let mut path = Path::new(b.name);
+                    path.points =
+                        b.points.into_iter().map(|p| path::Point { x: p.x, y: p.y, speed: p.speed }).collect();
+                    path.smooth = matches!(b.connection, gm8exe::asset::path::ConnectionKind::SmoothCurve);
+                    path.closed = b.closed;
+                    path.precision = b.precision;
+                    path.update();
+                    Box::new(path)
                 })
-                .transpose()
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect();
 
         let scripts = scripts
             .into_iter()
-            .map(|t| {
-                t.map(|b| {
-                    let compiled = match compiler.compile(&b.source) {
-                        Ok(s) => s,
-                        Err(e) => return Err(format!("Compiler error in script {}: {}", b.name, e)),
-                    };
-                    Ok(Box::new(Script { name: b.name, source: b.source, compiled }))
+            .zip(compiled_scripts.iter())
+            .map(|(t, compiled)| {
+                t.zip(compiled.as_ref()).map(|(b, compiled)| {
+                    Box::new(Script { name: b.name, source: b.source, bytecode: vm::Code::new(compiled) })
                 })
-                .transpose()
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect();
 
         let rooms = rooms
             .into_iter()
-            .map(|t| {
-                t.map(|b| {
-                    let creation_code = match compiler.compile(&b.creation_code) {
-                        Ok(c) => c,
-                        Err(e) => return Err(format!("Compiler error in room {} creation code: {}", b.name, e)),
-                    };
-                    Ok(Box::new(Room {
+            .zip(room_code)
+            .map(|(t, code)| {
+                t.zip(code).map(|(b, RoomCode { creation_code, instances: instance_code })| {
+                    Box::new(Room {
                         name: b.name,
                         caption: b.caption,
                         width: b.width,
@@ -471,6 +642,7 @@ impl Game {
                                 port_y: v.port_y,
                                 port_w: v.port_w,
                                 port_h: v.port_h,
+                                angle: Cell::new(0.0),
                                 follow_target: v.following.target,
                                 follow_hborder: v.following.hborder,
                                 follow_vborder: v.following.vborder,
@@ -481,24 +653,15 @@ impl Game {
                         instances: b
                             .instances
                             .into_iter()
-                            .map(|i| {
-                                Ok(room::Instance {
-                                    x: i.x,
-                                    y: i.y,
-                                    object: i.object,
-                                    id: i.id as usize,
-                                    creation: match compiler.compile(&i.creation_code) {
-                                        Ok(c) => c,
-                                        Err(e) => {
-                                            return Err(format!(
-                                                "Compiler error in creation code of instance {}: {}",
-                                                i.id, e
-                                            ))
-                                        },
-                                    },
-                                })
+                            .zip(instance_code)
+                            .map(|(i, creation)| room::Instance {
+                                x: i.x,
+                                y: i.y,
+                                object: i.object,
+                                id: i.id as usize,
+                                creation,
                             })
-                            .collect::<Result<Vec<_>, _>>()?,
+                            .collect(),
                         tiles: b
                             .tiles
                             .into_iter()
@@ -519,11 +682,15 @@ impl Game {
                                 visible: true,
                             })
                             .collect(),
-                    }))
+                    })
                 })
-                .transpose()
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect();
+
+        // Code which doesn't compile only raises its error if it's run, but every error is reported up front
+        for error in diagnostics.errors.iter() {
+            eprintln!("{}\n", error);
+        }
 
         // Make event holder lists
         let mut event_holders: [IndexMap<u32, Rc<RefCell<Vec<i32>>>>; 12] = Default::default();
@@ -585,32 +752,67 @@ impl Game {
             }
         }
 
+        let blank_texture = atlases.texture(1, 1, 0, 0, Box::new([0xFF; 4])).unwrap();
         renderer.upload_atlases(atlases)?;
 
+        // Load the emulated registry, merging in a .reg file if one was given to reproduce someone's setup
+        let sandbox = Sandbox::new(program_directory);
+        let mut registry = Registry::open(registry::default_path())?;
+        if let Some(path) = registry_import {
+            registry.import(&registry::decode(&fs::read(&path)?))?;
+            registry.save()?;
+        }
+
+        let joystick_backend = Box::new(joystick::GlfwBackend::new(glfw.clone()));
         let mut game = Self {
             compiler,
-            glfw,
-            glfw_events: events,
             instance_list: InstanceList::new(),
             tile_list: TileList::new(),
             rand: Random::new(),
             renderer: Box::new(renderer),
-            assets: Assets { backgrounds, fonts, objects, rooms, scripts, sprites, timelines },
+            assets: Assets { backgrounds, fonts, objects, paths, rooms, scripts: Rc::new(scripts), sprites, timelines },
+            blank_texture,
             event_holders,
+            input: Input::new(),
+            joystick_backend,
+            last_instance_id,
             room_id: room1_id,
             room_width: room1_width as i32,
             room_height: room1_height as i32,
+            views_enabled: false,
+            views: Vec::new(),
             globals: DummyFieldHolder::new(),
+            game_id,
+            clock: Box::new(SystemClock::new()),
+            sandbox,
+            open_ini: None,
+            registry,
+            registry_root: registry::HKey::CurrentUser,
+            mp_grids: Vec::new(),
+            mp_potential: Default::default(),
             uninit_fields_are_zero: settings.zero_uninitialized_vars,
             uninit_args_are_zero: !settings.error_on_uninitialized_args,
+            esc_close_game: settings.esc_close_game,
+            error_mode: if settings.abort_on_error {
+                ErrorMode::Abort
+            } else if settings.display_errors {
+                ErrorMode::Display
+            } else {
+                ErrorMode::Ignore
+            },
+            error_occurred: Cell::new(false),
+            error_last: RefCell::new(String::new()),
+            error_log: settings.write_to_log,
+            stack: Vec::new(),
         };
 
+        game.fold_constant_calls(compiled_scripts);
         game.load_room(room1_id)?;
 
         // Important: show window
         game.renderer.show_window();
 
-        Ok(game)
+        Ok((game, WindowEvents { glfw, receiver: events }))
     }
 
     pub fn load_room(&mut self, room_id: i32) -> Result<(), Box<dyn std::error::Error>> {
@@ -632,10 +834,426 @@ impl Game {
             for tile in room.tiles.iter() {
                 self.tile_list.insert(*tile);
             }
+            self.views_enabled = room.views_enabled;
+            self.views = room.views.clone();
             self.renderer.set_background_colour(if room.clear_screen { Some(room.bg_colour) } else { None });
             Ok(())
         } else {
             Err(format!("Tried to load non-existent room with id {}", room_id).into())
         }
     }
+
+    /// Works out calls to constant functions in all of the game's code wherever their arguments are known,
+    /// which can't be done while compiling as they're called on the game. See compiler::fold_functions.
+    /// Scripts only keep their bytecode, so they're folded from the instructions it was built from.
+    pub fn fold_constant_calls(&mut self, mut compiled_scripts: Vec<Option<Rc<[Instruction]>>>) {
+        // The code is taken out of the game while it's being changed, as the functions are called on the game
+        let mut scripts = mem::take(&mut self.assets.scripts);
+        let mut objects = mem::take(&mut self.assets.objects);
+        let mut rooms = mem::take(&mut self.assets.rooms);
+        let mut timelines = mem::take(&mut self.assets.timelines);
+        let mut context = Context::new(0, 0);
+
+        // Some constant functions are still unimplemented and panic when they're called. That shouldn't stop the
+        // game from starting, so a panic is quietly treated as an error and the call is left for when it's run.
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| ()));
+        let mut call = |index: usize, args: &[Value]| {
+            let (name, function, _) = mappings::FUNCTIONS[index];
+            panic::catch_unwind(AssertUnwindSafe(|| function(self, &mut context, args)))
+                .unwrap_or_else(|_| Err(gml::Error::FunctionError(name.into(), "not implemented".into())))
+        };
+
+        let compiled = compiled_scripts.iter_mut().map(|code| code.as_mut().and_then(Rc::get_mut));
+        for (script, code) in Rc::get_mut(&mut scripts).into_iter().flatten().zip(compiled) {
+            if let (Some(script), Some(code)) = (script, code) {
+                if compiler::fold_functions(code, &mut call) {
+                    script.bytecode = vm::Code::new(code);
+                }
+            }
+        }
+        for object in objects.iter_mut().flatten() {
+            for tree in object.events.iter_mut().flat_map(|events| events.values_mut()) {
+                if let Some(tree) = Rc::get_mut(tree) {
+                    tree.fold_functions(&mut call);
+                }
+            }
+        }
+        for timeline in timelines.iter_mut().flatten() {
+            timeline.moments.values_mut().for_each(|tree| tree.fold_functions(&mut call));
+        }
+        for room in rooms.iter_mut().flatten() {
+            let instances = room.instances.iter_mut().map(|instance| &mut instance.creation);
+            for code in Some(&mut room.creation_code).into_iter().chain(instances) {
+                if let Some(code) = Rc::get_mut(code) {
+                    compiler::fold_functions(code, &mut call);
+                }
+            }
+        }
+        panic::set_hook(hook);
+
+        self.assets.scripts = scripts;
+        self.assets.objects = objects;
+        self.assets.rooms = rooms;
+        self.assets.timelines = timelines;
+    }
+
+    /// Forgets all global variables, as restarting the game does.
+    pub fn reset_globals(&mut self) {
+        self.globals = DummyFieldHolder::new();
+    }
+
+    /// Runs an event for an instance. If its object doesn't have a handler for this event, the handler is
+    /// inherited from the nearest parent which does. Does nothing if none of them have one.
+    pub fn run_instance_event(
+        &mut self,
+        event_id: usize,
+        event_number: u32,
+        instance: usize,
+        other: usize,
+    ) -> gml::Result<()> {
+        let mut object_index = match self.instance_list.get(instance) {
+            Some(instance) => instance.object_index.get(),
+            None => return Ok(()),
+        };
+        let tree = loop {
+            let object = match self.assets.objects.get(object_index as usize) {
+                Some(Some(object)) => object,
+                _ => return Ok(()),
+            };
+            if let Some(tree) = object.events[event_id].get(&event_number) {
+                break tree.clone()
+            }
+            object_index = object.parent_index;
+        };
+
+        let mut context = Context {
+            event_type: event_id,
+            event_number: event_number as usize,
+            event_object: object_index as u32,
+            ..Context::new(instance, other)
+        };
+        self.execute_tree(&tree, &mut context).map(|_| ())
+    }
+
+    /// Runs an event for every instance of every object which has a handler for it, one object at a time.
+    pub fn run_object_event(&mut self, event_id: usize, event_number: u32) -> gml::Result<()> {
+        let objects = match self.event_holders[event_id].get(&event_number) {
+            Some(objects) => objects.borrow().clone(),
+            None => return Ok(()),
+        };
+        for object_id in objects {
+            let mut iter = self.instance_list.iter_by_object(object_id);
+            while let Some(idx) = iter.next(&self.instance_list) {
+                self.run_instance_event(event_id, event_number, idx, idx)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the keyboard, key press or key release events for every key which is in that state.
+    /// The vk_nokey and vk_anykey events come first, followed by each key in order of its code.
+    pub fn run_keyboard_events(&mut self, event_id: usize) -> gml::Result<()> {
+        let check = match event_id {
+            ev::KEYPRESS => Input::keyboard_check_pressed,
+            ev::KEYRELEASE => Input::keyboard_check_released,
+            _ => Input::keyboard_check,
+        };
+        let keys = self.event_holders[event_id]
+            .keys()
+            .copied()
+            .filter(|&key| check(&self.input, key as i32))
+            .collect::<Vec<_>>();
+        for key in keys {
+            self.run_object_event(event_id, key)?;
+        }
+        Ok(())
+    }
+
+    /// Works out where the mouse is in the room from where it is in the window. With views enabled, this goes
+    /// through the first visible view whose port the mouse is in, or the first visible view if it isn't in any.
+    pub fn update_mouse_position(&mut self) {
+        let (x, y) = (self.input.mouse_window_x, self.input.mouse_window_y);
+        let mut views = self.views.iter().filter(|view| view.visible);
+        let (room_x, room_y) = match views.clone().find(|view| view.port_contains(x, y)).or_else(|| views.next()) {
+            Some(view) if self.views_enabled => view.port_to_room(x, y),
+            _ => (x, y),
+        };
+        self.input.mouse_previous = (self.input.mouse_x, self.input.mouse_y);
+        self.input.mouse_x = room_x.floor() as i32;
+        self.input.mouse_y = room_y.floor() as i32;
+    }
+
+    /// Reads the state of the joysticks for this step.
+    pub fn update_joysticks(&mut self) {
+        self.input.joysticks = self.joystick_backend.poll();
+    }
+
+    /// Runs the mouse events, in order of their sub-event numbers. The button and enter/leave events only happen
+    /// to instances which are under the mouse, using precise collision checking, but the rest happen to everything.
+    pub fn run_mouse_events(&mut self) -> gml::Result<()> {
+        let (x, y) = (self.input.mouse_x, self.input.mouse_y);
+        let (previous_x, previous_y) = self.input.mouse_previous;
+        let subevents = self.event_holders[ev::MOUSE].keys().copied().collect::<Vec<_>>();
+        for subevent in subevents {
+            let button = |first| subevent as i32 - first as i32 + input::MB_LEFT;
+            let active = match subevent {
+                ev_mouse::LEFT_BUTTON..=ev_mouse::MIDDLE_BUTTON => {
+                    self.input.mouse_check_button(button(ev_mouse::LEFT_BUTTON))
+                },
+                ev_mouse::NO_BUTTON => self.input.mouse_check_button(input::MB_NONE),
+                ev_mouse::LEFT_PRESS..=ev_mouse::MIDDLE_PRESS => {
+                    self.input.mouse_check_button_pressed(button(ev_mouse::LEFT_PRESS))
+                },
+                ev_mouse::LEFT_RELEASE..=ev_mouse::MIDDLE_RELEASE => {
+                    self.input.mouse_check_button_released(button(ev_mouse::LEFT_RELEASE))
+                },
+                ev_mouse::MOUSE_ENTER | ev_mouse::MOUSE_LEAVE => true,
+                ev_mouse::JOYSTICK1_LEFT..=ev_mouse::JOYSTICK2_BUTTON8 => {
+                    joystick::event_active(subevent, &self.input.joysticks)
+                },
+                ev_mouse::GLOBAL_LEFT_BUTTON..=ev_mouse::GLOBAL_MIDDLE_BUTTON => {
+                    self.input.mouse_check_button(button(ev_mouse::GLOBAL_LEFT_BUTTON))
+                },
+                ev_mouse::GLOBAL_LEFT_PRESS..=ev_mouse::GLOBAL_MIDDLE_PRESS => {
+                    self.input.mouse_check_button_pressed(button(ev_mouse::GLOBAL_LEFT_PRESS))
+                },
+                ev_mouse::GLOBAL_LEFT_RELEASE..=ev_mouse::GLOBAL_MIDDLE_RELEASE => {
+                    self.input.mouse_check_button_released(button(ev_mouse::GLOBAL_LEFT_RELEASE))
+                },
+                ev_mouse::WHEEL_UP => self.input.mouse_wheel_up(),
+                ev_mouse::WHEEL_DOWN => self.input.mouse_wheel_down(),
+                _ => false,
+            };
+            if !active {
+                continue
+            }
+            if subevent > ev_mouse::MOUSE_LEAVE {
+                self.run_object_event(ev::MOUSE, subevent)?;
+                continue
+            }
+
+            let objects = self.event_holders[ev::MOUSE][&subevent].borrow().clone();
+            for object_id in objects {
+                let mut iter = self.instance_list.iter_by_object(object_id);
+                while let Some(idx) = iter.next(&self.instance_list) {
+                    let over = self.check_collision_point(idx, x, y);
+                    let run = match subevent {
+                        ev_mouse::MOUSE_ENTER => over && !self.check_collision_point(idx, previous_x, previous_y),
+                        ev_mouse::MOUSE_LEAVE => !over && self.check_collision_point(idx, previous_x, previous_y),
+                        _ => over,
+                    };
+                    if run {
+                        self.run_instance_event(ev::MOUSE, subevent, idx, idx)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves every instance by one step of its motion, after applying friction and gravity.
+    /// xprevious and yprevious are updated first, so they keep the position from the start of the step.
+    pub fn update_motion(&mut self) -> gml::Result<()> {
+        let mut iter = self.instance_list.iter_inserted();
+        while let Some(idx) = iter.next(&self.instance_list) {
+            if self.instance_list.exists(idx) {
+                let instance = self.instance_list.get(idx).unwrap();
+                instance.update_previous();
+                instance.apply_motion();
+                if instance.path_index.get() >= 0 {
+                    self.update_path(idx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves an instance along the path it's following, running its end of path event if it reaches the end.
+    fn update_path(&mut self, idx: usize) -> gml::Result<()> {
+        let instance = self.instance_list.get(idx).unwrap();
+        let path = match self.assets.paths.get(instance.path_index.get() as usize) {
+            Some(Some(path)) => path,
+            _ => return Ok(()),
+        };
+
+        // The speed at each point of the path is a percentage of path_speed
+        let length = path.length() * instance.path_scale.get();
+        let mut position = instance.path_position.get();
+        let speed = instance.path_speed.get() * path.get_point(position).speed / 100.0;
+        if length > 0.0 {
+            position += speed / length;
+        } else if speed != 0.0 {
+            position = if speed > 0.0 { 1.0 } else { 0.0 };
+        }
+
+        let ended = (speed > 0.0 && position >= 1.0) || (speed < 0.0 && position <= 0.0);
+        if ended {
+            // How far past the end the instance has gone, and where that would be from the other end
+            let overshoot = if speed > 0.0 { position - 1.0 } else { position };
+            let wrapped = if speed > 0.0 { overshoot } else { 1.0 + overshoot };
+            match instance.path_endaction.get() {
+                // Continue from the start
+                1 => position = wrapped,
+                // Continue from the current position, moving the path so it starts where the instance is
+                2 => {
+                    let (start_x, start_y) = instance.path_coords(path, 0.0);
+                    let (end_x, end_y) = instance.path_coords(path, 1.0);
+                    let sign = if speed > 0.0 { 1.0 } else { -1.0 };
+                    instance.path_xstart.set(instance.path_xstart.get() + (end_x - start_x) * sign);
+                    instance.path_ystart.set(instance.path_ystart.get() + (end_y - start_y) * sign);
+                    position = wrapped;
+                },
+                // Reverse
+                3 => {
+                    position = if speed > 0.0 { 1.0 - overshoot } else { -overshoot };
+                    instance.path_speed.set(-instance.path_speed.get());
+                },
+                // Stop
+                _ => position = if speed > 0.0 { 1.0 } else { 0.0 },
+            }
+        }
+
+        instance.path_position.set(position);
+        let (x, y) = instance.path_coords(path, position);
+        instance.set_position(x, y);
+        if ended {
+            if instance.path_endaction.get() == 0 {
+                instance.path_index.set(-1);
+            }
+            self.run_instance_event(ev::OTHER, ev_other::END_OF_PATH, idx, idx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes an object's event for compile errors, such as "Step Event of object obj_player".
+fn event_context(object_names: &[Option<String>], object: &str, event_type: usize, event_number: u32) -> String {
+    let collision_object = match object_names.get(event_number as usize) {
+        Some(Some(name)) if event_type == ev::COLLISION => Some(name.as_str()),
+        _ => None,
+    };
+    format!("{} of object {}", event_name(event_type, event_number, collision_object), object)
+}
+
+#[cfg(test)]
+impl Game {
+    /// Makes an empty game with no window and no rooms, for testing the runtime. Its only object is object0.
+    /// The sandbox is in a directory which doesn't exist, so anything using files should replace it.
+    /// The clock is stopped at 2009-07-04 15:04:05.
+    pub fn test() -> Self {
+        use crate::gml::datetime::{DateTime, FixedClock};
+        let object = Object {
+            name: "object0".into(),
+            solid: false,
+            visible: true,
+            persistent: false,
+            depth: 0,
+            sprite_index: -1,
+            mask_index: -1,
+            parent_index: -1,
+            events: Default::default(),
+            identities: Rc::new(RefCell::new(Some(0).into_iter().collect())),
+            children: Rc::new(RefCell::new(Some(0).into_iter().collect())),
+        };
+        let sandbox = Sandbox::new(std::env::temp_dir().join("gm8emulator-test-nonexistent"));
+        let registry = Registry::open(sandbox.root().join("registry.reg")).unwrap();
+        let mut compiler = Compiler::new();
+        compiler.register_constant(object.name.clone(), 0.0);
+        Self {
+            compiler,
+            instance_list: InstanceList::new(),
+            tile_list: TileList::new(),
+            rand: Random::new(),
+            renderer: Box::new(crate::render::NullRenderer),
+            assets: Assets {
+                backgrounds: Vec::new(),
+                fonts: Vec::new(),
+                objects: vec![Some(Box::new(object))],
+                paths: Vec::new(),
+                rooms: Vec::new(),
+                scripts: Rc::new(Vec::new()),
+                sprites: Vec::new(),
+                timelines: Vec::new(),
+            },
+            blank_texture: AtlasRef { atlas_id: 0, x: 0, y: 0, w: 1, h: 1, origin_x: 0.0, origin_y: 0.0 },
+            event_holders: Default::default(),
+            input: Input::new(),
+            joystick_backend: Box::new(joystick::ScriptedBackend::new()),
+            last_instance_id: 100000,
+            room_id: 0,
+            room_width: 640,
+            room_height: 480,
+            views_enabled: false,
+            views: Vec::new(),
+            globals: DummyFieldHolder::new(),
+            game_id: 0,
+            clock: Box::new(FixedClock::new(DateTime::combine(
+                DateTime::from_date(2009, 7, 4).unwrap(),
+                DateTime::from_time(15, 4, 5).unwrap(),
+            ))),
+            sandbox,
+            open_ini: None,
+            registry,
+            registry_root: registry::HKey::CurrentUser,
+            mp_grids: Vec::new(),
+            mp_potential: Default::default(),
+            uninit_fields_are_zero: false,
+            uninit_args_are_zero: false,
+            esc_close_game: true,
+            error_mode: ErrorMode::Abort,
+            error_occurred: Cell::new(false),
+            error_last: RefCell::new(String::new()),
+            error_log: false,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Creates an instance of object0 at the given position without running its create event.
+    pub fn test_instance(&mut self, x: f64, y: f64) -> usize {
+        self.last_instance_id += 1;
+        let object = self.assets.objects[0].as_ref().unwrap();
+        self.instance_list.insert(Instance::new(self.last_instance_id as _, x, y, 0, object))
+    }
+
+    /// Adds a script to the game's assets and registers its name with the compiler, returning its index.
+    pub fn test_script(&mut self, name: &str, source: &str) -> usize {
+        let bytecode = vm::Code::new(&self.compiler.compile(source).unwrap());
+        let index = self.assets.scripts.len();
+        let script = Script { name: name.into(), source: source.into(), bytecode };
+        Rc::get_mut(&mut self.assets.scripts).unwrap().push(Some(Box::new(script)));
+        self.compiler.register_script(name.into(), index);
+        index
+    }
+
+    /// Makes a context for running code as the given instance, outside of any event or script.
+    pub fn test_context(this: usize) -> Context {
+        Context::new(this, this)
+    }
+
+    /// Compiles and runs some code as bytecode as the given instance, returning what it exits with.
+    pub fn test_run(&mut self, this: usize, code: &str) -> gml::Result<gml::Value> {
+        let instructions = self.compiler.compile(code).map_err(|e| gml::Error::CompileError(e.to_string()))?;
+        let mut context = Self::test_context(this);
+        self.run(&vm::Code::new(&instructions), &mut context)?;
+        Ok(context.return_value)
+    }
+
+    /// Runs some code as the given instance and checks that it returns the given real.
+    #[track_caller]
+    pub fn assert_returns(&mut self, this: usize, code: &str, expected: f64) {
+        match self.test_run(this, code) {
+            Ok(gml::Value::Real(value)) => assert!((value - expected).abs() < 1e-6, "{} gave {}", code, value),
+            other => panic!("{} gave {:?}", code, other),
+        }
+    }
+
+    /// Runs some code as the given instance and checks that it fails with an error mentioning the given message.
+    #[track_caller]
+    pub fn assert_errors(&mut self, this: usize, code: &str, message: &str) {
+        match self.test_run(this, code) {
+            Err(error) => assert!(error.to_string().contains(message), "{} gave {}", code, error),
+            Ok(value) => panic!("{} returned {:?}", code, value),
+        }
+    }
 }
@@ -1,9 +1,15 @@
 pub mod compiler;
 pub mod context;
+pub mod datetime;
+pub mod decompiler;
+pub mod ini;
 pub mod kernel;
+pub mod mp;
 pub mod rand;
+pub mod registry;
 pub mod runtime;
 pub mod value;
+pub mod vm;
 
 pub use compiler::Compiler;
 pub use context::Context;
@@ -22,6 +28,12 @@ pub const NOONE: i32 = -4;
 pub const GLOBAL: i32 = -5;
 pub const LOCAL: i32 = -7;
 
+/// The most arguments a function or script can be called with.
+pub const MAX_ARGUMENTS: usize = 16;
+
+/// Instance ids start from here. Any lower non-negative number refers to an object.
+pub const FIRST_INSTANCE_ID: i32 = 100000;
+
 pub mod ev {
     pub const CREATE: usize = 0;
     pub const DESTROY: usize = 1;
@@ -37,6 +49,60 @@ pub mod ev {
     pub const TRIGGER: usize = 11;
 }
 
+/// Sub-events of the "other" event type
+pub mod ev_other {
+    pub const OUTSIDE: u32 = 0;
+    pub const BOUNDARY: u32 = 1;
+    pub const GAME_START: u32 = 2;
+    pub const GAME_END: u32 = 3;
+    pub const ROOM_START: u32 = 4;
+    pub const ROOM_END: u32 = 5;
+    pub const NO_MORE_LIVES: u32 = 6;
+    pub const ANIMATION_END: u32 = 7;
+    pub const END_OF_PATH: u32 = 8;
+    pub const NO_MORE_HEALTH: u32 = 9;
+    pub const USER0: u32 = 10;
+}
+
+/// Sub-events of the mouse event type
+pub mod ev_mouse {
+    pub const LEFT_BUTTON: u32 = 0;
+    pub const RIGHT_BUTTON: u32 = 1;
+    pub const MIDDLE_BUTTON: u32 = 2;
+    pub const NO_BUTTON: u32 = 3;
+    pub const LEFT_PRESS: u32 = 4;
+    pub const RIGHT_PRESS: u32 = 5;
+    pub const MIDDLE_PRESS: u32 = 6;
+    pub const LEFT_RELEASE: u32 = 7;
+    pub const RIGHT_RELEASE: u32 = 8;
+    pub const MIDDLE_RELEASE: u32 = 9;
+    pub const MOUSE_ENTER: u32 = 10;
+    pub const MOUSE_LEAVE: u32 = 11;
+    pub const JOYSTICK1_LEFT: u32 = 16;
+    pub const JOYSTICK1_RIGHT: u32 = 17;
+    pub const JOYSTICK1_UP: u32 = 18;
+    pub const JOYSTICK1_DOWN: u32 = 19;
+    pub const JOYSTICK1_BUTTON1: u32 = 21;
+    pub const JOYSTICK1_BUTTON8: u32 = 28;
+    pub const JOYSTICK2_LEFT: u32 = 31;
+    pub const JOYSTICK2_RIGHT: u32 = 32;
+    pub const JOYSTICK2_UP: u32 = 33;
+    pub const JOYSTICK2_DOWN: u32 = 34;
+    pub const JOYSTICK2_BUTTON1: u32 = 36;
+    pub const JOYSTICK2_BUTTON8: u32 = 43;
+    pub const GLOBAL_LEFT_BUTTON: u32 = 50;
+    pub const GLOBAL_RIGHT_BUTTON: u32 = 51;
+    pub const GLOBAL_MIDDLE_BUTTON: u32 = 52;
+    pub const GLOBAL_LEFT_PRESS: u32 = 53;
+    pub const GLOBAL_RIGHT_PRESS: u32 = 54;
+    pub const GLOBAL_MIDDLE_PRESS: u32 = 55;
+    pub const GLOBAL_LEFT_RELEASE: u32 = 56;
+    pub const GLOBAL_RIGHT_RELEASE: u32 = 57;
+    pub const GLOBAL_MIDDLE_RELEASE: u32 = 58;
+    pub const WHEEL_UP: u32 = 60;
+    pub const WHEEL_DOWN: u32 = 61;
+}
+
 /// Enum for each instance variable
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum InstanceVariable {
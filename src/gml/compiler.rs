@@ -8,11 +8,12 @@ use super::{
         ArrayAccessor, ModificationType, FieldAccessor, InstanceIdentifier, Instruction, Node, ReturnType,
         VariableAccessor,
     },
-    Value,
+    vm, Value,
 };
 use crate::gml;
-use std::{collections::HashMap, rc::Rc};
-use token::Operator;
+use std::{collections::{HashMap, HashSet}, fmt, rc::Rc};
+use lexer::Lexer;
+use token::{Keyword, Operator, Separator, Token};
 
 pub struct Compiler {
     /// List of identifiers which represent const values
@@ -23,12 +24,58 @@ pub struct Compiler {
 
     /// Lookup table of unique field names
     fields: Vec<String>,
+
+    /// Names declared with globalvar anywhere, which unqualified accesses in any code refer to the globals of
+    globalvars: HashSet<String>,
+
+    /// Code compiled at runtime, by its source, since games often run the same strings every step.
+    /// Each entry has when it was last used, so the least recently used one can make way when it's full.
+    cache: HashMap<Rc<str>, (Rc<vm::Code>, u64)>,
+
+    /// How many times the cache has been used, for timing its entries
+    cache_clock: u64,
+}
+
+/// How many pieces of code compiled at runtime are kept in the cache
+const CACHE_SIZE: usize = 256;
+
+/// The longest string a constant call is replaced with, so folding something like string_repeat doesn't fill
+/// the game's memory with strings it might never use
+const MAX_FOLDED_STRING: usize = 1024;
+
+/// Compile errors collected while compiling a whole game, so they can all be reported at once.
+#[derive(Default)]
+pub struct Diagnostics {
+    /// Which part of the game is being compiled, such as "script scr_init"
+    pub context: String,
+
+    /// Which action in it is being compiled, if it's made of actions, starting at 1
+    pub action: Option<usize>,
+
+    pub errors: Vec<String>,
+}
+
+impl Diagnostics {
+    /// Records an error found in the code currently being compiled.
+    pub fn report(&mut self, error: &dyn fmt::Display) {
+        self.errors.push(match self.action {
+            Some(action) => format!("COMPILATION ERROR in {}, action number {}:\n{}", self.context, action, error),
+            None => format!("COMPILATION ERROR in {}:\n{}", self.context, error),
+        });
+    }
 }
 
 impl Compiler {
     /// Create a compiler.
     pub fn new() -> Self {
-        Self { constants: HashMap::new(), script_names: HashMap::new(), fields: Vec::new() }
+        Self {
+            constants: HashMap::new(),
+            script_names: HashMap::new(),
+            fields: Vec::new(),
+            globalvars: HashSet::new(),
+            cache: HashMap::new(),
+            cache_clock: 0,
+        }
     }
 
     /// Reserve space to register at least the given number of constants.
@@ -56,9 +103,32 @@ impl Compiler {
         }
     }
 
+    /// Register the names declared with globalvar in some code, so that unqualified accesses to them in any code
+    /// compiled from then on refer to globals. A game's code should all be declared before any of it is compiled.
+    pub fn declare_globalvars(&mut self, source: &str) {
+        let mut declaring = false;
+        let mut declared = false;
+        for token in Lexer::new(source) {
+            match token {
+                Token::Keyword(Keyword::GlobalVar) => declaring = true,
+                Token::Identifier(name) if declaring => {
+                    if !mappings::INSTANCE_VARIABLES.iter().any(|(s, _)| *s == name) {
+                        declared |= self.globalvars.insert(name.into());
+                    }
+                },
+                Token::Separator(Separator::Comma) if declaring => (),
+                _ => declaring = false,
+            }
+        }
+        if declared {
+            self.cache.clear(); // anything cached may have been compiled before these were declared
+        }
+    }
+
     /// Compile a GML string into instructions.
     pub fn compile(&mut self, source: &str) -> Result<Rc<[Instruction]>, ast::Error> {
         let ast = ast::AST::new(source)?;
+        self.declare_globalvars(source);
 
         let mut instructions = Vec::new();
         let mut locals: Vec<&str> = Vec::new();
@@ -68,12 +138,51 @@ impl Compiler {
         Ok(instructions.into())
     }
 
+    /// Compile a GML string into instructions. If it doesn't compile, the error is reported and the code returned
+    /// raises it when it's run, so that one mistake doesn't stop the rest of the game from loading.
+    pub fn compile_reported(&mut self, source: &str, diagnostics: &mut Diagnostics) -> Rc<[Instruction]> {
+        match self.compile(source) {
+            Ok(instructions) => instructions,
+            Err(e) => {
+                diagnostics.report(&e);
+                Rc::new([Instruction::RuntimeError { error: gml::Error::CompileError(e.to_string()) }])
+            },
+        }
+    }
+
+    /// Compile a GML string into bytecode, reusing the result of any recent call with the same source.
+    /// Code which fails to compile isn't cached.
+    pub fn compile_cached(&mut self, source: Rc<str>) -> Result<Rc<vm::Code>, ast::Error> {
+        self.cache_clock += 1;
+        if let Some((code, last_used)) = self.cache.get_mut(&source) {
+            *last_used = self.cache_clock;
+            return Ok(code.clone())
+        }
+        let code = Rc::new(vm::Code::new(&self.compile(&source)?));
+        if self.cache.len() >= CACHE_SIZE {
+            let oldest = self.cache.iter().min_by_key(|(_, (_, last_used))| *last_used).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.cache.remove(&oldest);
+            }
+        }
+        self.cache.insert(source, (code.clone(), self.cache_clock));
+        Ok(code)
+    }
+
     /// Compile an expression into a format which can be evaluated.
     pub fn compile_expression(&mut self, source: &str) -> Result<Node, ast::Error> {
         let expr = ast::AST::expression(source)?;
         Ok(self.compile_ast_expr(&expr, &[]))
     }
 
+    /// Compile an expression as with compile_expression, reporting any error as compile_reported does.
+    pub fn compile_expression_reported(&mut self, source: &str, diagnostics: &mut Diagnostics) -> Node {
+        self.compile_expression(source).unwrap_or_else(|e| {
+            diagnostics.report(&e);
+            Node::RuntimeError { error: gml::Error::CompileError(e.to_string()) }
+        })
+    }
+
     /// Compile a single line of code from an AST expression.
     fn compile_ast_line<'a>(&mut self, line: &'a ast::Expr, output: &mut Vec<Instruction>, locals: &mut Vec<&'a str>) {
         match line {
@@ -103,8 +212,12 @@ impl Compiler {
                 let cond = self.compile_ast_expr(&for_expr.cond, locals);
                 let mut body = Vec::new();
                 self.compile_ast_line(&for_expr.body, &mut body, locals);
-                self.compile_ast_line(&for_expr.step, &mut body, locals);
-                output.push(Instruction::LoopWhile { cond, body: body.into_boxed_slice() });
+                let mut step = Vec::new();
+                self.compile_ast_line(&for_expr.step, &mut step, locals);
+                if !matches!(&cond, Node::Literal { value } if !value.is_true()) {
+                    let (body, step) = (body.into_boxed_slice(), step.into_boxed_slice());
+                    output.push(Instruction::LoopWhile { cond, body, step });
+                }
             },
 
             // Function or Script
@@ -136,11 +249,16 @@ impl Compiler {
                     if let Some(expr_else_body) = &if_expr.else_body {
                         self.compile_ast_line(expr_else_body, &mut else_body, locals);
                     }
-                    output.push(Instruction::IfElse {
-                        cond,
-                        if_body: if_body.into_boxed_slice(),
-                        else_body: else_body.into_boxed_slice(),
-                    });
+                    if if_body.is_empty() && else_body.is_empty() {
+                        // Neither branch does anything, but the condition might
+                        output.push(Instruction::EvalExpression { node: cond });
+                    } else {
+                        output.push(Instruction::IfElse {
+                            cond,
+                            if_body: if_body.into_boxed_slice(),
+                            else_body: else_body.into_boxed_slice(),
+                        });
+                    }
                 }
             },
 
@@ -149,7 +267,9 @@ impl Compiler {
                 let count = self.compile_ast_expr(&repeat_expr.count, locals);
                 let mut body = Vec::new();
                 self.compile_ast_line(&repeat_expr.body, &mut body, locals);
-                output.push(Instruction::Repeat { count, body: body.into_boxed_slice() });
+                if !matches!(&count, Node::Literal { value } if body.is_empty() || value.round() <= 0) {
+                    output.push(Instruction::Repeat { count, body: body.into_boxed_slice() });
+                }
             },
 
             // Return
@@ -162,16 +282,16 @@ impl Compiler {
             // "switch" block
             ast::Expr::Switch(switch_expr) => {
                 let input = self.compile_ast_expr(&switch_expr.input, locals);
-                if let ast::Expr::Group(group) = &switch_expr.body {
+                if let ast::Expr::Group(group) = switch_expr.body.unlocated() {
                     let mut cases = Vec::new();
                     let mut body = Vec::new();
                     let mut default: Option<usize> = None;
                     for expr in group {
-                        if let ast::Expr::Case(case_expr) = expr {
+                        if let ast::Expr::Case(case_expr) = expr.unlocated() {
                             if default.is_none() {
                                 cases.push((self.compile_ast_expr(case_expr, locals), body.len()));
                             }
-                        } else if let ast::Expr::Default = expr {
+                        } else if let ast::Expr::Default = expr.unlocated() {
                             if default.is_none() {
                                 default = Some(body.len());
                             }
@@ -197,7 +317,9 @@ impl Compiler {
                 let cond = self.compile_ast_expr(&while_expr.cond, locals);
                 let mut body = Vec::new();
                 self.compile_ast_line(&while_expr.body, &mut body, locals);
-                output.push(Instruction::LoopUntil { cond, body: body.into_boxed_slice() });
+                if !matches!(&cond, Node::Literal { value } if body.is_empty() && value.is_true()) {
+                    output.push(Instruction::LoopUntil { cond, body: body.into_boxed_slice() });
+                }
             },
 
             // "var" declaration
@@ -205,12 +327,17 @@ impl Compiler {
                 locals.extend_from_slice(&var_expr.vars);
             },
 
+            // "globalvar" declaration, which was already registered by declare_globalvars
+            ast::Expr::GlobalVar(_) => (),
+
             // "while" block
             ast::Expr::While(while_expr) => {
                 let cond = self.compile_ast_expr(&while_expr.cond, locals);
                 let mut body = Vec::new();
                 self.compile_ast_line(&while_expr.body, &mut body, locals);
-                output.push(Instruction::LoopWhile { cond, body: body.into_boxed_slice() });
+                if !matches!(&cond, Node::Literal { value } if !value.is_true()) {
+                    output.push(Instruction::LoopWhile { cond, body: body.into_boxed_slice(), step: Box::new([]) });
+                }
             },
 
             // "with" block
@@ -218,7 +345,22 @@ impl Compiler {
                 let target = self.compile_ast_expr(&with_expr.target, locals);
                 let mut body = Vec::new();
                 self.compile_ast_line(&with_expr.body, &mut body, locals);
-                output.push(Instruction::With { target, body: body.into_boxed_slice() });
+                if !(body.is_empty() && matches!(target, Node::Literal { .. })) {
+                    output.push(Instruction::With { target, body: body.into_boxed_slice() });
+                }
+            },
+
+            // Line of code along with where it starts, which is noted so errors in it can say where they happened
+            ast::Expr::Located(located) if matches!(located.expr, ast::Expr::Group(_)) => {
+                self.compile_ast_line(&located.expr, output, locals); // each line inside has its own position
+            },
+            ast::Expr::Located(located) => {
+                output.push(Instruction::SourcePosition { position: located.position });
+                let len = output.len();
+                self.compile_ast_line(&located.expr, output, locals);
+                if output.len() == len {
+                    output.pop(); // nothing to run, such as a var declaration
+                }
             },
 
             // Unknown/invalid AST
@@ -288,29 +430,11 @@ impl Compiler {
                 },
 
                 op => {
-                    let op_function = match op {
-                        Operator::Add => Value::add,
-                        Operator::And => Value::bool_and,
-                        Operator::BitwiseAnd => Value::bitand,
-                        Operator::BitwiseOr => Value::bitor,
-                        Operator::BinaryShiftLeft => Value::shl,
-                        Operator::BinaryShiftRight => Value::shr,
-                        Operator::BitwiseXor => Value::bitxor,
-                        Operator::Divide => Value::div,
-                        Operator::Equal => Value::gml_eq,
-                        Operator::GreaterThan => Value::gml_gt,
-                        Operator::GreaterThanOrEqual => Value::gml_gte,
-                        Operator::IntDivide => Value::intdiv,
-                        Operator::LessThan => Value::gml_lt,
-                        Operator::LessThanOrEqual => Value::gml_lte,
-                        Operator::Multiply => Value::mul,
-                        Operator::Modulo => Value::modulo,
-                        Operator::NotEqual => Value::gml_ne,
-                        Operator::Or => Value::bool_or,
-                        Operator::Subtract => Value::sub,
-                        Operator::Xor => Value::bool_xor,
-                        op => return Node::RuntimeError { error: gml::Error::InvalidBinaryOperator(*op) },
-                    };
+                    let (index, op_function) =
+                        match mappings::BINARY_OPERATORS.iter().enumerate().find(|(_, (o, _))| o == op) {
+                            Some((index, (_, function))) => (index, *function),
+                            None => return Node::RuntimeError { error: gml::Error::InvalidBinaryOperator(*op) },
+                        };
 
                     let left = self.compile_ast_expr(&binary_expr.left, locals);
                     let right = self.compile_ast_expr(&binary_expr.right, locals);
@@ -322,8 +446,11 @@ impl Compiler {
                                 Err(error) => Node::RuntimeError { error },
                             }
                         },
-                        (left, right) => {
-                            Node::Binary { left: Box::new(left), right: Box::new(right), operator: op_function }
+                        (left, right) => Node::Binary {
+                            left: Box::new(left),
+                            right: Box::new(right),
+                            operator: op_function,
+                            index,
                         },
                     }
                 },
@@ -341,7 +468,9 @@ impl Compiler {
                             .into_boxed_slice(),
                         script_id,
                     }
-                } else if let Some((_, f_ptr, _)) = mappings::FUNCTIONS.iter().find(|(n, _, _)| n == &function.name) {
+                } else if let Some((index, (_, f_ptr, _))) =
+                    mappings::FUNCTIONS.iter().enumerate().find(|(_, (n, _, _))| n == &function.name)
+                {
                     Node::Function {
                         args: function
                             .params
@@ -350,6 +479,7 @@ impl Compiler {
                             .collect::<Vec<_>>()
                             .into_boxed_slice(),
                         function: *f_ptr,
+                        index,
                     }
                 } else {
                     Node::RuntimeError { error: gml::Error::UnknownFunction(function.name.to_string()) }
@@ -358,19 +488,18 @@ impl Compiler {
 
             ast::Expr::Unary(unary_expr) => {
                 let new_node = self.compile_ast_expr(&unary_expr.child, locals);
-                let operator = match unary_expr.op {
-                    Operator::Add => return new_node,
-                    Operator::Subtract => Value::neg,
-                    Operator::Not => Value::not,
-                    Operator::Complement => Value::complement,
-                    _ => return Node::RuntimeError { error: gml::Error::InvalidUnaryOperator(unary_expr.op) },
-                };
+                let (index, operator) =
+                    match mappings::UNARY_OPERATORS.iter().enumerate().find(|(_, (o, _))| *o == unary_expr.op) {
+                        Some((index, (_, function))) => (index, *function),
+                        None if unary_expr.op == Operator::Add => return new_node,
+                        None => return Node::RuntimeError { error: gml::Error::InvalidUnaryOperator(unary_expr.op) },
+                    };
                 match new_node {
                     Node::Literal { value } => match operator(value) {
                         Ok(value) => Node::Literal { value },
                         Err(error) => Node::RuntimeError { error },
                     },
-                    node => Node::Unary { child: Box::new(node), operator },
+                    node => Node::Unary { child: Box::new(node), operator, index },
                 }
             },
 
@@ -379,7 +508,7 @@ impl Compiler {
     }
 
     /// Gets the unique id of a fieldname, registering one if it doesn't already exist.
-    fn get_field_id(&mut self, name: &str) -> usize {
+    pub fn get_field_id(&mut self, name: &str) -> usize {
         if let Some(i) = self.fields.iter().position(|x| x == name) {
             i
         } else {
@@ -465,6 +594,17 @@ impl Compiler {
         }
     }
 
+    /// Works out who owns an identifier which wasn't specified with an owner.
+    fn infer_owner(&self, identifier: &str, locals: &[&str]) -> InstanceIdentifier {
+        if locals.iter().any(|x| *x == identifier) {
+            InstanceIdentifier::Local
+        } else if self.globalvars.contains(identifier) {
+            InstanceIdentifier::Global
+        } else {
+            InstanceIdentifier::Own
+        }
+    }
+
     /// Converts an identifier to a Field, Variable or GameVariable accessor.
     /// If no VarOwner is provided (ie. the variable wasn't specified with one), this function will infer one.
     fn identifier_to_variable(
@@ -474,16 +614,7 @@ impl Compiler {
         array: ArrayAccessor,
         locals: &[&str],
     ) -> Node {
-        let owner = match owner {
-            Some(o) => o,
-            None => {
-                if locals.iter().any(|x| *x == identifier) {
-                    InstanceIdentifier::Local
-                } else {
-                    InstanceIdentifier::Own
-                }
-            },
-        };
+        let owner = owner.unwrap_or_else(|| self.infer_owner(identifier, locals));
 
         if let Some(var) = mappings::INSTANCE_VARIABLES.iter().find(|(s, _)| *s == identifier).map(|(_, v)| v) {
             Node::Variable { accessor: VariableAccessor { var: *var, array, owner } }
@@ -503,16 +634,7 @@ impl Compiler {
         value: Node,
         locals: &[&str],
     ) -> Instruction {
-        let owner = match owner {
-            Some(o) => o,
-            None => {
-                if locals.iter().any(|x| *x == identifier) {
-                    InstanceIdentifier::Local
-                } else {
-                    InstanceIdentifier::Own
-                }
-            },
-        };
+        let owner = owner.unwrap_or_else(|| self.infer_owner(identifier, locals));
 
         if let Some(var) = mappings::INSTANCE_VARIABLES.iter().find(|(s, _)| *s == identifier).map(|(_, v)| v) {
             Instruction::SetVariable { accessor: VariableAccessor { var: *var, array, owner }, value }
@@ -533,16 +655,7 @@ impl Compiler {
         value: Node,
         locals: &[&str],
     ) -> Instruction {
-        let owner = match owner {
-            Some(o) => o,
-            None => {
-                if locals.iter().any(|x| *x == identifier) {
-                    InstanceIdentifier::Local
-                } else {
-                    InstanceIdentifier::Own
-                }
-            },
-        };
+        let owner = owner.unwrap_or_else(|| self.infer_owner(identifier, locals));
 
         if let Some(var) = mappings::INSTANCE_VARIABLES.iter().find(|(s, _)| *s == identifier).map(|(_, v)| v) {
             Instruction::ModifyVariable { accessor: VariableAccessor { var: *var, array, owner }, modification_type, value }
@@ -581,8 +694,259 @@ impl Compiler {
         }
     }
 
+    /// Gets the unique id of a fieldname, if it's been registered.
+    pub fn find_field_id(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|x| x == name)
+    }
+
     /// Get a field name by its ID. This clones the string; it should only be used in the case of an error.
     pub fn get_field_name(&self, id: usize) -> Option<String> {
         self.fields.get(id).map(String::clone)
     }
+
+    /// Get a script name by its ID. This searches every script, so it should only be used for debugging.
+    pub fn get_script_name(&self, id: usize) -> Option<&str> {
+        self.script_names.iter().find(|(_, &i)| i == id).map(|(name, _)| name.as_str())
+    }
+
+    /// Iterates over the constants added with register_constant, such as asset names, in no particular order.
+    pub fn constants(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.constants.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Iterates over the scripts added with register_script and their indices, in no particular order.
+    pub fn scripts(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.script_names.iter().map(|(name, &index)| (name.as_str(), index))
+    }
+}
+
+
+/// Replaces calls to constant functions whose arguments are all literals with what they return, along with any
+/// operators which then only have literals under them. Kernel functions need a game to call them with, so this is
+/// done once the game has been set up, and the given closure calls the function with the given index in
+/// mappings::FUNCTIONS. Anything which raises an error, or makes a string longer than MAX_FOLDED_STRING, is left
+/// as it is so it happens when the code runs. Returns whether anything was replaced.
+pub fn fold_functions(
+    instructions: &mut [Instruction],
+    call: &mut dyn FnMut(usize, &[Value]) -> gml::Result<Value>,
+) -> bool {
+    let mut folded = false;
+    for instruction in instructions {
+        folded |= match instruction {
+            Instruction::SetField { accessor, value } | Instruction::ModifyField { accessor, value, .. } => {
+                fold_expression(value, call) | fold_accessor(&mut accessor.owner, &mut accessor.array, call)
+            },
+            Instruction::SetVariable { accessor, value } | Instruction::ModifyVariable { accessor, value, .. } => {
+                fold_expression(value, call) | fold_accessor(&mut accessor.owner, &mut accessor.array, call)
+            },
+            Instruction::EvalExpression { node } | Instruction::SetReturnValue { value: node } => {
+                fold_expression(node, call)
+            },
+            Instruction::IfElse { cond, if_body, else_body } => {
+                fold_expression(cond, call) | fold_functions(if_body, call) | fold_functions(else_body, call)
+            },
+            Instruction::LoopWhile { cond, body, step } => {
+                fold_expression(cond, call) | fold_functions(body, call) | fold_functions(step, call)
+            },
+            Instruction::LoopUntil { cond: node, body }
+            | Instruction::Repeat { count: node, body }
+            | Instruction::With { target: node, body } => fold_expression(node, call) | fold_functions(body, call),
+            Instruction::Switch { input, cases, body, .. } => {
+                let cases = cases.iter_mut().fold(false, |folded, (cond, _)| fold_expression(cond, call) | folded);
+                fold_expression(input, call) | cases | fold_functions(body, call)
+            },
+            Instruction::Return { .. }
+            | Instruction::SourcePosition { .. }
+            | Instruction::RuntimeError { .. } => false,
+        };
+    }
+    folded
+}
+
+/// Folds the constant function calls in an expression. See fold_functions.
+pub fn fold_expression(node: &mut Node, call: &mut dyn FnMut(usize, &[Value]) -> gml::Result<Value>) -> bool {
+    let folded = match node {
+        Node::Function { args, .. } | Node::Script { args, .. } => {
+            args.iter_mut().fold(false, |folded, arg| fold_expression(arg, call) | folded)
+        },
+        Node::Field { accessor } => fold_accessor(&mut accessor.owner, &mut accessor.array, call),
+        Node::Variable { accessor } => fold_accessor(&mut accessor.owner, &mut accessor.array, call),
+        Node::Binary { left, right, .. } => fold_expression(left, call) | fold_expression(right, call),
+        Node::Unary { child, .. } => fold_expression(child, call),
+        Node::Literal { .. } | Node::RuntimeError { .. } => false,
+    };
+    let result = match node {
+        Node::Function { args, index, .. } if matches!(mappings::FUNCTIONS.get(*index), Some((_, _, true))) => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    Node::Literal { value } => Some(value.clone()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            match args {
+                Some(args) => call(*index, &args),
+                None => return folded,
+            }
+        },
+        Node::Binary { left, right, operator, .. } => match (left.as_ref(), right.as_ref()) {
+            (Node::Literal { value: lhs }, Node::Literal { value: rhs }) => operator(lhs.clone(), rhs.clone()),
+            _ => return folded,
+        },
+        Node::Unary { child, operator, .. } => match child.as_ref() {
+            Node::Literal { value } => operator(value.clone()),
+            _ => return folded,
+        },
+        _ => return folded,
+    };
+    match result {
+        Ok(Value::Str(s)) if s.len() > MAX_FOLDED_STRING => folded,
+        Ok(value) => {
+            *node = Node::Literal { value };
+            true
+        },
+        Err(_) => folded,
+    }
+}
+
+fn fold_accessor(
+    owner: &mut InstanceIdentifier,
+    array: &mut ArrayAccessor,
+    call: &mut dyn FnMut(usize, &[Value]) -> gml::Result<Value>,
+) -> bool {
+    let owner = match owner {
+        InstanceIdentifier::Expression(node) => fold_expression(node, call),
+        _ => false,
+    };
+    let array = match array {
+        ArrayAccessor::None => false,
+        ArrayAccessor::Single(index) => fold_expression(index, call),
+        ArrayAccessor::Double(index1, index2) => fold_expression(index1, call) | fold_expression(index2, call),
+    };
+    owner | array
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_cached() {
+        let mut compiler = Compiler::new();
+        let source: Rc<str> = "a = 1; b = a + 2".into();
+        let first = compiler.compile_cached(source.clone()).unwrap();
+        let second = compiler.compile_cached("a = 1; b = a + 2".into()).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+
+        assert!(compiler.compile_cached("a = ".into()).is_err());
+        assert!(compiler.compile_cached("a = ".into()).is_err());
+
+        // Once it's full, the least recently used code is dropped
+        for i in 1..CACHE_SIZE {
+            compiler.compile_cached(format!("a = {}", i).into()).unwrap();
+        }
+        compiler.compile_cached(source.clone()).unwrap();
+        compiler.compile_cached("c = 0".into()).unwrap();
+        assert_eq!(compiler.cache.len(), CACHE_SIZE);
+        assert!(!compiler.cache.contains_key("a = 1"));
+        assert!(Rc::ptr_eq(&first, &compiler.compile_cached(source).unwrap()));
+    }
+
+    #[test]
+    fn compile_reported() {
+        let mut compiler = Compiler::new();
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.context = "script scr_test".into();
+        assert!(!compiler.compile_reported("a = 1", &mut diagnostics).is_empty());
+        diagnostics.action = Some(2);
+        match &compiler.compile_reported("a = 1\nb = )", &mut diagnostics)[..] {
+            [Instruction::RuntimeError { error: gml::Error::CompileError(message) }] => {
+                assert!(message.contains("at line 2, column 5"), "{}", message)
+            },
+            other => panic!("unexpected instructions: {:?}", other),
+        }
+        assert_eq!(diagnostics.errors.len(), 1);
+        assert!(diagnostics.errors[0].starts_with("COMPILATION ERROR in script scr_test, action number 2:\n"));
+    }
+
+    #[test]
+    fn globalvar() {
+        fn owner(instruction: &Instruction) -> (&InstanceIdentifier, &InstanceIdentifier) {
+            match instruction {
+                Instruction::SetField { accessor, value: Node::Field { accessor: read } } => {
+                    (&accessor.owner, &read.owner)
+                },
+                other => panic!("unexpected instruction: {:?}", other),
+            }
+        }
+        let mut compiler = Compiler::new();
+        let before = compiler.compile("b = a").unwrap();
+        assert!(matches!(owner(&before[1]), (InstanceIdentifier::Own, InstanceIdentifier::Own)));
+
+        compiler.declare_globalvars("globalvar a, b;");
+        let instructions = compiler.compile("var b; b = a; c = a; other.a = a").unwrap();
+        let owners = instructions.iter().filter(|i| !matches!(i, Instruction::SourcePosition { .. })).map(owner);
+        assert!(matches!(owners.collect::<Vec<_>>()[..], [
+            (InstanceIdentifier::Local, InstanceIdentifier::Global),
+            (InstanceIdentifier::Own, InstanceIdentifier::Global),
+            (InstanceIdentifier::Other, InstanceIdentifier::Global),
+        ]));
+        assert!(compiler.compile("globalvar;").unwrap().is_empty());
+    }
+
+    #[test]
+    fn argument_limit() {
+        let mut compiler = Compiler::new();
+        let call = |count| format!("a = max({})", vec!["1"; count].join(", "));
+        match &compiler.compile(&call(16)).unwrap()[..] {
+            [_, Instruction::SetField { value: Node::Function { args, .. }, .. }] => assert_eq!(args.len(), 16),
+            other => panic!("unexpected instructions: {:?}", other),
+        }
+        match compiler.compile(&call(17)) {
+            Err(e) => assert_eq!(e.message, "Too many arguments to max: 17 given, at most 16 allowed"),
+            Ok(instructions) => panic!("unexpected instructions: {:?}", instructions),
+        }
+    }
+
+    #[test]
+    fn source_positions() {
+        let mut compiler = Compiler::new();
+        let instructions = compiler.compile("var a;\na = 1\nif a {\n  a = 2 }").unwrap();
+        match &instructions[..] {
+            [
+                Instruction::SourcePosition { position: first },
+                Instruction::SetField { .. },
+                Instruction::SourcePosition { position: second },
+                Instruction::IfElse { if_body, .. },
+            ] => {
+                assert_eq!((first.line, first.column), (2, 1));
+                assert_eq!((second.line, second.column), (3, 1));
+                match &if_body[..] {
+                    [Instruction::SourcePosition { position }, Instruction::SetField { .. }] => {
+                        assert_eq!((position.line, position.column), (4, 3))
+                    },
+                    other => panic!("unexpected if body: {:?}", other),
+                }
+            },
+            other => panic!("unexpected instructions: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dead_code() {
+        let mut compiler = Compiler::new();
+        let source = "while false { a = 1 }\nrepeat 0 b = 1\nrepeat 3 {}\nfor (i = 0; 0; i += 1) {}\n\
+                      do {} until true\nwith all {}\nif a {} else {}";
+        match &compiler.compile(source).unwrap()[..] {
+            [
+                Instruction::SourcePosition { .. },
+                Instruction::SourcePosition { .. },
+                Instruction::SetField { .. },
+                Instruction::SourcePosition { .. },
+                Instruction::EvalExpression { node: Node::Field { .. } },
+            ] => (), // only the for loop's initialiser and the if statement's condition are left
+            other => panic!("unexpected instructions: {:?}", other),
+        }
+        assert_eq!(compiler.compile("while a {}; do {} until false; repeat b {}").unwrap().len(), 6);
+    }
 }
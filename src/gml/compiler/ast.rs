@@ -1,11 +1,12 @@
 use super::{
-    lexer::Lexer,
+    lexer::{Lexer, Position},
     token::{Keyword, Operator, Separator, Token},
 };
+use crate::gml;
 
 use std::{
     error, fmt,
-    iter::{IntoIterator, Peekable},
+    iter::IntoIterator,
     ops::{Deref, DerefMut},
 };
 
@@ -29,6 +30,7 @@ pub enum Expr<'a> {
     Repeat(Box<RepeatExpr<'a>>),
     Switch(Box<SwitchExpr<'a>>),
     Var(Box<VarExpr<'a>>),
+    GlobalVar(Box<VarExpr<'a>>),
     With(Box<WithExpr<'a>>),
     While(Box<WhileExpr<'a>>),
 
@@ -39,6 +41,15 @@ pub enum Expr<'a> {
     Break,
     Exit,
     Return(Box<Expr<'a>>),
+
+    Located(Box<LocatedExpr<'a>>),
+}
+
+/// A line of code along with where it starts in the source code.
+#[derive(Debug, PartialEq)]
+pub struct LocatedExpr<'a> {
+    pub expr: Expr<'a>,
+    pub position: Position,
 }
 
 #[derive(Debug, PartialEq)]
@@ -114,11 +125,34 @@ pub struct WhileExpr<'a> {
 #[derive(Debug)]
 pub struct Error {
     pub message: String,
+
+    /// Where in the source code the error was found, along with the text of that line.
+    pub position: Option<Position>,
+    pub line: String,
 }
 
 impl Error {
     pub fn new(message: String) -> Self {
-        Error { message }
+        Error { message, position: None, line: String::new() }
+    }
+
+    /// Attaches the position the error was found at, unless it already has one.
+    pub fn locate(mut self, source: &str, position: Position) -> Self {
+        if self.position.is_none() {
+            self.line = source.lines().nth(position.line - 1).unwrap_or_default().to_string();
+            self.position = Some(position);
+        }
+        self
+    }
+}
+
+impl<'a> Expr<'a> {
+    /// Returns the expression without any source location attached to it.
+    pub fn unlocated(&self) -> &Self {
+        match self {
+            Expr::Located(located) => located.expr.unlocated(),
+            expr => expr,
+        }
     }
 }
 
@@ -161,6 +195,11 @@ impl<'a> fmt::Display for Expr<'a> {
                 "(var {})",
                 var.vars.iter().fold(String::new(), |acc, varname| acc + &format!("{} ", varname)).trim_end()
             ),
+            Expr::GlobalVar(var) => write!(
+                f,
+                "(globalvar {})",
+                var.vars.iter().fold(String::new(), |acc, varname| acc + &format!("{} ", varname)).trim_end()
+            ),
             Expr::With(with) => write!(f, "(with {} {})", with.target, with.body),
             Expr::While(while_ex) => write!(f, "(while {} {})", while_ex.cond, while_ex.body),
 
@@ -171,6 +210,8 @@ impl<'a> fmt::Display for Expr<'a> {
             Expr::Break => write!(f, "(break)"),
             Expr::Exit => write!(f, "(exit)"),
             Expr::Return(e) => write!(f, "(return {})", e),
+
+            Expr::Located(located) => write!(f, "{}", located.expr),
         }
     }
 }
@@ -178,7 +219,23 @@ impl<'a> fmt::Display for Expr<'a> {
 impl error::Error for Error {}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self.position {
+            Some(position) => {
+                // Tabs are kept so the caret lines up under the line however wide they're shown
+                let indent = self
+                    .line
+                    .bytes()
+                    .take(position.column - 1)
+                    .map(|ch| if ch == b'\t' { '\t' } else { ' ' })
+                    .collect::<String>();
+                write!(
+                    f,
+                    "{} at line {}, column {}:\n{}\n{}^",
+                    self.message, position.line, position.column, self.line, indent
+                )
+            },
+            None => write!(f, "{}", self.message),
+        }
     }
 }
 
@@ -234,7 +291,7 @@ impl<'a> IntoIterator for AST<'a> {
 
 impl<'a> AST<'a> {
     pub fn new(source: &'a str) -> Result<Self, Error> {
-        let mut lex = Lexer::new(source).peekable();
+        let mut lex = Lexer::new(source);
         let mut expressions = Vec::new();
 
         loop {
@@ -242,20 +299,82 @@ impl<'a> AST<'a> {
             match AST::read_line(&mut lex) {
                 Ok(Some(expr)) => expressions.push(expr),
                 Ok(None) => break,
-                Err(e) => return Err(e),
+                Err(e) => return Err(e.locate(source, lex.position())),
             }
         }
 
         Ok(Self(expressions))
     }
 
+    /// Reads through some source code collecting every syntax error in it, rather than stopping at the first.
+    /// After an error, it skips to the next semicolon or line and carries on from there. Closing braces are
+    /// skipped from then on too, as they likely end a block the error was in.
+    pub fn errors(source: &'a str) -> Vec<Error> {
+        let mut lex = Lexer::new(source);
+        let mut errors = Vec::new();
+        loop {
+            if !errors.is_empty() && lex.peek() == Some(&Token::Separator(Separator::BraceRight)) {
+                lex.next();
+                continue
+            }
+            match AST::read_line(&mut lex) {
+                Ok(Some(_)) => (),
+                Ok(None) => break,
+                Err(e) => {
+                    let line = lex.position().line;
+                    errors.push(e.locate(source, lex.position()));
+                    while let Some(position) = lex.peek_position() {
+                        if position.line > line || lex.next() == Some(Token::Separator(Separator::Semicolon)) {
+                            break
+                        }
+                    }
+                },
+            }
+        }
+        errors
+    }
+
     pub fn expression(source: &'a str) -> Result<Expr<'a>, Error> {
-        let mut lex = Lexer::new(source).peekable();
-        let expr = AST::read_binary_tree(&mut lex, None, false)?;
-        Ok(expr)
+        let mut lex = Lexer::new(source);
+        AST::read_binary_tree(&mut lex, None, false).map_err(|e| e.locate(source, lex.position()))
     }
 
-    fn read_line(lex: &mut Peekable<Lexer<'a>>) -> Result<Option<Expr<'a>>, Error> {
+    /// Reads the list of names following a var or globalvar keyword.
+    fn read_var_names(lex: &mut Lexer<'a>) -> VarExpr<'a> {
+        let mut vars = Vec::new();
+        if let Some(&Token::Identifier(id)) = lex.peek() {
+            lex.next();
+            vars.push(id);
+
+            loop {
+                // Check next token
+                match lex.peek() {
+                    // If next token is a comma, skip it and expect another identifier after it
+                    Some(Token::Separator(Separator::Comma)) => {
+                        lex.next();
+                    },
+
+                    // If next token is an identifier, it's another var name
+                    Some(Token::Identifier(_)) => (),
+
+                    // Anything else (most likely a semicolon) means there are no more var names.
+                    _ => break,
+                }
+
+                // Read one identifier and store it as a var name
+                if let Some(Token::Identifier(id)) = lex.peek() {
+                    vars.push(id);
+                    lex.next();
+                } else {
+                    break
+                }
+            }
+        }
+        // With no names, this doesn't do anything in GML. We could probably make it a NOP.
+        VarExpr { vars }
+    }
+
+    fn read_line(lex: &mut Lexer<'a>) -> Result<Option<Expr<'a>>, Error> {
         let token = loop {
             match lex.next() {
                 Some(Token::Separator(Separator::Semicolon)) => continue,
@@ -263,48 +382,15 @@ impl<'a> AST<'a> {
                 None => return Ok(None), // EOF
             }
         };
+        let position = lex.position();
 
         // Use token type to determine what logic we should apply here
         let ret = match token {
             Token::Keyword(key) => {
                 match key {
-                    Keyword::Var => {
-                        // Read var identifiers
-                        if let Some(&Token::Identifier(id)) = lex.peek() {
-                            lex.next();
-                            let mut vars = Vec::with_capacity(1);
-                            vars.push(id);
-
-                            loop {
-                                // Check next token
-                                match lex.peek() {
-                                    // If next token is a comma, skip it and expect another identifier after it
-                                    Some(Token::Separator(Separator::Comma)) => {
-                                        lex.next();
-                                    },
-
-                                    // If next token is an identifier, it's another var name
-                                    Some(Token::Identifier(_)) => (),
-
-                                    // Anything else (most likely a semicolon) means there are no more var names.
-                                    _ => break,
-                                }
+                    Keyword::Var => Ok(Some(Expr::Var(Box::new(AST::read_var_names(lex))))),
 
-                                // Read one identifier and store it as a var name
-                                if let Some(Token::Identifier(id)) = lex.peek() {
-                                    vars.push(id);
-                                    lex.next();
-                                } else {
-                                    break
-                                }
-                            }
-
-                            Ok(Some(Expr::Var(Box::new(VarExpr { vars }))))
-                        } else {
-                            // This doesn't do anything in GML. We could probably make it a NOP.
-                            Ok(Some(Expr::Var(Box::new(VarExpr { vars: vec![] }))))
-                        }
-                    },
+                    Keyword::GlobalVar => Ok(Some(Expr::GlobalVar(Box::new(AST::read_var_names(lex))))),
 
                     Keyword::Do => {
                         let body = AST::read_line(lex)?
@@ -464,11 +550,11 @@ impl<'a> AST<'a> {
             lex.next();
         }
 
-        ret
+        ret.map(|line| line.map(|expr| Expr::Located(Box::new(LocatedExpr { expr, position }))))
     }
 
     fn read_binary_tree(
-        lex: &mut Peekable<Lexer<'a>>,
+        lex: &mut Lexer<'a>,
         first_token: Option<Token<'a>>, // Sometimes we've already parsed the first token, so it should be put here.
         expect_assignment: bool,        // Do we expect the first op to be an assignment?
     ) -> Result<Expr<'a>, Error> {
@@ -481,7 +567,7 @@ impl<'a> AST<'a> {
     }
 
     fn read_binary_tree_recursive(
-        lex: &mut Peekable<Lexer<'a>>,
+        lex: &mut Lexer<'a>,
         first_token: Option<Token<'a>>, // Sometimes we've already parsed the first token, so it should be put here.
         expect_assignment: bool,        // Do we expect the first op to be an assignment?
         lowest_prec: u8,                // We are not allowed to go below this operator precedence in this tree.
@@ -572,7 +658,7 @@ impl<'a> AST<'a> {
         }
     }
 
-    fn read_btree_expression(lex: &mut Peekable<Lexer<'a>>, first_token: Option<Token<'a>>) -> Result<Expr<'a>, Error> {
+    fn read_btree_expression(lex: &mut Lexer<'a>, first_token: Option<Token<'a>>) -> Result<Expr<'a>, Error> {
         // Get first token and match it
         let mut lhs = match if first_token.is_some() { first_token } else { lex.next() } {
             Some(Token::Separator(ref sep)) if *sep == Separator::ParenLeft => {
@@ -662,7 +748,7 @@ impl<'a> AST<'a> {
         Ok(lhs)
     }
 
-    fn read_function_call(lex: &mut Peekable<Lexer<'a>>, function_name: &'a str) -> Result<Expr<'a>, Error> {
+    fn read_function_call(lex: &mut Lexer<'a>, function_name: &'a str) -> Result<Expr<'a>, Error> {
         expect_token!(lex.next(), Separator(Separator::ParenLeft));
 
         let mut params = Vec::new();
@@ -685,10 +771,19 @@ impl<'a> AST<'a> {
                 }
             }
         }
+        if params.len() > gml::MAX_ARGUMENTS {
+            return Err(Error::new(format!(
+                "Too many arguments to {}: {} given, at most {} allowed",
+                function_name,
+                params.len(),
+                gml::MAX_ARGUMENTS
+            )))
+        }
         Ok(Expr::Function(Box::new(FunctionExpr { name: function_name, params })))
     }
 
-    fn get_op_precedence(op: &Operator) -> Option<u8> {
+    /// Gets how tightly a binary operator binds, higher being tighter, or None if it isn't a binary operator.
+    pub fn get_op_precedence(op: &Operator) -> Option<u8> {
         match op {
             Operator::Add => Some(4),
             Operator::Subtract => Some(4),
@@ -735,13 +830,99 @@ mod tests {
         match AST::new(input) {
             Ok(ast) => {
                 if let Some(e) = expected_output {
-                    assert_eq!(*ast, e);
+                    assert_eq!(ast.into_iter().map(unlocate).collect::<Vec<_>>(), e);
                 }
             },
             Err(e) => panic!("AST test encountered error: '{}' for input: {}", e, input),
         }
     }
 
+    /// Strips the source locations from every line, so tests don't have to spell them out.
+    fn unlocate(expr: Expr) -> Expr {
+        match expr {
+            Expr::Located(located) => unlocate(located.expr),
+            Expr::DoUntil(mut dountil) => {
+                dountil.body = unlocate(dountil.body);
+                Expr::DoUntil(dountil)
+            },
+            Expr::For(mut for_ex) => {
+                for_ex.start = unlocate(for_ex.start);
+                for_ex.step = unlocate(for_ex.step);
+                for_ex.body = unlocate(for_ex.body);
+                Expr::For(for_ex)
+            },
+            Expr::Group(group) => Expr::Group(group.into_iter().map(unlocate).collect()),
+            Expr::If(mut if_ex) => {
+                if_ex.body = unlocate(if_ex.body);
+                if_ex.else_body = if_ex.else_body.map(unlocate);
+                Expr::If(if_ex)
+            },
+            Expr::Repeat(mut repeat) => {
+                repeat.body = unlocate(repeat.body);
+                Expr::Repeat(repeat)
+            },
+            Expr::Switch(mut switch) => {
+                switch.body = unlocate(switch.body);
+                Expr::Switch(switch)
+            },
+            Expr::With(mut with) => {
+                with.body = unlocate(with.body);
+                Expr::With(with)
+            },
+            Expr::While(mut while_ex) => {
+                while_ex.body = unlocate(while_ex.body);
+                Expr::While(while_ex)
+            },
+            expr => expr,
+        }
+    }
+
+    #[test]
+    fn error_positions() {
+        let error = AST::new("a = 1;\n\tb = (2 + ;").unwrap_err();
+        assert_eq!(error.position.map(|p| (p.line, p.column)), Some((2, 11)));
+        assert!(error.to_string().ends_with(" at line 2, column 11:\n\tb = (2 + ;\n\t         ^"), "{}", error);
+
+        let error = AST::expression("1 +").unwrap_err();
+        assert_eq!(error.position.map(|p| (p.line, p.column)), Some((1, 3)));
+    }
+
+    #[test]
+    fn all_errors() {
+        let errors = AST::errors("a = (;
+if b {
+    c = 1 +
+}
+d = 2 e = ) f = 3; g = *
+h = 4");
+        let positions = errors.iter().map(|e| e.position.map(|p| (p.line, p.column))).collect::<Vec<_>>();
+        assert_eq!(positions, [Some((1, 6)), Some((4, 1)), Some((5, 11)), Some((5, 24))]);
+        assert!(AST::errors("a = 1;
+if b {
+    c = 2
+}").is_empty());
+    }
+
+    #[test]
+    fn line_positions() {
+        let ast = AST::new("a = 1;\n  if b {\n    c = 2\n}").unwrap();
+        let position = |expr: &Expr| match expr {
+            Expr::Located(located) => (located.position.line, located.position.column),
+            _ => panic!("expected a located expression, got {:?}", expr),
+        };
+        assert_eq!(position(&ast[0]), (1, 1));
+        assert_eq!(position(&ast[1]), (2, 3));
+        if let Expr::If(if_ex) = ast[1].unlocated() {
+            assert_eq!(position(&if_ex.body), (2, 8));
+            match if_ex.body.unlocated() {
+                Expr::Group(group) => assert_eq!(position(&group[0]), (3, 5)),
+                body => panic!("expected a group, got {:?}", body),
+            }
+        } else {
+            panic!("expected an if statement, got {:?}", ast[1]);
+        }
+    }
+
     #[test]
     fn nothing() {
         // Empty string
@@ -1331,6 +1512,19 @@ mod tests {
         )
     }
 
+    #[test]
+    fn globalvar_syntax() {
+        assert_ast(
+            // globalvar syntax - same as var
+            "globalvar a; globalvar b, c,; globalvar",
+            Some(vec![
+                Expr::GlobalVar(Box::new(VarExpr { vars: vec!["a"] })),
+                Expr::GlobalVar(Box::new(VarExpr { vars: vec!["b", "c"] })),
+                Expr::GlobalVar(Box::new(VarExpr { vars: vec![] })),
+            ]),
+        )
+    }
+
     #[test]
     #[should_panic]
     fn var_invalid_comma() {
@@ -6,51 +6,105 @@ use std::{
     str, u64,
 };
 
+/// A position in GML source code. Both fields are 1-based, as GM8 reports them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
 pub struct Lexer<'a> {
     /// GML source code to return references to.
     src: &'a str,
 
-    line_hint: usize,
-
     /// Iterator over the source code as raw bytes.
     iter: Peekable<Enumerate<str::Bytes<'a>>>,
+
+    /// Token read ahead by peek(), along with its starting offset.
+    peeked: Option<(usize, Option<Token<'a>>)>,
+
+    /// Starting offset of the token currently being read.
+    start: usize,
+
+    /// Starting offset of the last token returned.
+    last: usize,
+
+    /// Offset, line number and line start offset of the last position looked up.
+    /// Lookups only ever move forward, so this saves rescanning the source every time.
+    cursor: (usize, usize, usize),
+
+    /// Whether comments are returned as tokens rather than skipped over.
+    comments: bool,
 }
 
 impl<'a> Lexer<'a> {
     /// Creates a new Lexer over GML source code.
     pub fn new(src: &'a str) -> Self {
-        Lexer { src, line_hint: 1, iter: src.bytes().enumerate().peekable() }
+        Lexer {
+            src,
+            iter: src.bytes().enumerate().peekable(),
+            peeked: None,
+            start: 0,
+            last: 0,
+            cursor: (0, 1, 0),
+            comments: false,
+        }
     }
 
-    /// Returns the current line number in the source code.
-    pub fn line(&self) -> usize {
-        self.line_hint
+    /// Creates a new Lexer which also returns comments, for tools that need to keep them.
+    pub fn with_comments(src: &'a str) -> Self {
+        Lexer { comments: true, ..Lexer::new(src) }
     }
 
-    /// Fast-forwards the internal iterator to the next token, skipping over whitespace.
-    /// Returns how many lines (LF) were skipped in the process.
-    fn fast_forward(&mut self) -> usize {
-        let mut lines_skipped: usize = 0;
-        loop {
-            match self.iter.peek() {
-                Some(&(_, ch)) if ch <= b' ' => {
-                    if ch == b'\n' {
-                        lines_skipped += 1;
-                    }
-                    self.iter.next();
-                },
-                _ => break lines_skipped,
+    /// Returns the next token without consuming it.
+    pub fn peek(&mut self) -> Option<&Token<'a>> {
+        if self.peeked.is_none() {
+            let token = self.read_token();
+            self.peeked = Some((self.start, token));
+        }
+        self.peeked.as_ref().and_then(|(_, token)| token.as_ref())
+    }
+
+    /// Returns the position in the source code of the next token, without consuming it.
+    pub fn peek_position(&mut self) -> Option<Position> {
+        self.peek()?;
+        let offset = self.peeked.as_ref().map(|(offset, _)| *offset).unwrap_or(self.last);
+        Some(self.position_of(offset))
+    }
+
+    /// Returns the position in the source code of the last token returned by next().
+    pub fn position(&mut self) -> Position {
+        self.position_of(self.last)
+    }
+
+    /// Returns the position of the given byte offset, which must not be before the last one looked up.
+    fn position_of(&mut self, offset: usize) -> Position {
+        let (from, mut line, mut line_start) = self.cursor;
+        let offset = offset.min(self.src.len());
+        for (i, ch) in self.src.bytes().enumerate().take(offset).skip(from) {
+            if ch == b'\n' {
+                line += 1;
+                line_start = i + 1;
             }
         }
+        self.cursor = (offset.max(from), line, line_start);
+        Position { line, column: offset.saturating_sub(line_start) + 1 }
     }
-}
 
-impl<'a> Iterator for Lexer<'a> {
-    type Item = Token<'a>;
+    /// Fast-forwards the internal iterator to the next token, skipping over whitespace.
+    fn fast_forward(&mut self) {
+        while let Some(&(_, ch)) = self.iter.peek() {
+            if ch > b' ' {
+                break
+            }
+            self.iter.next();
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Reads the next token from the source code, recording where it starts.
+    fn read_token(&mut self) -> Option<Token<'a>> {
         // locate next token
-        self.line_hint = self.fast_forward();
+        self.fast_forward();
 
         /// Helper function to reconstruct our byte slices to a string easily.
         /// This is fine since we operate on something that is a &str in a first place,
@@ -61,6 +115,7 @@ impl<'a> Iterator for Lexer<'a> {
         }
 
         let head = *self.iter.peek()?;
+        self.start = head.0;
 
         #[allow(clippy::match_overlapping_arm)] // quotes overlap with the catch-all ASCII
         Some(match head.1 {
@@ -83,6 +138,7 @@ impl<'a> Iterator for Lexer<'a> {
                 match identifier {
                     // Keywords
                     "var" => Token::Keyword(Keyword::Var),
+                    "globalvar" => Token::Keyword(Keyword::GlobalVar),
                     "if" => Token::Keyword(Keyword::If),
                     "else" => Token::Keyword(Keyword::Else),
                     "with" => Token::Keyword(Keyword::With),
@@ -282,7 +338,11 @@ impl<'a> Iterator for Lexer<'a> {
                                         },
                                     }
                                 }
-                                return self.next()
+                                if self.comments {
+                                    let end = self.iter.peek().map(|&(i, _)| i).unwrap_or(self.src.len());
+                                    return Some(Token::Comment(sl(&self.src, head.0..end)))
+                                }
+                                return self.read_token()
                             },
 
                             _ => return Some(Token::Operator(op)),
@@ -332,7 +392,11 @@ impl<'a> Iterator for Lexer<'a> {
                                 },
                             }
                         }
-                        return self.next()
+                        if self.comments {
+                            let end = self.iter.peek().map(|&(i, _)| i).unwrap_or(self.src.len());
+                            return Some(Token::Comment(sl(&self.src, head.0..end)))
+                        }
+                        return self.read_token()
                     } else if op == Operator::LessThan && ch2 == b'>' {
                         // <> is the same as != (let's call it a diamond)
 
@@ -364,4 +428,57 @@ impl<'a> Iterator for Lexer<'a> {
     }
 }
 
-// The lexer is intrinsically tested via the AST tests.
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, token) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => {
+                let token = self.read_token();
+                (self.start, token)
+            },
+        };
+        self.last = start;
+        token
+    }
+}
+
+// The lexer is otherwise tested via the AST tests, which don't see comments.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(src: &str) -> Vec<Token<'_>> {
+        Lexer::with_comments(src).collect()
+    }
+
+    #[test]
+    fn line_comment() {
+        assert_eq!(tokens("a // b\nc"), vec![
+            Token::Identifier("a"),
+            Token::Comment("// b"),
+            Token::Identifier("c"),
+        ]);
+        assert_eq!(tokens("a // b"), vec![Token::Identifier("a"), Token::Comment("// b")]);
+        assert_eq!(Lexer::new("a // b\nc").collect::<Vec<_>>(), vec![Token::Identifier("a"), Token::Identifier("c")]);
+    }
+
+    #[test]
+    fn block_comment() {
+        assert_eq!(tokens("a /* b\n*c */ d"), vec![
+            Token::Identifier("a"),
+            Token::Comment("/* b\n*c */"),
+            Token::Identifier("d"),
+        ]);
+        assert_eq!(tokens("/**/"), vec![Token::Comment("/**/")]);
+        assert_eq!(Lexer::new("a /* b */ d").collect::<Vec<_>>(), vec![Token::Identifier("a"), Token::Identifier("d")]);
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        assert_eq!(tokens("a /* b\nc"), vec![Token::Identifier("a"), Token::Comment("/* b\nc")]);
+        assert_eq!(tokens("a /* b *"), vec![Token::Identifier("a"), Token::Comment("/* b *")]);
+        assert_eq!(Lexer::new("a /* b").collect::<Vec<_>>(), vec![Token::Identifier("a")]);
+    }
+}
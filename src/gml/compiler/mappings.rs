@@ -1,3 +1,4 @@
+use super::token::Operator;
 use crate::{
     game::Game,
     gml::{self, Context, InstanceVariable, Value},
@@ -324,6 +325,35 @@ pub const CONSTANTS: [(&str, f64); 317] = [
     ("vk_up", 38.0),
 ];
 
+/// Mappings of binary operators to the functions which apply them
+pub const BINARY_OPERATORS: [(Operator, fn(Value, Value) -> gml::Result<Value>); 20] = [
+    (Operator::Add, Value::add),
+    (Operator::And, Value::bool_and),
+    (Operator::BitwiseAnd, Value::bitand),
+    (Operator::BitwiseOr, Value::bitor),
+    (Operator::BinaryShiftLeft, Value::shl),
+    (Operator::BinaryShiftRight, Value::shr),
+    (Operator::BitwiseXor, Value::bitxor),
+    (Operator::Divide, Value::div),
+    (Operator::Equal, Value::gml_eq),
+    (Operator::GreaterThan, Value::gml_gt),
+    (Operator::GreaterThanOrEqual, Value::gml_gte),
+    (Operator::IntDivide, Value::intdiv),
+    (Operator::LessThan, Value::gml_lt),
+    (Operator::LessThanOrEqual, Value::gml_lte),
+    (Operator::Multiply, Value::mul),
+    (Operator::Modulo, Value::modulo),
+    (Operator::NotEqual, Value::gml_ne),
+    (Operator::Or, Value::bool_or),
+    (Operator::Subtract, Value::sub),
+    (Operator::Xor, Value::bool_xor),
+];
+
+/// Mappings of unary operators to the functions which apply them
+/// Unary + isn't listed as it doesn't do anything.
+pub const UNARY_OPERATORS: [(Operator, fn(Value) -> gml::Result<Value>); 3] =
+    [(Operator::Subtract, Value::neg), (Operator::Not, Value::not), (Operator::Complement, Value::complement)];
+
 /// Mappings of instance variable names to their enum identifiers
 pub const INSTANCE_VARIABLES: [(&str, InstanceVariable); 166] = [
     ("x", InstanceVariable::X),
@@ -618,10 +648,10 @@ pub const FUNCTIONS: [(&str, fn(&mut Game, &mut Context, &[Value]) -> gml::Resul
     ("draw_set_font", Game::draw_set_font, false),
     ("draw_set_halign", Game::draw_set_halign, false),
     ("draw_set_valign", Game::draw_set_valign, false),
-    ("string_width", Game::string_width, true),
-    ("string_height", Game::string_height, true),
-    ("string_width_ext", Game::string_width_ext, true),
-    ("string_height_ext", Game::string_height_ext, true),
+    ("string_width", Game::string_width, false),
+    ("string_height", Game::string_height, false),
+    ("string_width_ext", Game::string_width_ext, false),
+    ("string_height_ext", Game::string_height_ext, false),
     ("draw_text", Game::draw_text, false),
     ("draw_text_ext", Game::draw_text_ext, false),
     ("draw_text_transformed", Game::draw_text_transformed, false),
@@ -1218,7 +1248,7 @@ pub const FUNCTIONS: [(&str, fn(&mut Game, &mut Context, &[Value]) -> gml::Resul
     ("external_define", Game::external_define, false),
     ("external_call", Game::external_call, false),
     ("external_free", Game::external_free, false),
-    ("get_function_address", Game::get_function_address, true),
+    ("get_function_address", Game::get_function_address, false),
     ("external_define0", Game::external_define0, false),
     ("external_call0", Game::external_call0, false),
     ("external_define1", Game::external_define1, false),
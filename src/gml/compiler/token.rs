@@ -12,11 +12,15 @@ pub enum Token<'a> {
     String(&'a str),
 
     InvalidChar(usize, u8),
+
+    /// A comment, including its delimiters. Only returned by `Lexer::with_comments`.
+    Comment(&'a str),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Keyword {
     Var,
+    GlobalVar,
     If,
     Else,
     With,
@@ -188,6 +192,7 @@ impl fmt::Display for Keyword {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Keyword::Var => write!(f, "var"),
+            Keyword::GlobalVar => write!(f, "globalvar"),
             Keyword::If => write!(f, "if"),
             Keyword::Else => write!(f, "else"),
             Keyword::With => write!(f, "with"),
@@ -1,14 +1,14 @@
 use crate::{
-    gml::Value,
-    instance::{DummyFieldHolder, Instance},
+    gml::{self, Value},
+    instance::DummyFieldHolder,
 };
 
-pub struct Context<'a> {
-    /// Reference to the "self" instance
-    pub this: &'a Instance,
+pub struct Context {
+    /// Handle of the "self" instance in the instance list
+    pub this: usize,
 
-    /// Reference to the "other" instance
-    pub other: &'a Instance,
+    /// Handle of the "other" instance in the instance list
+    pub other: usize,
 
     /// Index of the action currently being executed, starting at 0
     pub event_action: usize,
@@ -26,8 +26,14 @@ pub struct Context<'a> {
     /// self.object_index, as the event could have been inherited from a parent object
     pub event_object: u32,
 
-    /// Arguments passed to scripts and such
-    pub arguments: &'a [Value],
+    /// Arguments passed to scripts and such, which can be written to like locals
+    pub arguments: [Value; gml::MAX_ARGUMENTS],
+
+    /// How many arguments were actually passed - the rest are uninitialized
+    pub argument_count: usize,
+
+    /// Which arguments have been written to since, one bit each, as those can be read even if they weren't passed
+    pub arguments_set: u16,
 
     /// Local variables specific to this context
     /// TODO: replace this with a dummy field-holder object? Global behaves the same way.
@@ -36,3 +42,36 @@ pub struct Context<'a> {
     /// Return value from this execution - should be initialized to zero as it won't necessarily be written
     pub return_value: Value,
 }
+
+impl Context {
+    /// Makes a context for running code outside of any event, with no arguments or locals.
+    pub fn new(this: usize, other: usize) -> Self {
+        Self {
+            this,
+            other,
+            event_action: 0,
+            relative: false,
+            event_type: 0,
+            event_number: 0,
+            event_object: 0,
+            arguments: Default::default(),
+            argument_count: 0,
+            arguments_set: 0,
+            locals: DummyFieldHolder::new(),
+            return_value: Default::default(),
+        }
+    }
+
+    /// Makes a context for running code from this one, such as a script, in the same event and action.
+    /// It starts with no arguments or locals of its own.
+    pub fn nested(&self, this: usize, other: usize) -> Self {
+        Self {
+            event_action: self.event_action,
+            relative: self.relative,
+            event_type: self.event_type,
+            event_number: self.event_number,
+            event_object: self.event_object,
+            ..Self::new(this, other)
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! Dates and times as GML sees them.
+//!
+//! GM8 uses Delphi's TDateTime: a real number where the integral part is the number of days since 1899-12-30,
+//! and the fractional part is the time of day. For dates before the epoch the fractional part still counts
+//! forwards, so -1.25 is 1899-12-29 06:00, not 1899-12-28 18:00.
+//!
+//! Nothing here asks the OS for the time directly. Everything goes through a `Clock`, so that the time can be
+//! frozen or driven by whatever's running the game.
+
+use std::time::Instant;
+
+pub const HOURS_PER_DAY: f64 = 24.0;
+pub const MINUTES_PER_DAY: f64 = HOURS_PER_DAY * 60.0;
+pub const SECONDS_PER_DAY: f64 = MINUTES_PER_DAY * 60.0;
+pub const MS_PER_DAY: f64 = SECONDS_PER_DAY * 1000.0;
+
+/// Days between 1899-12-30 and 1970-01-01.
+const UNIX_EPOCH_DAYS: i64 = 25569;
+
+/// The first and last days TDateTime can represent, 0001-01-01 and 9999-12-31.
+const MIN_DAYS: i64 = -693593;
+const MAX_DAYS: i64 = 2958465;
+
+/// Average lengths used by the `date_*_span` functions, same as Delphi's.
+const DAYS_PER_YEAR: f64 = 365.25;
+const DAYS_PER_MONTH: f64 = DAYS_PER_YEAR / 12.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct DateTime(pub f64);
+
+/// A source of the current time.
+pub trait Clock {
+    /// The current local date and time.
+    fn now(&self) -> DateTime;
+
+    /// Milliseconds since some fixed point, as reported by `current_time`.
+    fn millis(&self) -> u64;
+}
+
+/// Reads the real time from the OS.
+pub struct SystemClock {
+    start: Instant,
+}
+
+/// A clock which only changes when told to.
+pub struct FixedClock {
+    pub now: DateTime,
+    pub millis: u64,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        use chrono::{Datelike, Timelike};
+        let now = chrono::Local::now().naive_local();
+        let date = DateTime::from_date(now.year(), now.month() as i32, now.day() as i32).unwrap_or(DateTime(0.0));
+        let ms = f64::from(now.num_seconds_from_midnight()) * 1000.0 + f64::from(now.nanosecond() / 1_000_000);
+        DateTime(date.0 + ms / MS_PER_DAY)
+    }
+
+    fn millis(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime) -> Self {
+        Self { now, millis: 0 }
+    }
+
+    /// Moves the clock forwards by some number of milliseconds.
+    pub fn advance(&mut self, ms: u64) {
+        self.now = DateTime(self.now.linear() + ms as f64 / MS_PER_DAY).normalized();
+        self.millis += ms;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime {
+        self.now
+    }
+
+    fn millis(&self) -> u64 {
+        self.millis
+    }
+}
+
+/// Checks whether a year is a leap year in the proleptic Gregorian calendar.
+pub fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Gets the number of days in a month, or 0 if the month doesn't exist.
+pub fn days_in_month(year: i32, month: i32) -> i32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Checks whether a date could be passed to `date_create_date`.
+pub fn valid_date(year: i32, month: i32, day: i32) -> bool {
+    (1..=9999).contains(&year) && (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month)
+}
+
+/// Checks whether a time could be passed to `date_create_time`.
+pub fn valid_time(hour: i32, minute: i32, second: i32) -> bool {
+    (0..24).contains(&hour) && (0..60).contains(&minute) && (0..60).contains(&second)
+}
+
+impl DateTime {
+    /// Creates a date at midnight, or None if the date isn't valid.
+    pub fn from_date(year: i32, month: i32, day: i32) -> Option<Self> {
+        if !valid_date(year, month, day) {
+            return None
+        }
+
+        // Days from civil, counting years from March so the leap day comes last
+        let (y, m) = if month <= 2 { (i64::from(year) - 1, month + 9) } else { (i64::from(year), month - 3) };
+        let era = y.div_euclid(400);
+        let year_of_era = y - era * 400;
+        let day_of_year = i64::from((153 * m + 2) / 5 + day - 1);
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Self((era * 146097 + day_of_era - 719468 + UNIX_EPOCH_DAYS) as f64))
+    }
+
+    /// Creates a time on 1899-12-30, or None if the time isn't valid.
+    pub fn from_time(hour: i32, minute: i32, second: i32) -> Option<Self> {
+        if !valid_time(hour, minute, second) {
+            return None
+        }
+        Some(Self(f64::from(hour * 3600 + minute * 60 + second) / SECONDS_PER_DAY))
+    }
+
+    /// Combines the date part of one value with the time part of another.
+    pub fn combine(date: Self, time: Self) -> Self {
+        let date = date.0.trunc();
+        let time = time.0.fract().abs();
+        if date < 0.0 { Self(date - time) } else { Self(date + time) }
+    }
+
+    /// The value as a continuous number of days, with the time of day always counting forwards.
+    fn linear(self) -> f64 {
+        self.0.trunc() + self.0.fract().abs()
+    }
+
+    /// Converts a continuous number of days back into TDateTime form.
+    fn normalized(self) -> Self {
+        let days = self.0.floor();
+        Self::combine(Self(days), Self(self.0 - days))
+    }
+
+    /// Splits the value into whole days since the epoch and milliseconds into the day, rounded as Delphi does.
+    /// Days outside of the range TDateTime can represent are clamped to it.
+    fn days_and_ms(self) -> (i64, i64) {
+        let days = (self.0.trunc() as i64).clamp(MIN_DAYS, MAX_DAYS);
+        let ms = (self.0.fract().abs() * MS_PER_DAY).round() as i64;
+        if ms >= MS_PER_DAY as i64 { ((days + 1).min(MAX_DAYS), 0) } else { (days, ms) }
+    }
+
+    /// Gets the year, month and day.
+    pub fn ymd(self) -> (i32, i32, i32) {
+        // Civil from days, the inverse of from_date
+        let z = self.days_and_ms().0 - UNIX_EPOCH_DAYS + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year as i32, month as i32, day as i32)
+    }
+
+    /// Gets the hour, minute, second and millisecond.
+    pub fn hms(self) -> (i32, i32, i32, i32) {
+        let ms = self.days_and_ms().1 as i32;
+        (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+    }
+
+    pub fn year(self) -> i32 {
+        self.ymd().0
+    }
+
+    /// Day of the week, from 0 for Sunday to 6 for Saturday.
+    pub fn weekday(self) -> i32 {
+        // 1899-12-30 was a Saturday
+        (self.days_and_ms().0 + 6).rem_euclid(7) as i32
+    }
+
+    /// Day of the year, starting from 1.
+    pub fn day_of_year(self) -> i32 {
+        let (year, _, _) = self.ymd();
+        let start = Self::from_date(year, 1, 1).map_or(0, |d| d.0 as i64);
+        (self.days_and_ms().0 - start) as i32 + 1
+    }
+
+    /// ISO 8601 week number, from 1 to 53.
+    pub fn week(self) -> i32 {
+        let iso_weekday = (self.weekday() + 6) % 7 + 1;
+        let week = (self.day_of_year() - iso_weekday + 10) / 7;
+        let year = self.year();
+
+        // A year has 53 weeks if it starts on a Thursday, or on a Wednesday in a leap year
+        let weeks_in = |year: i32| match Self::from_date(year, 1, 1).map(Self::weekday) {
+            Some(4) => 53,
+            Some(3) if is_leap_year(year) => 53,
+            _ => 52,
+        };
+        if week < 1 {
+            weeks_in(year - 1)
+        } else if week > weeks_in(year) {
+            1
+        } else {
+            week
+        }
+    }
+
+    /// Only the date part, at midnight.
+    pub fn date(self) -> Self {
+        Self(self.0.trunc())
+    }
+
+    /// Only the time part. This is negative for dates before the epoch, as in Delphi.
+    pub fn time(self) -> Self {
+        Self(self.0.fract())
+    }
+
+    /// Adds a number of months, clamping the day to the length of the new month.
+    pub fn inc_month(self, months: i32) -> Self {
+        let (year, month, day) = self.ymd();
+        let total = i64::from(year) * 12 + i64::from(month - 1) + i64::from(months);
+        let (year, month) = (total.div_euclid(12) as i32, total.rem_euclid(12) as i32 + 1);
+        match Self::from_date(year, month, day.min(days_in_month(year, month))) {
+            Some(date) => Self::combine(date, self),
+            None => self,
+        }
+    }
+
+    /// Adds a number of days, which may be fractional.
+    pub fn inc_days(self, days: f64) -> Self {
+        Self(self.linear() + days).normalized()
+    }
+
+    /// The absolute difference between two values in days.
+    pub fn day_span(self, other: Self) -> f64 {
+        let ms = |d: Self| (d.linear() * MS_PER_DAY).round();
+        (ms(self) - ms(other)).abs() / MS_PER_DAY
+    }
+
+    pub fn week_span(self, other: Self) -> f64 {
+        self.day_span(other) / 7.0
+    }
+
+    pub fn month_span(self, other: Self) -> f64 {
+        self.day_span(other) / DAYS_PER_MONTH
+    }
+
+    pub fn year_span(self, other: Self) -> f64 {
+        self.day_span(other) / DAYS_PER_YEAR
+    }
+
+    /// Compares two values to the millisecond, returning -1, 0 or 1.
+    pub fn compare(self, other: Self) -> i32 {
+        let ms = |d: Self| (d.linear() * MS_PER_DAY).round() as i64;
+        ms(self).cmp(&ms(other)) as i32
+    }
+
+    /// Compares only the date parts of two values, returning -1, 0 or 1.
+    pub fn compare_date(self, other: Self) -> i32 {
+        self.days_and_ms().0.cmp(&other.days_and_ms().0) as i32
+    }
+
+    /// Compares only the time parts of two values, returning -1, 0 or 1.
+    pub fn compare_time(self, other: Self) -> i32 {
+        self.days_and_ms().1.cmp(&other.days_and_ms().1) as i32
+    }
+
+    /// Formats the date part, as an English (United States) Windows install would.
+    pub fn date_string(self) -> String {
+        let (year, month, day) = self.ymd();
+        format!("{}/{}/{:04}", month, day, year)
+    }
+
+    /// Formats the time part, as an English (United States) Windows install would.
+    pub fn time_string(self) -> String {
+        let (hour, minute, second, _) = self.hms();
+        let suffix = if hour < 12 { "AM" } else { "PM" };
+        let hour = match hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        format!("{}:{:02}:{:02} {}", hour, minute, second, suffix)
+    }
+
+    /// Formats the date and time. The time is left out entirely at midnight, as Delphi's DateTimeToStr does.
+    pub fn datetime_string(self) -> String {
+        if self.days_and_ms().1 == 0 {
+            self.date_string()
+        } else {
+            format!("{} {}", self.date_string(), self.time_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(year: i32, month: i32, day: i32, hour: i32, minute: i32, second: i32) -> DateTime {
+        DateTime::combine(
+            DateTime::from_date(year, month, day).unwrap(),
+            DateTime::from_time(hour, minute, second).unwrap(),
+        )
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(DateTime::from_date(1899, 12, 30), Some(DateTime(0.0)));
+        assert_eq!(DateTime::from_date(1900, 1, 1), Some(DateTime(2.0)));
+        assert_eq!(DateTime::from_date(1970, 1, 1), Some(DateTime(25569.0)));
+        assert_eq!(DateTime::from_date(2000, 2, 29), Some(DateTime(36585.0)));
+        assert_eq!(DateTime::from_date(1, 1, 1), Some(DateTime(-693593.0)));
+        assert_eq!(DateTime::from_date(9999, 12, 31), Some(DateTime(2958465.0)));
+        assert_eq!(DateTime::from_time(18, 0, 0), Some(DateTime(0.75)));
+        assert_eq!(datetime(1899, 12, 29, 6, 0, 0), DateTime(-1.25));
+    }
+
+    #[test]
+    fn validity() {
+        assert_eq!(DateTime::from_date(1900, 2, 29), None);
+        assert_eq!(DateTime::from_date(2001, 13, 1), None);
+        assert_eq!(DateTime::from_date(2001, 4, 31), None);
+        assert_eq!(DateTime::from_date(0, 1, 1), None);
+        assert_eq!(DateTime::from_date(10000, 1, 1), None);
+        assert_eq!(DateTime::from_time(24, 0, 0), None);
+        assert_eq!(DateTime::from_time(0, 60, 0), None);
+        assert_eq!(DateTime::from_time(0, 0, -1), None);
+        assert!(valid_date(2004, 2, 29));
+        assert!(valid_time(23, 59, 59));
+    }
+
+    #[test]
+    fn decode() {
+        let dates = [(1899, 12, 30), (1, 1, 1), (1600, 2, 29), (1899, 1, 31), (2020, 3, 1), (9999, 12, 31)];
+        for &(year, month, day) in dates.iter() {
+            assert_eq!(DateTime::from_date(year, month, day).unwrap().ymd(), (year, month, day));
+        }
+        let value = datetime(1850, 6, 15, 13, 45, 30);
+        assert_eq!(value.ymd(), (1850, 6, 15));
+        assert_eq!(value.hms(), (13, 45, 30, 0));
+        assert_eq!(DateTime(-0.5).ymd(), (1899, 12, 30));
+        assert_eq!(DateTime(-0.5).hms(), (12, 0, 0, 0));
+
+        // Rounding up to midnight moves to the next day rather than giving hour 24
+        assert_eq!(DateTime(1.9999999999).ymd(), (1900, 1, 1));
+        assert_eq!(DateTime(1.9999999999).hms(), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn out_of_range() {
+        let cases: &[(f64, (i32, i32, i32))] =
+            &[(1e300, (9999, 12, 31)), (-1e300, (1, 1, 1)), (2958466.0, (9999, 12, 31)), (f64::NAN, (1899, 12, 30))];
+        for &(value, ymd) in cases {
+            let value = DateTime(value);
+            assert_eq!(value.ymd(), ymd, "{:?}", value);
+            assert!((1..=366).contains(&value.day_of_year()), "{:?}", value);
+            assert!((1..=53).contains(&value.week()), "{:?}", value);
+        }
+        assert_eq!(DateTime(1e300).inc_month(1), DateTime(1e300));
+        assert_eq!(DateTime(-1e300).compare(DateTime(1e300)), -1);
+    }
+
+    #[test]
+    fn accessors() {
+        let value = datetime(2008, 12, 31, 12, 0, 0);
+        assert_eq!(value.weekday(), 3);
+        assert_eq!(value.day_of_year(), 366);
+        assert_eq!(value.week(), 1);
+        assert_eq!(DateTime(0.0).weekday(), 6);
+        assert_eq!(DateTime(-1.0).weekday(), 5);
+        assert_eq!(datetime(2010, 1, 3, 0, 0, 0).week(), 53);
+        assert_eq!(datetime(2015, 12, 31, 0, 0, 0).week(), 53);
+        assert_eq!(datetime(2016, 1, 4, 0, 0, 0).week(), 1);
+        assert_eq!(value.date(), DateTime::from_date(2008, 12, 31).unwrap());
+        assert_eq!(value.time(), DateTime(0.5));
+        assert_eq!(DateTime(-1.25).time(), DateTime(-0.25));
+    }
+
+    #[test]
+    fn increments() {
+        let value = datetime(2004, 1, 31, 10, 0, 0);
+        assert_eq!(value.inc_month(1), datetime(2004, 2, 29, 10, 0, 0));
+        assert_eq!(value.inc_month(13), datetime(2005, 2, 28, 10, 0, 0));
+        assert_eq!(value.inc_month(-2), datetime(2003, 11, 30, 10, 0, 0));
+        assert_eq!(value.inc_days(0.5), datetime(2004, 1, 31, 22, 0, 0));
+        assert_eq!(datetime(1899, 12, 30, 6, 0, 0).inc_days(-0.5), datetime(1899, 12, 29, 18, 0, 0));
+        assert_eq!(DateTime(-1.25).inc_days(1.0), DateTime(0.25));
+    }
+
+    #[test]
+    fn spans_and_comparisons() {
+        let a = datetime(1899, 12, 29, 18, 0, 0);
+        let b = datetime(1899, 12, 30, 6, 0, 0);
+        assert_eq!(a.day_span(b), 0.5);
+        assert_eq!(b.day_span(a), 0.5);
+        assert_eq!(DateTime(0.0).week_span(DateTime(14.0)), 2.0);
+        assert_eq!(DateTime(0.0).year_span(DateTime(365.25)), 1.0);
+        assert_eq!(a.compare(b), -1);
+        assert_eq!(b.compare(a), 1);
+        assert_eq!(a.compare(DateTime(a.0 + 1e-10)), 0);
+        assert_eq!(a.compare_date(b), -1);
+        assert_eq!(a.compare_time(b), 1);
+        assert_eq!(DateTime(5.25).compare_time(DateTime(-3.25)), 0);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(datetime(2009, 7, 4, 15, 4, 5).datetime_string(), "7/4/2009 3:04:05 PM");
+        assert_eq!(datetime(2009, 7, 4, 0, 30, 0).time_string(), "12:30:00 AM");
+        assert_eq!(datetime(2009, 7, 4, 12, 0, 0).time_string(), "12:00:00 PM");
+        assert_eq!(DateTime::from_date(1, 2, 3).unwrap().datetime_string(), "2/3/0001");
+    }
+
+    #[test]
+    fn fixed_clock() {
+        let mut clock = FixedClock::new(datetime(1999, 12, 31, 23, 59, 59));
+        clock.advance(1500);
+        assert_eq!(clock.millis(), 1500);
+        assert_eq!(clock.now().ymd(), (2000, 1, 1));
+        assert_eq!(clock.now().hms(), (0, 0, 0, 500));
+    }
+}
@@ -0,0 +1,537 @@
+use super::{
+    compiler::{ast::AST, mappings, token::Operator, Compiler},
+    runtime::{ArrayAccessor, InstanceIdentifier, Instruction, ModificationType, Node, ReturnType},
+    InstanceVariable, Value,
+};
+use std::collections::BTreeSet;
+
+#[cfg(feature = "gm8exe")]
+use super::runtime::event_name;
+#[cfg(feature = "gm8exe")]
+use crate::{game::GameCode, gml};
+#[cfg(feature = "gm8exe")]
+use gm8exe::GameAssets;
+#[cfg(feature = "gm8exe")]
+use std::{fs, io, path::Path};
+
+/// Writes compiled code back out as GML, for debugging the compiler.
+/// Names are looked up from the compiler the code was compiled with.
+pub struct Decompiler<'a> {
+    compiler: &'a Compiler,
+    output: String,
+    indent: usize,
+}
+
+/// Writes compiled code back out as GML.
+pub fn decompile(compiler: &Compiler, instructions: &[Instruction]) -> String {
+    let mut decompiler = Decompiler::new(compiler);
+    decompiler.code(instructions);
+    decompiler.finish()
+}
+
+/// Gets the name of a kernel function from its index in mappings::FUNCTIONS.
+pub fn function_name(index: usize) -> &'static str {
+    mappings::FUNCTIONS.get(index).map(|(name, _, _)| *name).unwrap_or("<function>")
+}
+
+/// Writes a real literal. GML has no way of writing infinity or NaN, so those are calculated instead.
+fn real_literal(real: f64) -> String {
+    if real.is_nan() {
+        "(power(2, 1024) - power(2, 1024))".into()
+    } else if real.is_infinite() {
+        format!("{}power(2, 1024)", if real < 0.0 { "-" } else { "" })
+    } else {
+        real.to_string()
+    }
+}
+
+/// Writes a string literal, using whichever quotes it doesn't contain.
+/// GML has no escape sequences, so a string with both is split up around its double quotes.
+fn string_literal(string: &str) -> String {
+    if !string.contains('"') {
+        format!("\"{}\"", string)
+    } else if !string.contains('\'') {
+        format!("'{}'", string)
+    } else {
+        let parts = string.split('"').map(|part| format!("\"{}\"", part)).collect::<Vec<_>>();
+        format!("({})", parts.join(" + '\"' + "))
+    }
+}
+
+impl<'a> Decompiler<'a> {
+    pub fn new(compiler: &'a Compiler) -> Self {
+        Self { compiler, output: String::new(), indent: 0 }
+    }
+
+    /// Returns everything written so far.
+    pub fn finish(self) -> String {
+        self.output
+    }
+
+    /// Writes a line at the current indentation.
+    pub fn line(&mut self, line: &str) {
+        for _ in 0..self.indent {
+            self.output.push_str("    ");
+        }
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    /// Writes a line which starts a block, such as `if (a) {`, and indents everything after it.
+    pub fn open(&mut self, line: &str) {
+        self.line(line);
+        self.indent += 1;
+    }
+
+    /// Ends the innermost block.
+    pub fn close(&mut self) {
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    /// Ends a block and starts another, as with `} else {`.
+    pub fn close_open(&mut self, line: &str) {
+        self.indent -= 1;
+        self.open(line);
+    }
+
+    /// Writes a whole piece of code, declaring the local variables it uses at the start.
+    pub fn code(&mut self, instructions: &[Instruction]) {
+        let mut locals = BTreeSet::new();
+        instructions.iter().for_each(|instruction| Self::find_locals(instruction, &mut locals));
+        if !locals.is_empty() {
+            let names = locals.into_iter().map(|id| self.field_name(id)).collect::<Vec<_>>();
+            self.line(&format!("var {};", names.join(", ")));
+        }
+        self.instructions(instructions);
+    }
+
+    /// Writes an expression.
+    pub fn expression(&self, node: &Node) -> String {
+        self.expression_within(node, 0)
+    }
+
+    fn instructions(&mut self, instructions: &[Instruction]) {
+        let mut iter = instructions.iter().filter(|i| !matches!(i, Instruction::SourcePosition { .. })).peekable();
+        while let Some(instruction) = iter.next() {
+            self.instruction(instruction);
+            // A return statement compiles to setting the return value and then exiting
+            if let Instruction::SetReturnValue { .. } = instruction {
+                if let Some(Instruction::Return { return_type: ReturnType::Exit }) = iter.peek() {
+                    iter.next();
+                }
+            }
+        }
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::SetField { accessor, value } => {
+                let field = self.accessor(&self.field_name(accessor.index), &accessor.owner, &accessor.array);
+                self.line(&format!("{} = {};", field, self.expression(value)));
+            },
+            Instruction::SetVariable { accessor, value } => {
+                let var = self.accessor(Self::variable_name(accessor.var), &accessor.owner, &accessor.array);
+                self.line(&format!("{} = {};", var, self.expression(value)));
+            },
+            Instruction::ModifyField { accessor, value, modification_type } => {
+                let field = self.accessor(&self.field_name(accessor.index), &accessor.owner, &accessor.array);
+                let op = Self::modification(*modification_type);
+                self.line(&format!("{} {} {};", field, op, self.expression(value)));
+            },
+            Instruction::ModifyVariable { accessor, value, modification_type } => {
+                let var = self.accessor(Self::variable_name(accessor.var), &accessor.owner, &accessor.array);
+                let op = Self::modification(*modification_type);
+                self.line(&format!("{} {} {};", var, op, self.expression(value)));
+            },
+            Instruction::EvalExpression { node } => self.line(&format!("{};", self.expression(node))),
+            Instruction::IfElse { cond, if_body, else_body } => {
+                self.open(&format!("if ({}) {{", self.expression(cond)));
+                self.instructions(if_body);
+                let mut else_body = &else_body[..];
+                loop {
+                    // An else containing only another if is written as an else if
+                    let mut lines = else_body.iter().filter(|i| !matches!(i, Instruction::SourcePosition { .. }));
+                    match (lines.next(), lines.next()) {
+                        (None, _) => break,
+                        (Some(Instruction::IfElse { cond, if_body, else_body: next }), None) => {
+                            self.close_open(&format!("}} else if ({}) {{", self.expression(cond)));
+                            self.instructions(if_body);
+                            else_body = next;
+                        },
+                        _ => {
+                            self.close_open("} else {");
+                            self.instructions(else_body);
+                            break
+                        },
+                    }
+                }
+                self.close();
+            },
+            Instruction::LoopUntil { cond, body } => {
+                self.open("do {");
+                self.instructions(body);
+                self.indent -= 1;
+                self.line(&format!("}} until ({});", self.expression(cond)));
+            },
+            Instruction::LoopWhile { cond, body, step } => {
+                self.open(&format!("while ({}) {{", self.expression(cond)));
+                self.instructions(body);
+                self.instructions(step);
+                self.close();
+            },
+            Instruction::Return { return_type } => match return_type {
+                ReturnType::Normal => (),
+                ReturnType::Continue => self.line("continue;"),
+                ReturnType::Break => self.line("break;"),
+                ReturnType::Exit => self.line("exit;"),
+            },
+            Instruction::Repeat { count, body } => {
+                self.open(&format!("repeat ({}) {{", self.expression(count)));
+                self.instructions(body);
+                self.close();
+            },
+            Instruction::SetReturnValue { value } => self.line(&format!("return {};", self.expression(value))),
+            Instruction::Switch { input, cases, default, body } => {
+                self.open(&format!("switch ({}) {{", self.expression(input)));
+                let mut starts = cases.iter().map(|(_, start)| *start).chain(*default).collect::<Vec<_>>();
+                starts.extend_from_slice(&[0, body.len()]);
+                starts.sort_unstable();
+                starts.dedup();
+                for range in starts.windows(2) {
+                    self.labels(cases, *default, range[0]);
+                    self.indent += 1;
+                    self.instructions(&body[range[0]..range[1]]);
+                    self.indent -= 1;
+                }
+                self.labels(cases, *default, body.len());
+                self.close();
+            },
+            Instruction::With { target, body } => {
+                self.open(&format!("with ({}) {{", self.expression(target)));
+                self.instructions(body);
+                self.close();
+            },
+            Instruction::SourcePosition { .. } => (),
+            Instruction::RuntimeError { error } => {
+                for line in error.to_string().lines() {
+                    self.line(&format!("// {}", line));
+                }
+            },
+        }
+    }
+
+    /// Writes the case and default labels of a switch which jump to the given instruction of its body.
+    fn labels(&mut self, cases: &[(Node, usize)], default: Option<usize>, index: usize) {
+        for (cond, _) in cases.iter().filter(|(_, start)| *start == index) {
+            self.line(&format!("case {}:", self.expression(cond)));
+        }
+        if default == Some(index) {
+            self.line("default:");
+        }
+    }
+
+    /// Writes an expression, bracketing it if its operator binds less tightly than the given precedence.
+    fn expression_within(&self, node: &Node, precedence: u8) -> String {
+        match node {
+            Node::Literal { value: Value::Real(real) } => real_literal(*real),
+            Node::Literal { value: Value::Str(string) } => string_literal(string),
+            Node::Function { args, index, .. } => format!("{}({})", function_name(*index), self.arguments(args)),
+            Node::Script { args, script_id } => match self.compiler.get_script_name(*script_id) {
+                Some(name) => format!("{}({})", name, self.arguments(args)),
+                None => format!("<script {}>({})", script_id, self.arguments(args)),
+            },
+            Node::Field { accessor } => {
+                self.accessor(&self.field_name(accessor.index), &accessor.owner, &accessor.array)
+            },
+            Node::Variable { accessor } => {
+                self.accessor(Self::variable_name(accessor.var), &accessor.owner, &accessor.array)
+            },
+            Node::Binary { left, right, index, .. } => {
+                let op = mappings::BINARY_OPERATORS.get(*index);
+                let (op, op_precedence) = match op.and_then(|(op, _)| AST::get_op_precedence(op).map(|p| (op, p))) {
+                    Some(op) => op,
+                    None => return "<operator>".into(),
+                };
+
+                // Operators are left-associative, so the right side is bracketed if it has the same precedence
+                let expression = format!(
+                    "{} {} {}",
+                    self.expression_within(left, op_precedence),
+                    op,
+                    self.expression_within(right, op_precedence + 1),
+                );
+                if op_precedence < precedence { format!("({})", expression) } else { expression }
+            },
+            Node::Unary { child, index, .. } => {
+                let op = mappings::UNARY_OPERATORS.get(*index).map(|(op, _)| op).unwrap_or(&Operator::Add);
+                match child.as_ref() {
+                    Node::Unary { .. } | Node::Literal { value: Value::Real(_) } => {
+                        format!("{}({})", op, self.expression(child))
+                    },
+                    // Unary operators bind more tightly than any binary operator
+                    child => format!("{}{}", op, self.expression_within(child, u8::MAX)),
+                }
+            },
+            Node::RuntimeError { error } => format!("/* {} */", error.to_string().replace('\n', " ")),
+        }
+    }
+
+    fn arguments(&self, args: &[Node]) -> String {
+        args.iter().map(|arg| self.expression(arg)).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Writes a variable along with its owner and array index.
+    fn accessor(&self, name: &str, owner: &InstanceIdentifier, array: &ArrayAccessor) -> String {
+        let owner = match owner {
+            InstanceIdentifier::Own | InstanceIdentifier::Local => String::new(),
+            InstanceIdentifier::Other => "other.".into(),
+            InstanceIdentifier::Global => "global.".into(),
+            InstanceIdentifier::Expression(node) => match node.as_ref() {
+                Node::Field { .. } | Node::Variable { .. } | Node::Function { .. } | Node::Script { .. } => {
+                    format!("{}.", self.expression(node))
+                },
+                node => format!("({}).", self.expression(node)),
+            },
+        };
+        match array {
+            ArrayAccessor::None => format!("{}{}", owner, name),
+            ArrayAccessor::Single(index) => format!("{}{}[{}]", owner, name, self.expression(index)),
+            ArrayAccessor::Double(index1, index2) => {
+                format!("{}{}[{}, {}]", owner, name, self.expression(index1), self.expression(index2))
+            },
+        }
+    }
+
+    fn field_name(&self, id: usize) -> String {
+        self.compiler.get_field_name(id).unwrap_or_else(|| format!("<field {}>", id))
+    }
+
+    fn variable_name(var: InstanceVariable) -> &'static str {
+        mappings::INSTANCE_VARIABLES.iter().find(|(_, v)| *v == var).map(|(name, _)| *name).unwrap_or("<variable>")
+    }
+
+    fn modification(modification_type: ModificationType) -> &'static str {
+        match modification_type {
+            ModificationType::Add => "+=",
+            ModificationType::Subtract => "-=",
+            ModificationType::Multiply => "*=",
+            ModificationType::Divide => "/=",
+            ModificationType::BitAnd => "&=",
+            ModificationType::BitOr => "|=",
+            ModificationType::BitXor => "^=",
+        }
+    }
+
+    /// Finds the field ids of all the local variables used in an instruction.
+    fn find_locals(instruction: &Instruction, locals: &mut BTreeSet<usize>) {
+        match instruction {
+            Instruction::SetField { accessor, .. } | Instruction::ModifyField { accessor, .. } => {
+                if let InstanceIdentifier::Local = accessor.owner {
+                    locals.insert(accessor.index);
+                }
+            },
+            Instruction::IfElse { if_body, else_body, .. } => {
+                if_body.iter().chain(else_body.iter()).for_each(|i| Self::find_locals(i, locals))
+            },
+            Instruction::LoopWhile { body, step, .. } => {
+                body.iter().chain(step.iter()).for_each(|i| Self::find_locals(i, locals))
+            },
+            Instruction::LoopUntil { body, .. }
+            | Instruction::Repeat { body, .. }
+            | Instruction::Switch { body, .. }
+            | Instruction::With { body, .. } => body.iter().for_each(|i| Self::find_locals(i, locals)),
+            _ => (),
+        }
+        Self::find_expression_locals(instruction, locals);
+    }
+
+    /// Finds the field ids of all the local variables read in the expressions of an instruction.
+    fn find_expression_locals(instruction: &Instruction, locals: &mut BTreeSet<usize>) {
+        let mut nodes: Vec<&Node> = Vec::new();
+        match instruction {
+            Instruction::SetField { accessor, value } | Instruction::ModifyField { accessor, value, .. } => {
+                nodes.push(value);
+                Self::accessor_nodes(&accessor.owner, &accessor.array, &mut nodes);
+            },
+            Instruction::SetVariable { accessor, value } | Instruction::ModifyVariable { accessor, value, .. } => {
+                nodes.push(value);
+                Self::accessor_nodes(&accessor.owner, &accessor.array, &mut nodes);
+            },
+            Instruction::EvalExpression { node: value }
+            | Instruction::IfElse { cond: value, .. }
+            | Instruction::LoopUntil { cond: value, .. }
+            | Instruction::LoopWhile { cond: value, .. }
+            | Instruction::Repeat { count: value, .. }
+            | Instruction::SetReturnValue { value }
+            | Instruction::With { target: value, .. } => nodes.push(value),
+            Instruction::Switch { input, cases, .. } => {
+                nodes.push(input);
+                nodes.extend(cases.iter().map(|(cond, _)| cond));
+            },
+            _ => (),
+        }
+        while let Some(node) = nodes.pop() {
+            match node {
+                Node::Function { args, .. } | Node::Script { args, .. } => nodes.extend(args.iter()),
+                Node::Field { accessor } => {
+                    if let InstanceIdentifier::Local = accessor.owner {
+                        locals.insert(accessor.index);
+                    }
+                    Self::accessor_nodes(&accessor.owner, &accessor.array, &mut nodes);
+                },
+                Node::Variable { accessor } => Self::accessor_nodes(&accessor.owner, &accessor.array, &mut nodes),
+                Node::Binary { left, right, .. } => {
+                    nodes.push(left);
+                    nodes.push(right);
+                },
+                Node::Unary { child, .. } => nodes.push(child),
+                Node::Literal { .. } | Node::RuntimeError { .. } => (),
+            }
+        }
+    }
+
+    fn accessor_nodes<'b>(owner: &'b InstanceIdentifier, array: &'b ArrayAccessor, nodes: &mut Vec<&'b Node>) {
+        if let InstanceIdentifier::Expression(node) = owner {
+            nodes.push(node);
+        }
+        match array {
+            ArrayAccessor::None => (),
+            ArrayAccessor::Single(index) => nodes.push(index),
+            ArrayAccessor::Double(index1, index2) => {
+                nodes.push(index1);
+                nodes.push(index2);
+            },
+        }
+    }
+}
+
+/// Makes a name safe to use as a file name.
+#[cfg(feature = "gm8exe")]
+fn file_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || " _-".contains(c) { c } else { '_' }).collect()
+}
+
+#[cfg(feature = "gm8exe")]
+impl GameCode {
+    /// Writes all of a game's compiled code to .gml files in the given directory, with a folder for each kind
+    /// of asset. Names are taken from the assets the code was compiled from. Returns how many files were written.
+    pub fn dump(&self, assets: &GameAssets, directory: &Path) -> io::Result<usize> {
+        let mut count = 0;
+        let mut write = |path: &Path, code: String| -> io::Result<()> {
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path.with_extension("gml"), code)?;
+            count += 1;
+            Ok(())
+        };
+
+        for (script, code) in assets.scripts.iter().zip(self.scripts.iter()) {
+            if let (Some(script), Some(code)) = (script, code) {
+                let path = directory.join("scripts").join(file_name(&script.name));
+                write(&path, decompile(&self.compiler, code))?;
+            }
+        }
+        for (object, events) in assets.objects.iter().zip(self.object_events.iter()) {
+            if let (Some(object), Some(events)) = (object, events) {
+                let object_directory = directory.join("objects").join(file_name(&object.name));
+                for (event_type, events) in events.iter().enumerate() {
+                    for (&event_number, tree) in events.iter() {
+                        let collision_object = match assets.objects.get(event_number as usize) {
+                            Some(Some(other)) if event_type == gml::ev::COLLISION => Some(other.name.as_str()),
+                            _ => None,
+                        };
+                        let mut decompiler = Decompiler::new(&self.compiler);
+                        tree.decompile(&mut decompiler);
+                        let name = event_name(event_type, event_number, collision_object);
+                        write(&object_directory.join(file_name(&name)), decompiler.finish())?;
+                    }
+                }
+            }
+        }
+        for (timeline, moments) in assets.timelines.iter().zip(self.timeline_moments.iter()) {
+            if let (Some(timeline), Some(moments)) = (timeline, moments) {
+                let timeline_directory = directory.join("timelines").join(file_name(&timeline.name));
+                for (moment, tree) in moments.iter() {
+                    let mut decompiler = Decompiler::new(&self.compiler);
+                    tree.decompile(&mut decompiler);
+                    write(&timeline_directory.join(format!("moment {}", moment)), decompiler.finish())?;
+                }
+            }
+        }
+        for (room, code) in assets.rooms.iter().zip(self.room_code.iter()) {
+            if let (Some(room), Some(code)) = (room, code) {
+                let room_directory = directory.join("rooms").join(file_name(&room.name));
+                if !code.creation_code.is_empty() {
+                    write(&room_directory.join("creation code"), decompile(&self.compiler, &code.creation_code))?;
+                }
+                for (instance, creation) in room.instances.iter().zip(code.instances.iter()) {
+                    if !creation.is_empty() {
+                        let path = room_directory.join(format!("instance {}", instance.id));
+                        write(&path, decompile(&self.compiler, creation))?;
+                    }
+                }
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(source: &str) -> String {
+        let mut compiler = Compiler::new();
+        let instructions = compiler.compile(source).unwrap();
+        decompile(&compiler, &instructions)
+    }
+
+    #[test]
+    fn statements() {
+        let source = "var i;\n\
+                      for (i = 0; i < 10; i += 1) {\n    a[i] = i * (2 + b);\n}\n\
+                      if (a == 1) {\n    b = 2;\n} else if (a == 2) {\n    exit;\n} else {\n    return 3;\n}\n\
+                      with (other) {\n    global.c -= 1;\n    other.x = (b - c) - (d - e);\n}\n\
+                      switch (a) {\n    case 1:\n    case 2:\n        show_message('\"hi\"');\n        break;\n    \
+                      default:\n        d = -(1);\n}\n";
+        let expected = "var i;\n\
+                        i = 0;\n\
+                        while (i < 10) {\n    a[i] = i * (2 + b);\n    i += 1;\n}\n\
+                        if (a == 1) {\n    b = 2;\n} else if (a == 2) {\n    exit;\n} else {\n    return 3;\n}\n\
+                        with (-2) {\n    global.c -= 1;\n    other.x = b - c - (d - e);\n}\n\
+                        switch (a) {\n    case 1:\n    case 2:\n        show_message('\"hi\"');\n        break;\n    \
+                        default:\n        d = -1;\n}\n";
+        assert_eq!(round_trip(source), expected);
+        assert_eq!(round_trip(expected), expected);
+    }
+
+    #[test]
+    fn expressions() {
+        let expected = "a = !(b || c) && -d;\nb = (5).x + c.y[1, 2];\n";
+        assert_eq!(round_trip("a = !(b || c) && -d; b = (5).x + c.y[1, 2]"), expected);
+        assert_eq!(round_trip("a = 'say \"it' + \"'s\" + '\"'"), "a = (\"say \" + '\"' + \"it's\" + '\"' + \"\");\n");
+    }
+
+    #[test]
+    fn functions_and_literals() {
+        // These two have the same body, so they can't be told apart by their function pointers
+        let source = "a = string_length(b) + string_byte_length(b);\n";
+        assert_eq!(round_trip(source), source);
+
+        let compiler = Compiler::new();
+        let decompiler = Decompiler::new(&compiler);
+        let cases: &[(f64, &str)] = &[
+            (1.5, "1.5"),
+            (f64::INFINITY, "power(2, 1024)"),
+            (f64::NEG_INFINITY, "-power(2, 1024)"),
+            (f64::NAN, "(power(2, 1024) - power(2, 1024))"),
+        ];
+        for &(value, expected) in cases {
+            assert_eq!(decompiler.expression(&Node::Literal { value: Value::Real(value) }), expected);
+        }
+
+        let set_only = [Instruction::SetReturnValue { value: Node::Literal { value: Value::Real(2.0) } }];
+        assert_eq!(decompile(&compiler, &set_only), "return 2;\n");
+        assert_eq!(round_trip("return 2; a = 1"), "return 2;\na = 1;\n");
+    }
+}
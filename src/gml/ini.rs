@@ -0,0 +1,290 @@
+//! INI file reading and writing, matching the behaviour of GetPrivateProfileString and friends.
+//!
+//! The file is kept as a list of raw lines so that anything we don't touch (comments, blank lines, odd spacing)
+//! is written back out exactly as it was read. Lines are interpreted on every lookup, which is fine given how
+//! small INI files tend to be.
+
+use std::{fmt, fs, io, path::PathBuf};
+
+pub struct IniFile {
+    /// Host path this file will be flushed to
+    path: PathBuf,
+
+    /// Raw lines of the file, without line endings
+    lines: Vec<String>,
+
+    /// Whether anything has been changed since the file was opened
+    dirty: bool,
+}
+
+/// What a single line of an INI file represents.
+#[derive(Debug, PartialEq)]
+enum Line<'a> {
+    Section(&'a str),
+    Pair(&'a str, &'a str),
+    Other,
+}
+
+impl<'a> Line<'a> {
+    fn parse(line: &'a str) -> Self {
+        let trimmed = line.trim();
+        if trimmed.starts_with(';') {
+            Line::Other
+        } else if let Some(name) = trimmed.strip_prefix('[') {
+            Line::Section(name.find(']').map_or(name, |end| &name[..end]).trim())
+        } else if let Some(eq) = trimmed.find('=') {
+            let key = trimmed[..eq].trim();
+            let mut value = trimmed[eq + 1..].trim();
+
+            // Matching quotes around a value are stripped, as in Windows
+            let bytes = value.as_bytes();
+            if bytes.len() >= 2 && (bytes[0] == b'"' || bytes[0] == b'\'') && bytes[0] == bytes[bytes.len() - 1] {
+                value = &value[1..value.len() - 1];
+            }
+            Line::Pair(key, value)
+        } else {
+            Line::Other
+        }
+    }
+}
+
+impl IniFile {
+    /// Opens an INI file at the given host path. A file which doesn't exist is treated as being empty,
+    /// and won't be created unless something is written to it.
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        match fs::read(&path) {
+            Ok(data) => Ok(Self::parse(path, &String::from_utf8_lossy(&data))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::parse(path, "")),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates an INI file from text content which will be flushed to the given path.
+    pub fn parse(path: PathBuf, content: &str) -> Self {
+        let mut lines: Vec<String> = content.split('\n').map(|l| l.trim_end_matches('\r').to_string()).collect();
+        if lines.last().map(String::is_empty) == Some(true) {
+            lines.pop();
+        }
+        Self { path, lines, dirty: false }
+    }
+
+    /// Writes the file back to disk if anything was changed. A file with no path, as parsed for testing, is never
+    /// written anywhere.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.dirty && !self.path.as_os_str().is_empty() {
+            fs::write(&self.path, self.to_string())?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Returns the range of line indices belonging to the first section with this name, not including its header.
+    fn section_range(&self, section: &str) -> Option<(usize, usize)> {
+        let start = self
+            .lines
+            .iter()
+            .position(|l| matches!(Line::parse(l), Line::Section(s) if s.eq_ignore_ascii_case(section)))?
+            + 1;
+        let end = self.lines[start..]
+            .iter()
+            .position(|l| matches!(Line::parse(l), Line::Section(_)))
+            .map_or(self.lines.len(), |i| start + i);
+        Some((start, end))
+    }
+
+    /// Finds the index of the line containing the given key in the given section.
+    fn find_key(&self, section: &str, key: &str) -> Option<usize> {
+        let (start, end) = self.section_range(section)?;
+        (start..end).find(|&i| matches!(Line::parse(&self.lines[i]), Line::Pair(k, _) if k.eq_ignore_ascii_case(key)))
+    }
+
+    /// Reads the value of a key, if it exists.
+    pub fn read(&self, section: &str, key: &str) -> Option<&str> {
+        match Line::parse(&self.lines[self.find_key(section, key)?]) {
+            Line::Pair(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Reads the value of a key as a real. Returns None if the key doesn't exist or isn't a valid number.
+    pub fn read_real(&self, section: &str, key: &str) -> Option<f64> {
+        let value = self.read(section, key)?;
+        match value.as_bytes().first() {
+            Some(b'0'..=b'9') | Some(b'-') | Some(b'+') | Some(b'.') => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of a key, creating the key and its section if they don't already exist.
+    pub fn write(&mut self, section: &str, key: &str, value: &str) {
+        if let Some(i) = self.find_key(section, key) {
+            // Keep the key as it was originally written, only the value changes
+            let old_key = match Line::parse(&self.lines[i]) {
+                Line::Pair(k, _) => k.to_string(),
+                _ => unreachable!(),
+            };
+            self.lines[i] = format!("{}={}", old_key, value);
+        } else if let Some((start, end)) = self.section_range(section) {
+            // New keys go after the last key in the section, so trailing comments and blank lines stay at the end
+            let insert_at = (start..end)
+                .rev()
+                .find(|&i| matches!(Line::parse(&self.lines[i]), Line::Pair(_, _)))
+                .map_or(start, |i| i + 1);
+            self.lines.insert(insert_at, format!("{}={}", key, value));
+        } else {
+            self.lines.push(format!("[{}]", section));
+            self.lines.push(format!("{}={}", key, value));
+        }
+        self.dirty = true;
+    }
+
+    /// Checks whether a key exists in a section.
+    pub fn key_exists(&self, section: &str, key: &str) -> bool {
+        self.find_key(section, key).is_some()
+    }
+
+    /// Checks whether a section exists.
+    pub fn section_exists(&self, section: &str) -> bool {
+        self.section_range(section).is_some()
+    }
+
+    /// Deletes a key from a section, if it exists.
+    pub fn delete_key(&mut self, section: &str, key: &str) {
+        if let Some(i) = self.find_key(section, key) {
+            self.lines.remove(i);
+            self.dirty = true;
+        }
+    }
+
+    /// Deletes a section along with everything in it, if it exists.
+    pub fn delete_section(&mut self, section: &str) {
+        if let Some((start, end)) = self.section_range(section) {
+            self.lines.drain(start - 1..end);
+            self.dirty = true;
+        }
+    }
+}
+
+impl fmt::Display for IniFile {
+    /// Formats the file as it would be written to disk, with CRLF line endings.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines.iter() {
+            write!(f, "{}\r\n", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ini(content: &str) -> IniFile {
+        IniFile::parse(PathBuf::new(), content)
+    }
+
+    #[test]
+    fn read_case_insensitive() {
+        let file = ini("[Settings]\r\nVolume=50\r\n[other]\r\nvolume=10\r\n");
+        assert_eq!(file.read("settings", "VOLUME"), Some("50"));
+        assert_eq!(file.read("OTHER", "Volume"), Some("10"));
+        assert_eq!(file.read("settings", "missing"), None);
+        assert_eq!(file.read("missing", "volume"), None);
+    }
+
+    #[test]
+    fn read_whitespace_and_quotes() {
+        let file = ini("  [ main ]  \nname = \"  Player One  \"\nshort='x'\nodd=\"abc'\nempty=\n");
+        assert_eq!(file.read("main", "name"), Some("  Player One  "));
+        assert_eq!(file.read("main", "short"), Some("x"));
+        assert_eq!(file.read("main", "odd"), Some("\"abc'"));
+        assert_eq!(file.read("main", "empty"), Some(""));
+    }
+
+    #[test]
+    fn comments() {
+        let file = ini("; a comment\n[a]\n;key=1\nkey=2 ; not a comment\nnot a pair\n");
+        assert_eq!(file.read("a", ";key"), None);
+        assert_eq!(file.read("a", "key"), Some("2 ; not a comment"));
+        assert!(!file.key_exists("a", "not a pair"));
+    }
+
+    #[test]
+    fn duplicates_use_first() {
+        let file = ini("[a]\nx=1\nx=2\n[a]\ny=3\n");
+        assert_eq!(file.read("a", "x"), Some("1"));
+        assert_eq!(file.read("a", "y"), None);
+    }
+
+    #[test]
+    fn keys_outside_sections() {
+        let file = ini("x=1\n[a]\n");
+        assert_eq!(file.read("", "x"), None);
+        assert!(!file.key_exists("a", "x"));
+    }
+
+    #[test]
+    fn read_real() {
+        let file = ini("[a]\nint=12\nneg=-3.5\nfrac=.25\nexp=1e3\ntext=12abc\ninf=inf\nempty=\n");
+        assert_eq!(file.read_real("a", "int"), Some(12.0));
+        assert_eq!(file.read_real("a", "neg"), Some(-3.5));
+        assert_eq!(file.read_real("a", "frac"), Some(0.25));
+        assert_eq!(file.read_real("a", "exp"), Some(1000.0));
+        assert_eq!(file.read_real("a", "text"), None);
+        assert_eq!(file.read_real("a", "inf"), None);
+        assert_eq!(file.read_real("a", "empty"), None);
+        assert_eq!(file.read_real("a", "missing"), None);
+    }
+
+    #[test]
+    fn write() {
+        let mut file = ini("; header\r\n[A]\r\nKey=1\r\n\r\n[b]\r\n");
+        file.write("a", "key", "2");
+        file.write("a", "new", "3");
+        file.write("B", "x", "y");
+        file.write("c", "z", "w");
+        assert_eq!(file.to_string(), "; header\r\n[A]\r\nKey=2\r\nnew=3\r\n\r\n[b]\r\nx=y\r\n[c]\r\nz=w\r\n");
+        assert!(file.dirty);
+    }
+
+    #[test]
+    fn delete() {
+        let mut file = ini("[a]\nx=1\ny=2\n[b]\nz=3\n[c]\n");
+        file.delete_key("a", "X");
+        file.delete_section("B");
+        assert_eq!(file.to_string(), "[a]\r\ny=2\r\n[c]\r\n");
+        assert!(!file.section_exists("b"));
+        assert!(file.section_exists("C"));
+    }
+
+    #[test]
+    fn untouched_file_is_clean() {
+        let mut file = ini("[a]\nx=1\n");
+        file.delete_key("a", "missing");
+        file.delete_section("missing");
+        assert!(!file.dirty);
+    }
+
+    #[test]
+    fn flush() {
+        let path = std::env::temp_dir().join(format!("gm8emulator-ini-{}.ini", std::process::id()));
+        let mut file = IniFile::open(path.clone()).unwrap();
+        file.flush().unwrap();
+        assert!(!path.exists(), "an unchanged file was written");
+        file.write("a", "x", "1");
+        drop(file);
+        assert!(!path.exists(), "the file was written without being flushed");
+
+        let mut file = IniFile::open(path.clone()).unwrap();
+        file.write("a", "x", "1");
+        file.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[a]\r\nx=1\r\n");
+        fs::remove_file(&path).unwrap();
+
+        // Nowhere to write it to
+        let mut file = ini("");
+        file.write("a", "x", "1");
+        file.flush().unwrap();
+        assert!(file.dirty);
+    }
+}
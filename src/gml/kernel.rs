@@ -1,9 +1,74 @@
 // This file was auto-generated based on a function table dump
 
 use crate::{
+    asset::{self, path, Path},
     game::Game,
-    gml::{self, Context, Value},
+    gml::{
+        self,
+        compiler::mappings,
+        datetime::{self, DateTime},
+        ev,
+        ini::IniFile,
+        mp::{self, Obstacles},
+        registry::{Data, HKey},
+        runtime::array_index,
+        Context, InstanceVariable, Value,
+    },
+    instance::Instance,
+    util,
 };
+use std::{cmp::Ordering, fs, rc::Rc};
+
+/// Converts a single argument into the given type, or returns an error if it's the wrong type.
+macro_rules! _arg_into {
+    (any, $i: expr, $v: expr) => {{
+        Ok($v.clone())
+    }};
+    (int, $i: expr, $v: expr) => {{
+        match $v {
+            Value::Real(r) => Ok(crate::util::ieee_round(*r)),
+            Value::Str(_) => Err(gml::Error::WrongArgumentType($i)),
+        }
+    }};
+    (real, $i: expr, $v: expr) => {{
+        match $v {
+            Value::Real(r) => Ok(*r),
+            Value::Str(_) => Err(gml::Error::WrongArgumentType($i)),
+        }
+    }};
+    (string, $i: expr, $v: expr) => {{
+        match $v {
+            Value::Str(s) => Ok(s.clone()),
+            Value::Real(_) => Err(gml::Error::WrongArgumentType($i)),
+        }
+    }};
+}
+
+/// Counts the number of token trees passed to it.
+macro_rules! _count_rep {
+    () => { 0usize };
+    ($x: tt $($xs: tt)*) => { 1usize + _count_rep!($($xs)*) };
+}
+
+/// Checks the count and types of a kernel function's arguments, and converts them into a tuple.
+///
+/// Usage: `let (x, y, name) = expect_args!(args, [real, int, string])?;`
+macro_rules! expect_args {
+    ($args: expr, [$($x: ident),*]) => {{
+        (|| -> gml::Result<_> {
+            let expected = _count_rep!($($x)*);
+            if $args.len() != expected {
+                return Err(gml::Error::WrongArgumentCount(expected, $args.len()))
+            }
+            let mut _i = 0;
+            Ok(($({
+                let arg = _arg_into!($x, _i, &$args[_i])?;
+                _i += 1;
+                arg
+            },)*))
+        })()
+    }};
+}
 
 impl Game {
     pub fn display_get_width(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -1725,224 +1790,264 @@ impl Game {
         unimplemented!("Called unimplemented kernel function action_effect")
     }
 
-    pub fn is_real(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn is_real(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function is_real")
+        Ok(matches!(expect_args!(args, [any])?.0, Value::Real(_)).into())
     }
 
-    pub fn is_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn is_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function is_string")
+        Ok(matches!(expect_args!(args, [any])?.0, Value::Str(_)).into())
     }
 
-    pub fn random(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn random(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function random")
+        let bound = expect_args!(args, [real])?.0;
+        Ok(self.rand.next(bound).into())
     }
 
-    pub fn random_range(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn random_range(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function random_range")
+        let (lower, upper) = expect_args!(args, [real, real])?;
+        Ok(self.rand.next_range(lower, upper).into())
     }
 
-    pub fn irandom(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn irandom(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function irandom")
+        // Negative bounds are reinterpreted as unsigned, which is what GM8 does
+        let bound = expect_args!(args, [int])?.0;
+        Ok(self.rand.next_int(bound as u32).into())
     }
 
-    pub fn irandom_range(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn irandom_range(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function irandom_range")
+        let (lower, upper) = expect_args!(args, [int, int])?;
+        Ok(self.rand.next_int_range(lower, upper).into())
     }
 
-    pub fn random_set_seed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn random_set_seed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function random_set_seed")
+        let seed = expect_args!(args, [int])?.0;
+        self.rand.set_seed(seed);
+        Ok(Default::default())
     }
 
-    pub fn random_get_seed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn random_get_seed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function random_get_seed")
+        expect_args!(args, [])?;
+        Ok(self.rand.seed().into())
     }
 
-    pub fn randomize(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn randomize(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function randomize")
+        expect_args!(args, [])?;
+        self.rand.randomize();
+        Ok(Default::default())
     }
 
-    pub fn abs(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn abs(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function abs")
+        Ok(expect_args!(args, [real])?.0.abs().into())
     }
 
-    pub fn round(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn round(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function round")
+        Ok(util::ieee_round_real(expect_args!(args, [real])?.0).into())
     }
 
-    pub fn floor(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn floor(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function floor")
+        Ok(expect_args!(args, [real])?.0.floor().into())
     }
 
-    pub fn ceil(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ceil(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function ceil")
+        Ok(expect_args!(args, [real])?.0.ceil().into())
     }
 
-    pub fn sign(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn sign(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function sign")
+        Ok(sign(expect_args!(args, [real])?.0).into())
     }
 
-    pub fn frac(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn frac(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function frac")
+        Ok(frac(expect_args!(args, [real])?.0).into())
     }
 
-    pub fn sqrt(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn sqrt(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function sqrt")
+        let x = expect_args!(args, [real])?.0;
+        if x < 0.0 {
+            return Err(gml::Error::FunctionError("sqrt".into(), "argument must not be negative".into()))
+        }
+        Ok(x.sqrt().into())
     }
 
-    pub fn sqr(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn sqr(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function sqr")
+        let x = expect_args!(args, [real])?.0;
+        Ok((x * x).into())
     }
 
-    pub fn exp(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn exp(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function exp")
+        Ok(expect_args!(args, [real])?.0.exp().into())
     }
 
-    pub fn ln(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ln(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function ln")
+        let x = expect_args!(args, [real])?.0;
+        Ok(positive("ln", x)?.ln().into())
     }
 
-    pub fn log2(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn log2(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function log2")
+        let x = expect_args!(args, [real])?.0;
+        Ok(positive("log2", x)?.log2().into())
     }
 
-    pub fn log10(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn log10(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function log10")
+        let x = expect_args!(args, [real])?.0;
+        Ok(positive("log10", x)?.log10().into())
     }
 
-    pub fn sin(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn sin(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function sin")
+        Ok(expect_args!(args, [real])?.0.sin().into())
     }
 
-    pub fn cos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn cos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function cos")
+        Ok(expect_args!(args, [real])?.0.cos().into())
     }
 
-    pub fn tan(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn tan(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function tan")
+        Ok(expect_args!(args, [real])?.0.tan().into())
     }
 
-    pub fn arcsin(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn arcsin(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function arcsin")
+        let x = expect_args!(args, [real])?.0;
+        Ok(unit_range("arcsin", x)?.asin().into())
     }
 
-    pub fn arccos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn arccos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function arccos")
+        let x = expect_args!(args, [real])?.0;
+        Ok(unit_range("arccos", x)?.acos().into())
     }
 
-    pub fn arctan(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn arctan(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function arctan")
+        Ok(expect_args!(args, [real])?.0.atan().into())
     }
 
-    pub fn arctan2(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn arctan2(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function arctan2")
+        let (y, x) = expect_args!(args, [real, real])?;
+        Ok(y.atan2(x).into())
     }
 
-    pub fn degtorad(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn degtorad(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function degtorad")
+        Ok(util::degtorad(expect_args!(args, [real])?.0).into())
     }
 
-    pub fn radtodeg(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn radtodeg(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function radtodeg")
+        Ok(util::radtodeg(expect_args!(args, [real])?.0).into())
     }
 
-    pub fn power(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn power(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function power")
+        let (x, n) = expect_args!(args, [real, real])?;
+        let result = x.powf(n);
+        if result.is_nan() && !x.is_nan() && !n.is_nan() {
+            return Err(gml::Error::FunctionError("power".into(), "negative base with a fractional exponent".into()))
+        }
+        Ok(result.into())
     }
 
-    pub fn logn(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn logn(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function logn")
+        let (n, x) = expect_args!(args, [real, real])?;
+        Ok((positive("logn", x)?.ln() / positive("logn", n)?.ln()).into())
     }
 
-    pub fn min(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function min")
+    pub fn min(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        Ok(extreme(args, Ordering::Less))
     }
 
-    pub fn max(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function max")
+    pub fn max(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        Ok(extreme(args, Ordering::Greater))
     }
 
-    pub fn min3(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn min3(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function min3")
+        let (a, b, c) = expect_args!(args, [real, real, real])?;
+        Ok(a.min(b).min(c).into())
     }
 
-    pub fn max3(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn max3(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function max3")
+        let (a, b, c) = expect_args!(args, [real, real, real])?;
+        Ok(a.max(b).max(c).into())
     }
 
-    pub fn mean(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function mean")
+    pub fn mean(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        let reals = reals(args)?;
+        Ok(if reals.is_empty() { 0.0 } else { reals.iter().sum::<f64>() / reals.len() as f64 }.into())
     }
 
-    pub fn median(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function median")
+    pub fn median(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        Ok(median(reals(args)?).into())
     }
 
-    pub fn choose(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function choose")
+    pub fn choose(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Picks from the same LCG as random(), so choose() calls stay in sync with GM8
+        match args.len() {
+            0 => Ok(Default::default()),
+            len => Ok(args[self.rand.next_index(len)].clone()),
+        }
     }
 
-    pub fn clamp(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn clamp(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function clamp")
+        let (x, min, max) = expect_args!(args, [real, real, real])?;
+        Ok(x.max(min).min(max).into())
     }
 
-    pub fn lerp(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn lerp(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function lerp")
+        let (a, b, amount) = expect_args!(args, [real, real, real])?;
+        Ok((a + (b - a) * amount).into())
     }
 
-    pub fn real(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn real(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function real")
+        Ok(match expect_args!(args, [any])?.0 {
+            Value::Real(real) => Value::Real(real),
+            Value::Str(string) => Value::Real(real(&string)),
+        })
     }
 
-    pub fn string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string")
+        Ok(Value::Str(expect_args!(args, [any])?.0.repr()))
     }
 
-    pub fn string_format(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_format(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function string_format")
+        let (value, total, decimals) = expect_args!(args, [real, int, int])?;
+        Ok(string_format(value, total, decimals).into())
     }
 
-    pub fn chr(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn chr(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function chr")
+        Ok(chr(expect_args!(args, [int])?.0).into())
     }
 
     pub fn ansi_char(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -1950,134 +2055,170 @@ impl Game {
         unimplemented!("Called unimplemented kernel function ansi_char")
     }
 
-    pub fn ord(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ord(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function ord")
+        Ok(ord(&expect_args!(args, [string])?.0).into())
     }
 
-    pub fn string_length(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_length(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_length")
+        Ok(string_length(&expect_args!(args, [string])?.0).into())
     }
 
-    pub fn string_byte_length(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_byte_length(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_byte_length")
+        Ok(string_length(&expect_args!(args, [string])?.0).into())
     }
 
-    pub fn string_byte_at(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_byte_at(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function string_byte_at")
+        let (string, index) = expect_args!(args, [string, int])?;
+        Ok(string_copy(&string, index, 1).chars().next().map_or(0.0, |c| f64::from(u32::from(c))).into())
     }
 
-    pub fn string_pos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_pos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function string_pos")
+        let (substring, string) = expect_args!(args, [string, string])?;
+        Ok(string_pos(&substring, &string).into())
     }
 
-    pub fn string_copy(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_copy(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function string_copy")
+        let (string, index, count) = expect_args!(args, [string, int, int])?;
+        Ok(string_copy(&string, index, count).into())
     }
 
-    pub fn string_char_at(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_char_at(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function string_char_at")
+        let (string, index) = expect_args!(args, [string, int])?;
+        Ok(string_copy(&string, index, 1).into())
     }
 
-    pub fn string_delete(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_delete(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function string_delete")
+        let (string, index, count) = expect_args!(args, [string, int, int])?;
+        Ok(string_delete(&string, index, count).into())
     }
 
-    pub fn string_insert(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_insert(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function string_insert")
+        let (substring, string, index) = expect_args!(args, [string, string, int])?;
+        Ok(string_insert(&substring, &string, index).into())
     }
 
-    pub fn string_lower(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_lower(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_lower")
+        Ok(expect_args!(args, [string])?.0.to_ascii_lowercase().into())
     }
 
-    pub fn string_upper(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_upper(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_upper")
+        Ok(expect_args!(args, [string])?.0.to_ascii_uppercase().into())
     }
 
-    pub fn string_repeat(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_repeat(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function string_repeat")
+        let (string, count) = expect_args!(args, [string, int])?;
+        Ok(string_repeat(&string, count).into())
     }
 
-    pub fn string_letters(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_letters(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_letters")
+        Ok(string_filter(&expect_args!(args, [string])?.0, char::is_ascii_alphabetic).into())
     }
 
-    pub fn string_digits(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_digits(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_digits")
+        Ok(string_filter(&expect_args!(args, [string])?.0, char::is_ascii_digit).into())
     }
 
-    pub fn string_lettersdigits(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_lettersdigits(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function string_lettersdigits")
+        Ok(string_filter(&expect_args!(args, [string])?.0, char::is_ascii_alphanumeric).into())
     }
 
-    pub fn string_replace(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_replace(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function string_replace")
+        let (string, substring, replacement) = expect_args!(args, [string, string, string])?;
+        Ok(string_replace(&string, &substring, &replacement, false).into())
     }
 
-    pub fn string_replace_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_replace_all(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function string_replace_all")
+        let (string, substring, replacement) = expect_args!(args, [string, string, string])?;
+        Ok(string_replace(&string, &substring, &replacement, true).into())
     }
 
-    pub fn string_count(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn string_count(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function string_count")
+        let (substring, string) = expect_args!(args, [string, string])?;
+        Ok(string_count(&substring, &string).into())
     }
 
-    pub fn dot_product(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn dot_product(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function dot_product")
+        let (x1, y1, x2, y2) = expect_args!(args, [real, real, real, real])?;
+        Ok((x1 * x2 + y1 * y2).into())
     }
 
-    pub fn dot_product_3d(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn dot_product_3d(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function dot_product_3d")
+        let (x1, y1, z1, x2, y2, z2) = expect_args!(args, [real, real, real, real, real, real])?;
+        Ok((x1 * x2 + y1 * y2 + z1 * z2).into())
     }
 
-    pub fn point_distance_3d(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn point_distance_3d(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function point_distance_3d")
+        let (x1, y1, z1, x2, y2, z2) = expect_args!(args, [real, real, real, real, real, real])?;
+        let (dx, dy, dz) = (x2 - x1, y2 - y1, z2 - z1);
+        Ok((dx * dx + dy * dy + dz * dz).sqrt().into())
     }
 
-    pub fn point_distance(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn point_distance(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function point_distance")
+        let (x1, y1, x2, y2) = expect_args!(args, [real, real, real, real])?;
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        Ok((dx * dx + dy * dy).sqrt().into())
     }
 
-    pub fn point_direction(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn point_direction(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function point_direction")
+        let (x1, y1, x2, y2) = expect_args!(args, [real, real, real, real])?;
+        Ok(point_direction(x1, y1, x2, y2).into())
     }
 
-    pub fn lengthdir_x(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn lengthdir_x(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function lengthdir_x")
+        let (length, direction) = expect_args!(args, [real, real])?;
+        Ok(lengthdir(length, direction).0.into())
     }
 
-    pub fn lengthdir_y(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn lengthdir_y(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function lengthdir_y")
+        let (length, direction) = expect_args!(args, [real, real])?;
+        Ok(lengthdir(length, direction).1.into())
     }
 
-    pub fn move_random(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_random(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function move_random")
+        let (hsnap, vsnap) = expect_args!(args, [real, real])?;
+
+        // Try a limited number of random positions, giving up if none of them are free
+        for _ in 0..100 {
+            let mut x = self.rand.next(f64::from(self.room_width));
+            let mut y = self.rand.next(f64::from(self.room_height));
+            if hsnap > 0.0 {
+                x = (x / hsnap).floor() * hsnap;
+            }
+            if vsnap > 0.0 {
+                y = (y / vsnap).floor() * vsnap;
+            }
+            if !self.check_collision_at(context.this, x, y, true) {
+                self.this_instance(context).set_position(x, y);
+                break
+            }
+        }
+        Ok(Default::default())
     }
 
     pub fn place_free(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2100,14 +2241,21 @@ impl Game {
         unimplemented!("Called unimplemented kernel function place_snapped")
     }
 
-    pub fn move_snap(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_snap(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function move_snap")
+        let (hsnap, vsnap) = expect_args!(args, [real, real])?;
+        let instance = self.this_instance(context);
+        instance.set_position(snap(instance.x.get(), hsnap), snap(instance.y.get(), vsnap));
+        Ok(Default::default())
     }
 
-    pub fn move_towards_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_towards_point(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function move_towards_point")
+        let (x, y, speed) = expect_args!(args, [real, real, real])?;
+        let instance = self.this_instance(context);
+        instance.direction.set(point_direction(instance.x.get(), instance.y.get(), x, y));
+        instance.set_speed(speed);
+        Ok(Default::default())
     }
 
     pub fn move_contact(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2115,24 +2263,32 @@ impl Game {
         unimplemented!("Called unimplemented kernel function move_contact")
     }
 
-    pub fn move_contact_solid(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_contact_solid(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function move_contact_solid")
+        let (direction, max_distance) = expect_args!(args, [real, real])?;
+        self.move_until_contact(context, direction, max_distance, true);
+        Ok(Default::default())
     }
 
-    pub fn move_contact_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_contact_all(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function move_contact_all")
+        let (direction, max_distance) = expect_args!(args, [real, real])?;
+        self.move_until_contact(context, direction, max_distance, false);
+        Ok(Default::default())
     }
 
-    pub fn move_outside_solid(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_outside_solid(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function move_outside_solid")
+        let (direction, max_distance) = expect_args!(args, [real, real])?;
+        self.move_until_outside(context, direction, max_distance, true);
+        Ok(Default::default())
     }
 
-    pub fn move_outside_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_outside_all(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function move_outside_all")
+        let (direction, max_distance) = expect_args!(args, [real, real])?;
+        self.move_until_outside(context, direction, max_distance, false);
+        Ok(Default::default())
     }
 
     pub fn move_bounce(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2140,29 +2296,49 @@ impl Game {
         unimplemented!("Called unimplemented kernel function move_bounce")
     }
 
-    pub fn move_bounce_solid(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_bounce_solid(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function move_bounce_solid")
+        let advanced = expect_args!(args, [any])?.0;
+        self.bounce(context, advanced.is_true(), true);
+        Ok(Default::default())
     }
 
-    pub fn move_bounce_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_bounce_all(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function move_bounce_all")
+        let advanced = expect_args!(args, [any])?.0;
+        self.bounce(context, advanced.is_true(), false);
+        Ok(Default::default())
     }
 
-    pub fn move_wrap(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn move_wrap(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function move_wrap")
+        let (horizontal, vertical, margin) = expect_args!(args, [any, any, real])?;
+        let instance = self.this_instance(context);
+        let (mut x, mut y) = (instance.x.get(), instance.y.get());
+        if horizontal.is_true() {
+            x = wrap(x, f64::from(self.room_width), margin);
+        }
+        if vertical.is_true() {
+            y = wrap(y, f64::from(self.room_height), margin);
+        }
+        instance.set_position(x, y);
+        Ok(Default::default())
     }
 
-    pub fn motion_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn motion_set(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function motion_set")
+        let (direction, speed) = expect_args!(args, [real, real])?;
+        let instance = self.this_instance(context);
+        instance.direction.set(direction);
+        instance.set_speed(speed);
+        Ok(Default::default())
     }
 
-    pub fn motion_add(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn motion_add(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function motion_add")
+        let (direction, speed) = expect_args!(args, [real, real])?;
+        self.this_instance(context).add_motion(direction, speed);
+        Ok(Default::default())
     }
 
     pub fn distance_to_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2175,109 +2351,252 @@ impl Game {
         unimplemented!("Called unimplemented kernel function distance_to_object")
     }
 
-    pub fn path_start(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_start(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function path_start")
-    }
-
-    pub fn path_end(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function path_end")
-    }
-
-    pub fn mp_linear_step(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+        let (path_id, speed, end_action, absolute) = expect_args!(args, [int, real, int, any])?;
+        let path = self.get_path(path_id)?;
+        let instance = self.this_instance(context);
+        let position = if speed < 0.0 { 1.0 } else { 0.0 };
+        instance.path_index.set(path_id);
+        instance.path_speed.set(speed);
+        instance.path_endaction.set(end_action);
+        instance.path_position.set(position);
+        instance.path_positionprevious.set(position);
+        if absolute.is_true() {
+            let start = path.start();
+            instance.path_xstart.set(start.x);
+            instance.path_ystart.set(start.y);
+            let (x, y) = instance.path_coords(path, position);
+            instance.set_position(x, y);
+        } else {
+            // Place the path so that the instance is already where it should be
+            instance.path_xstart.set(instance.x.get());
+            instance.path_ystart.set(instance.y.get());
+            let (x, y) = instance.path_coords(path, position);
+            instance.path_xstart.set(instance.path_xstart.get() * 2.0 - x);
+            instance.path_ystart.set(instance.path_ystart.get() * 2.0 - y);
+        }
+        Ok(Default::default())
+    }
+
+    pub fn path_end(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 0
+        expect_args!(args, [])?;
+        self.this_instance(context).path_index.set(-1);
+        Ok(Default::default())
+    }
+
+    pub fn mp_linear_step(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function mp_linear_step")
+        let (x, y, step_size, check_all) = expect_args!(args, [real, real, real, any])?;
+        let obstacles = if check_all.is_true() { Obstacles::All } else { Obstacles::Solid };
+        Ok(self.linear_step(context, x, y, step_size, obstacles).into())
     }
 
-    pub fn mp_linear_path(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_linear_path(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function mp_linear_path")
+        let (path_id, x, y, step_size, check_all) = expect_args!(args, [int, real, real, real, any])?;
+        let obstacles = if check_all.is_true() { Obstacles::All } else { Obstacles::Solid };
+        let max_steps = self.steps_to(context, x, y, step_size, 1.0);
+        let step = |game: &Self| game.linear_step(context, x, y, step_size, obstacles);
+        let reached = self.plan_path(context, path_id, max_steps, step)?;
+        Ok(reached.into())
     }
 
-    pub fn mp_linear_step_object(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_linear_step_object(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function mp_linear_step_object")
+        let (x, y, step_size, object_id) = expect_args!(args, [real, real, real, int])?;
+        Ok(self.linear_step(context, x, y, step_size, Obstacles::Object(object_id)).into())
     }
 
-    pub fn mp_linear_path_object(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_linear_path_object(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function mp_linear_path_object")
+        let (path_id, x, y, step_size, object_id) = expect_args!(args, [int, real, real, real, int])?;
+        let obstacles = Obstacles::Object(object_id);
+        let max_steps = self.steps_to(context, x, y, step_size, 1.0);
+        let step = |game: &Self| game.linear_step(context, x, y, step_size, obstacles);
+        let reached = self.plan_path(context, path_id, max_steps, step)?;
+        Ok(reached.into())
     }
 
-    pub fn mp_potential_settings(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_potential_settings(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function mp_potential_settings")
+        let (max_rotation, rotation_step, ahead, on_spot) = expect_args!(args, [real, real, real, any])?;
+        self.mp_potential = mp::PotentialSettings { ahead, max_rotation, rotation_step, on_spot: on_spot.is_true() };
+        Ok(Default::default())
     }
 
-    pub fn mp_potential_step(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_potential_step(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function mp_potential_step")
+        let (x, y, step_size, check_all) = expect_args!(args, [real, real, real, any])?;
+        let obstacles = if check_all.is_true() { Obstacles::All } else { Obstacles::Solid };
+        Ok(self.potential_step(context, x, y, step_size, obstacles).into())
     }
 
-    pub fn mp_potential_path(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_potential_path(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function mp_potential_path")
+        let (path_id, x, y, step_size, factor, check_all) = expect_args!(args, [int, real, real, real, real, any])?;
+        let obstacles = if check_all.is_true() { Obstacles::All } else { Obstacles::Solid };
+        let max_steps = self.steps_to(context, x, y, step_size, factor);
+        let step = |game: &Self| game.potential_step(context, x, y, step_size, obstacles);
+        let reached = self.plan_path(context, path_id, max_steps, step)?;
+        Ok(reached.into())
     }
 
-    pub fn mp_potential_step_object(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_potential_step_object(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function mp_potential_step_object")
+        let (x, y, step_size, object_id) = expect_args!(args, [real, real, real, int])?;
+        Ok(self.potential_step(context, x, y, step_size, Obstacles::Object(object_id)).into())
     }
 
-    pub fn mp_potential_path_object(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_potential_path_object(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function mp_potential_path_object")
+        let (path_id, x, y, step_size, factor, object_id) = expect_args!(args, [int, real, real, real, real, int])?;
+        let obstacles = Obstacles::Object(object_id);
+        let max_steps = self.steps_to(context, x, y, step_size, factor);
+        let step = |game: &Self| game.potential_step(context, x, y, step_size, obstacles);
+        let reached = self.plan_path(context, path_id, max_steps, step)?;
+        Ok(reached.into())
     }
 
-    pub fn mp_grid_create(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_create(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function mp_grid_create")
+        let (left, top, columns, rows, cell_width, cell_height) = expect_args!(args, [int, int, int, int, int, int])?;
+        match mp::Grid::new(left, top, columns, rows, cell_width, cell_height) {
+            Some(grid) => {
+                self.mp_grids.push(Some(grid));
+                Ok(((self.mp_grids.len() - 1) as f64).into())
+            },
+            None => Err(gml::Error::FunctionError(
+                "mp_grid_create".into(),
+                format!("a grid of {} by {} cells is too large", columns, rows),
+            )),
+        }
     }
 
-    pub fn mp_grid_destroy(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_destroy(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mp_grid_destroy")
+        let grid_id = expect_args!(args, [int])?.0;
+        self.mp_grid("mp_grid_destroy", grid_id)?;
+        self.mp_grids[grid_id as usize] = None;
+        Ok(Default::default())
     }
 
-    pub fn mp_grid_clear_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_clear_all(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mp_grid_clear_all")
+        let grid_id = expect_args!(args, [int])?.0;
+        self.mp_grid_mut("mp_grid_clear_all", grid_id)?.clear_all();
+        Ok(Default::default())
     }
 
-    pub fn mp_grid_clear_cell(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_clear_cell(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function mp_grid_clear_cell")
+        let (grid_id, column, row) = expect_args!(args, [int, int, int])?;
+        self.mp_grid_mut("mp_grid_clear_cell", grid_id)?.set_cell(column, row, false);
+        Ok(Default::default())
     }
 
-    pub fn mp_grid_clear_rectangle(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_clear_rectangle(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function mp_grid_clear_rectangle")
+        let (grid_id, x1, y1, x2, y2) = expect_args!(args, [int, int, int, int, int])?;
+        self.mp_grid_mut("mp_grid_clear_rectangle", grid_id)?.set_rectangle(x1, y1, x2, y2, false);
+        Ok(Default::default())
     }
 
-    pub fn mp_grid_add_cell(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_add_cell(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function mp_grid_add_cell")
+        let (grid_id, column, row) = expect_args!(args, [int, int, int])?;
+        self.mp_grid_mut("mp_grid_add_cell", grid_id)?.set_cell(column, row, true);
+        Ok(Default::default())
     }
 
-    pub fn mp_grid_add_rectangle(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mp_grid_add_rectangle(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function mp_grid_add_rectangle")
-    }
-
-    pub fn mp_grid_add_instances(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function mp_grid_add_instances")
-    }
-
-    pub fn mp_grid_path(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+        let (grid_id, x1, y1, x2, y2) = expect_args!(args, [int, int, int, int, int])?;
+        self.mp_grid_mut("mp_grid_add_rectangle", grid_id)?.set_rectangle(x1, y1, x2, y2, true);
+        Ok(Default::default())
+    }
+
+    pub fn mp_grid_add_instances(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 3
+        let (grid_id, object_id, precise) = expect_args!(args, [int, int, any])?;
+        let grid = self.mp_grid("mp_grid_add_instances", grid_id)?;
+        let mut cells = Vec::new();
+        for idx in self.find_instances(object_id, context) {
+            let (left, top, right, bottom) = self.instance_bbox(self.instance_list.get(idx).unwrap());
+            let (first_column, first_row, last_column, last_row) = grid.cells_in(left, top, right, bottom);
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    // Precise checking only counts cells which the instance's mask actually covers part of
+                    let (cell_left, cell_top, cell_right, cell_bottom) = grid.cell_rect(column, row);
+                    if !precise.is_true()
+                        || (cell_top.max(top)..cell_bottom.min(bottom + 1)).any(|y| {
+                            (cell_left.max(left)..cell_right.min(right + 1))
+                                .any(|x| self.check_collision_point(idx, x, y))
+                        })
+                    {
+                        cells.push((column, row));
+                    }
+                }
+            }
+        }
+        let grid = self.mp_grid_mut("mp_grid_add_instances", grid_id)?;
+        cells.into_iter().for_each(|(column, row)| grid.set_cell(column, row, true));
+        Ok(Default::default())
+    }
+
+    pub fn mp_grid_path(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 7
-        unimplemented!("Called unimplemented kernel function mp_grid_path")
-    }
-
-    pub fn mp_grid_draw(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mp_grid_draw")
+        let (grid_id, path_id, xstart, ystart, xgoal, ygoal, allow_diagonal) =
+            expect_args!(args, [int, int, real, real, real, real, any])?;
+        let grid = self.mp_grid("mp_grid_path", grid_id)?;
+        let start = grid.cell_at(xstart, ystart);
+        let goal = grid.cell_at(xgoal, ygoal);
+        let route = match grid.find_path(start, goal, allow_diagonal.is_true()) {
+            Some(route) => route,
+            None => return Ok(false.into()),
+        };
+
+        // The path goes from the exact start to the exact goal, through the centres of the cells in between
+        // If both are in the same cell, the route is only that cell and there's nothing in between
+        let mut points = vec![path::Point { x: xstart, y: ystart, speed: 100.0 }];
+        if route.len() >= 2 {
+            for &(column, row) in &route[1..route.len() - 1] {
+                let (left, top, right, bottom) = grid.cell_rect(column, row);
+                let (x, y) = (f64::from(left + right) / 2.0, f64::from(top + bottom) / 2.0);
+                points.push(path::Point { x, y, speed: 100.0 });
+            }
+        }
+        points.push(path::Point { x: xgoal, y: ygoal, speed: 100.0 });
+        let path = self.get_path_mut(path_id)?;
+        path.points = points;
+        path.closed = false;
+        path.update();
+        Ok(true.into())
+    }
+
+    pub fn mp_grid_draw(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 1
+        let grid_id = expect_args!(args, [int])?.0;
+        self.mp_grid("mp_grid_draw", grid_id)?;
+        let grid = self.mp_grids[grid_id as usize].as_ref().unwrap();
+        for row in 0..grid.rows {
+            for column in 0..grid.columns {
+                let (left, top, _, _) = grid.cell_rect(column, row);
+                let colour = if grid.is_forbidden(column, row) { 0x0000FF } else { 0x00FF00 };
+                self.renderer.draw_sprite(
+                    &self.blank_texture,
+                    f64::from(left),
+                    f64::from(top),
+                    f64::from(grid.cell_width),
+                    f64::from(grid.cell_height),
+                    0.0,
+                    colour,
+                    1.0,
+                );
+            }
+        }
+        Ok(Default::default())
     }
 
     pub fn collision_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2305,44 +2624,78 @@ impl Game {
         unimplemented!("Called unimplemented kernel function collision_line")
     }
 
-    pub fn instance_find(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_find(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function instance_find")
+        let (object_id, n) = expect_args!(args, [int, int])?;
+        if n < 0 {
+            return Ok(gml::NOONE.into())
+        }
+        let instance = self.find_instances(object_id, context).get(n as usize).copied();
+        Ok(self.instance_id_or_noone(instance))
     }
 
-    pub fn instance_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_exists(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function instance_exists")
+        let object_id = expect_args!(args, [int])?.0;
+        Ok((!self.find_instances(object_id, context).is_empty()).into())
     }
 
-    pub fn instance_number(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_number(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function instance_number")
+        let object_id = expect_args!(args, [int])?.0;
+        Ok((self.find_instances(object_id, context).len() as f64).into())
     }
 
-    pub fn instance_position(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_position(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function instance_position")
+        let (x, y, object_id) = expect_args!(args, [int, int, int])?;
+        let instance =
+            self.find_instances(object_id, context).into_iter().find(|&idx| self.check_collision_point(idx, x, y));
+        Ok(self.instance_id_or_noone(instance))
     }
 
-    pub fn instance_nearest(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_nearest(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function instance_nearest")
+        let (x, y, object_id) = expect_args!(args, [real, real, int])?;
+        let instance = self.instance_by_distance(object_id, x, y, Ordering::Less, context);
+        Ok(self.instance_id_or_noone(instance))
     }
 
-    pub fn instance_furthest(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_furthest(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function instance_furthest")
+        let (x, y, object_id) = expect_args!(args, [real, real, int])?;
+        let instance = self.instance_by_distance(object_id, x, y, Ordering::Greater, context);
+        Ok(self.instance_id_or_noone(instance))
     }
 
-    pub fn instance_place(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_place(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function instance_place")
+        let (x, y, object_id) = expect_args!(args, [real, real, int])?;
+        let instance = self.at_position(context.this, x, y, || {
+            self.find_instances(object_id, context)
+                .into_iter()
+                .find(|&idx| idx != context.this && self.check_collision(context.this, idx))
+        });
+        Ok(self.instance_id_or_noone(instance))
     }
 
-    pub fn instance_create(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_create(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function instance_create")
+        let (x, y, object_id) = expect_args!(args, [real, real, int])?;
+        let object = match self.assets.objects.get(object_id as usize) {
+            Some(Some(object)) => object,
+            _ => {
+                return Err(gml::Error::FunctionError(
+                    "instance_create".into(),
+                    format!("Creating instance for non-existing object: {}", object_id),
+                ))
+            },
+        };
+        self.last_instance_id += 1;
+        let id = self.last_instance_id;
+        let instance = self.instance_list.insert(Instance::new(id as usize, x, y, object_id, object));
+        self.run_instance_event(ev::CREATE, 0, instance, instance)?;
+        Ok(id.into())
     }
 
     pub fn instance_copy(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2350,14 +2703,50 @@ impl Game {
         unimplemented!("Called unimplemented kernel function instance_copy")
     }
 
-    pub fn instance_change(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function instance_change")
-    }
-
-    pub fn instance_destroy(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function instance_destroy")
+    pub fn instance_change(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 2
+        let (object_id, perform_events) = expect_args!(args, [int, any])?;
+        if !matches!(self.assets.objects.get(object_id as usize), Some(Some(_))) {
+            return Err(gml::Error::FunctionError(
+                "instance_change".into(),
+                format!("Changing instance to non-existing object: {}", object_id),
+            ))
+        }
+        if perform_events.is_true() {
+            self.run_instance_event(ev::DESTROY, 0, context.this, context.this)?;
+        }
+
+        self.instance_list.set_object(context.this, object_id);
+        if let (Some(instance), Some(Some(object))) =
+            (self.instance_list.get(context.this), self.assets.objects.get(object_id as usize))
+        {
+            instance.solid.set(object.solid);
+            instance.visible.set(object.visible);
+            instance.persistent.set(object.persistent);
+            instance.depth.set(object.depth);
+            instance.sprite_index.set(object.sprite_index);
+            instance.mask_index.set(object.mask_index);
+            instance.bbox_is_stale.set(true);
+        }
+
+        if perform_events.is_true() {
+            self.run_instance_event(ev::CREATE, 0, context.this, context.this)?;
+        }
+        Ok(Default::default())
+    }
+
+    pub fn instance_destroy(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 0
+        expect_args!(args, [])?;
+        if self.instance_list.exists(context.this) {
+            self.run_instance_event(ev::DESTROY, 0, context.this, context.this)?;
+
+            // The instance stays in the list until the end of the frame, but nothing can see it any more
+            if let Some(instance) = self.instance_list.get(context.this) {
+                instance.exists.set(false);
+            }
+        }
+        Ok(Default::default())
     }
 
     pub fn instance_sprite(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2385,34 +2774,77 @@ impl Game {
         unimplemented!("Called unimplemented kernel function position_change")
     }
 
-    pub fn instance_deactivate_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_deactivate_all(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function instance_deactivate_all")
+        let not_me = expect_args!(args, [any])?.0.is_true();
+        for instance in self.find_instances(gml::ALL, context) {
+            if !(not_me && instance == context.this) {
+                self.instance_list.deactivate(instance);
+            }
+        }
+        Ok(Default::default())
     }
 
-    pub fn instance_deactivate_object(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_deactivate_object(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function instance_deactivate_object")
+        let object_id = expect_args!(args, [int])?.0;
+        for instance in self.find_instances(object_id, context) {
+            self.instance_list.deactivate(instance);
+        }
+        Ok(Default::default())
     }
 
-    pub fn instance_deactivate_region(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn instance_deactivate_region(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function instance_deactivate_region")
-    }
-
-    pub fn instance_activate_all(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function instance_activate_all")
-    }
-
-    pub fn instance_activate_object(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function instance_activate_object")
-    }
-
-    pub fn instance_activate_region(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+        let (left, top, width, height, inside, not_me) = expect_args!(args, [int, int, int, int, any, any])?;
+        let region = (left, top, left.saturating_add(width), top.saturating_add(height));
+        for instance in self.find_instances(gml::ALL, context) {
+            if not_me.is_true() && instance == context.this {
+                continue
+            }
+            let bbox = match self.instance_list.get(instance) {
+                Some(inst) => self.instance_bbox(inst),
+                None => continue,
+            };
+            if in_region(bbox, region, inside.is_true()) {
+                self.instance_list.deactivate(instance);
+            }
+        }
+        Ok(Default::default())
+    }
+
+    pub fn instance_activate_all(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 0
+        expect_args!(args, [])?;
+        for instance in self.find_deactivated(gml::ALL) {
+            self.instance_list.activate(instance);
+        }
+        Ok(Default::default())
+    }
+
+    pub fn instance_activate_object(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        // Expected arg count: 1
+        let object_id = expect_args!(args, [int])?.0;
+        for instance in self.find_deactivated(object_id) {
+            self.instance_list.activate(instance);
+        }
+        Ok(Default::default())
+    }
+
+    pub fn instance_activate_region(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function instance_activate_region")
+        let (left, top, width, height, inside) = expect_args!(args, [int, int, int, int, any])?;
+        let region = (left, top, left.saturating_add(width), top.saturating_add(height));
+        for instance in self.find_deactivated(gml::ALL) {
+            let bbox = match self.instance_list.get(instance) {
+                Some(inst) => self.instance_bbox(inst),
+                None => continue,
+            };
+            if in_region(bbox, region, inside.is_true()) {
+                self.instance_list.activate(instance);
+            }
+        }
+        Ok(Default::default())
     }
 
     pub fn room_goto(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -2452,6 +2884,8 @@ impl Game {
 
     pub fn game_restart(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
+        self.reset_globals();
+        // TODO: destroy every instance and go back to the first room, once rooms can be changed
         unimplemented!("Called unimplemented kernel function game_restart")
     }
 
@@ -2820,109 +3254,164 @@ impl Game {
         unimplemented!("Called unimplemented kernel function environment_get_variable")
     }
 
-    pub fn registry_write_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_write_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function registry_write_string")
+        let (name, value) = expect_args!(args, [string, string])?;
+        let key = self.registry_default_key();
+        self.registry.write(HKey::CurrentUser, &key, &name, Data::Str(value.to_string()));
+        self.registry_save("registry_write_string")
     }
 
-    pub fn registry_write_real(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_write_real(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function registry_write_real")
+        let (name, value) = expect_args!(args, [string, real])?;
+        let key = self.registry_default_key();
+        self.registry.write(HKey::CurrentUser, &key, &name, Data::from_real(value));
+        self.registry_save("registry_write_real")
     }
 
-    pub fn registry_read_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_read_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function registry_read_string")
+        let name = expect_args!(args, [string])?.0;
+        let data = self.registry.read(HKey::CurrentUser, &self.registry_default_key(), &name);
+        Ok(data.and_then(Data::as_str).unwrap_or_default().into())
     }
 
-    pub fn registry_read_real(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_read_real(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function registry_read_real")
+        let name = expect_args!(args, [string])?.0;
+        let data = self.registry.read(HKey::CurrentUser, &self.registry_default_key(), &name);
+        Ok(data.and_then(Data::as_real).unwrap_or_default().into())
     }
 
-    pub fn registry_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_exists(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function registry_exists")
+        let name = expect_args!(args, [string])?.0;
+        Ok(self.registry.exists(HKey::CurrentUser, &self.registry_default_key(), &name).into())
     }
 
-    pub fn registry_write_string_ext(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_write_string_ext(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function registry_write_string_ext")
+        let (key, name, value) = expect_args!(args, [string, string, string])?;
+        self.registry.write(self.registry_root, &key, &name, Data::Str(value.to_string()));
+        self.registry_save("registry_write_string_ext")
     }
 
-    pub fn registry_write_real_ext(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_write_real_ext(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function registry_write_real_ext")
+        let (key, name, value) = expect_args!(args, [string, string, real])?;
+        self.registry.write(self.registry_root, &key, &name, Data::from_real(value));
+        self.registry_save("registry_write_real_ext")
     }
 
-    pub fn registry_read_string_ext(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_read_string_ext(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function registry_read_string_ext")
+        let (key, name) = expect_args!(args, [string, string])?;
+        let data = self.registry.read(self.registry_root, &key, &name);
+        Ok(data.and_then(Data::as_str).unwrap_or_default().into())
     }
 
-    pub fn registry_read_real_ext(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_read_real_ext(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function registry_read_real_ext")
+        let (key, name) = expect_args!(args, [string, string])?;
+        let data = self.registry.read(self.registry_root, &key, &name);
+        Ok(data.and_then(Data::as_real).unwrap_or_default().into())
     }
 
-    pub fn registry_exists_ext(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_exists_ext(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function registry_exists_ext")
+        let (key, name) = expect_args!(args, [string, string])?;
+        Ok(self.registry.exists(self.registry_root, &key, &name).into())
     }
 
-    pub fn registry_set_root(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn registry_set_root(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function registry_set_root")
+        // Unknown ids are ignored, leaving the root as it was
+        let root = expect_args!(args, [int])?.0;
+        if let Some(root) = HKey::from_id(root) {
+            self.registry_root = root;
+        }
+        Ok(Default::default())
     }
 
-    pub fn ini_open(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_open(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function ini_open")
+        let name = expect_args!(args, [string])?.0;
+        self.ini_flush("ini_open")?;
+        let path = self.sandbox.resolve(&name).ok_or_else(|| {
+            gml::Error::FunctionError("ini_open".into(), format!("path is outside of the sandbox: {}", name))
+        })?;
+        match IniFile::open(path) {
+            Ok(ini) => {
+                self.open_ini = Some(ini);
+                Ok(Default::default())
+            },
+            Err(e) => Err(gml::Error::FunctionError("ini_open".into(), format!("failed to read {}: {}", name, e))),
+        }
     }
 
-    pub fn ini_close(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_close(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function ini_close")
+        expect_args!(args, [])?;
+        self.ini_flush("ini_close")?;
+        self.open_ini = None;
+        Ok(Default::default())
     }
 
-    pub fn ini_read_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_read_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function ini_read_string")
+        let (section, key, default) = expect_args!(args, [string, string, string])?;
+        Ok(match self.ini("ini_read_string")?.read(&section, &key) {
+            Some(value) => Value::Str(value.into()),
+            None => Value::Str(default),
+        })
     }
 
-    pub fn ini_read_real(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_read_real(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function ini_read_real")
+        let (section, key, default) = expect_args!(args, [string, string, real])?;
+        Ok(Value::Real(self.ini("ini_read_real")?.read_real(&section, &key).unwrap_or(default)))
     }
 
-    pub fn ini_write_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_write_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function ini_write_string")
+        let (section, key, value) = expect_args!(args, [string, string, string])?;
+        self.ini_mut("ini_write_string")?.write(&section, &key, &value);
+        Ok(Default::default())
     }
 
-    pub fn ini_write_real(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_write_real(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function ini_write_real")
+        let (section, key, value) = expect_args!(args, [string, string, real])?;
+        let value = Value::Real(value).repr();
+        self.ini_mut("ini_write_real")?.write(&section, &key, &value);
+        Ok(Default::default())
     }
 
-    pub fn ini_key_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_key_exists(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function ini_key_exists")
+        let (section, key) = expect_args!(args, [string, string])?;
+        Ok(self.ini("ini_key_exists")?.key_exists(&section, &key).into())
     }
 
-    pub fn ini_section_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_section_exists(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function ini_section_exists")
+        let section = expect_args!(args, [string])?.0;
+        Ok(self.ini("ini_section_exists")?.section_exists(&section).into())
     }
 
-    pub fn ini_key_delete(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_key_delete(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function ini_key_delete")
+        let (section, key) = expect_args!(args, [string, string])?;
+        self.ini_mut("ini_key_delete")?.delete_key(&section, &key);
+        Ok(Default::default())
     }
 
-    pub fn ini_section_delete(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn ini_section_delete(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function ini_section_delete")
+        let section = expect_args!(args, [string])?.0;
+        self.ini_mut("ini_section_delete")?.delete_section(&section);
+        Ok(Default::default())
     }
 
     pub fn disk_free(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -3253,159 +3742,196 @@ impl Game {
         unimplemented!("Called unimplemented kernel function keyboard_set_numlock")
     }
 
-    pub fn keyboard_key_press(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_key_press(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_key_press")
+        let key = expect_args!(args, [int])?.0;
+        if (0..256).contains(&key) {
+            self.input.key_press(key as u8, None);
+        }
+        Ok(Default::default())
     }
 
-    pub fn keyboard_key_release(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_key_release(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_key_release")
+        let key = expect_args!(args, [int])?.0;
+        if (0..256).contains(&key) {
+            self.input.key_release(key as u8, None);
+        }
+        Ok(Default::default())
     }
 
-    pub fn keyboard_set_map(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_set_map(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function keyboard_set_map")
+        let (from, to) = expect_args!(args, [int, int])?;
+        self.input.set_map(from, to);
+        Ok(Default::default())
     }
 
-    pub fn keyboard_get_map(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_get_map(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_get_map")
+        let key = expect_args!(args, [int])?.0;
+        Ok(self.input.get_map(key).into())
     }
 
-    pub fn keyboard_unset_map(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_unset_map(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function keyboard_unset_map")
+        expect_args!(args, [])?;
+        self.input.unset_map();
+        Ok(Default::default())
     }
 
-    pub fn keyboard_check(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_check(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_check")
+        let key = expect_args!(args, [int])?.0;
+        Ok(self.input.keyboard_check(key).into())
     }
 
-    pub fn keyboard_check_pressed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_check_pressed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_check_pressed")
+        let key = expect_args!(args, [int])?.0;
+        Ok(self.input.keyboard_check_pressed(key).into())
     }
 
-    pub fn keyboard_check_released(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_check_released(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_check_released")
+        let key = expect_args!(args, [int])?.0;
+        Ok(self.input.keyboard_check_released(key).into())
     }
 
-    pub fn keyboard_check_direct(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_check_direct(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_check_direct")
+        let key = expect_args!(args, [int])?.0;
+        Ok(self.input.keyboard_check_direct(key).into())
     }
 
-    pub fn mouse_check_button(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mouse_check_button(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mouse_check_button")
+        let button = expect_args!(args, [int])?.0;
+        Ok(self.input.mouse_check_button(button).into())
     }
 
-    pub fn mouse_check_button_pressed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mouse_check_button_pressed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mouse_check_button_pressed")
+        let button = expect_args!(args, [int])?.0;
+        Ok(self.input.mouse_check_button_pressed(button).into())
     }
 
-    pub fn mouse_check_button_released(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mouse_check_button_released(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mouse_check_button_released")
+        let button = expect_args!(args, [int])?.0;
+        Ok(self.input.mouse_check_button_released(button).into())
     }
 
-    pub fn mouse_wheel_up(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mouse_wheel_up(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mouse_wheel_up")
+        expect_args!(args, [])?;
+        Ok(self.input.mouse_wheel_up().into())
     }
 
-    pub fn mouse_wheel_down(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mouse_wheel_down(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function mouse_wheel_down")
+        expect_args!(args, [])?;
+        Ok(self.input.mouse_wheel_down().into())
     }
 
-    pub fn joystick_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_exists(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_exists")
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).is_some().into())
     }
 
-    pub fn joystick_direction(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_direction(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_direction")
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).map(|joystick| joystick.direction()).unwrap_or(0).into())
     }
 
-    pub fn joystick_name(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_name(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_name")
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).map(|joystick| joystick.name.as_str()).unwrap_or("").into())
     }
 
-    pub fn joystick_axes(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_axes(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_axes")
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).map(|joystick| joystick.axes.len() as i32).unwrap_or(0).into())
     }
 
-    pub fn joystick_buttons(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_buttons(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_buttons")
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).map(|joystick| joystick.buttons.len() as i32).unwrap_or(0).into())
     }
 
-    pub fn joystick_has_pov(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_has_pov(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_has_pov")
+        let id = expect_args!(args, [int])?.0;
+        Ok(matches!(self.input.joystick(id), Some(joystick) if joystick.has_pov).into())
     }
 
-    pub fn joystick_check_button(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_check_button(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function joystick_check_button")
+        let (id, button) = expect_args!(args, [int, int])?;
+        Ok(matches!(self.input.joystick(id), Some(joystick) if joystick.button(button)).into())
     }
 
-    pub fn joystick_xpos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_xpos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_xpos")
+        self.joystick_axis(args, 0)
     }
 
-    pub fn joystick_ypos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_ypos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_ypos")
+        self.joystick_axis(args, 1)
     }
 
-    pub fn joystick_zpos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_zpos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_zpos")
+        self.joystick_axis(args, 2)
     }
 
-    pub fn joystick_rpos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_rpos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_rpos")
+        self.joystick_axis(args, 3)
     }
 
-    pub fn joystick_upos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_upos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_upos")
+        self.joystick_axis(args, 4)
     }
 
-    pub fn joystick_vpos(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_vpos(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_vpos")
+        self.joystick_axis(args, 5)
     }
 
-    pub fn joystick_pov(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn joystick_pov(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function joystick_pov")
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).and_then(|joystick| joystick.pov).unwrap_or(-1.0).into())
     }
 
-    pub fn keyboard_clear(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn keyboard_clear(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function keyboard_clear")
+        let key = expect_args!(args, [int])?.0;
+        self.input.keyboard_clear(key);
+        Ok(Default::default())
     }
 
-    pub fn mouse_clear(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn mouse_clear(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function mouse_clear")
+        let button = expect_args!(args, [int])?.0;
+        self.input.mouse_clear(button);
+        Ok(Default::default())
     }
 
-    pub fn io_clear(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn io_clear(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function io_clear")
+        expect_args!(args, [])?;
+        self.input.keyboard_clear_all();
+        self.input.mouse_clear_all();
+        Ok(Default::default())
     }
 
     pub fn io_handle(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -3696,12 +4222,23 @@ impl Game {
         unimplemented!("Called unimplemented kernel function external_call8")
     }
 
-    pub fn execute_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function execute_string")
+    pub fn execute_string(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        let (source, arguments) = code_args(args)?;
+        self.execute_code(context, source, arguments, "execute_string", "string")
     }
 
-    pub fn execute_file(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
-        unimplemented!("Called unimplemented kernel function execute_file")
+    pub fn execute_file(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
+        let (name, arguments) = code_args(args)?;
+        let path = self.sandbox.resolve(&name).ok_or_else(|| {
+            gml::Error::FunctionError("execute_file".into(), format!("path is outside of the sandbox: {}", name))
+        })?;
+        let source = match fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into(),
+            Err(e) => {
+                return Err(gml::Error::FunctionError("execute_file".into(), format!("failed to read {}: {}", name, e)))
+            },
+        };
+        self.execute_code(context, source, arguments, "execute_file", "file")
     }
 
     pub fn window_handle(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -3724,74 +4261,106 @@ impl Game {
         unimplemented!("Called unimplemented kernel function set_application_title")
     }
 
-    pub fn variable_global_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_exists(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function variable_global_exists")
+        let name = expect_args!(args, [string])?.0;
+        let exists = match instance_variable(&name) {
+            Some(var) => self.globals.vars.contains_key(&var),
+            None => matches!(self.compiler.find_field_id(&name), Some(id) if self.globals.fields.contains_key(&id)),
+        };
+        Ok(exists.into())
     }
 
-    pub fn variable_global_get(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_get(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function variable_global_get")
+        let name = expect_args!(args, [string])?.0;
+        self.get_global(&name, 0)
     }
 
-    pub fn variable_global_array_get(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_array_get(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function variable_global_array_get")
+        let (name, index) = expect_args!(args, [string, int])?;
+        self.get_global(&name, array_index(0, index)?)
     }
 
-    pub fn variable_global_array2_get(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_array2_get(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function variable_global_array2_get")
+        let (name, index1, index2) = expect_args!(args, [string, int, int])?;
+        self.get_global(&name, array_index(index1, index2)?)
     }
 
-    pub fn variable_global_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_set(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function variable_global_set")
+        let (name, value) = expect_args!(args, [string, any])?;
+        self.set_global(&name, 0, value);
+        Ok(Default::default())
     }
 
-    pub fn variable_global_array_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_array_set(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function variable_global_array_set")
+        let (name, index, value) = expect_args!(args, [string, int, any])?;
+        self.set_global(&name, array_index(0, index)?, value);
+        Ok(Default::default())
     }
 
-    pub fn variable_global_array2_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_global_array2_set(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function variable_global_array2_set")
+        let (name, index1, index2, value) = expect_args!(args, [string, int, int, any])?;
+        self.set_global(&name, array_index(index1, index2)?, value);
+        Ok(Default::default())
     }
 
-    pub fn variable_local_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_exists(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function variable_local_exists")
+        let name = expect_args!(args, [string])?.0;
+        let exists = match (self.instance_list.get(context.this), instance_variable(&name)) {
+            (Some(_), Some(var)) => is_instance_local(&var),
+            (Some(instance), None) => match self.compiler.find_field_id(&name) {
+                Some(id) => instance.fields.borrow().contains_key(&id),
+                None => false,
+            },
+            (None, _) => false,
+        };
+        Ok(exists.into())
     }
 
-    pub fn variable_local_get(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_get(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function variable_local_get")
+        let name = expect_args!(args, [string])?.0;
+        self.get_local("variable_local_get", context, &name, 0)
     }
 
-    pub fn variable_local_array_get(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_array_get(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function variable_local_array_get")
+        let (name, index) = expect_args!(args, [string, int])?;
+        self.get_local("variable_local_array_get", context, &name, array_index(0, index)?)
     }
 
-    pub fn variable_local_array2_get(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_array2_get(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function variable_local_array2_get")
+        let (name, index1, index2) = expect_args!(args, [string, int, int])?;
+        self.get_local("variable_local_array2_get", context, &name, array_index(index1, index2)?)
     }
 
-    pub fn variable_local_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_set(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function variable_local_set")
+        let (name, value) = expect_args!(args, [string, any])?;
+        self.set_local("variable_local_set", context, &name, 0, value)?;
+        Ok(Default::default())
     }
 
-    pub fn variable_local_array_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_array_set(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function variable_local_array_set")
+        let (name, index, value) = expect_args!(args, [string, int, any])?;
+        self.set_local("variable_local_array_set", context, &name, array_index(0, index)?, value)?;
+        Ok(Default::default())
     }
 
-    pub fn variable_local_array2_set(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn variable_local_array2_set(&mut self, context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function variable_local_array2_set")
+        let (name, index1, index2, value) = expect_args!(args, [string, int, int, any])?;
+        self.set_local("variable_local_array2_set", context, &name, array_index(index1, index2)?, value)?;
+        Ok(Default::default())
     }
 
     pub fn clipboard_has_text(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -3809,239 +4378,291 @@ impl Game {
         unimplemented!("Called unimplemented kernel function clipboard_get_text")
     }
 
-    pub fn date_current_datetime(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_current_datetime(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function date_current_datetime")
+        expect_args!(args, [])?;
+        Ok(self.clock.now().0.into())
     }
 
-    pub fn date_current_date(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_current_date(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function date_current_date")
+        expect_args!(args, [])?;
+        Ok(self.clock.now().date().0.into())
     }
 
-    pub fn date_current_time(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_current_time(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function date_current_time")
+        expect_args!(args, [])?;
+        Ok(self.clock.now().time().0.into())
     }
 
-    pub fn date_create_datetime(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_create_datetime(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function date_create_datetime")
+        let (year, month, day, hour, minute, second) = expect_args!(args, [int, int, int, int, int, int])?;
+        match (DateTime::from_date(year, month, day), DateTime::from_time(hour, minute, second)) {
+            (Some(date), Some(time)) => Ok(DateTime::combine(date, time).0.into()),
+            _ => Ok(Default::default()),
+        }
     }
 
-    pub fn date_create_date(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_create_date(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function date_create_date")
+        let (year, month, day) = expect_args!(args, [int, int, int])?;
+        Ok(DateTime::from_date(year, month, day).map_or(0.0, |d| d.0).into())
     }
 
-    pub fn date_create_time(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_create_time(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function date_create_time")
+        let (hour, minute, second) = expect_args!(args, [int, int, int])?;
+        Ok(DateTime::from_time(hour, minute, second).map_or(0.0, |d| d.0).into())
     }
 
-    pub fn date_valid_datetime(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_valid_datetime(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 6
-        unimplemented!("Called unimplemented kernel function date_valid_datetime")
+        let (year, month, day, hour, minute, second) = expect_args!(args, [int, int, int, int, int, int])?;
+        Ok((datetime::valid_date(year, month, day) && datetime::valid_time(hour, minute, second)).into())
     }
 
-    pub fn date_valid_date(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_valid_date(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function date_valid_date")
+        let (year, month, day) = expect_args!(args, [int, int, int])?;
+        Ok(datetime::valid_date(year, month, day).into())
     }
 
-    pub fn date_valid_time(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_valid_time(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function date_valid_time")
+        let (hour, minute, second) = expect_args!(args, [int, int, int])?;
+        Ok(datetime::valid_time(hour, minute, second).into())
     }
 
-    pub fn date_inc_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_year")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_month(amount.saturating_mul(12)).0.into())
     }
 
-    pub fn date_inc_month(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_month(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_month")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_month(amount).0.into())
     }
 
-    pub fn date_inc_week(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_week(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_week")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_days(f64::from(amount) * 7.0).0.into())
     }
 
-    pub fn date_inc_day(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_day(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_day")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_days(f64::from(amount)).0.into())
     }
 
-    pub fn date_inc_hour(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_hour(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_hour")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_days(f64::from(amount) / datetime::HOURS_PER_DAY).0.into())
     }
 
-    pub fn date_inc_minute(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_minute(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_minute")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_days(f64::from(amount) / datetime::MINUTES_PER_DAY).0.into())
     }
 
-    pub fn date_inc_second(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_inc_second(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_inc_second")
+        let (date, amount) = expect_args!(args, [real, int])?;
+        Ok(DateTime(date).inc_days(f64::from(amount) / datetime::SECONDS_PER_DAY).0.into())
     }
 
-    pub fn date_get_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_year")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).ymd().0.into())
     }
 
-    pub fn date_get_month(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_month(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_month")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).ymd().1.into())
     }
 
-    pub fn date_get_week(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_week(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_week")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).week().into())
     }
 
-    pub fn date_get_day(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_day(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_day")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).ymd().2.into())
     }
 
-    pub fn date_get_hour(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_hour(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_hour")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).hms().0.into())
     }
 
-    pub fn date_get_minute(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_minute(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_minute")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).hms().1.into())
     }
 
-    pub fn date_get_second(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_second(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_second")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).hms().2.into())
     }
 
-    pub fn date_get_weekday(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_weekday(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_weekday")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).weekday().into())
     }
 
-    pub fn date_get_day_of_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_day_of_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_day_of_year")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).day_of_year().into())
     }
 
-    pub fn date_get_hour_of_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_hour_of_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_hour_of_year")
+        let date = DateTime(expect_args!(args, [real])?.0);
+        Ok(((date.day_of_year() - 1) * 24 + date.hms().0).into())
     }
 
-    pub fn date_get_minute_of_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_minute_of_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_minute_of_year")
+        let date = DateTime(expect_args!(args, [real])?.0);
+        let (hour, minute, _, _) = date.hms();
+        Ok((((date.day_of_year() - 1) * 24 + hour) * 60 + minute).into())
     }
 
-    pub fn date_get_second_of_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_get_second_of_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_get_second_of_year")
+        let date = DateTime(expect_args!(args, [real])?.0);
+        let (hour, minute, second, _) = date.hms();
+        Ok(((((date.day_of_year() - 1) * 24 + hour) * 60 + minute) * 60 + second).into())
     }
 
-    pub fn date_year_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_year_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_year_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).year_span(DateTime(date2)).into())
     }
 
-    pub fn date_month_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_month_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_month_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).month_span(DateTime(date2)).into())
     }
 
-    pub fn date_week_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_week_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_week_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).week_span(DateTime(date2)).into())
     }
 
-    pub fn date_day_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_day_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_day_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).day_span(DateTime(date2)).into())
     }
 
-    pub fn date_hour_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_hour_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_hour_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok((DateTime(date1).day_span(DateTime(date2)) * datetime::HOURS_PER_DAY).into())
     }
 
-    pub fn date_minute_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_minute_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_minute_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok((DateTime(date1).day_span(DateTime(date2)) * datetime::MINUTES_PER_DAY).into())
     }
 
-    pub fn date_second_span(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_second_span(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_second_span")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok((DateTime(date1).day_span(DateTime(date2)) * datetime::SECONDS_PER_DAY).into())
     }
 
-    pub fn date_compare_datetime(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_compare_datetime(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_compare_datetime")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).compare(DateTime(date2)).into())
     }
 
-    pub fn date_compare_date(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_compare_date(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_compare_date")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).compare_date(DateTime(date2)).into())
     }
 
-    pub fn date_compare_time(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_compare_time(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function date_compare_time")
+        let (date1, date2) = expect_args!(args, [real, real])?;
+        Ok(DateTime(date1).compare_time(DateTime(date2)).into())
     }
 
-    pub fn date_date_of(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_date_of(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_date_of")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).date().0.into())
     }
 
-    pub fn date_time_of(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_time_of(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_time_of")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).time().0.into())
     }
 
-    pub fn date_datetime_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_datetime_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_datetime_string")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).datetime_string().into())
     }
 
-    pub fn date_date_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_date_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_date_string")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).date_string().into())
     }
 
-    pub fn date_time_string(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_time_string(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_time_string")
+        let date = expect_args!(args, [real])?.0;
+        Ok(DateTime(date).time_string().into())
     }
 
-    pub fn date_days_in_month(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_days_in_month(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_days_in_month")
+        let (year, month, _) = DateTime(expect_args!(args, [real])?.0).ymd();
+        Ok(datetime::days_in_month(year, month).into())
     }
 
-    pub fn date_days_in_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_days_in_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_days_in_year")
+        let year = DateTime(expect_args!(args, [real])?.0).year();
+        Ok((if datetime::is_leap_year(year) { 366 } else { 365 }).into())
     }
 
-    pub fn date_leap_year(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_leap_year(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_leap_year")
+        let year = DateTime(expect_args!(args, [real])?.0).year();
+        Ok(datetime::is_leap_year(year).into())
     }
 
-    pub fn date_is_today(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn date_is_today(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function date_is_today")
+        let date = expect_args!(args, [real])?.0;
+        Ok((DateTime(date).compare_date(self.clock.now()) == 0).into())
     }
 
     pub fn sprite_name(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -4437,169 +5058,253 @@ impl Game {
         unimplemented!("Called unimplemented kernel function script_execute")
     }
 
-    pub fn path_name(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_name(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_name")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(self.get_path(path_id)?.name.as_str().into())
     }
 
-    pub fn path_exists(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_exists(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_exists")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(self.get_path(path_id).is_ok().into())
     }
 
-    pub fn path_get_name(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_name(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_get_name")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(self.get_path(path_id)?.name.as_str().into())
     }
 
-    pub fn path_get_length(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_length(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_get_length")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(self.get_path(path_id)?.length().into())
     }
 
-    pub fn path_get_kind(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_kind(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_get_kind")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(self.get_path(path_id)?.smooth.into())
     }
 
-    pub fn path_get_closed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_closed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_get_closed")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(self.get_path(path_id)?.closed.into())
     }
 
-    pub fn path_get_precision(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_precision(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_get_precision")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok(f64::from(self.get_path(path_id)?.precision).into())
     }
 
-    pub fn path_get_number(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_number(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_get_number")
+        let path_id = expect_args!(args, [int])?.0;
+        Ok((self.get_path(path_id)?.points.len() as f64).into())
     }
 
-    pub fn path_get_point_x(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_point_x(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_get_point_x")
+        let (path_id, n) = expect_args!(args, [int, int])?;
+        Ok(self.get_path(path_id)?.points.get(n as usize).map_or(0.0, |p| p.x).into())
     }
 
-    pub fn path_get_point_y(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_point_y(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_get_point_y")
+        let (path_id, n) = expect_args!(args, [int, int])?;
+        Ok(self.get_path(path_id)?.points.get(n as usize).map_or(0.0, |p| p.y).into())
     }
 
-    pub fn path_get_point_speed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_point_speed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_get_point_speed")
+        let (path_id, n) = expect_args!(args, [int, int])?;
+        Ok(self.get_path(path_id)?.points.get(n as usize).map_or(0.0, |p| p.speed).into())
     }
 
-    pub fn path_get_x(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_x(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_get_x")
+        let (path_id, position) = expect_args!(args, [int, real])?;
+        Ok(self.get_path(path_id)?.get_point(position).x.into())
     }
 
-    pub fn path_get_y(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_y(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_get_y")
+        let (path_id, position) = expect_args!(args, [int, real])?;
+        Ok(self.get_path(path_id)?.get_point(position).y.into())
     }
 
-    pub fn path_get_speed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_get_speed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_get_speed")
+        let (path_id, position) = expect_args!(args, [int, real])?;
+        Ok(self.get_path(path_id)?.get_point(position).speed.into())
     }
 
-    pub fn path_set_kind(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_set_kind(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_set_kind")
+        let (path_id, kind) = expect_args!(args, [int, int])?;
+        let path = self.get_path_mut(path_id)?;
+        path.smooth = kind == 1;
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_set_closed(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_set_closed(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_set_closed")
+        let (path_id, closed) = expect_args!(args, [int, any])?;
+        let path = self.get_path_mut(path_id)?;
+        path.closed = closed.is_true();
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_set_precision(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_set_precision(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_set_precision")
+        let (path_id, precision) = expect_args!(args, [int, int])?;
+        let path = self.get_path_mut(path_id)?;
+        path.precision = precision.clamp(1, 8) as u32;
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_add(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_add(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 0
-        unimplemented!("Called unimplemented kernel function path_add")
+        expect_args!(args, [])?;
+        let path_id = self.assets.paths.len();
+        self.assets.paths.push(Some(Box::new(Path::new(format!("__newpath{}", path_id)))));
+        Ok((path_id as f64).into())
     }
 
-    pub fn path_duplicate(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_duplicate(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_duplicate")
+        let path_id = expect_args!(args, [int])?.0;
+        let mut path = self.get_path(path_id)?.clone();
+        let new_id = self.assets.paths.len();
+        path.name = format!("__newpath{}", new_id);
+        self.assets.paths.push(Some(Box::new(path)));
+        Ok((new_id as f64).into())
     }
 
-    pub fn path_assign(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_assign(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_assign")
+        let (path_id, source_id) = expect_args!(args, [int, int])?;
+        let mut source = self.get_path(source_id)?.clone();
+        let path = self.get_path_mut(path_id)?;
+        source.name = std::mem::take(&mut path.name);
+        *path = source;
+        Ok(Default::default())
     }
 
-    pub fn path_append(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_append(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_append")
+        let (path_id, source_id) = expect_args!(args, [int, int])?;
+        let points = self.get_path(source_id)?.points.clone();
+        let path = self.get_path_mut(path_id)?;
+        path.points.extend(points);
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_delete(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_delete(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_delete")
+        let path_id = expect_args!(args, [int])?.0;
+        self.get_path(path_id)?;
+        self.assets.paths[path_id as usize] = None;
+        Ok(Default::default())
     }
 
-    pub fn path_add_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_add_point(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 4
-        unimplemented!("Called unimplemented kernel function path_add_point")
+        let (path_id, x, y, speed) = expect_args!(args, [int, real, real, real])?;
+        let path = self.get_path_mut(path_id)?;
+        path.points.push(path::Point { x, y, speed });
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_insert_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_insert_point(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function path_insert_point")
+        let (path_id, n, x, y, speed) = expect_args!(args, [int, int, real, real, real])?;
+        let path = self.get_path_mut(path_id)?;
+        let n = (n.max(0) as usize).min(path.points.len());
+        path.points.insert(n, path::Point { x, y, speed });
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_change_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_change_point(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 5
-        unimplemented!("Called unimplemented kernel function path_change_point")
+        let (path_id, n, x, y, speed) = expect_args!(args, [int, int, real, real, real])?;
+        let path = self.get_path_mut(path_id)?;
+        if let Some(point) = path.points.get_mut(n as usize) {
+            *point = path::Point { x, y, speed };
+            path.update();
+        }
+        Ok(Default::default())
     }
 
-    pub fn path_delete_point(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_delete_point(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_delete_point")
+        let (path_id, n) = expect_args!(args, [int, int])?;
+        let path = self.get_path_mut(path_id)?;
+        if n >= 0 && (n as usize) < path.points.len() {
+            path.points.remove(n as usize);
+            path.update();
+        }
+        Ok(Default::default())
     }
 
-    pub fn path_clear_points(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_clear_points(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_clear_points")
+        let path_id = expect_args!(args, [int])?.0;
+        let path = self.get_path_mut(path_id)?;
+        path.points.clear();
+        path.update();
+        Ok(Default::default())
     }
 
-    pub fn path_reverse(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_reverse(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_reverse")
+        let path_id = expect_args!(args, [int])?.0;
+        self.get_path_mut(path_id)?.reverse();
+        Ok(Default::default())
     }
 
-    pub fn path_mirror(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_mirror(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_mirror")
+        let path_id = expect_args!(args, [int])?.0;
+        self.get_path_mut(path_id)?.mirror();
+        Ok(Default::default())
     }
 
-    pub fn path_flip(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_flip(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 1
-        unimplemented!("Called unimplemented kernel function path_flip")
+        let path_id = expect_args!(args, [int])?.0;
+        self.get_path_mut(path_id)?.flip();
+        Ok(Default::default())
     }
 
-    pub fn path_rotate(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_rotate(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 2
-        unimplemented!("Called unimplemented kernel function path_rotate")
+        let (path_id, angle) = expect_args!(args, [int, real])?;
+        self.get_path_mut(path_id)?.rotate(angle);
+        Ok(Default::default())
     }
 
-    pub fn path_scale(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_scale(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function path_scale")
+        let (path_id, xscale, yscale) = expect_args!(args, [int, real, real])?;
+        self.get_path_mut(path_id)?.scale(xscale, yscale);
+        Ok(Default::default())
     }
 
-    pub fn path_shift(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
+    pub fn path_shift(&mut self, _context: &mut Context, args: &[Value]) -> gml::Result<Value> {
         // Expected arg count: 3
-        unimplemented!("Called unimplemented kernel function path_shift")
+        let (path_id, xshift, yshift) = expect_args!(args, [int, real, real])?;
+        self.get_path_mut(path_id)?.shift(xshift, yshift);
+        Ok(Default::default())
     }
 
     pub fn timeline_name(&mut self, _context: &mut Context, _args: &[Value]) -> gml::Result<Value> {
@@ -6406,3 +7111,1213 @@ impl Game {
         unimplemented!("Called unimplemented kernel function d3d_model_floor")
     }
 }
+
+// Helpers shared between kernel functions
+impl Game {
+    /// Gets the open INI file, or an error if there isn't one.
+    fn ini(&self, function: &str) -> gml::Result<&IniFile> {
+        self.open_ini.as_ref().ok_or_else(|| gml::Error::FunctionError(function.into(), "no INI file is open".into()))
+    }
+
+    /// Gets the open INI file mutably, or an error if there isn't one.
+    fn ini_mut(&mut self, function: &str) -> gml::Result<&mut IniFile> {
+        self.open_ini.as_mut().ok_or_else(|| gml::Error::FunctionError(function.into(), "no INI file is open".into()))
+    }
+
+    /// Writes the open INI file to disk, if there is one.
+    fn ini_flush(&mut self, function: &str) -> gml::Result<()> {
+        match self.open_ini.as_mut().map(|ini| ini.flush()) {
+            Some(Err(e)) => Err(gml::Error::FunctionError(function.into(), format!("failed to write INI file: {}", e))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets the position of one of a joystick's axes for the joystick_*pos functions, or 0 if it isn't connected.
+    fn joystick_axis(&self, args: &[Value], axis: usize) -> gml::Result<Value> {
+        let id = expect_args!(args, [int])?.0;
+        Ok(self.input.joystick(id).map(|joystick| joystick.axis(axis)).unwrap_or(0.0).into())
+    }
+
+    /// Runs code given at runtime for execute_string or execute_file, with the caller's self and other.
+    /// Kind is what the code came from, for GM8's compilation error message.
+    fn execute_code(
+        &mut self,
+        context: &Context,
+        source: Rc<str>,
+        arguments: &[Value],
+        function: &str,
+        kind: &str,
+    ) -> gml::Result<Value> {
+        let code = self.compiler.compile_cached(source).map_err(|e| {
+            let message = format!("COMPILATION ERROR in {} to be executed\n{}", kind, e);
+            gml::Error::FunctionError(function.into(), message)
+        })?;
+        let mut new_context =
+            Context { argument_count: arguments.len(), ..context.nested(context.this, context.other) };
+        new_context.arguments.iter_mut().zip(arguments).for_each(|(dest, src)| *dest = src.clone());
+        self.run(&code, &mut new_context)?;
+        Ok(new_context.return_value)
+    }
+
+    /// Looks up a variable by name for the variable_* functions, registering a new field id if it's never been seen.
+    fn named_variable(&mut self, name: &str) -> NamedVariable {
+        match instance_variable(name) {
+            Some(var) => NamedVariable::Builtin(var),
+            None => NamedVariable::Field(self.compiler.get_field_id(name)),
+        }
+    }
+
+    /// Reads a global variable by name.
+    fn get_global(&mut self, name: &str, array_index: u32) -> gml::Result<Value> {
+        match self.named_variable(name) {
+            NamedVariable::Builtin(var) => self.get_dummy_var(&self.globals, &var, array_index),
+            NamedVariable::Field(id) => self.get_dummy_field(&self.globals, id, array_index),
+        }
+    }
+
+    /// Writes a global variable by name.
+    fn set_global(&mut self, name: &str, array_index: u32, value: Value) {
+        let variable = self.named_variable(name);
+        let mut globals = std::mem::take(&mut self.globals);
+        match variable {
+            NamedVariable::Builtin(var) => self.set_dummy_var(&mut globals, &var, array_index, value),
+            NamedVariable::Field(id) => self.set_dummy_field(&mut globals, id, array_index, value),
+        }
+        self.globals = globals;
+    }
+
+    /// Reads a variable of the self instance by name. There's nothing to read if there's no self instance.
+    fn get_local(&mut self, function: &str, context: &Context, name: &str, array_index: u32) -> gml::Result<Value> {
+        let variable = self.named_variable(name);
+        match (self.instance_list.get(context.this), variable) {
+            (Some(instance), NamedVariable::Builtin(var)) if is_instance_local(&var) => {
+                self.get_instance_var(instance, &var, array_index, context)
+            },
+            (Some(_), NamedVariable::Builtin(_)) => Err(not_local(function, name)),
+            (Some(instance), NamedVariable::Field(id)) => self.get_instance_field(instance, id, array_index),
+            (None, _) => Ok(Default::default()),
+        }
+    }
+
+    /// Writes a variable of the self instance by name.
+    fn set_local(
+        &mut self,
+        function: &str,
+        context: &mut Context,
+        name: &str,
+        array_index: u32,
+        value: Value,
+    ) -> gml::Result<()> {
+        let variable = self.named_variable(name);
+        match (self.instance_list.get(context.this), variable) {
+            (Some(instance), NamedVariable::Builtin(var)) if is_instance_local(&var) => {
+                self.set_instance_var(instance, &var, array_index, value, context)
+            },
+            (Some(_), NamedVariable::Builtin(_)) => Err(not_local(function, name)),
+            (Some(instance), NamedVariable::Field(id)) => {
+                self.set_instance_field(instance, id, array_index, value);
+                Ok(())
+            },
+            (None, _) => Ok(()),
+        }
+    }
+
+    /// The key which the non-_ext registry functions use, which is unique to each game.
+    fn registry_default_key(&self) -> String {
+        format!("Software\\GameMaker\\{}", self.game_id)
+    }
+
+    /// Saves the registry to disk after it's been written to.
+    fn registry_save(&self, function: &str) -> gml::Result<Value> {
+        match self.registry.save() {
+            Ok(()) => Ok(Default::default()),
+            Err(e) => Err(gml::Error::FunctionError(function.into(), format!("failed to save registry: {}", e))),
+        }
+    }
+
+    /// Gets the instance id of an instance handle, or noone if there isn't one.
+    fn instance_id_or_noone(&self, instance: Option<usize>) -> Value {
+        match instance.and_then(|idx| self.instance_list.get(idx)) {
+            Some(instance) => (instance.id.get() as i32).into(),
+            None => gml::NOONE.into(),
+        }
+    }
+
+    /// Gets a path asset, or an error if it doesn't exist.
+    fn get_path(&self, path_id: i32) -> gml::Result<&Path> {
+        match self.assets.paths.get(path_id as usize) {
+            Some(Some(path)) => Ok(path),
+            _ => Err(gml::Error::NonexistentAsset(asset::Type::Path, path_id as usize)),
+        }
+    }
+
+    /// Gets a path asset mutably, or an error if it doesn't exist.
+    fn get_path_mut(&mut self, path_id: i32) -> gml::Result<&mut Path> {
+        match self.assets.paths.get_mut(path_id as usize) {
+            Some(Some(path)) => Ok(path),
+            _ => Err(gml::Error::NonexistentAsset(asset::Type::Path, path_id as usize)),
+        }
+    }
+
+    /// Gets the instance which is running the current code. Instances are never removed from the list while
+    /// code is running, only at the end of a frame, so this can't fail.
+    fn this_instance(&self, context: &Context) -> &Instance {
+        self.instance_list.get(context.this).unwrap()
+    }
+
+    /// Moves an instance up to max_distance pixels in a direction, one pixel at a time, stopping just before it
+    /// would hit something. An instance which is already touching something doesn't move at all.
+    fn move_until_contact(&self, context: &Context, direction: f64, max_distance: f64, solid_only: bool) {
+        let instance = self.this_instance(context);
+        let max_distance = if max_distance <= 0.0 { 1000 } else { util::ieee_round(max_distance) };
+        let direction = util::degtorad(direction);
+        let (dx, dy) = (direction.cos(), -direction.sin());
+        if self.check_collision_at(context.this, instance.x.get(), instance.y.get(), solid_only) {
+            return
+        }
+        for _ in 0..max_distance {
+            let (x, y) = (instance.x.get() + dx, instance.y.get() + dy);
+            if self.check_collision_at(context.this, x, y, solid_only) {
+                break
+            }
+            instance.set_position(x, y);
+        }
+    }
+
+    /// Moves an instance up to max_distance pixels in a direction, one pixel at a time, until it isn't touching
+    /// anything any more.
+    fn move_until_outside(&self, context: &Context, direction: f64, max_distance: f64, solid_only: bool) {
+        let instance = self.this_instance(context);
+        let max_distance = if max_distance <= 0.0 { 1000 } else { util::ieee_round(max_distance) };
+        let direction = util::degtorad(direction);
+        let (dx, dy) = (direction.cos(), -direction.sin());
+        for _ in 0..max_distance {
+            if !self.check_collision_at(context.this, instance.x.get(), instance.y.get(), solid_only) {
+                break
+            }
+            instance.set_position(instance.x.get() + dx, instance.y.get() + dy);
+        }
+    }
+
+    /// Reverses an instance's motion if its next step would hit something.
+    /// Normal bouncing only flips hspeed and/or vspeed, depending on which of them cause the collision.
+    /// Advanced bouncing works out which way the surface is facing by checking which of the positions around the
+    /// instance are free, then reflects the motion off it.
+    fn bounce(&self, context: &Context, advanced: bool, solid_only: bool) {
+        let instance = self.this_instance(context);
+        let (x, y) = (instance.x.get(), instance.y.get());
+        let (hspeed, vspeed) = (instance.hspeed.get(), instance.vspeed.get());
+        let collides = |x, y| self.check_collision_at(context.this, x, y, solid_only);
+        if !collides(x + hspeed, y + vspeed) {
+            return
+        }
+
+        if advanced {
+            let speed = instance.speed.get().abs().max(1.0);
+            let (mut normal_x, mut normal_y) = (0.0, 0.0);
+            for i in 0..36 {
+                let angle = util::degtorad(f64::from(i * 10));
+                let (dx, dy) = (angle.cos(), -angle.sin());
+                if !collides(x + dx * speed, y + dy * speed) {
+                    normal_x += dx;
+                    normal_y += dy;
+                }
+            }
+            let length = normal_x.hypot(normal_y);
+            if length > 0.0 {
+                let (normal_x, normal_y) = (normal_x / length, normal_y / length);
+                let dot = hspeed * normal_x + vspeed * normal_y;
+                instance.hspeed.set(hspeed - 2.0 * dot * normal_x);
+                instance.vspeed.set(vspeed - 2.0 * dot * normal_y);
+                instance.update_speed_direction();
+                return
+            }
+        }
+
+        // If neither axis on its own causes the collision, it's a corner, so both get reversed
+        let mut hcollision = collides(x + hspeed, y);
+        let mut vcollision = collides(x, y + vspeed);
+        if !hcollision && !vcollision {
+            hcollision = true;
+            vcollision = true;
+        }
+        if hcollision {
+            instance.hspeed.set(-hspeed);
+        }
+        if vcollision {
+            instance.vspeed.set(-vspeed);
+        }
+        instance.update_speed_direction();
+    }
+
+    /// Gets a motion planning grid, or an error if it doesn't exist.
+    fn mp_grid(&self, function: &str, grid_id: i32) -> gml::Result<&mp::Grid> {
+        match self.mp_grids.get(grid_id as usize) {
+            Some(Some(grid)) => Ok(grid),
+            _ => Err(gml::Error::FunctionError(function.into(), format!("grid {} does not exist", grid_id))),
+        }
+    }
+
+    /// Gets a motion planning grid mutably, or an error if it doesn't exist.
+    fn mp_grid_mut(&mut self, function: &str, grid_id: i32) -> gml::Result<&mut mp::Grid> {
+        match self.mp_grids.get_mut(grid_id as usize) {
+            Some(Some(grid)) => Ok(grid),
+            _ => Err(gml::Error::FunctionError(function.into(), format!("grid {} does not exist", grid_id))),
+        }
+    }
+
+    /// Checks whether the current instance would run into any obstacles at the given position.
+    fn mp_blocked(&self, context: &Context, x: f64, y: f64, obstacles: Obstacles) -> bool {
+        match obstacles {
+            Obstacles::Solid => self.check_collision_at(context.this, x, y, true),
+            Obstacles::All => self.check_collision_at(context.this, x, y, false),
+            Obstacles::Object(object_id) => {
+                let others = self.find_instances(object_id, context);
+                self.at_position(context.this, x, y, || {
+                    others.iter().any(|&other| other != context.this && self.check_collision(context.this, other))
+                })
+            },
+        }
+    }
+
+    /// Moves the current instance a step straight towards a point, unless that would run into an obstacle.
+    /// Returns whether the instance has reached the point.
+    fn linear_step(&self, context: &Context, x: f64, y: f64, step_size: f64, obstacles: Obstacles) -> bool {
+        let instance = self.this_instance(context);
+        let (old_x, old_y) = (instance.x.get(), instance.y.get());
+        let distance = (x - old_x).hypot(y - old_y);
+        if distance == 0.0 {
+            return true
+        }
+        instance.set_direction(point_direction(old_x, old_y, x, y));
+        let (new_x, new_y, reached) = if distance <= step_size {
+            (x, y, true)
+        } else {
+            (old_x + (x - old_x) * step_size / distance, old_y + (y - old_y) * step_size / distance, false)
+        };
+        if step_size <= 0.0 || self.mp_blocked(context, new_x, new_y, obstacles) {
+            return false
+        }
+        instance.set_position(new_x, new_y);
+        reached
+    }
+
+    /// Moves the current instance a step towards a point, steering around obstacles using the potential field
+    /// settings. Directions are tried in order of how close they are to the goal, but only those within the
+    /// maximum rotation of the instance's current direction, and only if the way is clear for a few steps ahead.
+    /// Returns whether the instance has reached the point.
+    fn potential_step(&self, context: &Context, x: f64, y: f64, step_size: f64, obstacles: Obstacles) -> bool {
+        let instance = self.this_instance(context);
+        let (old_x, old_y) = (instance.x.get(), instance.y.get());
+        let distance = (x - old_x).hypot(y - old_y);
+        if distance == 0.0 {
+            return true
+        }
+        if distance <= step_size && !self.mp_blocked(context, x, y, obstacles) {
+            instance.set_direction(point_direction(old_x, old_y, x, y));
+            instance.set_position(x, y);
+            return true
+        }
+        if step_size <= 0.0 {
+            return false
+        }
+
+        let settings = self.mp_potential;
+        let goal_direction = point_direction(old_x, old_y, x, y);
+        let current = instance.direction.get();
+        let rotation_step = settings.rotation_step.max(1.0);
+        let mut offset = 0.0;
+        while offset <= 180.0 {
+            for &direction in &[goal_direction - offset, goal_direction + offset] {
+                if angle_difference(current, direction).abs() > settings.max_rotation {
+                    continue
+                }
+                let (dx, dy) = (util::degtorad(direction).cos(), -util::degtorad(direction).sin());
+                let ahead = step_size * settings.ahead.max(1.0);
+                let (new_x, new_y) = (old_x + dx * step_size, old_y + dy * step_size);
+                if !self.mp_blocked(context, old_x + dx * ahead, old_y + dy * ahead, obstacles)
+                    && !self.mp_blocked(context, new_x, new_y, obstacles)
+                {
+                    instance.set_direction(direction.rem_euclid(360.0));
+                    instance.set_position(new_x, new_y);
+                    return false
+                }
+            }
+            offset += rotation_step;
+        }
+
+        // There's nowhere to go, so turn towards the goal in the hope that there will be next time
+        if settings.on_spot {
+            let turn = angle_difference(current, goal_direction).clamp(-settings.max_rotation, settings.max_rotation);
+            instance.set_direction((current + turn).rem_euclid(360.0));
+        }
+        false
+    }
+
+    /// How many steps the mp_*_path functions may take to reach a point from the current instance's position.
+    fn steps_to(&self, context: &Context, x: f64, y: f64, step_size: f64, factor: f64) -> usize {
+        let instance = self.this_instance(context);
+        let distance = (x - instance.x.get()).hypot(y - instance.y.get());
+        if step_size > 0.0 { (distance * factor.max(0.0) / step_size).ceil() as usize + 1 } else { 0 }
+    }
+
+    /// Records the steps the current instance would take into a path, without actually moving it.
+    /// Returns whether the goal was reached within the given number of steps.
+    fn plan_path(
+        &mut self,
+        context: &Context,
+        path_id: i32,
+        max_steps: usize,
+        mut step: impl FnMut(&Self) -> bool,
+    ) -> gml::Result<bool> {
+        self.get_path(path_id)?;
+        let instance = self.this_instance(context);
+        let (x, y) = (instance.x.get(), instance.y.get());
+        let (direction, speed) = (instance.direction.get(), instance.speed.get());
+        let mut points = vec![path::Point { x, y, speed: 100.0 }];
+        let mut reached = false;
+        for _ in 0..max_steps {
+            reached = step(self);
+            let instance = self.this_instance(context);
+            let point = path::Point { x: instance.x.get(), y: instance.y.get(), speed: 100.0 };
+            if points.last() != Some(&point) {
+                points.push(point);
+            }
+            if reached {
+                break
+            }
+        }
+
+        let instance = self.this_instance(context);
+        instance.set_position(x, y);
+        instance.direction.set(direction);
+        instance.set_speed(speed);
+        let path = self.get_path_mut(path_id)?;
+        path.points = points;
+        path.closed = false;
+        path.update();
+        Ok(reached)
+    }
+
+    /// Finds the deactivated instances an identifier refers to, the same way find_instances does for active ones.
+    fn find_deactivated(&self, target: i32) -> Vec<usize> {
+        let children = match self.assets.objects.get(target as usize) {
+            Some(Some(object)) if target < gml::FIRST_INSTANCE_ID => Some(object.children.clone()),
+            _ => None,
+        };
+        self.instance_list
+            .iter_deactivated()
+            .filter(|&idx| {
+                let instance = self.instance_list.get(idx).unwrap();
+                match target {
+                    gml::ALL => true,
+                    target if target >= gml::FIRST_INSTANCE_ID => instance.id.get() as i32 == target,
+                    _ => children.as_ref().map(|c| c.borrow().contains(&instance.object_index.get())) == Some(true),
+                }
+            })
+            .collect()
+    }
+
+    /// Finds the nearest (Ordering::Less) or furthest (Ordering::Greater) instance of an object from a point.
+    fn instance_by_distance(
+        &self,
+        object_id: i32,
+        x: f64,
+        y: f64,
+        order: Ordering,
+        context: &Context,
+    ) -> Option<usize> {
+        let instances = self.find_instances(object_id, context);
+        closest_by(
+            instances.into_iter().filter_map(|idx| {
+                self.instance_list.get(idx).map(|instance| (idx, instance.x.get(), instance.y.get()))
+            }),
+            x,
+            y,
+            order,
+        )
+    }
+}
+
+// Instance searching and movement, kept separate from Game so it can be tested on its own.
+
+/// Snaps a coordinate to the nearest multiple of a grid size. A grid size of zero or less does nothing.
+fn snap(coord: f64, grid: f64) -> f64 {
+    if grid > 0.0 { f64::from(util::ieee_round(coord / grid)) * grid } else { coord }
+}
+
+/// Wraps a coordinate around to the other side of the room once it's more than margin pixels outside of it.
+fn wrap(coord: f64, size: f64, margin: f64) -> f64 {
+    if coord < -margin {
+        coord + size + margin * 2.0
+    } else if coord >= size + margin {
+        coord - size - margin * 2.0
+    } else {
+        coord
+    }
+}
+
+/// Checks a bounding box against a region, both given as (left, top, right, bottom).
+/// If inside is true, this is whether the box is at least partly inside the region, otherwise whether it's
+/// completely outside it.
+fn in_region(bbox: (i32, i32, i32, i32), region: (i32, i32, i32, i32), inside: bool) -> bool {
+    let (left, top, right, bottom) = bbox;
+    let (region_left, region_top, region_right, region_bottom) = region;
+    let overlaps = right >= region_left && left <= region_right && bottom >= region_top && top <= region_bottom;
+    overlaps == inside
+}
+
+/// Picks the point nearest to or furthest from (x, y), depending on the given ordering.
+/// If several are the same distance away, the first one wins.
+fn closest_by<T>(points: impl Iterator<Item = (T, f64, f64)>, x: f64, y: f64, order: Ordering) -> Option<T> {
+    let mut best: Option<(T, f64)> = None;
+    for (item, px, py) in points {
+        let distance = (px - x).hypot(py - y);
+        let better = match &best {
+            Some((_, best_distance)) => distance.partial_cmp(best_distance) == Some(order),
+            None => true,
+        };
+        if better {
+            best = Some((item, distance));
+        }
+    }
+    best.map(|(item, _)| item)
+}
+
+// Maths helpers, kept separate from Game so they can be tested on their own.
+
+/// Checks that an argument is greater than zero, as required by the logarithm functions.
+fn positive(function: &str, x: f64) -> gml::Result<f64> {
+    if x > 0.0 { Ok(x) } else { Err(gml::Error::FunctionError(function.into(), "argument must be positive".into())) }
+}
+
+/// Checks that an argument is between -1 and 1, as required by arcsin and arccos.
+fn unit_range(function: &str, x: f64) -> gml::Result<f64> {
+    if (-1.0..=1.0).contains(&x) {
+        Ok(x)
+    } else {
+        Err(gml::Error::FunctionError(function.into(), "argument must be between -1 and 1".into()))
+    }
+}
+
+// Variables named at runtime
+
+/// A variable as named by the variable_* functions: either a built-in one, or a field with the given id.
+enum NamedVariable {
+    Builtin(InstanceVariable),
+    Field(usize),
+}
+
+/// Finds the built-in variable with the given name, if there is one.
+fn instance_variable(name: &str) -> Option<InstanceVariable> {
+    mappings::INSTANCE_VARIABLES.iter().find(|(s, _)| *s == name).map(|(_, var)| *var)
+}
+
+/// Checks whether a built-in variable belongs to each instance, rather than to the game as a whole.
+/// Only these can be accessed with the variable_local_* functions.
+fn is_instance_local(var: &InstanceVariable) -> bool {
+    use InstanceVariable::*;
+    matches!(
+        var,
+        X | Y
+            | Xprevious
+            | Yprevious
+            | Xstart
+            | Ystart
+            | Hspeed
+            | Vspeed
+            | Direction
+            | Speed
+            | Friction
+            | Gravity
+            | GravityDirection
+            | ObjectIndex
+            | Id
+            | Alarm
+            | Solid
+            | Visible
+            | Persistent
+            | Depth
+            | BboxLeft
+            | BboxRight
+            | BboxTop
+            | BboxBottom
+            | SpriteIndex
+            | ImageIndex
+            | ImageSingle
+            | ImageNumber
+            | SpriteWidth
+            | SpriteHeight
+            | SpriteXoffset
+            | SpriteYoffset
+            | ImageXscale
+            | ImageYscale
+            | ImageAngle
+            | ImageAlpha
+            | ImageBlend
+            | ImageSpeed
+            | MaskIndex
+            | PathIndex
+            | PathPosition
+            | PathPositionprevious
+            | PathSpeed
+            | PathScale
+            | PathOrientation
+            | PathEndaction
+            | TimelineIndex
+            | TimelinePosition
+            | TimelineSpeed
+            | TimelineRunning
+            | TimelineLoop
+    )
+}
+
+/// The error for using a game-wide built-in variable with the variable_local_* functions.
+fn not_local(function: &str, name: &str) -> gml::Error {
+    gml::Error::FunctionError(function.into(), format!("{} is not a local variable", name))
+}
+
+/// Splits the arguments of execute_string and execute_file into the string they take and the arguments to pass on.
+fn code_args(args: &[Value]) -> gml::Result<(Rc<str>, &[Value])> {
+    match args.split_first() {
+        Some((Value::Str(string), arguments)) => Ok((string.clone(), arguments)),
+        Some(_) => Err(gml::Error::WrongArgumentType(0)),
+        None => Err(gml::Error::WrongArgumentCount(1, 0)),
+    }
+}
+
+/// Converts every argument of a variadic function into a real.
+fn reals(args: &[Value]) -> gml::Result<Vec<f64>> {
+    args.iter()
+        .enumerate()
+        .map(|(i, arg)| match arg {
+            Value::Real(real) => Ok(*real),
+            Value::Str(_) => Err(gml::Error::WrongArgumentType(i)),
+        })
+        .collect()
+}
+
+/// The fractional part of a real, which has the same sign as the real.
+fn frac(x: f64) -> f64 {
+    x - x.trunc()
+}
+
+fn sign(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+/// Finds the argument which compares as `target` to all the others, keeping the first in a tie.
+/// Reals and strings can be mixed, in which case any string counts as greater than any real.
+fn extreme(args: &[Value], target: Ordering) -> Value {
+    let compare = |a: &Value, b: &Value| match (a, b) {
+        (Value::Real(a), Value::Real(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (Value::Str(a), Value::Str(b)) => a.cmp(b),
+        (Value::Real(_), Value::Str(_)) => Ordering::Less,
+        (Value::Str(_), Value::Real(_)) => Ordering::Greater,
+    };
+    let mut result = match args.first() {
+        Some(first) => first,
+        None => return Default::default(),
+    };
+    for arg in &args[1..] {
+        if compare(arg, result) == target {
+            result = arg;
+        }
+    }
+    result.clone()
+}
+
+/// The middle value, or the lower of the two middle values if there's an even number of them.
+fn median(mut reals: Vec<f64>) -> f64 {
+    reals.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    if reals.is_empty() { 0.0 } else { reals[(reals.len() - 1) / 2] }
+}
+
+/// The horizontal and vertical components of a vector, with y pointing down.
+fn lengthdir(length: f64, direction: f64) -> (f64, f64) {
+    let (sin, cos) = util::degtorad(direction).sin_cos();
+    (length * cos, -length * sin)
+}
+
+/// Direction from one point to another in degrees, from 0 to 360 going anticlockwise with y pointing down.
+fn point_direction(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    let direction = util::radtodeg((y1 - y2).atan2(x2 - x1));
+    if direction < 0.0 { direction + 360.0 } else { direction }
+}
+
+/// Gets the signed difference between two directions in degrees, from -180 to 180.
+fn angle_difference(from: f64, to: f64) -> f64 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+// String manipulation, kept separate from Game so it can be tested on its own.
+// GM8 strings are indexed from 1, and out-of-range indices are clamped the way Delphi's Copy, Delete and Insert do.
+
+/// Parses as much of the start of a string as looks like a number, so "12abc" gives 12. Anything else gives 0.
+fn real(string: &str) -> f64 {
+    let string = string.trim_start();
+    let bytes = string.as_bytes();
+    let digits_from = |mut i: usize| {
+        while bytes.get(i).map(u8::is_ascii_digit) == Some(true) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut end = if let Some(b'+') | Some(b'-') = bytes.first() { 1 } else { 0 };
+    let int_end = digits_from(end);
+    let mut has_digits = int_end > end;
+    end = int_end;
+    if bytes.get(end) == Some(&b'.') {
+        let frac_end = digits_from(end + 1);
+        has_digits |= frac_end > end + 1;
+        end = frac_end;
+    }
+    if !has_digits {
+        return 0.0
+    }
+
+    // An exponent only counts if it has digits after it
+    if let Some(b'e') | Some(b'E') = bytes.get(end) {
+        let sign = if let Some(b'+') | Some(b'-') = bytes.get(end + 1) { 1 } else { 0 };
+        let exp_end = digits_from(end + 1 + sign);
+        if exp_end > end + 1 + sign {
+            end = exp_end;
+        }
+    }
+    string[..end].parse().unwrap_or(0.0)
+}
+
+fn string_format(value: f64, total: i32, decimals: i32) -> String {
+    // The total is the minimum width of the part before the decimal point, which is padded with spaces
+    let formatted = crate::util::format_decimal(value, decimals.max(0) as usize);
+    let int_width = formatted.find('.').unwrap_or(formatted.len());
+    let padding = (total.max(0) as usize).saturating_sub(int_width);
+    format!("{}{}", " ".repeat(padding), formatted)
+}
+
+fn chr(code: i32) -> String {
+    char::from(code as u8).to_string()
+}
+
+fn ord(string: &str) -> i32 {
+    string.chars().next().map_or(0, |c| c as i32)
+}
+
+fn string_length(string: &str) -> i32 {
+    string.chars().count() as i32
+}
+
+fn string_pos(substring: &str, string: &str) -> i32 {
+    match string.find(substring) {
+        Some(offset) if !substring.is_empty() => string_length(&string[..offset]) + 1,
+        _ => 0,
+    }
+}
+
+fn string_copy(string: &str, index: i32, count: i32) -> String {
+    string.chars().skip(index.max(1) as usize - 1).take(count.max(0) as usize).collect()
+}
+
+fn string_delete(string: &str, index: i32, count: i32) -> String {
+    if index < 1 || index > string_length(string) || count <= 0 {
+        return string.to_string()
+    }
+    let start = index as usize - 1;
+    string.chars().enumerate().filter(|(i, _)| *i < start || i - start >= count as usize).map(|(_, c)| c).collect()
+}
+
+fn string_insert(substring: &str, string: &str, index: i32) -> String {
+    let at = index.max(1).min(string_length(string) + 1) as usize - 1;
+    let offset = string.char_indices().nth(at).map_or(string.len(), |(offset, _)| offset);
+    format!("{}{}{}", &string[..offset], substring, &string[offset..])
+}
+
+fn string_repeat(string: &str, count: i32) -> String {
+    string.repeat(count.max(0) as usize)
+}
+
+fn string_filter(string: &str, keep: fn(&char) -> bool) -> String {
+    string.chars().filter(keep).collect()
+}
+
+fn string_replace(string: &str, substring: &str, replacement: &str, all: bool) -> String {
+    if substring.is_empty() {
+        string.to_string()
+    } else if all {
+        string.replace(substring, replacement)
+    } else {
+        string.replacen(substring, replacement, 1)
+    }
+}
+
+fn string_count(substring: &str, string: &str) -> i32 {
+    if substring.is_empty() { 0 } else { string.matches(substring).count() as i32 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn math_sign() {
+        let cases: &[(f64, f64)] = &[(5.5, 1.0), (-0.1, -1.0), (0.0, 0.0), (-0.0, 0.0), (f64::INFINITY, 1.0)];
+        for &(x, expected) in cases {
+            assert_eq!(sign(x), expected, "sign({})", x);
+        }
+    }
+
+    #[test]
+    fn math_round_frac() {
+        let cases: &[(f64, i32, f64)] =
+            &[(2.5, 2, 0.5), (3.5, 4, 0.5), (-2.5, -2, -0.5), (-2.75, -3, -0.75), (0.25, 0, 0.25), (7.0, 7, 0.0)];
+        for &(x, rounded, fraction) in cases {
+            assert_eq!(expect_args!([Value::Real(x)], [int]).unwrap().0, rounded, "round({})", x);
+            assert_eq!(frac(x), fraction, "frac({})", x);
+        }
+
+        let mut game = Game::test();
+        let mut context = Game::test_context(0);
+        let cases: &[(f64, f64)] = &[
+            (-0.5, 0.0),
+            (-1.5, -2.0),
+            (-2.5, -2.0),
+            (1e10, 1e10),
+            (-1e10 - 0.5, -1e10),
+            (2147483647.7, 2147483648.0),
+            (-2147483648.5, -2147483648.0),
+        ];
+        for &(x, expected) in cases {
+            match game.round(&mut context, &[Value::Real(x)]) {
+                Ok(Value::Real(rounded)) => assert_eq!(rounded, expected, "round({})", x),
+                other => panic!("round({}): {:?}", x, other),
+            }
+        }
+    }
+
+    #[test]
+    fn math_lengthdir() {
+        let cases: &[((f64, f64), (f64, f64))] = &[
+            ((10.0, 0.0), (10.0, 0.0)),
+            ((10.0, 90.0), (0.0, -10.0)),
+            ((10.0, 180.0), (-10.0, 0.0)),
+            ((10.0, 270.0), (0.0, 10.0)),
+            ((-4.0, 45.0), (-2.0 * 2f64.sqrt(), 2.0 * 2f64.sqrt())),
+            ((5.0, 720.0), (5.0, 0.0)),
+        ];
+        for &((length, direction), (x, y)) in cases {
+            let (dx, dy) = lengthdir(length, direction);
+            assert!((dx - x).abs() < 1e-12 && (dy - y).abs() < 1e-12, "lengthdir({}, {})", length, direction);
+        }
+    }
+
+    #[test]
+    fn math_domains() {
+        let cases: &[(f64, bool, bool)] =
+            &[(2.0, true, false), (1.0, true, true), (0.5, true, true), (0.0, false, true), (-1.0, false, true)];
+        for &(x, is_positive, in_unit_range) in cases {
+            assert_eq!(positive("ln", x).is_ok(), is_positive, "positive({})", x);
+            assert_eq!(unit_range("arcsin", x).is_ok(), in_unit_range, "unit_range({})", x);
+        }
+    }
+
+    #[test]
+    fn math_extremes() {
+        let s = |s: &str| Value::Str(s.into());
+        let cases = [
+            (vec![Value::Real(3.0), Value::Real(-1.0), Value::Real(2.0)], Value::Real(-1.0), Value::Real(3.0)),
+            (vec![s("b"), s("abc"), s("c")], s("abc"), s("c")),
+            (vec![Value::Real(100.0), s("a"), Value::Real(-5.0)], Value::Real(-5.0), s("a")),
+            (vec![s("z"), Value::Real(1.0)], Value::Real(1.0), s("z")),
+            (vec![Value::Real(7.0)], Value::Real(7.0), Value::Real(7.0)),
+            (vec![], Value::Real(0.0), Value::Real(0.0)),
+        ];
+        for (args, min, max) in cases.iter() {
+            assert!(extreme(args, Ordering::Less).almost_equals(min), "min({:?})", args);
+            assert!(extreme(args, Ordering::Greater).almost_equals(max), "max({:?})", args);
+        }
+    }
+
+    #[test]
+    fn math_mean_median() {
+        let cases: &[(&[f64], f64)] =
+            &[(&[3.0, 1.0, 2.0], 2.0), (&[4.0, 1.0, 3.0, 2.0], 2.0), (&[5.0], 5.0), (&[], 0.0), (&[-1.0, -2.0], -2.0)];
+        for &(args, expected) in cases {
+            assert_eq!(median(args.to_vec()), expected, "median({:?})", args);
+        }
+        assert!(reals(&[Value::Real(1.0), Value::Str("2".into())]).is_err());
+    }
+
+    #[test]
+    fn math_point_direction() {
+        let cases: &[((f64, f64, f64, f64), f64)] = &[
+            ((0.0, 0.0, 10.0, 0.0), 0.0),
+            ((0.0, 0.0, 0.0, -10.0), 90.0),
+            ((0.0, 0.0, -10.0, 0.0), 180.0),
+            ((0.0, 0.0, 0.0, 10.0), 270.0),
+            ((5.0, 5.0, 10.0, 0.0), 45.0),
+            ((5.0, 5.0, 5.0, 5.0), 0.0),
+        ];
+        for &((x1, y1, x2, y2), expected) in cases {
+            let direction = point_direction(x1, y1, x2, y2);
+            let args = format!("{}, {}, {}, {}", x1, y1, x2, y2);
+            assert!((direction - expected).abs() < 1e-12, "point_direction({}) = {}", args, direction);
+        }
+    }
+
+    #[test]
+    fn math_angle_difference() {
+        assert_eq!(angle_difference(10.0, 30.0), 20.0);
+        assert_eq!(angle_difference(350.0, 10.0), 20.0);
+        assert_eq!(angle_difference(10.0, 350.0), -20.0);
+        assert_eq!(angle_difference(0.0, 180.0), -180.0);
+        assert_eq!(angle_difference(90.0, -270.0), 0.0);
+    }
+
+    #[test]
+    fn real_from_string() {
+        let cases: &[(&str, f64)] = &[
+            ("12", 12.0),
+            ("12abc", 12.0),
+            ("  -3.5", -3.5),
+            ("+.25x", 0.25),
+            ("7.", 7.0),
+            ("1e3", 1000.0),
+            ("1e", 1.0),
+            ("2E-2z", 0.02),
+            ("1.5e+", 1.5),
+            ("abc", 0.0),
+            ("", 0.0),
+            ("-", 0.0),
+            (".", 0.0),
+            ("- 5", 0.0),
+            ("0x10", 0.0),
+        ];
+        for &(input, expected) in cases {
+            assert_eq!(real(input), expected, "real({:?})", input);
+        }
+    }
+
+    #[test]
+    fn string_from_value() {
+        let cases: &[(Value, &str)] = &[
+            (Value::Real(0.1), "0.10"),
+            (Value::Real(3.0), "3"),
+            (Value::Real(-2.0), "-2"),
+            (Value::Real(0.125), "0.13"),
+            (Value::Real(1.999), "2.00"),
+            (Value::Real(-0.5), "-0.50"),
+            (Value::Real(-0.0), "0"),
+            (Value::Real(1e15), "1000000000000000"),
+            (Value::Real(f64::INFINITY), "INF"),
+            (Value::Real(f64::NAN), "NAN"),
+            (Value::Str("text".into()), "text"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.repr().as_ref(), *expected, "string({:?})", input);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn format() {
+        let cases: &[(f64, i32, i32, &str)] = &[
+            (2.71828, 0, 2, "2.72"),
+            (2.71828, 4, 2, "   2.72"),
+            (-2.71828, 4, 0, "  -3"),
+            (123.456, 2, 1, "123.5"),
+            (0.5, 3, -1, "  1"),
+            (7.0, -5, 3, "7.000"),
+        ];
+        for &(value, total, decimals, expected) in cases {
+            let args = format!("{}, {}, {}", value, total, decimals);
+            assert_eq!(string_format(value, total, decimals), expected, "string_format({})", args);
+        }
+    }
+
+    #[test]
+    fn chr_ord() {
+        let cases: &[(i32, &str, i32)] =
+            &[(65, "A", 65), (48, "0", 48), (321, "A", 65), (-191, "A", 65), (233, "é", 233)];
+        for &(code, string, back) in cases {
+            assert_eq!(chr(code), string, "chr({})", code);
+            assert_eq!(ord(string), back, "ord({:?})", string);
+        }
+        assert_eq!(ord(""), 0);
+        assert_eq!(ord("abc"), 97);
+    }
+
+    #[test]
+    fn length() {
+        let cases: &[(&str, i32)] = &[("", 0), ("a", 1), ("hello", 5), ("é€", 2)];
+        for &(string, expected) in cases {
+            assert_eq!(string_length(string), expected, "string_length({:?})", string);
+        }
+    }
+
+    #[test]
+    fn pos() {
+        let cases: &[(&str, &str, i32)] = &[
+            ("b", "abcb", 2),
+            ("cb", "abcb", 3),
+            ("x", "abcb", 0),
+            ("", "abc", 0),
+            ("abc", "", 0),
+            ("c", "éc", 2),
+        ];
+        for &(substring, string, expected) in cases {
+            assert_eq!(string_pos(substring, string), expected, "string_pos({:?}, {:?})", substring, string);
+        }
+    }
+
+    #[test]
+    fn copy() {
+        let cases: &[(&str, i32, i32, &str)] = &[
+            ("abcdef", 2, 3, "bcd"),
+            ("abcdef", 1, 6, "abcdef"),
+            ("abcdef", 4, 10, "def"),
+            ("abcdef", 0, 2, "ab"),
+            ("abcdef", -5, 2, "ab"),
+            ("abcdef", 7, 1, ""),
+            ("abcdef", 2, 0, ""),
+            ("abcdef", 2, -1, ""),
+            ("éàü", 2, 1, "à"),
+        ];
+        for &(string, index, count, expected) in cases {
+            let args = format!("{:?}, {}, {}", string, index, count);
+            assert_eq!(string_copy(string, index, count), expected, "string_copy({})", args);
+        }
+    }
+
+    #[test]
+    fn delete() {
+        let cases: &[(&str, i32, i32, &str)] = &[
+            ("abcdef", 2, 3, "aef"),
+            ("abcdef", 1, 1, "bcdef"),
+            ("abcdef", 5, 10, "abcd"),
+            ("abcdef", 0, 2, "abcdef"),
+            ("abcdef", 7, 1, "abcdef"),
+            ("abcdef", 3, 0, "abcdef"),
+            ("abcdef", 3, -2, "abcdef"),
+            ("éàü", 2, 1, "éü"),
+        ];
+        for &(string, index, count, expected) in cases {
+            let args = format!("{:?}, {}, {}", string, index, count);
+            assert_eq!(string_delete(string, index, count), expected, "string_delete({})", args);
+        }
+    }
+
+    #[test]
+    fn insert() {
+        let cases: &[(&str, &str, i32, &str)] = &[
+            ("XY", "abc", 1, "XYabc"),
+            ("XY", "abc", 2, "aXYbc"),
+            ("XY", "abc", 4, "abcXY"),
+            ("XY", "abc", 10, "abcXY"),
+            ("XY", "abc", 0, "XYabc"),
+            ("XY", "abc", -3, "XYabc"),
+            ("XY", "", 1, "XY"),
+            ("X", "éà", 2, "éXà"),
+        ];
+        for &(substring, string, index, expected) in cases {
+            let args = format!("{:?}, {:?}, {}", substring, string, index);
+            assert_eq!(string_insert(substring, string, index), expected, "string_insert({})", args);
+        }
+    }
+
+    #[test]
+    fn repeat() {
+        let cases: &[(&str, i32, &str)] =
+            &[("ab", 3, "ababab"), ("ab", 1, "ab"), ("ab", 0, ""), ("ab", -2, ""), ("", 5, "")];
+        for &(string, count, expected) in cases {
+            assert_eq!(string_repeat(string, count), expected, "string_repeat({:?}, {})", string, count);
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let cases: &[(&str, &str, &str, &str)] = &[
+            ("a1 b2_C3!", "abC", "123", "a1b2C3"),
+            ("", "", "", ""),
+            ("é5", "", "5", "5"),
+        ];
+        for &(string, letters, digits, both) in cases {
+            assert_eq!(string_filter(string, char::is_ascii_alphabetic), letters, "string_letters({:?})", string);
+            assert_eq!(string_filter(string, char::is_ascii_digit), digits, "string_digits({:?})", string);
+            assert_eq!(string_filter(string, char::is_ascii_alphanumeric), both, "string_lettersdigits({:?})", string);
+        }
+    }
+
+    #[test]
+    fn replace() {
+        let cases: &[(&str, &str, &str, &str, &str)] = &[
+            ("aXbXc", "X", "-", "a-bXc", "a-b-c"),
+            ("aaaa", "aa", "b", "baa", "bb"),
+            ("abc", "x", "y", "abc", "abc"),
+            ("abc", "", "y", "abc", "abc"),
+            ("abc", "b", "", "ac", "ac"),
+        ];
+        for &(string, substring, replacement, first, all) in cases {
+            let args = format!("{:?}, {:?}, {:?}", string, substring, replacement);
+            assert_eq!(string_replace(string, substring, replacement, false), first, "string_replace({})", args);
+            assert_eq!(string_replace(string, substring, replacement, true), all, "string_replace_all({})", args);
+        }
+    }
+
+    #[test]
+    fn count() {
+        let cases: &[(&str, &str, i32)] =
+            &[("a", "banana", 3), ("ana", "banana", 1), ("x", "banana", 0), ("", "banana", 0)];
+        for &(substring, string, expected) in cases {
+            assert_eq!(string_count(substring, string), expected, "string_count({:?}, {:?})", substring, string);
+        }
+    }
+
+    #[test]
+    fn nearest_and_furthest() {
+        let points = [(1, 10.0, 0.0), (2, 0.0, 3.0), (3, -3.0, 0.0), (4, 0.0, -10.0)];
+        let closest = |order| closest_by(points.iter().copied(), 0.0, 0.0, order);
+        assert_eq!(closest(Ordering::Less), Some(2));
+        assert_eq!(closest(Ordering::Greater), Some(1));
+        assert_eq!(closest_by(std::iter::empty::<(i32, f64, f64)>(), 0.0, 0.0, Ordering::Less), None);
+    }
+
+    #[test]
+    fn region() {
+        let region = (0, 0, 100, 100);
+        let cases = [
+            ((10, 10, 20, 20), true),
+            ((-10, -10, 0, 0), true),
+            ((90, 50, 150, 60), true),
+            ((-50, -50, 150, 150), true),
+            ((101, 0, 120, 10), false),
+            ((0, -20, 10, -1), false),
+        ];
+        for &(bbox, overlaps) in cases.iter() {
+            assert_eq!(in_region(bbox, region, true), overlaps, "{:?} inside", bbox);
+            assert_eq!(in_region(bbox, region, false), !overlaps, "{:?} outside", bbox);
+        }
+
+        // Regions reaching past the edge of what an int can hold stop at the edge
+        let mut game = Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        let big = 2_000_000_000;
+        let deactivated = |game: &Game| game.instance_list.iter_deactivated().any(|i| i == inst);
+        game.test_run(inst, &format!("instance_deactivate_region(-{0}, -10, {0}, {0}, true, false)", big)).unwrap();
+        assert!(deactivated(&game));
+        game.test_run(inst, &format!("instance_activate_region({0}, 0, {0}, 10, true)", big)).unwrap();
+        assert!(deactivated(&game));
+        game.test_run(inst, &format!("instance_activate_region(-10, -10, {0}, {0}, true)", big)).unwrap();
+        assert!(!deactivated(&game));
+    }
+
+    #[test]
+    fn snap_and_wrap() {
+        assert_eq!(snap(13.0, 8.0), 16.0);
+        assert_eq!(snap(12.0, 8.0), 16.0);
+        assert_eq!(snap(4.0, 8.0), 0.0);
+        assert_eq!(snap(-13.0, 8.0), -16.0);
+        assert_eq!(snap(13.0, 0.0), 13.0);
+
+        assert_eq!(wrap(-1.0, 640.0, 0.0), 639.0);
+        assert_eq!(wrap(640.0, 640.0, 0.0), 0.0);
+        assert_eq!(wrap(320.0, 640.0, 0.0), 320.0);
+        assert_eq!(wrap(-20.0, 640.0, 16.0), 652.0);
+        assert_eq!(wrap(-10.0, 640.0, 16.0), -10.0);
+        assert_eq!(wrap(660.0, 640.0, 16.0), -12.0);
+    }
+
+    #[test]
+    fn dates() {
+        let mut game = Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.assert_returns(inst, "return date_current_date()", 39998.0);
+        game.assert_returns(inst, "return date_current_time() * 86400", 15.0 * 3600.0 + 4.0 * 60.0 + 5.0);
+        game.assert_returns(inst, "return date_is_today(date_create_date(2009, 7, 4))", 1.0);
+        game.assert_returns(inst, "return current_time", 0.0);
+        game.assert_returns(inst, "return date_get_year(power(10, 300))", 9999.0);
+        game.assert_returns(inst, "return date_get_month(-power(10, 300))", 1.0);
+        game.assert_returns(inst, "return date_get_second_of_year(power(10, 300))", 364.0 * 86400.0);
+    }
+
+    #[test]
+    fn ini_reals() {
+        let mut game = Game::test();
+        game.open_ini = Some(IniFile::parse(Default::default(), ""));
+        let inst = game.test_instance(0.0, 0.0);
+        game.test_run(inst, "ini_write_real('a', 'x', 0.125) ini_write_real('a', 'y', -3)").unwrap();
+        game.test_run(inst, "ini_write_real('a', 'z', power(10, 20))").unwrap();
+        let ini = game.open_ini.as_ref().unwrap();
+        assert_eq!(ini.read("a", "x"), Some("0.13"));
+        assert_eq!(ini.read("a", "y"), Some("-3"));
+        assert_eq!(ini.read("a", "z"), Some("100000000000000000000"));
+    }
+
+    #[test]
+    fn variable_names() {
+        assert_eq!(instance_variable("image_xscale"), Some(InstanceVariable::ImageXscale));
+        assert_eq!(instance_variable("my_var"), None);
+        assert_eq!(array_index(0, 5).unwrap(), 5);
+        assert_eq!(array_index(2, 31999).unwrap(), 95999);
+        assert!(matches!(array_index(-1, 0), Err(gml::Error::InvalidArrayIndex(-1))));
+        assert!(matches!(array_index(0, 32000), Err(gml::Error::InvalidArrayIndex(32000))));
+    }
+
+    #[test]
+    fn grid_path() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.test_run(inst, "g = mp_grid_create(0, 0, 4, 4, 16, 16); p = path_add()").unwrap();
+        // Start and goal in the same cell
+        game.assert_returns(inst, "return mp_grid_path(g, p, 4, 4, 10, 12, 1) + path_get_number(p) * 10", 21.0);
+        game.assert_returns(inst, "return path_get_x(p, 1) + path_get_y(p, 1)", 22.0);
+        game.assert_returns(inst, "return mp_grid_path(g, p, 8, 8, 56, 8, 0) + path_get_number(p) * 10", 41.0);
+        game.assert_errors(inst, "mp_grid_create(0, 0, 100000, 100000, 1, 1)", "too large");
+    }
+
+    #[test]
+    fn execute_code() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        let root = std::env::temp_dir().join(format!("gm8emulator-execute-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("double.gml"), "return argument0 * 2").unwrap();
+        fs::write(root.join("broken.gml"), "return (").unwrap();
+        game.sandbox = crate::sandbox::Sandbox::new(root.clone());
+
+        game.assert_returns(inst, "return execute_string('return argument0 + argument1', 2, 3)", 5.0);
+        game.assert_returns(inst, "x = 4; return execute_string('return x + argument_count')", 4.0);
+        game.assert_returns(inst, "return execute_file('double.gml', 21)", 42.0);
+        game.assert_errors(inst, "execute_string('x = ')", "COMPILATION ERROR in string to be executed");
+        game.assert_errors(inst, "execute_file('broken.gml')", "COMPILATION ERROR in file to be executed");
+        game.assert_errors(inst, "execute_file('missing.gml')", "failed to read missing.gml");
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn variable_local() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.assert_returns(inst, "variable_local_set('x', 12); return x", 12.0);
+        game.assert_returns(inst, "speed = 3; return variable_local_get('speed')", 3.0);
+        game.assert_returns(inst, "return variable_local_array_get('alarm', 2)", -1.0);
+        game.assert_returns(inst, "variable_local_array_set('alarm', 2, 30); return alarm[2]", 30.0);
+        game.assert_returns(inst, "variable_local_set('hp', 5); return hp + variable_local_get('hp')", 10.0);
+        game.assert_returns(inst, "variable_local_array2_set('grid', 1, 2, 7); return grid[1, 2]", 7.0);
+        let code = "image_speed = 1; variable_local_set('image_single', 2); return image_index * 10 + image_speed";
+        game.assert_returns(inst, code, 20.0);
+        game.assert_returns(inst, "return variable_local_get('image_single') + variable_local_get('visible')", 3.0);
+        game.assert_returns(inst, "return variable_local_get('id')", 100001.0);
+        game.assert_returns(inst, "return variable_local_exists('x') + variable_local_exists('hp') * 2", 3.0);
+        game.assert_returns(inst, "return variable_local_exists('room') + variable_local_exists('nothing')", 0.0);
+        game.assert_errors(inst, "variable_local_get('room')", "room is not a local variable");
+        game.assert_errors(inst, "variable_local_array_set('score', 0, 1)", "score is not a local variable");
+        game.assert_errors(inst, "variable_local_set('id', 1)", "Cannot assign to the variable id");
+    }
+}
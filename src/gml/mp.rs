@@ -0,0 +1,248 @@
+//! Motion planning: the grids used by the mp_grid functions, and the settings for the potential field stepper.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Costs of moving to a neighbouring cell, scaled so that they stay integers.
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// The most cells a grid can have, which keeps both its memory and the cost of a path through it reasonable.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// A grid of cells over the room which are either free or forbidden, as made by mp_grid_create.
+pub struct Grid {
+    pub left: i32,
+    pub top: i32,
+    pub columns: i32,
+    pub rows: i32,
+    pub cell_width: i32,
+    pub cell_height: i32,
+
+    /// Whether each cell is forbidden, row by row
+    cells: Vec<bool>,
+}
+
+/// Which instances get in the way of the mp_linear and mp_potential functions.
+#[derive(Clone, Copy, Debug)]
+pub enum Obstacles {
+    Solid,
+    All,
+    Object(i32),
+}
+
+/// The settings used by the mp_potential functions, as set by mp_potential_settings.
+#[derive(Clone, Copy, Debug)]
+pub struct PotentialSettings {
+    /// How many steps ahead the way must be clear before a direction is taken
+    pub ahead: f64,
+
+    /// The furthest the instance can turn in a single step, in degrees
+    pub max_rotation: f64,
+
+    /// How far apart the directions that are tried are, in degrees
+    pub rotation_step: f64,
+
+    /// Whether the instance turns on the spot when there's nowhere it can go
+    pub on_spot: bool,
+}
+
+impl Default for PotentialSettings {
+    fn default() -> Self {
+        Self { ahead: 3.0, max_rotation: 30.0, rotation_step: 10.0, on_spot: true }
+    }
+}
+
+impl Grid {
+    /// Creates a grid with every cell free, or None if it would have more than MAX_CELLS cells.
+    pub fn new(left: i32, top: i32, columns: i32, rows: i32, cell_width: i32, cell_height: i32) -> Option<Self> {
+        let (columns, rows) = (columns.max(0), rows.max(0));
+        let count = (columns as usize).checked_mul(rows as usize).filter(|&count| count <= MAX_CELLS)?;
+        Some(Self {
+            left,
+            top,
+            columns,
+            rows,
+            cell_width: cell_width.max(1),
+            cell_height: cell_height.max(1),
+            cells: vec![false; count],
+        })
+    }
+
+    fn index(&self, column: i32, row: i32) -> Option<usize> {
+        if column >= 0 && row >= 0 && column < self.columns && row < self.rows {
+            Some(row as usize * self.columns as usize + column as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a cell is forbidden. Cells outside the grid always are.
+    pub fn is_forbidden(&self, column: i32, row: i32) -> bool {
+        match self.index(column, row) {
+            Some(i) => self.cells[i],
+            None => true,
+        }
+    }
+
+    /// Marks a cell as forbidden or free. Cells outside the grid are ignored.
+    pub fn set_cell(&mut self, column: i32, row: i32, forbidden: bool) {
+        if let Some(i) = self.index(column, row) {
+            self.cells[i] = forbidden;
+        }
+    }
+
+    /// Marks every cell as free.
+    pub fn clear_all(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = false);
+    }
+
+    /// Gets the cell containing a point in the room, which may be outside the grid.
+    pub fn cell_at(&self, x: f64, y: f64) -> (i32, i32) {
+        (
+            ((x - f64::from(self.left)) / f64::from(self.cell_width)).floor() as i32,
+            ((y - f64::from(self.top)) / f64::from(self.cell_height)).floor() as i32,
+        )
+    }
+
+    /// Gets the area a cell covers in the room as (left, top, right, bottom), with the right and bottom exclusive.
+    /// Edges past what an int can hold are clamped to it.
+    pub fn cell_rect(&self, column: i32, row: i32) -> (i32, i32, i32, i32) {
+        let left = i64::from(self.left) + i64::from(column) * i64::from(self.cell_width);
+        let top = i64::from(self.top) + i64::from(row) * i64::from(self.cell_height);
+        let right = left + i64::from(self.cell_width);
+        let bottom = top + i64::from(self.cell_height);
+        let clamp = |edge: i64| edge.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32;
+        (clamp(left), clamp(top), clamp(right), clamp(bottom))
+    }
+
+    /// Gets the range of cells (first column, first row, last column, last row) which overlap a rectangle in the
+    /// room, clipped to the grid. The rectangle's corners can be given in either order.
+    pub fn cells_in(&self, x1: i32, y1: i32, x2: i32, y2: i32) -> (i32, i32, i32, i32) {
+        let (left, top) = self.cell_at(f64::from(x1.min(x2)), f64::from(y1.min(y2)));
+        let (right, bottom) = self.cell_at(f64::from(x1.max(x2)), f64::from(y1.max(y2)));
+        (left.max(0), top.max(0), right.min(self.columns - 1), bottom.min(self.rows - 1))
+    }
+
+    /// Marks all the cells overlapping a rectangle in the room as forbidden or free.
+    pub fn set_rectangle(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, forbidden: bool) {
+        let (left, top, right, bottom) = self.cells_in(x1, y1, x2, y2);
+        for row in top..=bottom {
+            for column in left..=right {
+                self.set_cell(column, row, forbidden);
+            }
+        }
+    }
+
+    /// Finds the shortest route between two cells using A*, returning the cells along it including both ends.
+    /// Diagonal moves are only made if allowed, and never cut the corner of a forbidden cell.
+    pub fn find_path(&self, start: (i32, i32), goal: (i32, i32), allow_diagonal: bool) -> Option<Vec<(i32, i32)>> {
+        let (start_index, goal_index) = (self.index(start.0, start.1)?, self.index(goal.0, goal.1)?);
+        if self.cells[start_index] || self.cells[goal_index] {
+            return None
+        }
+
+        let heuristic = |(column, row): (i32, i32)| {
+            let dx = (column - goal.0).unsigned_abs();
+            let dy = (row - goal.1).unsigned_abs();
+            if allow_diagonal {
+                STRAIGHT_COST * dx.max(dy) - (STRAIGHT_COST * 2 - DIAGONAL_COST) * dx.min(dy)
+            } else {
+                STRAIGHT_COST * (dx + dy)
+            }
+        };
+
+        let mut costs = vec![u32::MAX; self.cells.len()];
+        let mut came_from = vec![usize::MAX; self.cells.len()];
+        let mut open = BinaryHeap::new();
+        costs[start_index] = 0;
+        open.push(Reverse((heuristic(start), 0, start_index)));
+
+        while let Some(Reverse((_, cost, index))) = open.pop() {
+            if index == goal_index {
+                let mut route = vec![goal];
+                let mut index = goal_index;
+                while index != start_index {
+                    index = came_from[index];
+                    route.push((index as i32 % self.columns, index as i32 / self.columns));
+                }
+                route.reverse();
+                return Some(route)
+            }
+            if cost > costs[index] {
+                continue // A cheaper way here was already found
+            }
+
+            let (column, row) = (index as i32 % self.columns, index as i32 / self.columns);
+            for &(dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let diagonal = dx != 0 && dy != 0;
+                if diagonal
+                    && (!allow_diagonal || self.is_forbidden(column + dx, row) || self.is_forbidden(column, row + dy))
+                {
+                    continue
+                }
+                let next = match self.index(column + dx, row + dy) {
+                    Some(next) if !self.cells[next] => next,
+                    _ => continue,
+                };
+                let next_cost = cost + if diagonal { DIAGONAL_COST } else { STRAIGHT_COST };
+                if next_cost < costs[next] {
+                    costs[next] = next_cost;
+                    came_from[next] = index;
+                    open.push(Reverse((next_cost + heuristic((column + dx, row + dy)), next_cost, next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        let mut grid = Grid::new(16, 16, 4, 3, 32, 32).unwrap();
+        assert_eq!(grid.cell_at(16.0, 16.0), (0, 0));
+        assert_eq!(grid.cell_at(15.0, 80.0), (-1, 2));
+        assert_eq!(grid.cells_in(100, 0, 0, 1000), (0, 0, 2, 2));
+        grid.set_rectangle(48, 48, 48, 48, true);
+        assert!(grid.is_forbidden(1, 1) && !grid.is_forbidden(0, 0) && !grid.is_forbidden(1, 2));
+        assert!(grid.is_forbidden(4, 0));
+        grid.clear_all();
+        assert!(!grid.is_forbidden(1, 1));
+
+        assert!(Grid::new(0, 0, 100_000, 100_000, 1, 1).is_none());
+        assert!(Grid::new(0, 0, 4096, 4096, 1, 1).is_some());
+        let grid = Grid::new(i32::MAX - 10, 0, 2, 2, i32::MAX, i32::MAX).unwrap();
+        assert_eq!(grid.cell_rect(1, 1), (i32::MAX, i32::MAX, i32::MAX, i32::MAX));
+        assert!(!grid.is_forbidden(1, 1));
+    }
+
+    #[test]
+    fn path() {
+        // . . . .
+        // . # # .
+        // . . # .
+        let mut grid = Grid::new(0, 0, 4, 3, 16, 16).unwrap();
+        grid.set_cell(1, 1, true);
+        grid.set_cell(2, 1, true);
+        grid.set_cell(2, 2, true);
+
+        let route = grid.find_path((0, 2), (3, 2), false).unwrap();
+        assert_eq!(route.len(), 8);
+        assert_eq!(route.first(), Some(&(0, 2)));
+        assert_eq!(route.last(), Some(&(3, 2)));
+
+        // Diagonals can't cut the corners of forbidden cells
+        assert_eq!(grid.find_path((0, 2), (3, 2), true).unwrap().len(), 8);
+        assert_eq!(grid.find_path((0, 0), (3, 0), true).unwrap().len(), 4);
+        assert_eq!(grid.find_path((0, 2), (1, 0), true), Some(vec![(0, 2), (0, 1), (0, 0), (1, 0)]));
+        assert_eq!(Grid::new(0, 0, 3, 3, 16, 16).unwrap().find_path((0, 0), (2, 2), true).unwrap().len(), 3);
+
+        assert_eq!(grid.find_path((0, 0), (1, 1), true), None);
+        assert_eq!(grid.find_path((0, 0), (4, 0), true), None);
+        grid.set_cell(3, 0, true);
+        assert_eq!(grid.find_path((0, 0), (3, 2), true), None);
+    }
+}
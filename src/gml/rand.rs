@@ -82,4 +82,152 @@ impl Random {
         let lb = u64::from(bound).wrapping_add(1);
         ((ls.wrapping_mul(lb)) >> 32) as _
     }
+
+    /// Equivalent to GML random_range(lower, upper).
+    ///
+    /// Returns a random float between lower (inclusive) and upper (exclusive). The bounds aren't swapped if lower
+    /// is greater, so the result is then between upper (exclusive) and lower (inclusive).
+    #[inline]
+    pub fn next_range(&mut self, lower: f64, upper: f64) -> f64 {
+        lower + self.next(upper - lower)
+    }
+
+    /// Equivalent to GML irandom_range(lower, upper).
+    ///
+    /// Returns a random integer between lower and upper (inclusive), whichever way round they're given.
+    #[inline]
+    pub fn next_int_range(&mut self, lower: i32, upper: i32) -> i32 {
+        let (lower, upper) = if lower <= upper { (lower, upper) } else { (upper, lower) };
+        lower.wrapping_add(self.next_int(upper.wrapping_sub(lower) as u32))
+    }
+
+    /// Equivalent to the choice GML choose(...) makes.
+    ///
+    /// Returns a random index into a list of the given length, which mustn't be empty.
+    #[inline]
+    pub fn next_index(&mut self, len: usize) -> usize {
+        self.next_int(len as u32 - 1) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // GM8 uses Delphi's Random. The expected values below were worked out separately from this implementation,
+    // following System.Random in Delphi 7's RTL source (System.pas): RandSeed := RandSeed * $08088405 + 1, then
+    // Random(Range) is the top 32 bits of UInt32(RandSeed) * Range, and Random is UInt32(RandSeed) / 2^32.
+    // irandom(n) is Random(n + 1). None of them are captures from a running copy of GM8.
+
+    #[test]
+    fn seed_sequence() {
+        let cases: &[(i32, [i32; 5])] = &[
+            (0, [1, 134775814, -596792289, 870078620, 1172187917]),
+            (12345, [1655067934, 1242767767, 342459380, -1917230139, -1573353766]),
+            (-1, [-134775812, 866343917, -2063663198, 567969323, -540357928]),
+        ];
+        for (seed, expected) in cases {
+            let mut rand = Random::with_seed(*seed);
+            let seeds: Vec<i32> = (0..5)
+                .map(|_| {
+                    rand.cycle();
+                    rand.seed()
+                })
+                .collect();
+            assert_eq!(seeds, expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn irandom() {
+        let cases: &[(i32, u32, [i32; 5])] = &[
+            (0, 99, [0, 3, 86, 20, 27]),
+            (12345, 99, [38, 28, 7, 55, 63]),
+            (-1, 99, [96, 20, 51, 13, 87]),
+            (0, 0, [0, 0, 0, 0, 0]),
+            (0, -5i32 as u32, [0, 134775813, -596792293, 870078619, 1172187915]),
+        ];
+        for (seed, bound, expected) in cases {
+            let mut rand = Random::with_seed(*seed);
+            let values: Vec<i32> = (0..5).map(|_| rand.next_int(*bound)).collect();
+            assert_eq!(values, expected, "seed {}, irandom({})", seed, *bound as i32);
+        }
+    }
+
+    #[test]
+    fn random() {
+        let cases: &[(i32, f64, [f64; 3])] = &[
+            (0, 1.0, [2.3283064365386963e-10, 0.031379939522594213, 0.8610484672244638]),
+            (12345, 1.0, [0.3853505323641002, 0.2893544191028923, 0.07973503787070513]),
+            (12345, -10.0, [-3.853505323641002, -2.893544191028923, -0.7973503787070513]),
+            (-1, 0.5, [0.48431003047153354, 0.1008557059103623, 0.2597579846624285]),
+        ];
+        for (seed, bound, expected) in cases {
+            let mut rand = Random::with_seed(*seed);
+            for (i, value) in expected.iter().enumerate() {
+                assert!((rand.next(*bound) - value).abs() < 1e-15, "seed {}, random({}) #{}", seed, bound, i);
+            }
+        }
+    }
+
+    #[test]
+    fn set_seed() {
+        let mut rand = Random::with_seed(0);
+        rand.set_seed(42);
+        assert_eq!(rand.seed(), 42);
+        rand.cycle();
+        assert_eq!(rand.seed(), 42i32.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT));
+    }
+
+    #[test]
+    fn random_range() {
+        let cases: &[(i32, f64, f64, [f64; 3])] = &[
+            (12345, 5.0, 6.0, [5.385350532364100, 5.289354419102892, 5.079735037870705]),
+            (12345, 6.0, 5.0, [5.614649467635900, 5.710645580897108, 5.920264962129295]),
+            (12345, -0.5, 0.5, [-0.114649467635900, -0.210645580897108, -0.420264962129295]),
+        ];
+        for (seed, lower, upper, expected) in cases {
+            let mut rand = Random::with_seed(*seed);
+            for (i, value) in expected.iter().enumerate() {
+                let actual = rand.next_range(*lower, *upper);
+                assert!((actual - value).abs() < 1e-12, "seed {}, random_range({}, {}) #{}", seed, lower, upper, i);
+            }
+        }
+
+        // Bounds are half-open on the side of upper, even when it's the lower of the two
+        let mut rand = Random::with_seed(-1);
+        for _ in 0..1000 {
+            let value = rand.next_range(3.0, 1.0);
+            assert!(value > 1.0 && value <= 3.0, "random_range(3, 1) = {}", value);
+        }
+    }
+
+    #[test]
+    fn irandom_range() {
+        let cases: &[(i32, i32, i32, [i32; 5])] = &[
+            (0, 1, 100, [1, 4, 87, 21, 28]),
+            (0, 100, 1, [1, 4, 87, 21, 28]),
+            (12345, -3, 3, [-1, -1, -3, 0, 1]),
+            (12345, 7, 7, [7, 7, 7, 7, 7]),
+        ];
+        for (seed, lower, upper, expected) in cases {
+            let mut rand = Random::with_seed(*seed);
+            let values: Vec<i32> = (0..5).map(|_| rand.next_int_range(*lower, *upper)).collect();
+            assert_eq!(values, expected, "seed {}, irandom_range({}, {})", seed, lower, upper);
+        }
+    }
+
+    #[test]
+    fn choose() {
+        // choose() draws from the same sequence as irandom(), so they stay in step
+        let mut rand = Random::with_seed(0);
+        let mut irandom = rand.clone();
+        let indices: Vec<usize> = (0..5).map(|_| rand.next_index(3)).collect();
+        assert_eq!(indices, [0, 0, 2, 0, 0]);
+        assert_eq!(indices, (0..5).map(|_| irandom.next_int(2) as usize).collect::<Vec<_>>());
+        assert!(rand == irandom);
+
+        let mut rand = Random::with_seed(12345);
+        assert!((0..5).all(|_| rand.next_index(1) == 0));
+    }
 }
@@ -0,0 +1,496 @@
+//! Emulation of the parts of the Windows registry that GML can see.
+//!
+//! Keys and values live in memory and are saved to disk in the same format as a REGEDIT4 `.reg` export,
+//! so the file can be inspected by hand, and an export taken from a real player's machine can be imported
+//! to reproduce their environment. Key paths and value names are case-insensitive, as they are on Windows.
+
+use indexmap::IndexMap;
+use std::{env, fmt, fs, io, path::PathBuf};
+
+/// One of the predefined root keys which `registry_set_root` can select.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HKey {
+    CurrentUser,
+    LocalMachine,
+    ClassesRoot,
+    Users,
+}
+
+/// Data held by a registry value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Data {
+    /// REG_SZ
+    Str(String),
+
+    /// REG_DWORD
+    Dword(u32),
+
+    /// Anything else, stored as raw bytes along with its REG_* type number
+    Binary(u32, Vec<u8>),
+}
+
+struct Key {
+    /// Full path of the key as it was first written, including the root
+    path: String,
+
+    /// Values in the key, indexed by their lowercase name. The default value has an empty name.
+    values: IndexMap<String, (String, Data)>,
+}
+
+pub struct Registry {
+    /// Host path this registry will be saved to
+    path: PathBuf,
+
+    /// All keys which have values, indexed by their lowercase full path
+    keys: IndexMap<String, Key>,
+}
+
+/// An error in a `.reg` file, with the line number it occurred on.
+#[derive(Debug)]
+pub struct ParseError(pub usize, pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.0, self.1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl HKey {
+    /// Gets the root matching a GML root id, as passed to `registry_set_root`.
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(HKey::CurrentUser),
+            1 => Some(HKey::LocalMachine),
+            2 => Some(HKey::ClassesRoot),
+            3 => Some(HKey::Users),
+            _ => None,
+        }
+    }
+
+    /// The name of this root as it appears in `.reg` files.
+    pub fn name(self) -> &'static str {
+        match self {
+            HKey::CurrentUser => "HKEY_CURRENT_USER",
+            HKey::LocalMachine => "HKEY_LOCAL_MACHINE",
+            HKey::ClassesRoot => "HKEY_CLASSES_ROOT",
+            HKey::Users => "HKEY_USERS",
+        }
+    }
+
+    /// Parses a root name, accepting the usual abbreviations.
+    fn parse(name: &str) -> Option<Self> {
+        [
+            (HKey::CurrentUser, "HKCU"),
+            (HKey::LocalMachine, "HKLM"),
+            (HKey::ClassesRoot, "HKCR"),
+            (HKey::Users, "HKU"),
+        ]
+        .iter()
+        .find(|(root, short)| name.eq_ignore_ascii_case(root.name()) || name.eq_ignore_ascii_case(short))
+        .map(|(root, _)| *root)
+    }
+}
+
+impl Data {
+    const REG_SZ: u32 = 1;
+    const REG_EXPAND_SZ: u32 = 2;
+    const REG_BINARY: u32 = 3;
+
+    /// Stores a real the same way GM8 does, as the 8 raw bytes of a double.
+    pub fn from_real(value: f64) -> Self {
+        Data::Binary(Self::REG_BINARY, value.to_le_bytes().to_vec())
+    }
+
+    /// Interprets the data as a string, if it is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Data::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Interprets the data as a real, if it can be one.
+    pub fn as_real(&self) -> Option<f64> {
+        match self {
+            Data::Dword(d) => Some(f64::from(*d)),
+            Data::Binary(Self::REG_BINARY, bytes) if bytes.len() == 8 => {
+                let mut raw = [0u8; 8];
+                raw.copy_from_slice(bytes);
+                Some(f64::from_le_bytes(raw))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Gets where the emulated registry is saved, which is in the user's application data directory. It's kept out of
+/// the sandbox so games can't read or delete it with their own file functions, and like the real registry, every
+/// game shares it.
+pub fn default_path() -> PathBuf {
+    let data = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+    };
+    data.unwrap_or_else(env::temp_dir).join("gm8emulator").join("registry.reg")
+}
+
+impl Registry {
+    /// Loads the registry saved at the given host path. A file which doesn't exist gives an empty registry.
+    pub fn open(path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut registry = Self { path, keys: IndexMap::new() };
+        match fs::read(&registry.path) {
+            Ok(data) => registry.import(&decode(&data))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+        Ok(registry)
+    }
+
+    /// Saves the registry to disk, creating the directory it goes in if there isn't one.
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(&self.path, self.to_string())
+    }
+
+    /// Merges the contents of a `.reg` file into the registry.
+    /// Both REGEDIT4 and version 5 files are accepted, including the `[-key]` and `"value"=-` deletion syntax.
+    pub fn import(&mut self, content: &str) -> Result<(), ParseError> {
+        let mut current: Option<(HKey, String)> = None;
+        let mut lines = content.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let line_no = i + 1;
+            let mut line = line.trim().to_string();
+
+            // A trailing backslash continues the line, which regedit uses to wrap long hex data
+            while line.ends_with('\\') && !line.starts_with('[') {
+                line.pop();
+                match lines.next() {
+                    Some((_, next)) => line.push_str(next.trim()),
+                    None => break,
+                }
+            }
+
+            if i == 0 {
+                if line != "REGEDIT4" && line != "Windows Registry Editor Version 5.00" {
+                    return Err(ParseError(line_no, "missing REGEDIT4 header".into()))
+                }
+            } else if line.is_empty() || line.starts_with(';') {
+                continue
+            } else if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(|| ParseError(line_no, "unclosed key".into()))?;
+                let (delete, header) = match header.strip_prefix('-') {
+                    Some(header) => (true, header),
+                    None => (false, header),
+                };
+                let (root, key) = split_root(header)
+                    .ok_or_else(|| ParseError(line_no, format!("unknown registry root in {}", header)))?;
+                if delete {
+                    self.delete_key(root, key);
+                    current = None;
+                } else {
+                    current = Some((root, key.to_string()));
+                }
+            } else {
+                let (root, key) =
+                    current.as_ref().ok_or_else(|| ParseError(line_no, "value outside of a key".into()))?;
+                let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+                    (String::new(), rest)
+                } else if line.starts_with('"') {
+                    parse_quoted(&line).ok_or_else(|| ParseError(line_no, "unclosed value name".into()))?
+                } else {
+                    return Err(ParseError(line_no, format!("unexpected line: {}", line)))
+                };
+                let data = rest
+                    .trim_start()
+                    .strip_prefix('=')
+                    .ok_or_else(|| ParseError(line_no, "expected '=' after value name".into()))?
+                    .trim();
+                if data == "-" {
+                    self.delete_value(*root, key, &name);
+                } else {
+                    let data = parse_data(data).ok_or_else(|| ParseError(line_no, format!("invalid data: {}", data)))?;
+                    self.write(*root, key, &name, data);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a value, if it exists.
+    pub fn read(&self, root: HKey, key: &str, name: &str) -> Option<&Data> {
+        let key = self.keys.get(&full_path(root, key).to_lowercase())?;
+        key.values.get(&name.to_lowercase()).map(|(_, data)| data)
+    }
+
+    /// Writes a value, creating its key if it doesn't exist.
+    pub fn write(&mut self, root: HKey, key: &str, name: &str, data: Data) {
+        let path = full_path(root, key);
+        let key = self.keys.entry(path.to_lowercase()).or_insert_with(|| Key { path, values: IndexMap::new() });
+        match key.values.get_mut(&name.to_lowercase()) {
+            Some(value) => value.1 = data,
+            None => {
+                key.values.insert(name.to_lowercase(), (name.to_string(), data));
+            },
+        }
+    }
+
+    /// Checks whether a value exists.
+    pub fn exists(&self, root: HKey, key: &str, name: &str) -> bool {
+        self.read(root, key, name).is_some()
+    }
+
+    /// Deletes a value, if it exists.
+    pub fn delete_value(&mut self, root: HKey, key: &str, name: &str) {
+        if let Some(key) = self.keys.get_mut(&full_path(root, key).to_lowercase()) {
+            key.values.shift_remove(&name.to_lowercase());
+        }
+    }
+
+    /// Deletes a key along with all of its subkeys.
+    pub fn delete_key(&mut self, root: HKey, key: &str) {
+        let path = full_path(root, key).to_lowercase();
+        let prefix = format!("{}\\", path);
+        self.keys.retain(|k, _| *k != path && !k.starts_with(&prefix));
+    }
+}
+
+impl fmt::Display for Registry {
+    /// Formats the registry as a REGEDIT4 file.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "REGEDIT4\r\n")?;
+        for key in self.keys.values().filter(|k| !k.values.is_empty()) {
+            write!(f, "\r\n[{}]\r\n", key.path)?;
+            for (name, data) in key.values.values() {
+                if name.is_empty() {
+                    write!(f, "@=")?;
+                } else {
+                    write!(f, "\"{}\"=", escape(name))?;
+                }
+                match data {
+                    // REGEDIT4 strings can't hold line breaks, so those are written as UTF-16 like regedit would
+                    Data::Str(s) if s.contains(&['\r', '\n'][..]) => {
+                        let utf16 = s.encode_utf16().chain(Some(0));
+                        let bytes: Vec<u8> = utf16.flat_map(|c| c.to_le_bytes().to_vec()).collect();
+                        write_hex(f, Data::REG_SZ, &bytes)?
+                    },
+                    Data::Str(s) => write!(f, "\"{}\"", escape(s))?,
+                    Data::Dword(d) => write!(f, "dword:{:08x}", d)?,
+                    Data::Binary(kind, bytes) => write_hex(f, *kind, bytes)?,
+                }
+                write!(f, "\r\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the raw bytes of a `.reg` file. Version 5 files are UTF-16, REGEDIT4 files are ANSI.
+pub fn decode(data: &[u8]) -> String {
+    match data {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        },
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+/// Joins a root and a key path, removing any stray backslashes from the ends of the key.
+fn full_path(root: HKey, key: &str) -> String {
+    let key = key.trim_matches('\\');
+    if key.is_empty() { root.name().to_string() } else { format!("{}\\{}", root.name(), key) }
+}
+
+/// Splits a full key path into its root and the rest of the path.
+fn split_root(path: &str) -> Option<(HKey, &str)> {
+    let (root, key) = match path.find('\\') {
+        Some(i) => (&path[..i], &path[i + 1..]),
+        None => (path, ""),
+    };
+    Some((HKey::parse(root)?, key))
+}
+
+/// Parses a quoted, escaped string from the start of a line, returning it and the rest of the line.
+fn parse_quoted(line: &str) -> Option<(String, &str)> {
+    let mut output = String::new();
+    let mut chars = line.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((output, &line[i + 1..])),
+            '\\' => output.push(chars.next()?.1),
+            c => output.push(c),
+        }
+    }
+    None
+}
+
+/// Parses the data half of a value line.
+fn parse_data(data: &str) -> Option<Data> {
+    if data.starts_with('"') {
+        let (s, rest) = parse_quoted(data)?;
+        if rest.trim().is_empty() { Some(Data::Str(s)) } else { None }
+    } else if let Some(hex) = data.strip_prefix("dword:") {
+        u32::from_str_radix(hex.trim(), 16).ok().map(Data::Dword)
+    } else {
+        let (kind, hex) = if let Some(hex) = data.strip_prefix("hex:") {
+            (Data::REG_BINARY, hex)
+        } else {
+            let rest = data.strip_prefix("hex(")?;
+            let end = rest.find("):")?;
+            (u32::from_str_radix(&rest[..end], 16).ok()?, &rest[end + 2..])
+        };
+        let bytes = hex
+            .split(',')
+            .map(str::trim)
+            .filter(|b| !b.is_empty())
+            .map(|b| u8::from_str_radix(b, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        match kind {
+            Data::REG_SZ | Data::REG_EXPAND_SZ => {
+                let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+                let s = String::from_utf16_lossy(&units);
+                Some(Data::Str(s.trim_end_matches('\0').to_string()))
+            },
+            kind => Some(Data::Binary(kind, bytes)),
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn write_hex(f: &mut fmt::Formatter, kind: u32, bytes: &[u8]) -> fmt::Result {
+    if kind == Data::REG_BINARY {
+        write!(f, "hex:")?;
+    } else {
+        write!(f, "hex({:x}):", kind)?;
+    }
+    for (i, byte) in bytes.iter().enumerate() {
+        if i != 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(content: &str) -> Registry {
+        let mut registry = Registry { path: PathBuf::new(), keys: IndexMap::new() };
+        registry.import(content).unwrap();
+        registry
+    }
+
+    #[test]
+    fn import() {
+        let reg = registry(concat!(
+            "Windows Registry Editor Version 5.00\r\n",
+            "\r\n",
+            "; a comment\r\n",
+            "[HKEY_CURRENT_USER\\Software\\Game]\r\n",
+            "@=\"default\"\r\n",
+            "\"Name\"=\"a \\\"quoted\\\" \\\\ path\"\r\n",
+            "\"Count\"=dword:0000002a\r\n",
+            "\"Score\"=hex:00,00,00,00,00,00,\\\r\n",
+            "  f8,3f\r\n",
+            "\"Wide\"=hex(2):68,00,69,00,00,00\r\n",
+            "\r\n",
+            "[HKLM\\Software]\r\n",
+            "\"x\"=\"y\"\r\n",
+        ));
+        let key = "Software\\Game";
+        assert_eq!(reg.read(HKey::CurrentUser, key, ""), Some(&Data::Str("default".into())));
+        assert_eq!(reg.read(HKey::CurrentUser, key, "name"), Some(&Data::Str("a \"quoted\" \\ path".into())));
+        assert_eq!(reg.read(HKey::CurrentUser, key, "COUNT").and_then(Data::as_real), Some(42.0));
+        assert_eq!(reg.read(HKey::CurrentUser, key, "Score").and_then(Data::as_real), Some(1.5));
+        assert_eq!(reg.read(HKey::CurrentUser, key, "Wide"), Some(&Data::Str("hi".into())));
+        assert!(reg.exists(HKey::LocalMachine, "software", "X"));
+        assert!(!reg.exists(HKey::CurrentUser, "software", "x"));
+    }
+
+    #[test]
+    fn import_deletions() {
+        let mut reg = registry(concat!(
+            "REGEDIT4\n",
+            "[HKCU\\A]\n\"x\"=\"1\"\n\"y\"=\"2\"\n",
+            "[HKCU\\A\\B]\n\"z\"=\"3\"\n",
+            "[HKCU\\AB]\n\"w\"=\"4\"\n",
+        ));
+        reg.import("REGEDIT4\n[HKCU\\A]\n\"x\"=-\n").unwrap();
+        assert!(!reg.exists(HKey::CurrentUser, "A", "x"));
+        assert!(reg.exists(HKey::CurrentUser, "A", "y"));
+        reg.import("REGEDIT4\n[-HKEY_CURRENT_USER\\a]\n").unwrap();
+        assert!(!reg.exists(HKey::CurrentUser, "A", "y"));
+        assert!(!reg.exists(HKey::CurrentUser, "A\\B", "z"));
+        assert!(reg.exists(HKey::CurrentUser, "AB", "w"));
+    }
+
+    #[test]
+    fn import_errors() {
+        let mut reg = registry("");
+        assert_eq!(reg.import("REGEDIT4\n\"x\"=\"1\"").unwrap_err().0, 2);
+        assert_eq!(reg.import("REGEDIT4\n\n[HKEY_NOWHERE\\A]").unwrap_err().0, 3);
+        assert_eq!(reg.import("REGEDIT4\n[HKCU\\A]\n\"x\"=dword:xyz").unwrap_err().0, 3);
+    }
+
+    #[test]
+    fn write_and_read() {
+        let mut reg = registry("");
+        reg.write(HKey::CurrentUser, "\\Software\\Game\\", "Volume", Data::from_real(0.75));
+        reg.write(HKey::CurrentUser, "software\\game", "volume", Data::Str("loud".into()));
+        reg.write(HKey::ClassesRoot, "software\\game", "volume", Data::Dword(3));
+        assert_eq!(reg.read(HKey::CurrentUser, "Software\\Game", "VOLUME").and_then(Data::as_str), Some("loud"));
+        assert_eq!(reg.read(HKey::CurrentUser, "Software\\Game", "VOLUME").and_then(Data::as_real), None);
+        assert_eq!(reg.read(HKey::ClassesRoot, "Software\\Game", "volume").and_then(Data::as_real), Some(3.0));
+        assert_eq!(reg.read(HKey::Users, "Software\\Game", "volume"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut reg = registry("");
+        reg.write(HKey::CurrentUser, "Software\\Game", "Name", Data::Str("\"quoted\"\\".into()));
+        reg.write(HKey::CurrentUser, "Software\\Game", "Lines", Data::Str("a\r\nb".into()));
+        reg.write(HKey::CurrentUser, "Software\\Game", "Score", Data::from_real(-2.5));
+        reg.write(HKey::Users, "", "", Data::Dword(0xdeadbeef));
+        let text = reg.to_string();
+        assert_eq!(
+            text,
+            concat!(
+                "REGEDIT4\r\n",
+                "\r\n",
+                "[HKEY_CURRENT_USER\\Software\\Game]\r\n",
+                "\"Name\"=\"\\\"quoted\\\"\\\\\"\r\n",
+                "\"Lines\"=hex(1):61,00,0d,00,0a,00,62,00,00,00\r\n",
+                "\"Score\"=hex:00,00,00,00,00,00,04,c0\r\n",
+                "\r\n",
+                "[HKEY_USERS]\r\n",
+                "@=dword:deadbeef\r\n",
+            )
+        );
+        let copy = registry(&text);
+        for name in &["Name", "Lines", "Score"] {
+            let key = "Software\\Game";
+            assert_eq!(copy.read(HKey::CurrentUser, key, name), reg.read(HKey::CurrentUser, key, name));
+        }
+        assert_eq!(copy.read(HKey::Users, "", ""), Some(&Data::Dword(0xdeadbeef)));
+    }
+
+    #[test]
+    fn decode_utf16() {
+        let text = "REGEDIT4".encode_utf16().flat_map(|c| c.to_le_bytes().to_vec());
+        let bytes: Vec<u8> = [0xFF, 0xFE].iter().copied().chain(text).collect();
+        assert_eq!(decode(&bytes), "REGEDIT4");
+    }
+}
@@ -1,6 +1,6 @@
 use super::{
-    compiler::{mappings, token::Operator},
-    Context, InstanceVariable, Value,
+    compiler::{lexer::Position, mappings, token::Operator},
+    ev, ev_other, Context, InstanceVariable, Value,
 };
 use crate::{
     asset,
@@ -8,7 +8,10 @@ use crate::{
     gml,
     instance::{DummyFieldHolder, Field, Instance},
 };
-use std::fmt;
+use std::{fmt, fs, io::Write};
+
+/// Name of the file in the sandbox which errors are appended to when they're logged.
+pub const ERROR_LOG_FILE: &str = "game_errors.log";
 
 /// A compiled runtime instruction. Generally represents a line of code.
 #[derive(Debug)]
@@ -20,29 +23,43 @@ pub enum Instruction {
     EvalExpression { node: Node },
     IfElse { cond: Node, if_body: Box<[Instruction]>, else_body: Box<[Instruction]> },
     LoopUntil { cond: Node, body: Box<[Instruction]> },
-    LoopWhile { cond: Node, body: Box<[Instruction]> },
+    LoopWhile { cond: Node, body: Box<[Instruction]>, step: Box<[Instruction]> }, // step is a for loop's
     Return { return_type: ReturnType },
     Repeat { count: Node, body: Box<[Instruction]> },
     SetReturnValue { value: Node },
     Switch { input: Node, cases: Box<[(Node, usize)]>, default: Option<usize>, body: Box<[Instruction]> },
     With { target: Node, body: Box<[Instruction]> },
+    SourcePosition { position: Position },
     RuntimeError { error: Error },
 }
 
 /// Node representing one value in an expression.
 pub enum Node {
     Literal { value: Value },
-    Function { args: Box<[Node]>, function: fn(&mut Game, &mut Context, &[Value]) -> gml::Result<Value> },
+    Function {
+        args: Box<[Node]>,
+        function: fn(&mut Game, &mut Context, &[Value]) -> gml::Result<Value>,
+        index: usize, // into mappings::FUNCTIONS, which is where its name is
+    },
     Script { args: Box<[Node]>, script_id: usize },
     Field { accessor: FieldAccessor },
     Variable { accessor: VariableAccessor },
-    Binary { left: Box<Node>, right: Box<Node>, operator: fn(Value, Value) -> gml::Result<Value> },
-    Unary { child: Box<Node>, operator: fn(Value) -> gml::Result<Value> },
+    Binary {
+        left: Box<Node>,
+        right: Box<Node>,
+        operator: fn(Value, Value) -> gml::Result<Value>,
+        index: usize, // into mappings::BINARY_OPERATORS, which is where its symbol is
+    },
+    Unary {
+        child: Box<Node>,
+        operator: fn(Value) -> gml::Result<Value>,
+        index: usize, // into mappings::UNARY_OPERATORS
+    },
     RuntimeError { error: Error },
 }
 
 /// Type of variable modification.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum ModificationType {
     Add,
     Subtract,
@@ -88,6 +105,15 @@ pub enum ArrayAccessor {
     Double(Box<Node>, Box<Node>),
 }
 
+/// Where a variable is read from or written to, once its owner has been worked out at runtime.
+pub enum Target {
+    Globals,
+    Locals,
+    /// The self or other instance, which doesn't need a list to be looked up
+    Single(usize),
+    Instances(Vec<usize>),
+}
+
 /// Identifies an instance or multiple instances.
 /// If we know at compile time that this represents a magic value (self, other, global, local)
 /// then we can represent it that way in the tree and skip evaluating it during runtime.
@@ -119,6 +145,116 @@ pub enum Error {
     UnexpectedASTExpr(String), // string repr. because Expr<'a>
     UninitializedVariable(String, u32),
     TooManyArrayDimensions(usize),
+    WrongArgumentCount(usize, usize), // expected, got
+    WrongArgumentType(usize),         // index of the offending argument
+    FunctionError(String, String),    // function name, message
+    CompileError(String),             // code which failed to compile at load time is replaced with this
+    Located(Box<Error>, Box<Location>),
+}
+
+/// Where an error happened. Each part is filled in as the error makes its way out of the code it happened in.
+#[derive(Clone, Debug, Default)]
+pub struct Location {
+    pub object: Option<String>,
+    pub event: Option<String>,
+    pub action: Option<usize>, // starting at 1
+    pub script: Option<String>,
+    pub position: Option<Position>,
+}
+
+/// What happens when an action raises an error, from the game's error settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorMode {
+    /// The game ends, showing the error.
+    Abort,
+
+    /// As with Ignore, but the error is also shown.
+    Display,
+
+    /// The action is stopped and the error is recorded in error_occurred and error_last.
+    Ignore,
+}
+
+impl Error {
+    /// Attaches where the error happened. Anything already known about its location is kept,
+    /// since errors are located from the innermost code outwards.
+    pub fn locate(self, with: Location) -> Self {
+        match self {
+            Error::Located(error, mut location) => {
+                location.object = location.object.or(with.object);
+                location.event = location.event.or(with.event);
+                location.action = location.action.or(with.action);
+                location.script = location.script.or(with.script);
+                location.position = location.position.or(with.position);
+                Error::Located(error, location)
+            },
+            error => Error::Located(Box::new(error), Box::new(with)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidOperandsUnary(op, _) => write!(f, "Wrong type of argument to unary operator {}.", op),
+            Error::InvalidOperandsBinary(op, _, _) => write!(f, "Wrong type of arguments to {}.", op),
+            Error::InvalidUnaryOperator(op) => write!(f, "Invalid unary operator {}", op),
+            Error::InvalidBinaryOperator(op) => write!(f, "Invalid binary operator {}", op),
+            Error::InvalidAssignment(expr) => write!(f, "Invalid assignment: {}", expr),
+            Error::InvalidArrayAccessor(expr) => write!(f, "Invalid array accessor: {}", expr),
+            Error::InvalidArrayIndex(index) if *index < 0 => write!(f, "Negative array index"),
+            Error::InvalidArrayIndex(index) => write!(f, "Array index out of bounds: {}", index),
+            Error::InvalidDeref(expr) => write!(f, "Invalid variable reference: {}", expr),
+            Error::InvalidIndexLhs(expr) => write!(f, "Cannot index {}", expr),
+            Error::InvalidIndex(expr) => write!(f, "Invalid index: {}", expr),
+            Error::InvalidSwitchBody(expr) => write!(f, "Invalid switch body: {}", expr),
+            Error::NonexistentAsset(kind, index) => write!(f, "Trying to use non-existing {:?} {}", kind, index),
+            Error::ReadOnlyVariable(var) => write!(f, "Cannot assign to the variable {}", var_name(var)),
+            Error::UnknownFunction(name) => write!(f, "Unknown function or script: {}", name),
+            Error::UnexpectedASTExpr(expr) => write!(f, "Unexpected expression: {}", expr),
+            Error::UninitializedVariable(name, 0) => write!(f, "Unknown variable {}", name),
+            Error::UninitializedVariable(name, _) => {
+                write!(f, "Unknown variable {} or array index out of bounds", name)
+            },
+            Error::TooManyArrayDimensions(count) => {
+                write!(f, "Arrays can have at most 2 dimensions, but {} were given", count)
+            },
+            Error::WrongArgumentCount(expected, got) => {
+                write!(f, "Wrong number of arguments to function or script: expected {}, got {}", expected, got)
+            },
+            Error::WrongArgumentType(index) => {
+                write!(f, "Wrong type of arguments to function or script (argument{})", index)
+            },
+            Error::FunctionError(name, message) => write!(f, "Error in function {}: {}", name, message),
+            Error::CompileError(message) => write!(f, "COMPILATION ERROR\n{}", message),
+            Error::Located(error, location) => {
+                if location.action.is_some() || location.event.is_some() || location.object.is_some() {
+                    writeln!(f, "ERROR in")?;
+                    if let Some(action) = location.action {
+                        writeln!(f, "action number {}", action)?;
+                    }
+                    if let Some(event) = &location.event {
+                        writeln!(f, "of {}", event)?;
+                    }
+                    if let Some(object) = &location.object {
+                        writeln!(f, "for object {}:", object)?;
+                    }
+                    writeln!(f)?;
+                }
+                if let Some(script) = &location.script {
+                    writeln!(f, "In script {}:", script)?;
+                }
+                match location.position {
+                    Some(position) => write!(
+                        f,
+                        "Error in code at line {}:\nat position {}: {}",
+                        position.line, position.column, error
+                    ),
+                    None => write!(f, "{}", error),
+                }
+            },
+        }
+    }
 }
 
 impl fmt::Debug for Node {
@@ -128,166 +264,321 @@ impl fmt::Debug for Node {
                 Value::Real(r) => write!(f, "{:?}", r),
                 Value::Str(s) => write!(f, "{:?}", s),
             },
-            Node::Function { args, function: _ } => write!(f, "<function: {:?}>", args),
+            Node::Function { args, .. } => write!(f, "<function: {:?}>", args),
             Node::Script { args, script_id } => write!(f, "<script {:?}: {:?}>", script_id, args),
             Node::Field { accessor } => write!(f, "<field: {:?}>", accessor),
             Node::Variable { accessor } => write!(f, "<variable: {:?}>", accessor),
-            Node::Binary { left, right, operator: _ } => write!(f, "<binary: {:?}, {:?}>", left, right),
-            Node::Unary { child, operator: _ } => write!(f, "<unary: {:?}>", child),
+            Node::Binary { left, right, .. } => write!(f, "<binary: {:?}, {:?}>", left, right),
+            Node::Unary { child, .. } => write!(f, "<unary: {:?}>", child),
             Node::RuntimeError { error } => write!(f, "<error: {:?}>", error),
         }
     }
 }
 
 impl Game {
-    pub fn execute(&mut self, instructions: &[Instruction], context: &mut Context) -> gml::Result<ReturnType> {
-        for instruction in instructions.iter() {
-            match self.exec_instruction(instruction, context)? {
-                ReturnType::Normal => (),
-                r => return Ok(r),
+    /// Deals with an error raised by an action according to the error mode, after noting which action it was.
+    /// Unless the game should abort, the error is recorded so the game can check for it and Ok is returned.
+    pub fn handle_error(&mut self, error: Error, context: &Context) -> gml::Result<()> {
+        let object = match self.assets.objects.get(context.event_object as usize) {
+            Some(Some(object)) => Some(object.name.clone()),
+            _ => None,
+        };
+        let error = error.locate(Location {
+            object,
+            event: Some(self.event_name(context.event_type, context.event_number as u32)),
+            action: Some(context.event_action + 1),
+            ..Default::default()
+        });
+        if self.error_log {
+            let path = self.sandbox.root().join(ERROR_LOG_FILE);
+            if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(path) {
+                // Failing to write the log shouldn't stop the game, as it's only there to help debug it
+                let _ = writeln!(file, "{}\n", error);
             }
         }
-        Ok(ReturnType::Normal)
-    }
-
-    fn exec_instruction(&mut self, instruction: &Instruction, context: &mut Context) -> gml::Result<ReturnType> {
-        match instruction {
-            Instruction::SetField { accessor: _, value: _ } => todo!(),
-            Instruction::SetVariable { accessor: _, value: _ } => todo!(),
-            Instruction::ModifyField { accessor: _, value: _, modification_type: _ } => todo!(),
-            Instruction::ModifyVariable { accessor: _, value: _, modification_type: _ } => todo!(),
-            Instruction::EvalExpression { node } => match self.eval(node, context) {
-                Err(e) => return Err(e),
-                _ => (),
-            },
-            Instruction::IfElse { cond, if_body, else_body } => {
-                let return_type = if self.eval(cond, context)?.is_true() {
-                    self.execute(if_body, context)
-                } else {
-                    self.execute(else_body, context)
-                }?;
-                if return_type != ReturnType::Normal {
-                    return Ok(return_type)
-                }
-            },
-            Instruction::LoopUntil { cond, body } => loop {
-                let return_type = self.execute(body, context)?;
-                if return_type != ReturnType::Normal {
-                    return Ok(return_type)
-                }
-                if self.eval(cond, context)?.is_true() {
+        match self.error_mode {
+            ErrorMode::Abort => return Err(error),
+            ErrorMode::Display => eprintln!("{}", error),
+            ErrorMode::Ignore => (),
+        }
+        self.error_occurred.set(true);
+        self.error_last.replace(error.to_string());
+        Ok(())
+    }
+
+    /// Describes an event the way GM8 does in its error messages.
+    fn event_name(&self, event_type: usize, event_number: u32) -> String {
+        let collision_object = match self.assets.objects.get(event_number as usize) {
+            Some(Some(object)) if event_type == ev::COLLISION => Some(object.name.as_str()),
+            _ => None,
+        };
+        event_name(event_type, event_number, collision_object)
+    }
+
+    /// Runs the body of a with statement for each instance the target refers to, with the given executor.
+    /// A break only stops the with, but anything else applies to the code around it.
+    pub fn run_with(
+        &mut self,
+        target: i32,
+        context: &mut Context,
+        mut execute: impl FnMut(&mut Self, &mut Context) -> gml::Result<ReturnType>,
+    ) -> gml::Result<ReturnType> {
+        let mut new_context = Context {
+            arguments: std::mem::take(&mut context.arguments),
+            argument_count: context.argument_count,
+            arguments_set: context.arguments_set,
+            locals: std::mem::take(&mut context.locals),
+            return_value: std::mem::take(&mut context.return_value),
+            ..context.nested(context.this, context.this)
+        };
+
+        // Local variables and arguments are shared with the body of the with, so they're handed back afterwards
+        let mut result = Ok(ReturnType::Normal);
+        for instance in self.find_instances(target, context) {
+            if !self.instance_list.exists(instance) {
+                continue
+            }
+            new_context.this = instance;
+            match execute(self, &mut new_context) {
+                Ok(ReturnType::Normal) | Ok(ReturnType::Continue) => (),
+                Ok(ReturnType::Break) => break,
+                r => {
+                    result = r;
                     break
-                }
-            },
-            Instruction::LoopWhile { cond, body } => {
-                while self.eval(cond, context)?.is_true() {
-                    let return_type = self.execute(body, context)?;
-                    if return_type != ReturnType::Normal {
-                        return Ok(return_type)
-                    }
-                }
-            },
-            Instruction::Return { return_type } => return Ok(*return_type),
-            Instruction::Repeat { count, body } => {
-                let mut count = self.eval(count, context)?.round();
-                while count > 0 {
-                    let return_type = self.execute(body, context)?;
-                    if return_type != ReturnType::Normal {
-                        return Ok(return_type)
+                },
+            }
+        }
+        context.arguments = new_context.arguments;
+        context.arguments_set = new_context.arguments_set;
+        context.locals = new_context.locals;
+        context.return_value = new_context.return_value;
+        result
+    }
+
+    /// Resolves an instance identifier, as used by `with` and the instance functions, to a list of instance handles.
+    /// Positive values below 100000 are object indices, matching instances of that object and all its children.
+    /// Values from 100000 upwards are instance ids. Destroyed instances are never included.
+    pub fn find_instances(&self, target: i32, context: &Context) -> Vec<usize> {
+        match target {
+            gml::SELF if self.instance_list.exists(context.this) => vec![context.this],
+            gml::OTHER if self.instance_list.exists(context.other) => vec![context.other],
+            gml::ALL => {
+                let mut instances = Vec::new();
+                let mut iter = self.instance_list.iter_inserted();
+                while let Some(idx) = iter.next(&self.instance_list) {
+                    if self.instance_list.exists(idx) {
+                        instances.push(idx);
                     }
-                    count -= 1;
                 }
+                instances
             },
-            Instruction::SetReturnValue { value } => {
-                context.return_value = self.eval(value, context)?;
+            target if target >= gml::FIRST_INSTANCE_ID => {
+                self.instance_list.get_by_instid(target).into_iter().collect()
             },
-            Instruction::Switch { input, cases, default, body } => {
-                let input = self.eval(input, context)?;
-                for (cond, start) in cases.iter() {
-                    if self.eval(cond, context)?.almost_equals(&input) {
-                        return self.execute(&body[*start..], context)
+            target if target >= 0 => match self.assets.objects.get(target as usize) {
+                Some(Some(object)) => {
+                    let mut instances = Vec::new();
+                    let mut iter = self.instance_list.iter_by_identity(object.children.clone());
+                    while let Some(idx) = iter.next(&self.instance_list) {
+                        instances.push(idx);
                     }
-                }
-                if let Some(start) = default {
-                    return self.execute(&body[*start..], context)
-                }
+                    instances
+                },
+                _ => Vec::new(),
             },
-            Instruction::With { target: _, body: _ } => todo!(),
-            Instruction::RuntimeError { error } => return Err(error.clone()),
+            _ => Vec::new(),
         }
+    }
+
+    /// Targets the self or other instance, or nothing if it no longer exists.
+    pub fn single_target(&self, idx: usize) -> Target {
+        if self.instance_list.exists(idx) { Target::Single(idx) } else { Target::Instances(Vec::new()) }
+    }
+
+    /// Works out where a variable belongs from the value of the expression before the dot, as in `owner.x`.
+    pub fn owner_target(&self, owner: i32, context: &Context) -> Target {
+        match owner {
+            gml::GLOBAL => Target::Globals,
+            gml::LOCAL => Target::Locals,
+            gml::SELF => self.single_target(context.this),
+            gml::OTHER => self.single_target(context.other),
+            target => Target::Instances(self.find_instances(target, context)),
+        }
+    }
 
-        Ok(ReturnType::Normal)
+    /// Reads a field from wherever it's been worked out to belong.
+    pub fn read_field(
+        &self,
+        target: Target,
+        field_id: usize,
+        array_index: u32,
+        context: &Context,
+    ) -> gml::Result<Value> {
+        match target {
+            Target::Globals => self.get_dummy_field(&self.globals, field_id, array_index),
+            Target::Locals => self.get_dummy_field(&context.locals, field_id, array_index),
+            Target::Single(idx) => self.get_instance_field(self.instance_list.get(idx).unwrap(), field_id, array_index),
+            Target::Instances(instances) => match instances.first() {
+                Some(&idx) => self.get_instance_field(self.instance_list.get(idx).unwrap(), field_id, array_index),
+                None => Err(Error::UninitializedVariable(
+                    self.compiler.get_field_name(field_id).unwrap_or_default(),
+                    array_index,
+                )),
+            },
+        }
     }
 
-    fn eval(&mut self, node: &Node, context: &mut Context) -> gml::Result<Value> {
-        match node {
-            Node::Literal { value } => Ok(value.clone()),
-            Node::Function { args, function } => {
-                let mut arg_values: [Value; 16] = Default::default();
-                for (src, dest) in args.iter().zip(arg_values.iter_mut()) {
-                    *dest = self.eval(src, context)?;
+    /// Sets or modifies a field wherever it's been worked out to belong.
+    pub fn write_field(
+        &mut self,
+        target: Target,
+        id: usize,
+        index: u32,
+        value: Value,
+        modification_type: Option<&ModificationType>,
+        context: &mut Context,
+    ) -> gml::Result<()> {
+        match target {
+            Target::Globals => {
+                let mut globals = std::mem::take(&mut self.globals);
+                let result = match modification_type {
+                    Some(m) => self.get_dummy_field(&globals, id, index).and_then(|old| modify(old, m, value)),
+                    None => Ok(value),
+                };
+                if let Ok(value) = &result {
+                    self.set_dummy_field(&mut globals, id, index, value.clone());
+                }
+                self.globals = globals;
+                result.map(|_| ())
+            },
+            Target::Locals => {
+                let value = match modification_type {
+                    Some(m) => modify(self.get_dummy_field(&context.locals, id, index)?, m, value)?,
+                    None => value,
+                };
+                self.set_dummy_field(&mut context.locals, id, index, value);
+                Ok(())
+            },
+            Target::Single(idx) => self.write_instance_field(idx, id, index, value, modification_type),
+            Target::Instances(instances) => {
+                for idx in instances {
+                    self.write_instance_field(idx, id, index, value.clone(), modification_type)?;
                 }
-                function(self, context, &arg_values[..args.len()])
+                Ok(())
             },
-            Node::Script { args, script_id } => {
-                if let Some(Some(script)) = self.assets.scripts.get(*script_id) {
-                    let instructions = script.compiled.clone();
+        }
+    }
 
-                    let mut arg_values: [Value; 16] = Default::default();
-                    for (src, dest) in args.iter().zip(arg_values.iter_mut()) {
-                        *dest = self.eval(src, context)?;
-                    }
+    /// Sets or modifies a field of one instance.
+    fn write_instance_field(
+        &self,
+        idx: usize,
+        id: usize,
+        index: u32,
+        value: Value,
+        modification_type: Option<&ModificationType>,
+    ) -> gml::Result<()> {
+        let instance = self.instance_list.get(idx).unwrap();
+        let value = match modification_type {
+            Some(m) => modify(self.get_instance_field(instance, id, index)?, m, value)?,
+            None => value,
+        };
+        self.set_instance_field(instance, id, index, value);
+        Ok(())
+    }
 
-                    let mut new_context = Context {
-                        this: context.this,
-                        other: context.other,
-                        event_action: context.event_action,
-                        relative: context.relative,
-                        event_type: context.event_type,
-                        event_number: context.event_number,
-                        event_object: context.event_object,
-                        arguments: &arg_values[..args.len()],
-                        locals: DummyFieldHolder::new(),
-                        return_value: Default::default(),
-                    };
-                    self.execute(&instructions, &mut new_context)?;
-                    Ok(new_context.return_value)
-                } else {
-                    Err(Error::NonexistentAsset(asset::Type::Script, *script_id))
-                }
+    /// Reads a built-in variable from wherever it's been worked out to belong.
+    pub fn read_var(
+        &self,
+        target: Target,
+        var: &InstanceVariable,
+        array_index: u32,
+        context: &Context,
+    ) -> gml::Result<Value> {
+        if let Some(result) = self.read_context_var(var, array_index, context) {
+            return result
+        }
+        match target {
+            Target::Globals => self.get_dummy_var(&self.globals, var, array_index),
+            Target::Locals => self.get_dummy_var(&context.locals, var, array_index),
+            Target::Single(idx) => {
+                self.get_instance_var(self.instance_list.get(idx).unwrap(), var, array_index, context)
+            },
+            Target::Instances(instances) => match instances.first() {
+                Some(&idx) => self.get_instance_var(self.instance_list.get(idx).unwrap(), var, array_index, context),
+                None => Err(Error::UninitializedVariable(var_name(var).into(), array_index)),
             },
-            Node::Field { accessor: _ } => todo!(),
-            Node::Variable { accessor: _ } => todo!(),
-            Node::Binary { left, right, operator } => operator(self.eval(left, context)?, self.eval(right, context)?),
-            Node::Unary { child, operator } => operator(self.eval(child, context)?),
-            Node::RuntimeError { error } => Err(error.clone()),
         }
     }
 
-    // Resolves an ArrayAccessor to an index (u32)
-    fn get_array_index(&mut self, accessor: &ArrayAccessor, context: &mut Context) -> gml::Result<u32> {
-        match accessor {
-            ArrayAccessor::None => Ok(0),
-            ArrayAccessor::Single(node) => {
-                let index = self.eval(node, context)?.round();
-                if index < 0 || index >= 32000 { Err(Error::InvalidArrayIndex(index)) } else { Ok(index as u32) }
-            },
-            ArrayAccessor::Double(node1, node2) => {
-                let index1 = self.eval(node1, context)?.round();
-                let index2 = self.eval(node2, context)?.round();
-                if index1 < 0 || index1 >= 32000 {
-                    Err(Error::InvalidArrayIndex(index1))
-                } else if index2 < 0 || index2 >= 32000 {
-                    Err(Error::InvalidArrayIndex(index2))
-                } else {
-                    Ok(((index1 * 32000) + index2) as u32)
+    /// Sets or modifies a built-in variable wherever it's been worked out to belong.
+    pub fn write_var(
+        &mut self,
+        target: Target,
+        var: &InstanceVariable,
+        index: u32,
+        value: Value,
+        modification_type: Option<&ModificationType>,
+        context: &mut Context,
+    ) -> gml::Result<()> {
+        if let Some(n) = argument_number(var, index) {
+            let value = match modification_type {
+                Some(m) => modify(self.get_argument(context, n)?, m, value)?,
+                None => value,
+            };
+            return set_argument(context, n, value)
+        }
+        match target {
+            Target::Globals => {
+                let mut globals = std::mem::take(&mut self.globals);
+                let result = match modification_type {
+                    Some(m) => self.get_dummy_var(&globals, var, index).and_then(|old| modify(old, m, value)),
+                    None => Ok(value),
+                };
+                if let Ok(value) = &result {
+                    self.set_dummy_var(&mut globals, var, index, value.clone());
                 }
+                self.globals = globals;
+                result.map(|_| ())
+            },
+            Target::Locals => {
+                let value = match modification_type {
+                    Some(m) => modify(self.get_dummy_var(&context.locals, var, index)?, m, value)?,
+                    None => value,
+                };
+                self.set_dummy_var(&mut context.locals, var, index, value);
+                Ok(())
+            },
+            Target::Single(idx) => self.write_instance_var(idx, var, index, value, modification_type, context),
+            Target::Instances(instances) => {
+                for idx in instances {
+                    self.write_instance_var(idx, var, index, value.clone(), modification_type, context)?;
+                }
+                Ok(())
             },
         }
     }
 
+    /// Sets or modifies a built-in variable of one instance.
+    fn write_instance_var(
+        &self,
+        idx: usize,
+        var: &InstanceVariable,
+        index: u32,
+        value: Value,
+        modification_type: Option<&ModificationType>,
+        context: &mut Context,
+    ) -> gml::Result<()> {
+        let instance = self.instance_list.get(idx).unwrap();
+        let value = match modification_type {
+            Some(m) => modify(self.get_instance_var(instance, var, index, context)?, m, value)?,
+            None => value,
+        };
+        self.set_instance_var(instance, var, index, value, context)
+    }
+
     // Get a field value from an instance
-    fn get_instance_field(&self, instance: &Instance, field_id: usize, array_index: u32) -> gml::Result<Value> {
+    pub fn get_instance_field(&self, instance: &Instance, field_id: usize, array_index: u32) -> gml::Result<Value> {
         if let Some(Some(value)) = instance.fields.borrow().get(&field_id).map(|field| field.get(array_index)) {
             Ok(value)
         } else {
@@ -300,7 +591,7 @@ impl Game {
     }
 
     // Set a field on an instance
-    fn set_instance_field(&self, instance: &Instance, field_id: usize, array_index: u32, value: Value) {
+    pub fn set_instance_field(&self, instance: &Instance, field_id: usize, array_index: u32, value: Value) {
         let mut fields = instance.fields.borrow_mut();
         if let Some(field) = fields.get_mut(&field_id) {
             field.set(array_index, value)
@@ -309,85 +600,137 @@ impl Game {
         }
     }
 
+    /// Reads one of the current script's arguments. Ones it wasn't given are uninitialized, unless the game's
+    /// settings say they're 0, or the script has set them.
+    fn get_argument(&self, context: &Context, n: usize) -> gml::Result<Value> {
+        match context.arguments.get(n) {
+            Some(value) if n < context.argument_count || context.arguments_set & (1 << n) != 0 => Ok(value.clone()),
+            Some(value) if self.uninit_args_are_zero => Ok(value.clone()),
+            Some(_) => Err(Error::UninitializedVariable(format!("argument{}", n), 0)),
+            None => Err(Error::InvalidArrayIndex(n as i32)),
+        }
+    }
+
+    /// Reads a built-in variable which belongs to the running code rather than to any instance, such as the
+    /// arguments, so it can still be read after self is destroyed. Returns None for any other variable.
+    fn read_context_var(
+        &self,
+        var: &InstanceVariable,
+        array_index: u32,
+        context: &Context,
+    ) -> Option<gml::Result<Value>> {
+        match var {
+            InstanceVariable::ArgumentRelative => Some(Ok(context.relative.into())),
+            InstanceVariable::ArgumentCount => Some(Ok((context.argument_count as i32).into())),
+            _ => argument_number(var, array_index).map(|n| self.get_argument(context, n)),
+        }
+    }
+
     // Get an instance variable from an instance, converted into a Value
-    fn get_instance_var(
+    pub fn get_instance_var(
         &self,
-        _instance: &Instance,
+        instance: &Instance,
         var: &InstanceVariable,
-        _array_index: u32,
-        _context: &Context,
+        array_index: u32,
+        context: &Context,
     ) -> gml::Result<Value> {
+        let sprite = || self.assets.sprites.get(instance.sprite_index.get() as usize).and_then(|s| s.as_ref());
         match var {
-            InstanceVariable::X => todo!(),
-            InstanceVariable::Y => todo!(),
-            InstanceVariable::Xprevious => todo!(),
-            InstanceVariable::Yprevious => todo!(),
-            InstanceVariable::Xstart => todo!(),
-            InstanceVariable::Ystart => todo!(),
-            InstanceVariable::Hspeed => todo!(),
-            InstanceVariable::Vspeed => todo!(),
-            InstanceVariable::Direction => todo!(),
-            InstanceVariable::Speed => todo!(),
-            InstanceVariable::Friction => todo!(),
-            InstanceVariable::Gravity => todo!(),
-            InstanceVariable::GravityDirection => todo!(),
-            InstanceVariable::ObjectIndex => todo!(),
-            InstanceVariable::Id => todo!(),
-            InstanceVariable::Alarm => todo!(),
-            InstanceVariable::Solid => todo!(),
-            InstanceVariable::Visible => todo!(),
-            InstanceVariable::Persistent => todo!(),
-            InstanceVariable::Depth => todo!(),
-            InstanceVariable::BboxLeft => todo!(),
-            InstanceVariable::BboxRight => todo!(),
-            InstanceVariable::BboxTop => todo!(),
-            InstanceVariable::BboxBottom => todo!(),
-            InstanceVariable::SpriteIndex => todo!(),
-            InstanceVariable::ImageIndex => todo!(),
-            InstanceVariable::ImageSingle => todo!(),
-            InstanceVariable::ImageNumber => todo!(),
-            InstanceVariable::SpriteWidth => todo!(),
-            InstanceVariable::SpriteHeight => todo!(),
-            InstanceVariable::SpriteXoffset => todo!(),
-            InstanceVariable::SpriteYoffset => todo!(),
-            InstanceVariable::ImageXscale => todo!(),
-            InstanceVariable::ImageYscale => todo!(),
-            InstanceVariable::ImageAngle => todo!(),
-            InstanceVariable::ImageAlpha => todo!(),
-            InstanceVariable::ImageBlend => todo!(),
-            InstanceVariable::ImageSpeed => todo!(),
-            InstanceVariable::MaskIndex => todo!(),
-            InstanceVariable::PathIndex => todo!(),
-            InstanceVariable::PathPosition => todo!(),
-            InstanceVariable::PathPositionprevious => todo!(),
-            InstanceVariable::PathSpeed => todo!(),
-            InstanceVariable::PathScale => todo!(),
-            InstanceVariable::PathOrientation => todo!(),
-            InstanceVariable::PathEndaction => todo!(),
-            InstanceVariable::TimelineIndex => todo!(),
-            InstanceVariable::TimelinePosition => todo!(),
-            InstanceVariable::TimelineSpeed => todo!(),
-            InstanceVariable::TimelineRunning => todo!(),
-            InstanceVariable::TimelineLoop => todo!(),
-            InstanceVariable::ArgumentRelative => todo!(),
-            InstanceVariable::Argument0 => todo!(),
-            InstanceVariable::Argument1 => todo!(),
-            InstanceVariable::Argument2 => todo!(),
-            InstanceVariable::Argument3 => todo!(),
-            InstanceVariable::Argument4 => todo!(),
-            InstanceVariable::Argument5 => todo!(),
-            InstanceVariable::Argument6 => todo!(),
-            InstanceVariable::Argument7 => todo!(),
-            InstanceVariable::Argument8 => todo!(),
-            InstanceVariable::Argument9 => todo!(),
-            InstanceVariable::Argument10 => todo!(),
-            InstanceVariable::Argument11 => todo!(),
-            InstanceVariable::Argument12 => todo!(),
-            InstanceVariable::Argument13 => todo!(),
-            InstanceVariable::Argument14 => todo!(),
-            InstanceVariable::Argument15 => todo!(),
-            InstanceVariable::Argument => todo!(),
-            InstanceVariable::ArgumentCount => todo!(),
+            InstanceVariable::X => Ok(instance.x.get().into()),
+            InstanceVariable::Y => Ok(instance.y.get().into()),
+            InstanceVariable::Xprevious => Ok(instance.xprevious.get().into()),
+            InstanceVariable::Yprevious => Ok(instance.yprevious.get().into()),
+            InstanceVariable::Xstart => Ok(instance.xstart.get().into()),
+            InstanceVariable::Ystart => Ok(instance.ystart.get().into()),
+            InstanceVariable::Hspeed => Ok(instance.hspeed.get().into()),
+            InstanceVariable::Vspeed => Ok(instance.vspeed.get().into()),
+            InstanceVariable::Direction => Ok(instance.direction.get().into()),
+            InstanceVariable::Speed => Ok(instance.speed.get().into()),
+            InstanceVariable::Friction => Ok(instance.friction.get().into()),
+            InstanceVariable::Gravity => Ok(instance.gravity.get().into()),
+            InstanceVariable::GravityDirection => Ok(instance.gravity_direction.get().into()),
+            InstanceVariable::ObjectIndex => Ok(instance.object_index.get().into()),
+            InstanceVariable::Id => Ok((instance.id.get() as i32).into()),
+            InstanceVariable::Alarm => {
+                Ok(instance.alarms.borrow().get(&array_index).cloned().unwrap_or_else(|| Value::Real(-1.0)))
+            },
+            InstanceVariable::Solid => Ok(instance.solid.get().into()),
+            InstanceVariable::Visible => Ok(instance.visible.get().into()),
+            InstanceVariable::Persistent => Ok(instance.persistent.get().into()),
+            InstanceVariable::Depth => Ok(instance.depth.get().into()),
+            InstanceVariable::BboxLeft => {
+                self.update_instance_bbox(instance);
+                Ok(instance.bbox_left.get().into())
+            },
+            InstanceVariable::BboxRight => {
+                self.update_instance_bbox(instance);
+                Ok(instance.bbox_right.get().into())
+            },
+            InstanceVariable::BboxTop => {
+                self.update_instance_bbox(instance);
+                Ok(instance.bbox_top.get().into())
+            },
+            InstanceVariable::BboxBottom => {
+                self.update_instance_bbox(instance);
+                Ok(instance.bbox_bottom.get().into())
+            },
+            InstanceVariable::SpriteIndex => Ok(instance.sprite_index.get().into()),
+            InstanceVariable::ImageIndex => Ok(instance.image_index.get().into()),
+            InstanceVariable::ImageSingle => {
+                // -1 means the sprite is animating, otherwise it's the frame it's been stopped on
+                if instance.image_speed.get() == 0.0 { Ok(instance.image_index.get().into()) } else { Ok((-1).into()) }
+            },
+            InstanceVariable::ImageNumber => Ok(sprite().map_or(0, |s| s.frames.len() as i32).into()),
+            InstanceVariable::SpriteWidth => {
+                Ok(sprite().map_or(0.0, |s| f64::from(s.width) * instance.image_xscale.get()).into())
+            },
+            InstanceVariable::SpriteHeight => {
+                Ok(sprite().map_or(0.0, |s| f64::from(s.height) * instance.image_yscale.get()).into())
+            },
+            InstanceVariable::SpriteXoffset => {
+                Ok(sprite().map_or(0.0, |s| f64::from(s.origin_x) * instance.image_xscale.get()).into())
+            },
+            InstanceVariable::SpriteYoffset => {
+                Ok(sprite().map_or(0.0, |s| f64::from(s.origin_y) * instance.image_yscale.get()).into())
+            },
+            InstanceVariable::ImageXscale => Ok(instance.image_xscale.get().into()),
+            InstanceVariable::ImageYscale => Ok(instance.image_yscale.get().into()),
+            InstanceVariable::ImageAngle => Ok(instance.image_angle.get().into()),
+            InstanceVariable::ImageAlpha => Ok(instance.image_alpha.get().into()),
+            InstanceVariable::ImageBlend => Ok(instance.image_blend.get().into()),
+            InstanceVariable::ImageSpeed => Ok(instance.image_speed.get().into()),
+            InstanceVariable::MaskIndex => Ok(instance.mask_index.get().into()),
+            InstanceVariable::PathIndex => Ok(instance.path_index.get().into()),
+            InstanceVariable::PathPosition => Ok(instance.path_position.get().into()),
+            InstanceVariable::PathPositionprevious => Ok(instance.path_positionprevious.get().into()),
+            InstanceVariable::PathSpeed => Ok(instance.path_speed.get().into()),
+            InstanceVariable::PathScale => Ok(instance.path_scale.get().into()),
+            InstanceVariable::PathOrientation => Ok(instance.path_orientation.get().into()),
+            InstanceVariable::PathEndaction => Ok(instance.path_endaction.get().into()),
+            InstanceVariable::TimelineIndex => Ok(instance.timeline_index.get().into()),
+            InstanceVariable::TimelinePosition => Ok(instance.timeline_position.get().into()),
+            InstanceVariable::TimelineSpeed => Ok(instance.timeline_speed.get().into()),
+            InstanceVariable::TimelineRunning => Ok(instance.timeline_running.get().into()),
+            InstanceVariable::TimelineLoop => Ok(instance.timeline_loop.get().into()),
+            InstanceVariable::ArgumentRelative => self.read_context_var(var, array_index, context).unwrap(),
+            InstanceVariable::Argument0
+            | InstanceVariable::Argument1
+            | InstanceVariable::Argument2
+            | InstanceVariable::Argument3
+            | InstanceVariable::Argument4
+            | InstanceVariable::Argument5
+            | InstanceVariable::Argument6
+            | InstanceVariable::Argument7
+            | InstanceVariable::Argument8
+            | InstanceVariable::Argument9
+            | InstanceVariable::Argument10
+            | InstanceVariable::Argument11
+            | InstanceVariable::Argument12
+            | InstanceVariable::Argument13
+            | InstanceVariable::Argument14
+            | InstanceVariable::Argument15
+            | InstanceVariable::Argument => self.read_context_var(var, array_index, context).unwrap(),
+            InstanceVariable::ArgumentCount => self.read_context_var(var, array_index, context).unwrap(),
             InstanceVariable::Room => todo!(),
             InstanceVariable::RoomFirst => todo!(),
             InstanceVariable::RoomLast => todo!(),
@@ -397,9 +740,10 @@ impl Game {
             InstanceVariable::Lives => todo!(),
             InstanceVariable::Health => todo!(),
             InstanceVariable::GameId => todo!(),
-            InstanceVariable::WorkingDirectory => todo!(),
+            InstanceVariable::WorkingDirectory | InstanceVariable::ProgramDirectory => {
+                Ok(self.sandbox.directory().into())
+            },
             InstanceVariable::TempDirectory => todo!(),
-            InstanceVariable::ProgramDirectory => todo!(),
             InstanceVariable::InstanceCount => todo!(),
             InstanceVariable::InstanceId => todo!(),
             InstanceVariable::RoomWidth => todo!(),
@@ -435,20 +779,23 @@ impl Game {
             InstanceVariable::ViewYport => todo!(),
             InstanceVariable::ViewWport => todo!(),
             InstanceVariable::ViewHport => todo!(),
-            InstanceVariable::ViewAngle => todo!(),
+            InstanceVariable::ViewAngle => match self.views.get(array_index as usize) {
+                Some(view) => Ok(view.angle.get().into()),
+                None => Err(Error::InvalidArrayIndex(array_index as i32)),
+            },
             InstanceVariable::ViewHborder => todo!(),
             InstanceVariable::ViewVborder => todo!(),
             InstanceVariable::ViewHspeed => todo!(),
             InstanceVariable::ViewVspeed => todo!(),
             InstanceVariable::ViewObject => todo!(),
-            InstanceVariable::MouseX => todo!(),
-            InstanceVariable::MouseY => todo!(),
-            InstanceVariable::MouseButton => todo!(),
-            InstanceVariable::MouseLastbutton => todo!(),
-            InstanceVariable::KeyboardKey => todo!(),
-            InstanceVariable::KeyboardLastkey => todo!(),
-            InstanceVariable::KeyboardLastchar => todo!(),
-            InstanceVariable::KeyboardString => todo!(),
+            InstanceVariable::MouseX => Ok(self.input.mouse_x.into()),
+            InstanceVariable::MouseY => Ok(self.input.mouse_y.into()),
+            InstanceVariable::MouseButton => Ok(self.input.mouse_button.get().into()),
+            InstanceVariable::MouseLastbutton => Ok(self.input.mouse_lastbutton.get().into()),
+            InstanceVariable::KeyboardKey => Ok(self.input.keyboard_key.get().into()),
+            InstanceVariable::KeyboardLastkey => Ok(self.input.keyboard_lastkey.get().into()),
+            InstanceVariable::KeyboardLastchar => Ok(self.input.keyboard_lastchar.borrow().as_str().into()),
+            InstanceVariable::KeyboardString => Ok(self.input.keyboard_string.borrow().as_str().into()),
             InstanceVariable::CursorSprite => todo!(),
             InstanceVariable::ShowScore => todo!(),
             InstanceVariable::ShowLives => todo!(),
@@ -457,22 +804,22 @@ impl Game {
             InstanceVariable::CaptionLives => todo!(),
             InstanceVariable::CaptionHealth => todo!(),
             InstanceVariable::Fps => todo!(),
-            InstanceVariable::CurrentTime => todo!(),
-            InstanceVariable::CurrentYear => todo!(),
-            InstanceVariable::CurrentMonth => todo!(),
-            InstanceVariable::CurrentDay => todo!(),
-            InstanceVariable::CurrentWeekday => todo!(),
-            InstanceVariable::CurrentHour => todo!(),
-            InstanceVariable::CurrentMinute => todo!(),
-            InstanceVariable::CurrentSecond => todo!(),
+            InstanceVariable::CurrentTime => Ok(Value::Real(self.clock.millis() as f64)),
+            InstanceVariable::CurrentYear => Ok(self.clock.now().ymd().0.into()),
+            InstanceVariable::CurrentMonth => Ok(self.clock.now().ymd().1.into()),
+            InstanceVariable::CurrentDay => Ok(self.clock.now().ymd().2.into()),
+            InstanceVariable::CurrentWeekday => Ok(self.clock.now().weekday().into()),
+            InstanceVariable::CurrentHour => Ok(self.clock.now().hms().0.into()),
+            InstanceVariable::CurrentMinute => Ok(self.clock.now().hms().1.into()),
+            InstanceVariable::CurrentSecond => Ok(self.clock.now().hms().2.into()),
             InstanceVariable::EventType => todo!(),
             InstanceVariable::EventNumber => todo!(),
             InstanceVariable::EventObject => todo!(),
             InstanceVariable::EventAction => todo!(),
             InstanceVariable::SecureMode => todo!(),
             InstanceVariable::DebugMode => todo!(),
-            InstanceVariable::ErrorOccurred => todo!(),
-            InstanceVariable::ErrorLast => todo!(),
+            InstanceVariable::ErrorOccurred => Ok(self.error_occurred.get().into()),
+            InstanceVariable::ErrorLast => Ok(self.error_last.borrow().as_str().into()),
             InstanceVariable::GamemakerRegistered => todo!(),
             InstanceVariable::GamemakerPro => todo!(),
             InstanceVariable::GamemakerVersion => todo!(),
@@ -488,71 +835,100 @@ impl Game {
     }
 
     // Set an instance variable on an instance
-    fn set_instance_var(
+    pub fn set_instance_var(
         &self,
-        _instance: &Instance,
+        instance: &Instance,
         var: &InstanceVariable,
-        _array_index: u32,
-        _value: Value,
-        _context: &mut Context,
+        array_index: u32,
+        value: Value,
+        context: &mut Context,
     ) -> gml::Result<()> {
         match var {
-            InstanceVariable::X => todo!(),
-            InstanceVariable::Y => todo!(),
-            InstanceVariable::Xprevious => todo!(),
-            InstanceVariable::Yprevious => todo!(),
-            InstanceVariable::Xstart => todo!(),
-            InstanceVariable::Ystart => todo!(),
-            InstanceVariable::Hspeed => todo!(),
-            InstanceVariable::Vspeed => todo!(),
-            InstanceVariable::Direction => todo!(),
-            InstanceVariable::Speed => todo!(),
-            InstanceVariable::Friction => todo!(),
-            InstanceVariable::Gravity => todo!(),
-            InstanceVariable::GravityDirection => todo!(),
-            InstanceVariable::Alarm => todo!(),
-            InstanceVariable::Solid => todo!(),
-            InstanceVariable::Visible => todo!(),
-            InstanceVariable::Persistent => todo!(),
-            InstanceVariable::Depth => todo!(),
-            InstanceVariable::SpriteIndex => todo!(),
-            InstanceVariable::ImageIndex => todo!(),
-            InstanceVariable::ImageSingle => todo!(),
-            InstanceVariable::ImageXscale => todo!(),
-            InstanceVariable::ImageYscale => todo!(),
-            InstanceVariable::ImageAngle => todo!(),
-            InstanceVariable::ImageAlpha => todo!(),
-            InstanceVariable::ImageBlend => todo!(),
-            InstanceVariable::ImageSpeed => todo!(),
-            InstanceVariable::MaskIndex => todo!(),
-            InstanceVariable::PathPosition => todo!(),
-            InstanceVariable::PathPositionprevious => todo!(),
-            InstanceVariable::PathSpeed => todo!(),
-            InstanceVariable::PathScale => todo!(),
-            InstanceVariable::PathOrientation => todo!(),
-            InstanceVariable::PathEndaction => todo!(),
-            InstanceVariable::TimelineIndex => todo!(),
-            InstanceVariable::TimelinePosition => todo!(),
-            InstanceVariable::TimelineSpeed => todo!(),
-            InstanceVariable::TimelineRunning => todo!(),
-            InstanceVariable::TimelineLoop => todo!(),
-            InstanceVariable::Argument0 => todo!(),
-            InstanceVariable::Argument1 => todo!(),
-            InstanceVariable::Argument2 => todo!(),
-            InstanceVariable::Argument3 => todo!(),
-            InstanceVariable::Argument4 => todo!(),
-            InstanceVariable::Argument5 => todo!(),
-            InstanceVariable::Argument6 => todo!(),
-            InstanceVariable::Argument7 => todo!(),
-            InstanceVariable::Argument8 => todo!(),
-            InstanceVariable::Argument9 => todo!(),
-            InstanceVariable::Argument10 => todo!(),
-            InstanceVariable::Argument11 => todo!(),
-            InstanceVariable::Argument12 => todo!(),
-            InstanceVariable::Argument13 => todo!(),
-            InstanceVariable::Argument14 => todo!(),
-            InstanceVariable::Argument15 => todo!(),
-            InstanceVariable::Argument => todo!(),
+            InstanceVariable::X => instance.set_position(value.into(), instance.y.get()),
+            InstanceVariable::Y => instance.set_position(instance.x.get(), value.into()),
+            InstanceVariable::Xprevious => instance.xprevious.set(value.into()),
+            InstanceVariable::Yprevious => instance.yprevious.set(value.into()),
+            InstanceVariable::Xstart => instance.xstart.set(value.into()),
+            InstanceVariable::Ystart => instance.ystart.set(value.into()),
+            InstanceVariable::Hspeed => instance.set_hspeed(value.into()),
+            InstanceVariable::Vspeed => instance.set_vspeed(value.into()),
+            InstanceVariable::Direction => instance.set_direction(value.into()),
+            InstanceVariable::Speed => instance.set_speed(value.into()),
+            InstanceVariable::Friction => instance.friction.set(value.into()),
+            InstanceVariable::Gravity => instance.gravity.set(value.into()),
+            InstanceVariable::GravityDirection => instance.gravity_direction.set(value.into()),
+            InstanceVariable::Alarm => {
+                instance.alarms.borrow_mut().insert(array_index, value);
+            },
+            InstanceVariable::Solid => instance.solid.set(value.is_true()),
+            InstanceVariable::Visible => instance.visible.set(value.is_true()),
+            InstanceVariable::Persistent => instance.persistent.set(value.is_true()),
+            InstanceVariable::Depth => instance.depth.set(value.round()),
+            InstanceVariable::SpriteIndex => {
+                instance.sprite_index.set(value.round());
+                instance.bbox_is_stale.set(true);
+            },
+            InstanceVariable::ImageIndex => {
+                instance.image_index.set(value.into());
+                instance.bbox_is_stale.set(true);
+            },
+            InstanceVariable::ImageSingle => {
+                let frame = f64::from(value);
+                if frame == -1.0 {
+                    instance.image_speed.set(1.0);
+                } else {
+                    instance.image_index.set(frame);
+                    instance.image_speed.set(0.0);
+                    instance.bbox_is_stale.set(true);
+                }
+            },
+            InstanceVariable::ImageXscale => {
+                instance.image_xscale.set(value.into());
+                instance.bbox_is_stale.set(true);
+            },
+            InstanceVariable::ImageYscale => {
+                instance.image_yscale.set(value.into());
+                instance.bbox_is_stale.set(true);
+            },
+            InstanceVariable::ImageAngle => {
+                instance.image_angle.set(value.into());
+                instance.bbox_is_stale.set(true);
+            },
+            InstanceVariable::ImageAlpha => instance.image_alpha.set(value.into()),
+            InstanceVariable::ImageBlend => instance.image_blend.set(value.round()),
+            InstanceVariable::ImageSpeed => instance.image_speed.set(value.into()),
+            InstanceVariable::MaskIndex => {
+                instance.mask_index.set(value.round());
+                instance.bbox_is_stale.set(true);
+            },
+            InstanceVariable::PathPosition => instance.path_position.set(value.into()),
+            InstanceVariable::PathPositionprevious => instance.path_positionprevious.set(value.into()),
+            InstanceVariable::PathSpeed => instance.path_speed.set(value.into()),
+            InstanceVariable::PathScale => instance.path_scale.set(value.into()),
+            InstanceVariable::PathOrientation => instance.path_orientation.set(value.into()),
+            InstanceVariable::PathEndaction => instance.path_endaction.set(value.round()),
+            InstanceVariable::TimelineIndex => instance.timeline_index.set(value.round()),
+            InstanceVariable::TimelinePosition => instance.timeline_position.set(value.into()),
+            InstanceVariable::TimelineSpeed => instance.timeline_speed.set(value.into()),
+            InstanceVariable::TimelineRunning => instance.timeline_running.set(value.is_true()),
+            InstanceVariable::TimelineLoop => instance.timeline_loop.set(value.is_true()),
+            InstanceVariable::Argument0
+            | InstanceVariable::Argument1
+            | InstanceVariable::Argument2
+            | InstanceVariable::Argument3
+            | InstanceVariable::Argument4
+            | InstanceVariable::Argument5
+            | InstanceVariable::Argument6
+            | InstanceVariable::Argument7
+            | InstanceVariable::Argument8
+            | InstanceVariable::Argument9
+            | InstanceVariable::Argument10
+            | InstanceVariable::Argument11
+            | InstanceVariable::Argument12
+            | InstanceVariable::Argument13
+            | InstanceVariable::Argument14
+            | InstanceVariable::Argument15
+            | InstanceVariable::Argument => set_argument(context, argument_number(var, array_index).unwrap(), value)?,
             InstanceVariable::Room => todo!(),
             InstanceVariable::TransitionKind => todo!(),
             InstanceVariable::TransitionSteps => todo!(),
@@ -587,18 +963,25 @@ impl Game {
             InstanceVariable::ViewYport => todo!(),
             InstanceVariable::ViewWport => todo!(),
             InstanceVariable::ViewHport => todo!(),
-            InstanceVariable::ViewAngle => todo!(),
+            InstanceVariable::ViewAngle => match self.views.get(array_index as usize) {
+                Some(view) => view.angle.set(value.into()),
+                None => return Err(Error::InvalidArrayIndex(array_index as i32)),
+            },
             InstanceVariable::ViewHborder => todo!(),
             InstanceVariable::ViewVborder => todo!(),
             InstanceVariable::ViewHspeed => todo!(),
             InstanceVariable::ViewVspeed => todo!(),
             InstanceVariable::ViewObject => todo!(),
-            InstanceVariable::MouseButton => todo!(),
-            InstanceVariable::MouseLastbutton => todo!(),
-            InstanceVariable::KeyboardKey => todo!(),
-            InstanceVariable::KeyboardLastkey => todo!(),
-            InstanceVariable::KeyboardLastchar => todo!(),
-            InstanceVariable::KeyboardString => todo!(),
+            InstanceVariable::MouseButton => self.input.mouse_button.set(value.round()),
+            InstanceVariable::MouseLastbutton => self.input.mouse_lastbutton.set(value.round()),
+            InstanceVariable::KeyboardKey => self.input.keyboard_key.set(value.round()),
+            InstanceVariable::KeyboardLastkey => self.input.keyboard_lastkey.set(value.round()),
+            InstanceVariable::KeyboardLastchar => {
+                self.input.keyboard_lastchar.replace(value.repr().to_string());
+            },
+            InstanceVariable::KeyboardString => {
+                self.input.keyboard_string.replace(value.repr().to_string());
+            },
             InstanceVariable::CursorSprite => todo!(),
             InstanceVariable::ShowScore => todo!(),
             InstanceVariable::ShowLives => todo!(),
@@ -606,15 +989,17 @@ impl Game {
             InstanceVariable::CaptionScore => todo!(),
             InstanceVariable::CaptionLives => todo!(),
             InstanceVariable::CaptionHealth => todo!(),
-            InstanceVariable::ErrorOccurred => todo!(),
-            InstanceVariable::ErrorLast => todo!(),
+            InstanceVariable::ErrorOccurred => self.error_occurred.set(value.is_true()),
+            InstanceVariable::ErrorLast => {
+                self.error_last.replace(value.repr().to_string());
+            },
             _ => return Err(Error::ReadOnlyVariable(*var)),
         }
-        //Ok(()) //uncomment this when it's not unreachable
+        Ok(())
     }
 
     // Get a field value from a DummyFieldHolder
-    fn get_dummy_field(&self, dummy: &DummyFieldHolder, field_id: usize, array_index: u32) -> gml::Result<Value> {
+    pub fn get_dummy_field(&self, dummy: &DummyFieldHolder, field_id: usize, array_index: u32) -> gml::Result<Value> {
         if let Some(Some(value)) = dummy.fields.get(&field_id).map(|field| field.get(array_index)) {
             Ok(value)
         } else {
@@ -627,7 +1012,7 @@ impl Game {
     }
 
     // Set a field on a DummyFieldHolder
-    fn set_dummy_field(&self, dummy: &mut DummyFieldHolder, field_id: usize, array_index: u32, value: Value) {
+    pub fn set_dummy_field(&self, dummy: &mut DummyFieldHolder, field_id: usize, array_index: u32, value: Value) {
         if let Some(field) = dummy.fields.get_mut(&field_id) {
             field.set(array_index, value)
         } else {
@@ -636,23 +1021,25 @@ impl Game {
     }
 
     // Get an instance variable value from a DummyFieldHolder
-    fn get_dummy_var(&self, dummy: &DummyFieldHolder, var: &InstanceVariable, array_index: u32) -> gml::Result<Value> {
+    pub fn get_dummy_var(
+        &self,
+        dummy: &DummyFieldHolder,
+        var: &InstanceVariable,
+        array_index: u32,
+    ) -> gml::Result<Value> {
         if let Some(Some(value)) = dummy.vars.get(var).map(|field| field.get(array_index)) {
             Ok(value)
         } else {
             if self.uninit_fields_are_zero {
                 Ok(Value::Real(0.0))
             } else {
-                Err(Error::UninitializedVariable(
-                    String::from(mappings::INSTANCE_VARIABLES.iter().find(|(_, x)| x == var).unwrap().0),
-                    array_index,
-                ))
+                Err(Error::UninitializedVariable(var_name(var).into(), array_index))
             }
         }
     }
 
     // Set an instance variable on a DummyFieldHolder
-    fn set_dummy_var(&self, dummy: &mut DummyFieldHolder, var: &InstanceVariable, array_index: u32, value: Value) {
+    pub fn set_dummy_var(&self, dummy: &mut DummyFieldHolder, var: &InstanceVariable, array_index: u32, value: Value) {
         if let Some(field) = dummy.vars.get_mut(var) {
             field.set(array_index, value)
         } else {
@@ -660,3 +1047,259 @@ impl Game {
         }
     }
 }
+
+/// Describes an event the way GM8 does, such as "Step Event" or "Other Event: Room Start".
+/// For collision events, this should be given the name of the object collided with if there is one.
+pub fn event_name(event_type: usize, event_number: u32, collision_object: Option<&str>) -> String {
+    match event_type {
+        ev::CREATE => "Create Event".into(),
+        ev::DESTROY => "Destroy Event".into(),
+        ev::ALARMS => format!("Alarm Event for alarm {}", event_number),
+        ev::STEP => match event_number {
+            1 => "Begin Step Event".into(),
+            2 => "End Step Event".into(),
+            _ => "Step Event".into(),
+        },
+        ev::COLLISION => match collision_object {
+            Some(name) => format!("Collision Event with object {}", name),
+            None => format!("Collision Event with object {}", event_number),
+        },
+        ev::KEYBOARD => format!("Keyboard Event for key {}", event_number),
+        ev::MOUSE => format!("Mouse Event {}", event_number),
+        ev::OTHER => match event_number {
+            ev_other::OUTSIDE => "Other Event: Outside Room".into(),
+            ev_other::BOUNDARY => "Other Event: Intersect Boundary".into(),
+            ev_other::GAME_START => "Other Event: Game Start".into(),
+            ev_other::GAME_END => "Other Event: Game End".into(),
+            ev_other::ROOM_START => "Other Event: Room Start".into(),
+            ev_other::ROOM_END => "Other Event: Room End".into(),
+            ev_other::NO_MORE_LIVES => "Other Event: No More Lives".into(),
+            ev_other::ANIMATION_END => "Other Event: Animation End".into(),
+            ev_other::END_OF_PATH => "Other Event: End of Path".into(),
+            ev_other::NO_MORE_HEALTH => "Other Event: No More Health".into(),
+            n if (ev_other::USER0..ev_other::USER0 + 16).contains(&n) => {
+                format!("Other Event: User Defined {}", n - ev_other::USER0)
+            },
+            n => format!("Other Event {}", n),
+        },
+        ev::DRAW => "Draw Event".into(),
+        ev::KEYPRESS => format!("Key Press Event for key {}", event_number),
+        ev::KEYRELEASE => format!("Key Release Event for key {}", event_number),
+        ev::TRIGGER => format!("Trigger Event {}", event_number),
+        _ => format!("Event {} {}", event_type, event_number),
+    }
+}
+
+/// Gets the index into an array field of the element at [index1, index2], as the runtime stores it.
+/// Each index must be from 0 to 31999. A 1D array access is the same as a 2D one with 0 as its first index.
+pub fn array_index(index1: i32, index2: i32) -> gml::Result<u32> {
+    match (index1, index2) {
+        (0..=31999, 0..=31999) => Ok((index1 * 32000 + index2) as u32),
+        (0..=31999, _) => Err(Error::InvalidArrayIndex(index2)),
+        _ => Err(Error::InvalidArrayIndex(index1)),
+    }
+}
+
+/// Sets one of the current script's arguments, which in GM8 are writable like any other local.
+fn set_argument(context: &mut Context, n: usize, value: Value) -> gml::Result<()> {
+    match context.arguments.get_mut(n) {
+        Some(argument) => {
+            *argument = value;
+            context.arguments_set |= 1 << n;
+            Ok(())
+        },
+        None => Err(Error::InvalidArrayIndex(n as i32)),
+    }
+}
+
+/// Gets which argument a built-in variable refers to, if it's argument0 to argument15 or argument[n].
+fn argument_number(var: &InstanceVariable, array_index: u32) -> Option<usize> {
+    Some(match var {
+        InstanceVariable::Argument0 => 0,
+        InstanceVariable::Argument1 => 1,
+        InstanceVariable::Argument2 => 2,
+        InstanceVariable::Argument3 => 3,
+        InstanceVariable::Argument4 => 4,
+        InstanceVariable::Argument5 => 5,
+        InstanceVariable::Argument6 => 6,
+        InstanceVariable::Argument7 => 7,
+        InstanceVariable::Argument8 => 8,
+        InstanceVariable::Argument9 => 9,
+        InstanceVariable::Argument10 => 10,
+        InstanceVariable::Argument11 => 11,
+        InstanceVariable::Argument12 => 12,
+        InstanceVariable::Argument13 => 13,
+        InstanceVariable::Argument14 => 14,
+        InstanceVariable::Argument15 => 15,
+        InstanceVariable::Argument => array_index as usize,
+        _ => return None,
+    })
+}
+
+/// Gets the name of a built-in variable, for error messages.
+fn var_name(var: &InstanceVariable) -> &'static str {
+    mappings::INSTANCE_VARIABLES.iter().find(|(_, x)| x == var).unwrap().0
+}
+
+/// Applies an assignment operator such as += to a variable's old value.
+fn modify(old: Value, modification_type: &ModificationType, value: Value) -> gml::Result<Value> {
+    match modification_type {
+        ModificationType::Add => old.add(value),
+        ModificationType::Subtract => old.sub(value),
+        ModificationType::Multiply => old.mul(value),
+        ModificationType::Divide => old.div(value),
+        ModificationType::BitAnd => old.bitand(value),
+        ModificationType::BitOr => old.bitor(value),
+        ModificationType::BitXor => old.bitxor(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn located_error_message() {
+        let error = Error::UninitializedVariable("foo".into(), 0)
+            .locate(Location { position: Some(Position { line: 3, column: 5 }), ..Default::default() })
+            .locate(Location { script: Some("scr_move".into()), position: None, ..Default::default() })
+            .locate(Location {
+                object: Some("obj_player".into()),
+                event: Some("Step Event".into()),
+                action: Some(1),
+                script: Some("ignored".into()),
+                position: Some(Position { line: 1, column: 1 }),
+            });
+        assert_eq!(
+            error.to_string(),
+            "ERROR in\naction number 1\nof Step Event\nfor object obj_player:\n\n\
+             In script scr_move:\nError in code at line 3:\nat position 5: Unknown variable foo",
+        );
+        assert_eq!(Error::UnknownFunction("foo".into()).to_string(), "Unknown function or script: foo");
+    }
+
+    #[test]
+    fn motion_variables() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(10.0, 20.0);
+        game.assert_returns(inst, "x += 5; return x", 15.0);
+        game.assert_returns(inst, "y = 4; return y", 4.0);
+        game.assert_returns(inst, "return xstart + ystart", 30.0);
+        game.assert_returns(inst, "direction = 90; speed = 2; return vspeed", -2.0);
+        game.assert_returns(inst, "hspeed = -3; vspeed = 0; return direction", 180.0);
+        game.assert_returns(inst, "return speed", 3.0);
+        let code = "friction = 0.5; gravity = 1; gravity_direction = 270; return friction + gravity";
+        game.assert_returns(inst, code, 1.5);
+        game.assert_returns(inst, "return gravity_direction", 270.0);
+        game.assert_returns(inst, "xprevious = 1; yprevious = 2; return xprevious * 10 + yprevious", 12.0);
+        let instance = game.instance_list.get(inst).unwrap();
+        assert_eq!((instance.x.get(), instance.y.get()), (15.0, 4.0));
+    }
+
+    #[test]
+    fn path_variables() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.assert_returns(inst, "return path_index", -1.0);
+        let code = "path_position = 0.25; path_positionprevious = 0.5; return path_position + path_positionprevious";
+        game.assert_returns(inst, code, 0.75);
+        game.assert_returns(inst, "path_speed = -2; path_scale = 3; return path_speed * path_scale", -6.0);
+        let code = "path_orientation = 45; path_endaction = 2.4; return path_orientation + path_endaction";
+        game.assert_returns(inst, code, 47.0);
+        let error = game.test_run(inst, "path_index = 0").unwrap_err();
+        assert!(matches!(error, Error::Located(ref e, _) if matches!(**e, Error::ReadOnlyVariable(_))), "{:?}", error);
+    }
+
+    #[test]
+    fn globalvar_across_scripts() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.test_script("scr_declare", "globalvar shared; shared = 5");
+        game.test_script("scr_read", "shared += 1; return shared");
+        match game.test_run(inst, "scr_declare(); return scr_read() + global.shared") {
+            Ok(Value::Real(value)) => assert_eq!(value, 12.0),
+            other => panic!("got {:?}", other),
+        }
+        assert!(game.instance_list.get(inst).unwrap().fields.borrow().is_empty());
+        game.reset_globals();
+        assert!(game.test_run(inst, "return scr_read()").is_err());
+    }
+
+    #[test]
+    fn error_modes() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        let root = std::env::temp_dir().join(format!("gm8emulator-errors-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        game.sandbox = crate::sandbox::Sandbox::new(root.clone());
+        let context = Context { event_action: 1, event_type: ev::STEP, ..crate::game::Game::test_context(inst) };
+        let error = || Error::UnknownFunction("foo".into());
+        let message = "ERROR in\naction number 2\nof Step Event\nfor object object0:\n\n\
+                       Unknown function or script: foo";
+
+        match game.handle_error(error(), &context) {
+            Err(e) => assert_eq!(e.to_string(), message),
+            Ok(()) => panic!("aborting didn't return the error"),
+        }
+        assert!(!game.error_occurred.get());
+
+        game.error_mode = ErrorMode::Ignore;
+        assert!(game.handle_error(error(), &context).is_ok());
+        assert!(game.error_occurred.get());
+        assert_eq!(*game.error_last.borrow(), message);
+        assert!(!root.join(ERROR_LOG_FILE).exists());
+        assert!(matches!(game.test_run(inst, "return error_occurred"), Ok(Value::Real(v)) if v == 1.0));
+
+        game.error_mode = ErrorMode::Display;
+        game.error_log = true;
+        assert!(game.handle_error(error(), &context).is_ok());
+        assert!(game.handle_error(error(), &context).is_ok());
+        assert_eq!(fs::read_to_string(root.join(ERROR_LOG_FILE)).unwrap(), format!("{}\n\n", message).repeat(2));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn script_arguments() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.test_script("scr_args", "argument5 = 1; argument[1] *= 10; return argument0 + argument1 + argument5");
+        game.test_script("scr_count", "argument[3] = 0; return argument_count");
+        game.test_script("scr_unset", "return argument3");
+        game.test_script("scr_destroyed", "instance_destroy(); argument1 = 2; return argument0 + argument1");
+        game.assert_returns(inst, "return scr_args(1, 2)", 22.0);
+        game.assert_returns(inst, "return scr_count(1, 2)", 2.0);
+        game.assert_returns(inst, "var a; a = 0; with (noone) a = argument0; return scr_destroyed(3)", 5.0);
+        assert!(game.test_run(inst, "return scr_unset(1, 2)").is_err());
+        game.uninit_args_are_zero = true;
+        assert!(matches!(game.test_run(inst, "return scr_unset(1, 2)"), Ok(Value::Real(v)) if v == 0.0));
+    }
+
+    #[test]
+    fn constant_calls() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        let folded = game.test_script("scr_folded", "return string_upper('ab' + string(sqr(3))) + string(x)");
+        let error = game.test_script("scr_error", "return sqrt(-1)");
+        let random = game.test_script("scr_random", "return random(1)");
+        let unimplemented = game.test_script("scr_unimplemented", "return make_color_rgb(1, 2, 3)");
+        let long = game.test_script("scr_long", "return string_repeat('ab', 1000)");
+        let run = |game: &mut crate::game::Game| {
+            ["scr_folded()", "scr_error()", "scr_random() < 1"]
+                .iter()
+                .map(|call| format!("{:?}", game.test_run(inst, &format!("return {}", call))))
+                .collect::<Vec<_>>()
+        };
+        let unfolded = run(&mut game);
+        let (scripts, compiler) = (&game.assets.scripts, &mut game.compiler);
+        let compiled = scripts.iter().map(|s| s.as_ref().map(|s| compiler.compile(&s.source).unwrap())).collect();
+        game.fold_constant_calls(compiled);
+        assert_eq!(run(&mut game), unfolded);
+
+        let ops = |script: usize| format!("{:?}", game.assets.scripts[script].as_ref().unwrap().bytecode.ops());
+        assert!(ops(folded).contains("Push(Str(\"AB9\"))") && !ops(folded).contains("string_upper"), "{}", ops(folded));
+        assert!(ops(error).contains("function: sqrt"), "{}", ops(error));
+        assert!(ops(random).contains("function: random"), "{}", ops(random));
+        assert!(ops(unimplemented).contains("function: make_color_rgb"), "{}", ops(unimplemented));
+        assert!(ops(long).contains("function: string_repeat"), "{}", ops(long));
+    }
+}
@@ -86,6 +86,18 @@ impl Value {
         }
     }
 
+    /// Converts the value to a string the way GML's string() does.
+    /// Whole numbers have no decimal places, anything else is shown to two, so 0.1 becomes "0.10".
+    pub fn repr(&self) -> Rc<str> {
+        match self {
+            Real(real) if real.is_nan() => "NAN".into(),
+            Real(real) if real.is_infinite() => if *real > 0.0 { "INF" } else { "-INF" }.into(),
+            Real(real) if real.fract() == 0.0 => util::format_decimal(*real, 0).into(),
+            Real(real) => util::format_decimal(*real, 2).into(),
+            Str(string) => string.clone(),
+        }
+    }
+
     /// Formats the value as a number or a string with quotes around it so you can see that it is.
     /// Used in generating error messages.
     fn log_fmt(&self) -> String {
@@ -287,6 +299,46 @@ impl Default for Value {
     }
 }
 
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        if b { Real(super::TRUE) } else { Real(super::FALSE) }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Real(f)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Real(i.into())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Str(s.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Str(s.into())
+    }
+}
+
+impl From<Value> for f64 {
+    /// Strings become 0, as they do when the runner rounds them.
+    fn from(v: Value) -> Self {
+        match v {
+            Real(f) => f,
+            Str(_) => 0.0,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,728 @@
+use super::{
+    compiler::lexer::Position,
+    decompiler,
+    runtime::{
+        array_index, ArrayAccessor, Error, InstanceIdentifier, Instruction, Location, ModificationType, Node,
+        ReturnType, Target,
+    },
+    Context, InstanceVariable, Value,
+};
+use crate::{
+    asset::{self, Script},
+    game::Game,
+    gml,
+};
+use std::{fmt, mem};
+
+/// Compiled GML flattened into a list of operations on a value stack, which runs in a single dispatch loop.
+/// It's built from the compiler's instructions, which are kept as they are for folding and decompiling.
+#[derive(Debug)]
+pub struct Code {
+    ops: Box<[Op]>,
+}
+
+/// A single bytecode operation. Operands are taken from the top of the stack, with the last one on top.
+#[derive(Debug)]
+pub enum Op {
+    /// Notes where the code after this starts in the source code, for errors to say where they happened.
+    Position(Position),
+    Push(Value),
+    Pop,
+    CallFunction { function: Function, argc: usize },
+    CallScript { script_id: usize, argc: usize },
+    GetField { index: usize, owner: Owner, array: Array },
+    SetField { index: usize, owner: Owner, array: Array, modification: Option<ModificationType> },
+    GetVariable { var: InstanceVariable, owner: Owner, array: Array },
+    SetVariable { var: InstanceVariable, owner: Owner, array: Array, modification: Option<ModificationType> },
+    Binary(fn(Value, Value) -> gml::Result<Value>),
+    Unary(fn(Value) -> gml::Result<Value>),
+    Jump(usize),
+    JumpIfFalse(usize),
+
+    /// Pops a case value and compares it with the switch input under it. If they're equal, the input is popped
+    /// too and execution jumps to the case's code.
+    Case(usize),
+
+    /// Pops the number of times to repeat onto the repeat counter stack.
+    RepeatStart,
+
+    /// Counts down the innermost repeat, or jumps to the end of it once it's done.
+    RepeatNext(usize),
+    RepeatEnd,
+
+    /// Pops the target and runs the body once for each instance it refers to.
+    With(Box<Code>),
+    SetReturnValue,
+    Return(ReturnType),
+    Error(Error),
+}
+
+/// A kernel function along with its index in mappings::FUNCTIONS, so it can be shown by its name when debugging.
+#[derive(Clone, Copy)]
+pub struct Function(pub fn(&mut Game, &mut Context, &[Value]) -> gml::Result<Value>, pub usize);
+
+/// Who a variable belongs to. An owner given by an expression is evaluated onto the stack before the access.
+#[derive(Clone, Copy, Debug)]
+pub enum Owner {
+    Own,
+    Other,
+    Global,
+    Local,
+    Stack,
+}
+
+/// How many array indices are evaluated onto the stack for a variable access, after its owner.
+#[derive(Clone, Copy, Debug)]
+pub enum Array {
+    None,
+    Single,
+    Double,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", decompiler::function_name(self.1))
+    }
+}
+
+/// A loop or switch which is being built, along with the jumps out of it which need to know where it ends.
+struct Breakable {
+    is_switch: bool,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// Flattens instructions into bytecode.
+#[derive(Default)]
+struct Builder {
+    ops: Vec<Op>,
+    breakables: Vec<Breakable>,
+
+    /// The last source position noted, so it can be noted again before a loop condition is re-evaluated
+    position: Option<Position>,
+}
+
+impl Code {
+    /// Builds bytecode which does the same as the given instructions.
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let mut builder = Builder::default();
+        builder.instructions(instructions);
+        Self { ops: builder.ops.into_boxed_slice() }
+    }
+
+    /// Builds bytecode which leaves the value of an expression on the stack, to be run with Game::eval.
+    pub fn expression(node: &Node) -> Self {
+        let mut builder = Builder::default();
+        builder.node(node);
+        Self { ops: builder.ops.into_boxed_slice() }
+    }
+
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+}
+
+impl Builder {
+    fn instructions(&mut self, instructions: &[Instruction]) {
+        for instruction in instructions {
+            self.instruction(instruction);
+        }
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::SetField { accessor, value } | Instruction::ModifyField { accessor, value, .. } => {
+                let modification = match instruction {
+                    Instruction::ModifyField { modification_type, .. } => Some(*modification_type),
+                    _ => None,
+                };
+                self.node(value);
+                let owner = self.owner(&accessor.owner);
+                let array = self.array(&accessor.array);
+                self.ops.push(Op::SetField { index: accessor.index, owner, array, modification });
+            },
+            Instruction::SetVariable { accessor, value } | Instruction::ModifyVariable { accessor, value, .. } => {
+                let modification = match instruction {
+                    Instruction::ModifyVariable { modification_type, .. } => Some(*modification_type),
+                    _ => None,
+                };
+                self.node(value);
+                let owner = self.owner(&accessor.owner);
+                let array = self.array(&accessor.array);
+                self.ops.push(Op::SetVariable { var: accessor.var, owner, array, modification });
+            },
+            Instruction::EvalExpression { node } => {
+                self.node(node);
+                self.ops.push(Op::Pop);
+            },
+            Instruction::IfElse { cond, if_body, else_body } => {
+                self.node(cond);
+                let to_else = self.placeholder();
+                self.instructions(if_body);
+                if else_body.is_empty() {
+                    self.patch(to_else, self.ops.len());
+                } else {
+                    let to_end = self.placeholder();
+                    self.patch(to_else, self.ops.len());
+                    self.instructions(else_body);
+                    self.patch(to_end, self.ops.len());
+                }
+            },
+            Instruction::LoopUntil { cond, body } => {
+                let position = self.position;
+                let start = self.ops.len();
+                self.breakables.push(Breakable { is_switch: false, breaks: Vec::new(), continues: Vec::new() });
+                self.instructions(body);
+                let check = self.ops.len();
+                self.note_position(position);
+                self.node(cond);
+                self.ops.push(Op::JumpIfFalse(start));
+                self.close_breakable(self.ops.len(), Some(check));
+            },
+            Instruction::LoopWhile { cond, body, step } => {
+                // The loop's own position is usually just before it, so jumping back there notes it again
+                let start = match self.ops.last() {
+                    Some(Op::Position(_)) => self.ops.len() - 1,
+                    _ => {
+                        let start = self.ops.len();
+                        self.note_position(self.position);
+                        start
+                    },
+                };
+                self.node(cond);
+                let to_end = self.ops.len();
+                self.ops.push(Op::JumpIfFalse(0));
+                self.breakables.push(Breakable { is_switch: false, breaks: vec![to_end], continues: Vec::new() });
+                self.instructions(body);
+                let step_start = self.ops.len();
+                self.instructions(step);
+                self.ops.push(Op::Jump(start));
+                self.close_breakable(self.ops.len(), Some(step_start));
+            },
+            Instruction::Return { return_type } => self.jump_out(*return_type),
+            Instruction::Repeat { count, body } => {
+                self.node(count);
+                self.ops.push(Op::RepeatStart);
+                let next = self.ops.len();
+                self.ops.push(Op::RepeatNext(0));
+                self.breakables.push(Breakable { is_switch: false, breaks: vec![next], continues: Vec::new() });
+                self.instructions(body);
+                self.ops.push(Op::Jump(next));
+                self.close_breakable(self.ops.len(), Some(next));
+                self.ops.push(Op::RepeatEnd);
+            },
+            Instruction::SetReturnValue { value } => {
+                self.node(value);
+                self.ops.push(Op::SetReturnValue);
+            },
+            Instruction::Switch { input, cases, default, body } => {
+                self.node(input);
+                let mut case_jumps = Vec::with_capacity(cases.len());
+                for (cond, start) in cases.iter() {
+                    self.node(cond);
+                    case_jumps.push((self.ops.len(), *start));
+                    self.ops.push(Op::Case(0));
+                }
+                self.ops.push(Op::Pop);
+                let to_default = self.ops.len();
+                self.ops.push(Op::Jump(0));
+
+                // Cases refer to instructions in the body, so note where each one starts
+                let breaks = if default.is_none() { vec![to_default] } else { Vec::new() };
+                self.breakables.push(Breakable { is_switch: true, breaks, continues: Vec::new() });
+                let mut starts = Vec::with_capacity(body.len() + 1);
+                for instruction in body.iter() {
+                    starts.push(self.ops.len());
+                    self.instruction(instruction);
+                }
+                starts.push(self.ops.len());
+                for (op, start) in case_jumps {
+                    self.ops[op] = Op::Case(starts[start]);
+                }
+                if let Some(start) = default {
+                    self.ops[to_default] = Op::Jump(starts[*start]);
+                }
+                self.close_breakable(self.ops.len(), None);
+            },
+            Instruction::With { target, body } => {
+                self.node(target);
+                self.ops.push(Op::With(Box::new(Code::new(body))));
+            },
+            Instruction::SourcePosition { position } => {
+                self.position = Some(*position);
+                self.ops.push(Op::Position(*position));
+            },
+            Instruction::RuntimeError { error } => self.ops.push(Op::Error(error.clone())),
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Literal { value } => self.ops.push(Op::Push(value.clone())),
+            Node::Function { args, function, index } => {
+                args.iter().for_each(|arg| self.node(arg));
+                self.ops.push(Op::CallFunction { function: Function(*function, *index), argc: args.len() });
+            },
+            Node::Script { args, script_id } => {
+                args.iter().for_each(|arg| self.node(arg));
+                self.ops.push(Op::CallScript { script_id: *script_id, argc: args.len() });
+            },
+            Node::Field { accessor } => {
+                let owner = self.owner(&accessor.owner);
+                let array = self.array(&accessor.array);
+                self.ops.push(Op::GetField { index: accessor.index, owner, array });
+            },
+            Node::Variable { accessor } => {
+                let owner = self.owner(&accessor.owner);
+                let array = self.array(&accessor.array);
+                self.ops.push(Op::GetVariable { var: accessor.var, owner, array });
+            },
+            Node::Binary { left, right, operator, .. } => {
+                self.node(left);
+                self.node(right);
+                self.ops.push(Op::Binary(*operator));
+            },
+            Node::Unary { child, operator, .. } => {
+                self.node(child);
+                self.ops.push(Op::Unary(*operator));
+            },
+            Node::RuntimeError { error } => self.ops.push(Op::Error(error.clone())),
+        }
+    }
+
+    fn owner(&mut self, owner: &InstanceIdentifier) -> Owner {
+        match owner {
+            InstanceIdentifier::Own => Owner::Own,
+            InstanceIdentifier::Other => Owner::Other,
+            InstanceIdentifier::Global => Owner::Global,
+            InstanceIdentifier::Local => Owner::Local,
+            InstanceIdentifier::Expression(node) => {
+                self.node(node);
+                Owner::Stack
+            },
+        }
+    }
+
+    fn array(&mut self, array: &ArrayAccessor) -> Array {
+        match array {
+            ArrayAccessor::None => Array::None,
+            ArrayAccessor::Single(node) => {
+                self.node(node);
+                Array::Single
+            },
+            ArrayAccessor::Double(node1, node2) => {
+                self.node(node1);
+                self.node(node2);
+                Array::Double
+            },
+        }
+    }
+
+    /// Notes the source position again, if there is one.
+    fn note_position(&mut self, position: Option<Position>) {
+        if let Some(position) = position {
+            self.position = Some(position);
+            self.ops.push(Op::Position(position));
+        }
+    }
+
+    /// Adds a conditional jump to be pointed somewhere later on, returning where it is.
+    fn placeholder(&mut self) -> usize {
+        self.ops.push(Op::JumpIfFalse(0));
+        self.ops.len() - 1
+    }
+
+    /// Points an unfinished jump, or the end of a repeat, at the given op.
+    fn patch(&mut self, op: usize, target: usize) {
+        self.ops[op] = match self.ops[op] {
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::RepeatNext(_) => Op::RepeatNext(target),
+            _ => Op::Jump(target),
+        };
+    }
+
+    /// Leaves the innermost loop or switch if it's a break or the innermost loop if it's a continue,
+    /// as a loop in the tree would. With nothing to leave, the code stops as it would at the top of the tree.
+    fn jump_out(&mut self, return_type: ReturnType) {
+        let breakable = match return_type {
+            ReturnType::Break => self.breakables.last_mut(),
+            ReturnType::Continue => self.breakables.iter_mut().rev().find(|b| !b.is_switch),
+            ReturnType::Exit => None,
+            ReturnType::Normal => return,
+        };
+        match breakable {
+            Some(breakable) => {
+                let jumps = match return_type {
+                    ReturnType::Break => &mut breakable.breaks,
+                    _ => &mut breakable.continues,
+                };
+                jumps.push(self.ops.len());
+                self.ops.push(Op::Jump(0));
+            },
+            None => self.ops.push(Op::Return(return_type)),
+        }
+    }
+
+    /// Finishes the innermost loop or switch, pointing its breaks at its end and its continues at the given op.
+    fn close_breakable(&mut self, end: usize, check: Option<usize>) {
+        let breakable = self.breakables.pop().unwrap();
+        for op in breakable.breaks {
+            self.patch(op, end);
+        }
+        if let Some(check) = check {
+            for op in breakable.continues {
+                self.patch(op, check);
+            }
+        }
+    }
+}
+
+/// Takes the top value off the stack. Bytecode always pushes everything it pops, so the default is never used.
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().unwrap_or_default()
+}
+
+/// Takes the array indices of a variable access off the stack.
+fn pop_array_index(stack: &mut Vec<Value>, array: Array) -> gml::Result<u32> {
+    match array {
+        Array::None => Ok(0),
+        Array::Single => array_index(0, pop(stack).round()),
+        Array::Double => {
+            let index2 = pop(stack).round();
+            let index1 = pop(stack).round();
+            array_index(index1, index2)
+        },
+    }
+}
+
+impl Game {
+    /// Runs bytecode in the given context.
+    pub fn run(&mut self, code: &Code, context: &mut Context) -> gml::Result<ReturnType> {
+        // Code run from inside a kernel function finds the stack already taken, and has to make its own
+        let scripts = self.assets.scripts.clone();
+        let mut stack = mem::take(&mut self.stack);
+        let result = self.run_frame(code, &scripts, context, &mut stack);
+        self.stack = stack;
+        result
+    }
+
+    /// Works out the value of an expression from its bytecode, as built by Code::expression.
+    pub fn eval(&mut self, code: &Code, context: &mut Context) -> gml::Result<Value> {
+        let scripts = self.assets.scripts.clone();
+        let mut stack = mem::take(&mut self.stack);
+        let base = stack.len();
+        let result = self.dispatch(code, &scripts, context, &mut stack, &mut None);
+        let value = stack.split_off(base).pop();
+        self.stack = stack;
+        result.map(|_| value.unwrap_or_default())
+    }
+
+    /// Runs bytecode on the end of a stack shared with whatever called it, leaving the stack as it was.
+    fn run_frame(
+        &mut self,
+        code: &Code,
+        scripts: &[Option<Box<Script>>],
+        context: &mut Context,
+        stack: &mut Vec<Value>,
+    ) -> gml::Result<ReturnType> {
+        let base = stack.len();
+        let mut position = None;
+        let result = self.dispatch(code, scripts, context, stack, &mut position);
+        stack.truncate(base);
+        result.map_err(|e| match position {
+            Some(position) => e.locate(Location { position: Some(position), ..Default::default() }),
+            None => e,
+        })
+    }
+
+    fn dispatch(
+        &mut self,
+        code: &Code,
+        scripts: &[Option<Box<Script>>],
+        context: &mut Context,
+        stack: &mut Vec<Value>,
+        position: &mut Option<Position>,
+    ) -> gml::Result<ReturnType> {
+        let mut pc = 0;
+        while let Some(op) = code.ops.get(pc) {
+            pc += 1;
+            match op {
+                Op::Position(p) => *position = Some(*p),
+                Op::Push(value) => stack.push(value.clone()),
+                Op::Pop => {
+                    pop(stack);
+                },
+                Op::CallFunction { function, argc } => {
+                    let base = stack.len().saturating_sub(*argc);
+                    let value = (function.0)(self, context, &stack[base..])?;
+                    stack.truncate(base);
+                    stack.push(value);
+                },
+                Op::CallScript { script_id, argc } => {
+                    let script = match scripts.get(*script_id) {
+                        Some(Some(script)) => script,
+                        _ => return Err(Error::NonexistentAsset(asset::Type::Script, *script_id)),
+                    };
+                    let mut new_context =
+                        Context { argument_count: *argc, ..context.nested(context.this, context.other) };
+                    let base = stack.len().saturating_sub(*argc);
+                    for (dest, src) in new_context.arguments.iter_mut().zip(stack.drain(base..)) {
+                        *dest = src;
+                    }
+                    if let Err(e) = self.run_frame(&script.bytecode, scripts, &mut new_context, stack) {
+                        return Err(e.locate(Location { script: Some(script.name.clone()), ..Default::default() }))
+                    }
+                    stack.push(new_context.return_value);
+                },
+                Op::GetField { index, owner, array } => {
+                    let array_index = pop_array_index(stack, *array)?;
+                    let target = self.stack_target(*owner, stack, context);
+                    let value = self.read_field(target, *index, array_index, context)?;
+                    stack.push(value);
+                },
+                Op::SetField { index, owner, array, modification } => {
+                    let array_index = pop_array_index(stack, *array)?;
+                    let target = self.stack_target(*owner, stack, context);
+                    let value = pop(stack);
+                    self.write_field(target, *index, array_index, value, modification.as_ref(), context)?;
+                },
+                Op::GetVariable { var, owner, array } => {
+                    let array_index = pop_array_index(stack, *array)?;
+                    let target = self.stack_target(*owner, stack, context);
+                    let value = self.read_var(target, var, array_index, context)?;
+                    stack.push(value);
+                },
+                Op::SetVariable { var, owner, array, modification } => {
+                    let array_index = pop_array_index(stack, *array)?;
+                    let target = self.stack_target(*owner, stack, context);
+                    let value = pop(stack);
+                    self.write_var(target, var, array_index, value, modification.as_ref(), context)?;
+                },
+                Op::Binary(operator) => {
+                    let right = pop(stack);
+                    let left = pop(stack);
+                    stack.push(operator(left, right)?);
+                },
+                Op::Unary(operator) => {
+                    let child = pop(stack);
+                    stack.push(operator(child)?);
+                },
+                Op::Jump(target) => pc = *target,
+                Op::JumpIfFalse(target) => {
+                    if !pop(stack).is_true() {
+                        pc = *target;
+                    }
+                },
+                Op::Case(target) => {
+                    let case = pop(stack);
+                    if stack.last().map(|input| case.almost_equals(input)).unwrap_or(false) {
+                        pop(stack);
+                        pc = *target;
+                    }
+                },
+                Op::RepeatStart => {
+                    let count = pop(stack).round();
+                    stack.push(count.into());
+                },
+                Op::RepeatNext(end) => match stack.last_mut() {
+                    Some(Value::Real(count)) if *count > 0.0 => *count -= 1.0,
+                    _ => pc = *end,
+                },
+                Op::RepeatEnd => {
+                    pop(stack);
+                },
+                Op::With(body) => {
+                    let target = pop(stack).round();
+                    let execute = |game: &mut Self, context: &mut Context| {
+                        game.run_frame(body, scripts, context, stack)
+                    };
+                    match self.run_with(target, context, execute)? {
+                        ReturnType::Normal => (),
+                        r => return Ok(r),
+                    }
+                },
+                Op::SetReturnValue => context.return_value = pop(stack),
+                Op::Return(return_type) => return Ok(*return_type),
+                Op::Error(error) => return Err(error.clone()),
+            }
+        }
+        Ok(ReturnType::Normal)
+    }
+
+    /// Works out where a variable belongs, taking its owner off the stack if it was given by an expression.
+    fn stack_target(&self, owner: Owner, stack: &mut Vec<Value>, context: &Context) -> Target {
+        match owner {
+            Owner::Own => self.single_target(context.this),
+            Owner::Other => self.single_target(context.other),
+            Owner::Global => Target::Globals,
+            Owner::Local => Target::Locals,
+            Owner::Stack => self.owner_target(pop(stack).round(), context),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gml::Compiler;
+
+    fn ops(source: &str) -> Vec<String> {
+        let mut compiler = Compiler::new();
+        let code = Code::new(&compiler.compile(source).unwrap());
+        code.ops()
+            .iter()
+            .map(|op| match op {
+                Op::Position(position) => format!("Position({})", position.line),
+                Op::Push(value) => format!("Push({})", value.repr()),
+                Op::Binary(_) => "Binary".into(),
+                Op::SetField { .. } => "SetField".into(),
+                Op::GetField { .. } => "GetField".into(),
+                op => format!("{:?}", op),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loops() {
+        assert_eq!(ops("while a {\n    if b break\n    continue\n}"), [
+            "Position(1)",
+            "GetField",
+            "JumpIfFalse(11)",
+            "Position(2)",
+            "GetField",
+            "JumpIfFalse(8)",
+            "Position(2)",
+            "Jump(11)",
+            "Position(3)",
+            "Jump(10)",
+            "Jump(0)",
+        ]);
+        assert_eq!(ops("do a += 1 until a > 2"), [
+            "Position(1)",
+            "Position(1)",
+            "Push(1)",
+            "SetField",
+            "Position(1)",
+            "GetField",
+            "Push(2)",
+            "Binary",
+            "JumpIfFalse(1)",
+        ]);
+        assert_eq!(ops("repeat 3 { break }"), [
+            "Position(1)",
+            "Push(3)",
+            "RepeatStart",
+            "RepeatNext(7)",
+            "Position(1)",
+            "Jump(7)",
+            "Jump(3)",
+            "RepeatEnd",
+        ]);
+        assert_eq!(ops("break; exit"), ["Position(1)", "Return(Break)", "Position(1)", "Return(Exit)"]);
+    }
+
+    #[test]
+    fn switch() {
+        assert_eq!(ops("switch a { case 1: b = 2; break; default: exit }"), [
+            "Position(1)",
+            "GetField",
+            "Push(1)",
+            "Case(6)",
+            "Pop",
+            "Jump(11)",
+            "Position(1)",
+            "Push(2)",
+            "SetField",
+            "Position(1)",
+            "Jump(13)",
+            "Position(1)",
+            "Return(Exit)",
+        ]);
+    }
+
+    #[test]
+    fn function_names() {
+        assert_eq!(ops("a = max(1, 2)"), [
+            "Position(1)",
+            "Push(1)",
+            "Push(2)",
+            "CallFunction { function: max, argc: 2 }",
+            "SetField",
+        ]);
+    }
+
+    /// Runs some code in a fresh test game with two instances and a script, and describes what it exits with
+    /// or the error it raises.
+    fn run(source: &str) -> String {
+        let mut game = Game::test();
+        game.test_script("scr_add", "argument3 = 10; return argument0 + argument1 + argument3");
+        let this = game.test_instance(2.0, 3.0);
+        game.test_instance(5.0, 7.0);
+        let code = Code::new(&game.compiler.compile(source).unwrap());
+        let mut context = Game::test_context(this);
+        match game.run(&code, &mut context) {
+            Ok(return_type) => format!("{:?} {}", return_type, context.return_value.repr()),
+            Err(e) => format!("error: {}", e),
+        }
+    }
+
+    #[test]
+    fn results() {
+        let cases: &[(&str, &str)] = &[
+            ("return 1 + 2 * 3 - 4 / 8", "Exit 6.50"),
+            ("return 'a' + string(5 mod 3) + chr(66)", "Exit a2B"),
+            (
+                "var i, s; s = 0; for (i = 0; i < 10; i += 1) { if i == 3 continue; if i == 7 break; s += i } return s",
+                "Exit 18",
+            ),
+            ("var i; i = 0; while true { i += 1; if i > 4 break } return i", "Exit 5"),
+            ("var i; i = 0; do { i += 1; if i mod 2 continue; i += 10 } until i > 30; return i", "Exit 36"),
+            ("var n; n = 0; repeat 5 { repeat 3 { n += 1; if n mod 4 == 0 break } } return n", "Exit 11"),
+            ("var n; n = 0; repeat 3 { n += 1; if n == 2 exit } return n", "Exit 0"),
+            (
+                "var r; r = ''; switch 2 { case 1: r += 'a'; case 2: r += 'b'; case 3: r += 'c'; break; default: \
+                 r += 'd' } return r",
+                "Exit bc",
+            ),
+            (
+                "var i, r; r = ''; for (i = 0; i < 4; i += 1) { switch i { case 1: continue; case 2: break; default: \
+                 r += string(i) } r += '.' } return r",
+                "Exit 0..3.",
+            ),
+            ("switch 'x' { case 1: return 1; default: return 2 }", "Exit 2"),
+            ("var t; t = 0; with (object0) t += x; return t", "Exit 7"),
+            ("var t; t = 0; with (all) { if x > 3 break; t += y } return t", "Exit 3"),
+            ("with (object0) { y = 1; exit } return y", "Exit 0"),
+            ("var t; t = 0; with (noone) t = 1; return t", "Exit 0"),
+            ("return scr_add(1, 2) + scr_add(3, 4, 5, 6)", "Exit 30"),
+            ("a[3] = 4; a[1, 2] = 5; a[3] += 1; return a[3] + a[1, 2]", "Exit 10"),
+            ("globalvar g; g = 3; global.h = g * 2; return global.g + global.h", "Exit 9"),
+            ("other.x += 1; return x + other.x", "Exit 6"),
+            (
+                "return undefined_variable",
+                "error: Error in code at line 1:\nat position 1: Unknown variable undefined_variable",
+            ),
+            ("a = 1;\nb = a + 'text'", "error: Error in code at line 2:\nat position 1: Wrong type of arguments to +."),
+            (
+                "return scr_add(1)",
+                "error: In script scr_add:\nError in code at line 1:\nat position 17: Unknown variable argument1",
+            ),
+            ("exit", "Exit 0"),
+        ];
+        for &(source, expected) in cases {
+            assert_eq!(run(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn expression() {
+        let mut game = Game::test();
+        let this = game.test_instance(2.0, 3.0);
+        let node = game.compiler.compile_expression("x * 10 + string_length('abc')").unwrap();
+        let mut context = Game::test_context(this);
+        assert_eq!(game.eval(&Code::expression(&node), &mut context).unwrap().round(), 23);
+        let node = game.compiler.compile_expression("missing + 1").unwrap();
+        assert!(game.eval(&Code::expression(&node), &mut context).is_err());
+        assert!(game.stack.is_empty());
+    }
+}
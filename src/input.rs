@@ -0,0 +1,543 @@
+//! Input state, updated from window events and read by the kernel functions and input events.
+//!
+//! Keys are identified by their Windows virtual-key codes, as GM8 games expect.
+//! Mouse buttons are identified by GM8's mb_* constants.
+
+pub mod joystick;
+
+use std::cell::{Cell, RefCell};
+
+/// Pseudo key code which is held whenever no other key is.
+pub const VK_NOKEY: i32 = 0;
+/// Pseudo key code which is held whenever any other key is.
+pub const VK_ANYKEY: i32 = 1;
+
+pub const VK_BACKSPACE: u8 = 8;
+
+pub const MB_ANY: i32 = -1;
+pub const MB_NONE: i32 = 0;
+pub const MB_LEFT: i32 = 1;
+pub const MB_RIGHT: i32 = 2;
+pub const MB_MIDDLE: i32 = 3;
+
+/// The most characters keyboard_string can hold. Older characters are dropped from the start.
+const KEYBOARD_STRING_LIMIT: usize = 1024;
+
+pub struct Input {
+    /// Keys held, pressed this step and released this step, after keyboard_set_map has been applied
+    keys_held: [bool; 256],
+    keys_pressed: [bool; 256],
+    keys_released: [bool; 256],
+
+    /// Keys physically held, as keyboard_check_direct sees them. This includes the left and right versions of
+    /// shift, control and alt, which the other keyboard functions never see.
+    keys_direct: [bool; 256],
+
+    /// Which key each key acts as, as set by keyboard_set_map
+    key_map: [u8; 256],
+
+    // Keyboard variables, which scripts can also set
+    pub keyboard_key: Cell<i32>,
+    pub keyboard_lastkey: Cell<i32>,
+    pub keyboard_lastchar: RefCell<String>,
+    pub keyboard_string: RefCell<String>,
+
+    /// Mouse buttons held, pressed this step and released this step, indexed by their mb_* constants
+    buttons_held: [bool; 4],
+    buttons_pressed: [bool; 4],
+    buttons_released: [bool; 4],
+
+    /// Whether the mouse wheel was scrolled up or down this step
+    wheel_up: bool,
+    wheel_down: bool,
+
+    /// Where the mouse is in the window, as it would be if the window was its expected (unscaled) size
+    pub mouse_window_x: f64,
+    pub mouse_window_y: f64,
+
+    /// Where the mouse is in the room, which is worked out at the start of every step, and where it was before
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub mouse_previous: (i32, i32),
+
+    // Mouse variables, which scripts can also set
+    pub mouse_button: Cell<i32>,
+    pub mouse_lastbutton: Cell<i32>,
+
+    /// The state of each joystick as of the start of this step, or None if it isn't connected
+    pub joysticks: [Option<joystick::State>; joystick::JOYSTICK_COUNT],
+}
+
+impl Input {
+    pub fn new() -> Self {
+        let mut key_map = [0; 256];
+        key_map.iter_mut().enumerate().for_each(|(i, key)| *key = i as u8);
+        Self {
+            keys_held: [false; 256],
+            keys_pressed: [false; 256],
+            keys_released: [false; 256],
+            keys_direct: [false; 256],
+            key_map,
+            keyboard_key: Cell::new(0),
+            keyboard_lastkey: Cell::new(0),
+            keyboard_lastchar: RefCell::new(String::new()),
+            keyboard_string: RefCell::new(String::new()),
+            buttons_held: [false; 4],
+            buttons_pressed: [false; 4],
+            buttons_released: [false; 4],
+            wheel_up: false,
+            wheel_down: false,
+            mouse_window_x: 0.0,
+            mouse_window_y: 0.0,
+            mouse_x: 0,
+            mouse_y: 0,
+            mouse_previous: (0, 0),
+            mouse_button: Cell::new(MB_NONE),
+            mouse_lastbutton: Cell::new(MB_NONE),
+            joysticks: Default::default(),
+        }
+    }
+
+    /// Handles a key being pressed, or repeated while held. Sided is the left/right specific code of a modifier key.
+    pub fn key_press(&mut self, key: u8, sided: Option<u8>) {
+        self.keys_direct[usize::from(key)] = true;
+        if let Some(sided) = sided {
+            self.keys_direct[usize::from(sided)] = true;
+        }
+        let key = self.key_map[usize::from(key)];
+        if !self.keys_held[usize::from(key)] {
+            self.keys_pressed[usize::from(key)] = true;
+        }
+        self.keys_held[usize::from(key)] = true;
+        self.keyboard_key.set(key.into());
+        self.keyboard_lastkey.set(key.into());
+    }
+
+    /// Handles a key being released.
+    pub fn key_release(&mut self, key: u8, sided: Option<u8>) {
+        self.keys_direct[usize::from(key)] = false;
+        if let Some(sided) = sided {
+            self.keys_direct[usize::from(sided)] = false;
+        }
+        let key = self.key_map[usize::from(key)];
+        if self.keys_held[usize::from(key)] {
+            self.keys_released[usize::from(key)] = true;
+        }
+        self.keys_held[usize::from(key)] = false;
+        if self.keyboard_key.get() == i32::from(key) {
+            self.keyboard_key.set(0);
+        }
+    }
+
+    /// Handles a character being typed. A backspace character deletes the last character of keyboard_string.
+    pub fn char_typed(&mut self, ch: char) {
+        let mut string = self.keyboard_string.borrow_mut();
+        if ch == char::from(VK_BACKSPACE) {
+            string.pop();
+        } else {
+            string.push(ch);
+            let excess = string.chars().count().saturating_sub(KEYBOARD_STRING_LIMIT);
+            if excess > 0 {
+                let cut = string.char_indices().nth(excess).map_or(string.len(), |(i, _)| i);
+                string.replace_range(..cut, "");
+            }
+        }
+        self.keyboard_lastchar.replace(ch.to_string());
+    }
+
+    /// Forgets which keys and mouse buttons were pressed and released. This is done at the start of every step.
+    pub fn clear_presses(&mut self) {
+        self.keys_pressed = [false; 256];
+        self.keys_released = [false; 256];
+        self.buttons_pressed = [false; 4];
+        self.buttons_released = [false; 4];
+        self.wheel_up = false;
+        self.wheel_down = false;
+    }
+
+    /// Checks a key against a state, handling vk_nokey and vk_anykey.
+    fn check(state: &[bool; 256], key: i32) -> bool {
+        match key {
+            VK_NOKEY => !state[2..].iter().any(|&held| held),
+            VK_ANYKEY => state[2..].iter().any(|&held| held),
+            key if key > 0 && key < 256 => state[key as usize],
+            _ => false,
+        }
+    }
+
+    pub fn keyboard_check(&self, key: i32) -> bool {
+        Self::check(&self.keys_held, key)
+    }
+
+    pub fn keyboard_check_pressed(&self, key: i32) -> bool {
+        Self::check(&self.keys_pressed, key)
+    }
+
+    pub fn keyboard_check_released(&self, key: i32) -> bool {
+        Self::check(&self.keys_released, key)
+    }
+
+    pub fn keyboard_check_direct(&self, key: i32) -> bool {
+        Self::check(&self.keys_direct, key)
+    }
+
+    /// Clears the state of a single key, so it's treated as not held until it's pressed again.
+    pub fn keyboard_clear(&mut self, key: i32) {
+        if key > 0 && key < 256 {
+            self.keys_held[key as usize] = false;
+            self.keys_pressed[key as usize] = false;
+            self.keys_released[key as usize] = false;
+        }
+        if self.keyboard_key.get() == key {
+            self.keyboard_key.set(0);
+        }
+    }
+
+    /// Clears the state of every key.
+    pub fn keyboard_clear_all(&mut self) {
+        self.keys_held = [false; 256];
+        self.keys_pressed = [false; 256];
+        self.keys_released = [false; 256];
+        self.keyboard_key.set(0);
+    }
+
+    /// Makes one key act as another.
+    pub fn set_map(&mut self, from: i32, to: i32) {
+        if (0..256).contains(&from) && (0..256).contains(&to) {
+            self.key_map[from as usize] = to as u8;
+        }
+    }
+
+    pub fn get_map(&self, key: i32) -> i32 {
+        if (0..256).contains(&key) { self.key_map[key as usize].into() } else { key }
+    }
+
+    pub fn unset_map(&mut self) {
+        self.key_map.iter_mut().enumerate().for_each(|(i, key)| *key = i as u8);
+    }
+
+    /// Handles a mouse button being pressed.
+    pub fn mouse_press(&mut self, button: i32) {
+        if let Some(i) = button_index(button) {
+            self.buttons_pressed[i] = true;
+            self.buttons_held[i] = true;
+            self.mouse_button.set(button);
+            self.mouse_lastbutton.set(button);
+        }
+    }
+
+    /// Handles a mouse button being released.
+    pub fn mouse_release(&mut self, button: i32) {
+        if let Some(i) = button_index(button) {
+            self.buttons_released[i] = true;
+            self.buttons_held[i] = false;
+            if self.mouse_button.get() == button {
+                self.mouse_button.set(MB_NONE);
+            }
+        }
+    }
+
+    /// Handles the mouse wheel being scrolled. Positive amounts are upwards.
+    pub fn mouse_scroll(&mut self, amount: f64) {
+        if amount > 0.0 {
+            self.wheel_up = true;
+        } else if amount < 0.0 {
+            self.wheel_down = true;
+        }
+    }
+
+    /// Checks a mouse button against a state, handling mb_any and mb_none.
+    fn check_button(state: &[bool; 4], button: i32) -> bool {
+        match button {
+            MB_ANY => state.iter().any(|&held| held),
+            MB_NONE => !state.iter().any(|&held| held),
+            button => matches!(button_index(button), Some(i) if state[i]),
+        }
+    }
+
+    pub fn mouse_check_button(&self, button: i32) -> bool {
+        Self::check_button(&self.buttons_held, button)
+    }
+
+    pub fn mouse_check_button_pressed(&self, button: i32) -> bool {
+        Self::check_button(&self.buttons_pressed, button)
+    }
+
+    pub fn mouse_check_button_released(&self, button: i32) -> bool {
+        Self::check_button(&self.buttons_released, button)
+    }
+
+    pub fn mouse_wheel_up(&self) -> bool {
+        self.wheel_up
+    }
+
+    pub fn mouse_wheel_down(&self) -> bool {
+        self.wheel_down
+    }
+
+    /// Clears the state of a single mouse button, so it's treated as not held until it's pressed again.
+    pub fn mouse_clear(&mut self, button: i32) {
+        if let Some(i) = button_index(button) {
+            self.buttons_held[i] = false;
+            self.buttons_pressed[i] = false;
+            self.buttons_released[i] = false;
+        }
+        if self.mouse_button.get() == button {
+            self.mouse_button.set(MB_NONE);
+        }
+    }
+
+    /// Clears the state of every mouse button.
+    pub fn mouse_clear_all(&mut self) {
+        self.buttons_held = [false; 4];
+        self.buttons_pressed = [false; 4];
+        self.buttons_released = [false; 4];
+        self.wheel_up = false;
+        self.wheel_down = false;
+        self.mouse_button.set(MB_NONE);
+    }
+
+    /// Gets the state of a joystick by its GM8 id, which starts from 1, if it's connected.
+    pub fn joystick(&self, id: i32) -> Option<&joystick::State> {
+        match id {
+            1..=2 => self.joysticks[id as usize - 1].as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Gets the index of a real mouse button in the button state arrays.
+fn button_index(button: i32) -> Option<usize> {
+    match button {
+        MB_LEFT | MB_RIGHT | MB_MIDDLE => Some(button as usize),
+        _ => None,
+    }
+}
+
+/// Gets the mb_* constant for a mouse button.
+#[cfg(feature = "window")]
+pub fn mb_from_glfw(button: glfw::MouseButton) -> Option<i32> {
+    match button {
+        glfw::MouseButtonLeft => Some(MB_LEFT),
+        glfw::MouseButtonRight => Some(MB_RIGHT),
+        glfw::MouseButtonMiddle => Some(MB_MIDDLE),
+        _ => None,
+    }
+}
+
+/// Gets the virtual-key code GM8 would see for a key, along with the sided code for modifier keys.
+#[cfg(feature = "window")]
+pub fn vk_from_glfw(key: glfw::Key) -> Option<(u8, Option<u8>)> {
+    use glfw::Key::*;
+    Some(match key {
+        Backspace => (VK_BACKSPACE, None),
+        Tab => (0x09, None),
+        Enter | KpEnter => (0x0D, None),
+        LeftShift => (0x10, Some(0xA0)),
+        RightShift => (0x10, Some(0xA1)),
+        LeftControl => (0x11, Some(0xA2)),
+        RightControl => (0x11, Some(0xA3)),
+        LeftAlt => (0x12, Some(0xA4)),
+        RightAlt => (0x12, Some(0xA5)),
+        Pause => (0x13, None),
+        CapsLock => (0x14, None),
+        Escape => (0x1B, None),
+        Space => (0x20, None),
+        PageUp => (0x21, None),
+        PageDown => (0x22, None),
+        End => (0x23, None),
+        Home => (0x24, None),
+        Left => (0x25, None),
+        Up => (0x26, None),
+        Right => (0x27, None),
+        Down => (0x28, None),
+        PrintScreen => (0x2C, None),
+        Insert => (0x2D, None),
+        Delete => (0x2E, None),
+        Num0 => (b'0', None),
+        Num1 => (b'1', None),
+        Num2 => (b'2', None),
+        Num3 => (b'3', None),
+        Num4 => (b'4', None),
+        Num5 => (b'5', None),
+        Num6 => (b'6', None),
+        Num7 => (b'7', None),
+        Num8 => (b'8', None),
+        Num9 => (b'9', None),
+        A => (b'A', None),
+        B => (b'B', None),
+        C => (b'C', None),
+        D => (b'D', None),
+        E => (b'E', None),
+        F => (b'F', None),
+        G => (b'G', None),
+        H => (b'H', None),
+        I => (b'I', None),
+        J => (b'J', None),
+        K => (b'K', None),
+        L => (b'L', None),
+        M => (b'M', None),
+        N => (b'N', None),
+        O => (b'O', None),
+        P => (b'P', None),
+        Q => (b'Q', None),
+        R => (b'R', None),
+        S => (b'S', None),
+        T => (b'T', None),
+        U => (b'U', None),
+        V => (b'V', None),
+        W => (b'W', None),
+        X => (b'X', None),
+        Y => (b'Y', None),
+        Z => (b'Z', None),
+        LeftSuper => (0x5B, None),
+        RightSuper => (0x5C, None),
+        Menu => (0x5D, None),
+        Kp0 => (0x60, None),
+        Kp1 => (0x61, None),
+        Kp2 => (0x62, None),
+        Kp3 => (0x63, None),
+        Kp4 => (0x64, None),
+        Kp5 => (0x65, None),
+        Kp6 => (0x66, None),
+        Kp7 => (0x67, None),
+        Kp8 => (0x68, None),
+        Kp9 => (0x69, None),
+        KpMultiply => (0x6A, None),
+        KpAdd => (0x6B, None),
+        KpSubtract => (0x6D, None),
+        KpDecimal => (0x6E, None),
+        KpDivide => (0x6F, None),
+        F1 => (0x70, None),
+        F2 => (0x71, None),
+        F3 => (0x72, None),
+        F4 => (0x73, None),
+        F5 => (0x74, None),
+        F6 => (0x75, None),
+        F7 => (0x76, None),
+        F8 => (0x77, None),
+        F9 => (0x78, None),
+        F10 => (0x79, None),
+        F11 => (0x7A, None),
+        F12 => (0x7B, None),
+        F13 => (0x7C, None),
+        F14 => (0x7D, None),
+        F15 => (0x7E, None),
+        F16 => (0x7F, None),
+        F17 => (0x80, None),
+        F18 => (0x81, None),
+        F19 => (0x82, None),
+        F20 => (0x83, None),
+        F21 => (0x84, None),
+        F22 => (0x85, None),
+        F23 => (0x86, None),
+        F24 => (0x87, None),
+        NumLock => (0x90, None),
+        ScrollLock => (0x91, None),
+        Semicolon => (0xBA, None),
+        Equal => (0xBB, None),
+        Comma => (0xBC, None),
+        Minus => (0xBD, None),
+        Period => (0xBE, None),
+        Slash => (0xBF, None),
+        GraveAccent => (0xC0, None),
+        LeftBracket => (0xDB, None),
+        Backslash => (0xDC, None),
+        RightBracket => (0xDD, None),
+        Apostrophe => (0xDE, None),
+        World1 => (0xE2, None),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_and_release() {
+        let mut input = Input::new();
+        assert!(input.keyboard_check(VK_NOKEY) && !input.keyboard_check(VK_ANYKEY));
+        input.key_press(b'A', None);
+        assert!(input.keyboard_check(i32::from(b'A')) && input.keyboard_check_pressed(i32::from(b'A')));
+        assert!(input.keyboard_check(VK_ANYKEY) && !input.keyboard_check(VK_NOKEY));
+        assert_eq!(input.keyboard_key.get(), i32::from(b'A'));
+
+        // Repeats while held don't count as presses
+        input.clear_presses();
+        input.key_press(b'A', None);
+        assert!(!input.keyboard_check_pressed(i32::from(b'A')));
+
+        input.key_release(b'A', None);
+        assert!(!input.keyboard_check(i32::from(b'A')) && input.keyboard_check_released(i32::from(b'A')));
+        assert!(input.keyboard_check_released(VK_ANYKEY));
+        assert_eq!((input.keyboard_key.get(), input.keyboard_lastkey.get()), (0, i32::from(b'A')));
+    }
+
+    #[test]
+    fn sided_modifiers() {
+        let mut input = Input::new();
+        input.key_press(0x10, Some(0xA1));
+        assert!(input.keyboard_check(0x10) && !input.keyboard_check(0xA1));
+        assert!(input.keyboard_check_direct(0x10) && input.keyboard_check_direct(0xA1));
+        assert!(!input.keyboard_check_direct(0xA0));
+    }
+
+    #[test]
+    fn mapping() {
+        let mut input = Input::new();
+        input.set_map(i32::from(b'W'), 0x26);
+        input.key_press(b'W', None);
+        assert!(input.keyboard_check(0x26) && !input.keyboard_check(i32::from(b'W')));
+        assert!(input.keyboard_check_direct(i32::from(b'W')));
+        assert_eq!(input.get_map(i32::from(b'W')), 0x26);
+        input.unset_map();
+        assert_eq!(input.get_map(i32::from(b'W')), i32::from(b'W'));
+    }
+
+    #[test]
+    fn keyboard_string() {
+        let mut input = Input::new();
+        "hé".chars().for_each(|ch| input.char_typed(ch));
+        input.char_typed(char::from(VK_BACKSPACE));
+        assert_eq!(*input.keyboard_string.borrow(), "h");
+        (0..KEYBOARD_STRING_LIMIT).for_each(|_| input.char_typed('x'));
+        input.char_typed('y');
+        assert_eq!(input.keyboard_string.borrow().len(), KEYBOARD_STRING_LIMIT);
+        assert!(input.keyboard_string.borrow().starts_with("xx") && input.keyboard_string.borrow().ends_with("xy"));
+        assert_eq!(*input.keyboard_lastchar.borrow(), "y");
+    }
+
+    #[test]
+    fn clearing() {
+        let mut input = Input::new();
+        input.key_press(b'A', None);
+        input.key_press(b'B', None);
+        input.keyboard_clear(i32::from(b'B'));
+        assert!(input.keyboard_check(i32::from(b'A')) && !input.keyboard_check(i32::from(b'B')));
+        assert_eq!(input.keyboard_key.get(), 0);
+        input.keyboard_clear_all();
+        assert!(input.keyboard_check(VK_NOKEY));
+    }
+
+    #[test]
+    fn mouse_buttons() {
+        let mut input = Input::new();
+        assert!(input.mouse_check_button(MB_NONE) && !input.mouse_check_button(MB_ANY));
+        input.mouse_press(MB_RIGHT);
+        assert!(input.mouse_check_button(MB_RIGHT) && input.mouse_check_button_pressed(MB_ANY));
+        assert!(!input.mouse_check_button(MB_LEFT) && !input.mouse_check_button(MB_NONE));
+        assert_eq!((input.mouse_button.get(), input.mouse_lastbutton.get()), (MB_RIGHT, MB_RIGHT));
+
+        input.clear_presses();
+        input.mouse_release(MB_RIGHT);
+        input.mouse_scroll(-1.0);
+        assert!(!input.mouse_check_button_pressed(MB_RIGHT) && input.mouse_check_button_released(MB_RIGHT));
+        assert!(input.mouse_wheel_down() && !input.mouse_wheel_up());
+        assert_eq!((input.mouse_button.get(), input.mouse_lastbutton.get()), (MB_NONE, MB_RIGHT));
+
+        input.mouse_press(MB_LEFT);
+        input.mouse_clear(MB_LEFT);
+        assert!(input.mouse_check_button(MB_NONE) && !input.mouse_check_button_pressed(MB_LEFT));
+    }
+}
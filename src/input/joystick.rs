@@ -0,0 +1,215 @@
+//! Joystick state, and the backends it can be read from.
+//!
+//! GM8 only supports two joysticks, with ids 1 and 2. Their state is read once at the start of every step.
+
+use crate::gml::ev_mouse;
+use std::collections::VecDeque;
+
+/// How many joysticks GM8 supports.
+pub const JOYSTICK_COUNT: usize = 2;
+
+/// How far an axis has to be pushed for the joystick to count as pointing that way.
+const THRESHOLD: f64 = 0.5;
+
+/// The key code of numpad 1, which joystick_direction counts from.
+const VK_NUMPAD1: i32 = 0x61;
+
+/// Somewhere joystick state can be read from.
+pub trait Backend {
+    /// Reads the current state of each joystick, or None for any that aren't connected.
+    fn poll(&mut self) -> [Option<State>; JOYSTICK_COUNT];
+}
+
+/// The state of a single joystick.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct State {
+    pub name: String,
+
+    /// Position of each axis, from -1 to 1, in the order x, y, z, r, u, v
+    pub axes: Vec<f64>,
+
+    /// Whether each button is held, starting from button 1
+    pub buttons: Vec<bool>,
+
+    /// Whether the joystick has a POV hat
+    pub has_pov: bool,
+
+    /// Direction the POV hat is pushed in, in degrees clockwise from forwards, or None if it's centred
+    pub pov: Option<f64>,
+}
+
+impl State {
+    /// Gets the position of an axis, or 0 if the joystick doesn't have it.
+    pub fn axis(&self, n: usize) -> f64 {
+        self.axes.get(n).copied().unwrap_or(0.0)
+    }
+
+    /// Checks whether a button is held. Buttons are numbered from 1.
+    pub fn button(&self, n: i32) -> bool {
+        n >= 1 && self.buttons.get(n as usize - 1) == Some(&true)
+    }
+
+    pub fn left(&self) -> bool {
+        self.axis(0) < -THRESHOLD
+    }
+
+    pub fn right(&self) -> bool {
+        self.axis(0) > THRESHOLD
+    }
+
+    pub fn up(&self) -> bool {
+        self.axis(1) < -THRESHOLD
+    }
+
+    pub fn down(&self) -> bool {
+        self.axis(1) > THRESHOLD
+    }
+
+    /// Gets the numpad key code for the direction the joystick is pushed in, as joystick_direction does.
+    /// Numpad 5 means it isn't pushed in any direction.
+    pub fn direction(&self) -> i32 {
+        let column = if self.left() { 0 } else if self.right() { 2 } else { 1 };
+        let row = if self.down() { 0 } else if self.up() { 2 } else { 1 };
+        VK_NUMPAD1 + row * 3 + column
+    }
+}
+
+/// Checks whether one of the joystick sub-events of the mouse event should happen.
+pub fn event_active(subevent: u32, joysticks: &[Option<State>; JOYSTICK_COUNT]) -> bool {
+    let (joystick, offset) = match subevent {
+        ev_mouse::JOYSTICK1_LEFT..=ev_mouse::JOYSTICK1_BUTTON8 => (&joysticks[0], subevent - ev_mouse::JOYSTICK1_LEFT),
+        ev_mouse::JOYSTICK2_LEFT..=ev_mouse::JOYSTICK2_BUTTON8 => (&joysticks[1], subevent - ev_mouse::JOYSTICK2_LEFT),
+        _ => return false,
+    };
+    let state = match joystick {
+        Some(state) => state,
+        None => return false,
+    };
+    // Both joysticks' sub-events are laid out the same way: left, right, up, down, a gap, then buttons 1 to 8
+    match offset {
+        0 => state.left(),
+        1 => state.right(),
+        2 => state.up(),
+        3 => state.down(),
+        offset => {
+            let first_button = ev_mouse::JOYSTICK1_BUTTON1 - ev_mouse::JOYSTICK1_LEFT;
+            offset >= first_button && state.button((offset - first_button) as i32 + 1)
+        },
+    }
+}
+
+/// Reads joysticks through GLFW.
+#[cfg(feature = "window")]
+pub struct GlfwBackend {
+    glfw: glfw::Glfw,
+}
+
+#[cfg(feature = "window")]
+impl GlfwBackend {
+    pub fn new(glfw: glfw::Glfw) -> Self {
+        Self { glfw }
+    }
+
+    fn read(&self, id: glfw::JoystickId) -> Option<State> {
+        let joystick = self.glfw.get_joystick(id);
+        if !joystick.is_present() {
+            return None
+        }
+        let hats = joystick.get_hats();
+        Some(State {
+            name: joystick.get_name().unwrap_or_default(),
+            axes: joystick.get_axes().into_iter().map(f64::from).collect(),
+            buttons: joystick.get_buttons().into_iter().map(|button| button != 0).collect(),
+            has_pov: !hats.is_empty(),
+            pov: hats.first().and_then(|&hat| {
+                let up = hat.contains(glfw::JoystickHats::Up);
+                let right = hat.contains(glfw::JoystickHats::Right);
+                let down = hat.contains(glfw::JoystickHats::Down);
+                let left = hat.contains(glfw::JoystickHats::Left);
+                match (up, right, down, left) {
+                    (true, false, false, false) => Some(0.0),
+                    (true, true, false, false) => Some(45.0),
+                    (false, true, false, false) => Some(90.0),
+                    (false, true, true, false) => Some(135.0),
+                    (false, false, true, false) => Some(180.0),
+                    (false, false, true, true) => Some(225.0),
+                    (false, false, false, true) => Some(270.0),
+                    (true, false, false, true) => Some(315.0),
+                    _ => None,
+                }
+            }),
+        })
+    }
+}
+
+#[cfg(feature = "window")]
+impl Backend for GlfwBackend {
+    fn poll(&mut self) -> [Option<State>; JOYSTICK_COUNT] {
+        [self.read(glfw::JoystickId::Joystick1), self.read(glfw::JoystickId::Joystick2)]
+    }
+}
+
+/// Reports joystick states it's been given in advance, one step at a time, for testing.
+/// Once it runs out, it keeps reporting the last step's states.
+#[derive(Default)]
+pub struct ScriptedBackend {
+    steps: VecDeque<[Option<State>; JOYSTICK_COUNT]>,
+    current: [Option<State>; JOYSTICK_COUNT],
+}
+
+impl ScriptedBackend {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the joystick states for another step.
+    pub fn push(&mut self, states: [Option<State>; JOYSTICK_COUNT]) {
+        self.steps.push_back(states);
+    }
+}
+
+impl Backend for ScriptedBackend {
+    fn poll(&mut self) -> [Option<State>; JOYSTICK_COUNT] {
+        if let Some(states) = self.steps.pop_front() {
+            self.current = states;
+        }
+        self.current.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stick(x: f64, y: f64, buttons: &[bool]) -> State {
+        State { name: "Pad".into(), axes: vec![x, y, 0.0], buttons: buttons.to_vec(), has_pov: false, pov: None }
+    }
+
+    #[test]
+    fn direction() {
+        assert_eq!(stick(0.0, 0.0, &[]).direction(), 0x65);
+        assert_eq!(stick(-1.0, -1.0, &[]).direction(), 0x67);
+        assert_eq!(stick(1.0, 0.2, &[]).direction(), 0x66);
+        assert_eq!(stick(0.0, 0.9, &[]).direction(), 0x62);
+        assert_eq!(stick(0.6, 0.6, &[]).direction(), 0x63);
+    }
+
+    #[test]
+    fn scripted_events() {
+        let mut backend = ScriptedBackend::new();
+        assert_eq!(backend.poll(), [None, None]);
+        backend.push([Some(stick(-1.0, 0.0, &[false, true])), None]);
+        backend.push([None, Some(stick(0.0, 1.0, &[true]))]);
+
+        let states = backend.poll();
+        let active = (0..64).filter(|&sub| event_active(sub, &states)).collect::<Vec<_>>();
+        assert_eq!(active, vec![ev_mouse::JOYSTICK1_LEFT, ev_mouse::JOYSTICK1_BUTTON1 + 1]);
+
+        let states = backend.poll();
+        let active = (0..64).filter(|&sub| event_active(sub, &states)).collect::<Vec<_>>();
+        assert_eq!(active, vec![ev_mouse::JOYSTICK2_DOWN, ev_mouse::JOYSTICK2_BUTTON1]);
+
+        // It keeps reporting the last states it was given
+        assert_eq!(backend.poll(), states);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    asset::{Object, Sprite},
+    asset::{Object, Path, Sprite},
     gml::{InstanceVariable, Value},
     util,
 };
@@ -11,8 +11,6 @@ use std::{
 
 // Default in GameMaker 8
 const BBOX_DEFAULT: i32 = -100000;
-// Rust can't represent this many decimal places yet I think. In GM8 it's a TBYTE definition
-const PI: f64 = 3.1415926535897932380;
 
 pub struct Instance {
     pub exists: Cell<bool>,
@@ -51,6 +49,8 @@ pub struct Instance {
     pub path_scale: Cell<f64>,
     pub path_orientation: Cell<f64>,
     pub path_endaction: Cell<i32>, // https://docs.yoyogames.com/source/dadiospice/002_reference/paths/path_start.html
+    pub path_xstart: Cell<f64>, // Where the start of the path is placed in the room
+    pub path_ystart: Cell<f64>,
     pub timeline_index: Cell<i32>,
     pub timeline_running: Cell<bool>,
     pub timeline_speed: Cell<f64>,
@@ -118,6 +118,8 @@ impl Instance {
             path_scale: Cell::new(1.0),
             path_orientation: Cell::new(0.0),
             path_endaction: Cell::new(0),
+            path_xstart: Cell::new(0.0),
+            path_ystart: Cell::new(0.0),
             timeline_index: Cell::new(-1),
             timeline_running: Cell::new(false),
             timeline_speed: Cell::new(1.0),
@@ -133,6 +135,13 @@ impl Instance {
         }
     }
 
+    // Moves the instance, marking its bbox as stale
+    pub fn set_position(&self, x: f64, y: f64) {
+        self.x.set(x);
+        self.y.set(y);
+        self.bbox_is_stale.set(true);
+    }
+
     // Sets direction, also updating hspeed and vspeed
     pub fn set_direction(&self, direction: f64) {
         self.direction.set(direction);
@@ -157,16 +166,65 @@ impl Instance {
         self.update_speed_direction()
     }
 
-    // Sets hspeed and vspeed based on direction and speed
+    // Sets hspeed and vspeed based on direction (in degrees) and speed
     fn update_hvspeed(&self) {
-        self.hspeed.set(-self.direction.get().cos() * self.speed.get());
-        self.vspeed.set(self.direction.get().sin() * self.speed.get());
+        let direction = util::degtorad(self.direction.get());
+        self.hspeed.set(direction.cos() * self.speed.get());
+        self.vspeed.set(-direction.sin() * self.speed.get());
     }
 
     // Sets direction and speed based on hspeed and vspeed
-    fn update_speed_direction(&self) {
-        self.direction.set((-self.vspeed.get()).atan2(self.hspeed.get()));
-        self.speed.set((self.hspeed.get().powi(2) + self.vspeed.get().powi(2)).sqrt());
+    pub fn update_speed_direction(&self) {
+        let direction = util::radtodeg((-self.vspeed.get()).atan2(self.hspeed.get()));
+        self.direction.set(if direction < 0.0 { direction + 360.0 } else { direction });
+        self.speed.set(self.hspeed.get().hypot(self.vspeed.get()));
+    }
+
+    // Adds a motion vector to the current motion, as motion_add and gravity do
+    pub fn add_motion(&self, direction: f64, speed: f64) {
+        let direction = util::degtorad(direction);
+        self.hspeed.set(self.hspeed.get() + direction.cos() * speed);
+        self.vspeed.set(self.vspeed.get() - direction.sin() * speed);
+        self.update_speed_direction()
+    }
+
+    // Remembers the current position as xprevious and yprevious, which GM8 does at the start of every step
+    pub fn update_previous(&self) {
+        self.xprevious.set(self.x.get());
+        self.yprevious.set(self.y.get());
+        self.path_positionprevious.set(self.path_position.get());
+    }
+
+    // Applies one step of friction and gravity, then moves the instance by its hspeed and vspeed
+    pub fn apply_motion(&self) {
+        // Friction slows the instance towards a standstill, but never reverses it
+        let friction = self.friction.get();
+        if friction != 0.0 {
+            let speed = self.speed.get();
+            if speed > 0.0 {
+                self.set_speed((speed - friction).max(0.0));
+            } else if speed < 0.0 {
+                self.set_speed((speed + friction).min(0.0));
+            }
+        }
+
+        if self.gravity.get() != 0.0 {
+            self.add_motion(self.gravity_direction.get(), self.gravity.get());
+        }
+
+        if self.hspeed.get() != 0.0 || self.vspeed.get() != 0.0 {
+            self.set_position(self.x.get() + self.hspeed.get(), self.y.get() + self.vspeed.get());
+        }
+    }
+
+    // Gets the room coordinates of a position along a path, as placed by this instance's path variables
+    pub fn path_coords(&self, path: &Path, position: f64) -> (f64, f64) {
+        let start = path.start();
+        let point = path.get_point(position);
+        let scale = self.path_scale.get();
+        let (dx, dy) = ((point.x - start.x) * scale, (point.y - start.y) * scale);
+        let (sin, cos) = util::degtorad(self.path_orientation.get()).sin_cos();
+        (self.path_xstart.get() + dx * cos + dy * sin, self.path_ystart.get() - dx * sin + dy * cos)
     }
 
     // Updates the bbox variables if they're stale, otherwise does nothing
@@ -211,7 +269,7 @@ impl Instance {
                 let mut bottom_left_y = bottom_right_y;
 
                 // Rotate these points
-                let angle = -self.image_angle.get() * PI / 180.0;
+                let angle = util::degtorad(-self.image_angle.get());
                 let sin = angle.sin();
                 let cos = angle.cos();
                 rotate_around(&mut top_left_x, &mut top_left_y, x, y, sin, cos);
@@ -305,4 +363,78 @@ impl DummyFieldHolder {
             vars: HashMap::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance() -> Instance {
+        let object = Object {
+            name: "object0".into(),
+            solid: false,
+            visible: true,
+            persistent: false,
+            depth: 0,
+            sprite_index: -1,
+            mask_index: -1,
+            parent_index: -1,
+            events: Default::default(),
+            identities: Default::default(),
+            children: Default::default(),
+        };
+        Instance::new(100001, 10.0, 20.0, 0, &object)
+    }
+
+    fn assert_close(actual: f64, expected: f64, what: &str) {
+        assert!((actual - expected).abs() < 1e-9, "{} was {}, expected {}", what, actual, expected);
+    }
+
+    #[test]
+    fn speed_and_direction() {
+        let inst = instance();
+        inst.set_direction(90.0);
+        inst.set_speed(2.0);
+        assert_close(inst.hspeed.get(), 0.0, "hspeed");
+        assert_close(inst.vspeed.get(), -2.0, "vspeed");
+
+        inst.set_hspeed(-3.0);
+        inst.set_vspeed(0.0);
+        assert_close(inst.direction.get(), 180.0, "direction");
+        assert_close(inst.speed.get(), 3.0, "speed");
+
+        inst.set_vspeed(3.0);
+        assert_close(inst.direction.get(), 225.0, "direction");
+    }
+
+    #[test]
+    fn friction() {
+        let inst = instance();
+        inst.set_speed(3.0);
+        inst.friction.set(1.0);
+        inst.apply_motion();
+        assert_close(inst.speed.get(), 2.0, "speed");
+        assert_close(inst.x.get(), 12.0, "x");
+
+        inst.friction.set(5.0);
+        inst.apply_motion();
+        assert_close(inst.speed.get(), 0.0, "speed");
+        assert_close(inst.x.get(), 12.0, "x");
+    }
+
+    #[test]
+    fn gravity() {
+        let inst = instance();
+        inst.gravity.set(0.5);
+        inst.gravity_direction.set(270.0);
+        inst.update_previous();
+        inst.apply_motion();
+        inst.update_previous();
+        inst.apply_motion();
+        assert_close(inst.vspeed.get(), 1.0, "vspeed");
+        assert_close(inst.direction.get(), 270.0, "direction");
+        assert_close(inst.y.get(), 21.5, "y");
+        assert_close(inst.yprevious.get(), 20.5, "yprevious");
+        assert_close(inst.x.get(), 10.0, "x");
+    }
+}
@@ -1,5 +1,12 @@
 use crate::{instance::Instance, tile::Tile};
-use std::{alloc, cmp::Ordering, collections::HashMap, ptr};
+use std::{
+    alloc,
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    ptr,
+    rc::Rc,
+};
 
 /// Elements per Chunk (fixed size).
 const CHUNK_SIZE: usize = 256;
@@ -141,7 +148,9 @@ pub struct InstanceList {
     chunks: ChunkList<Instance>,
     order: Vec<usize>,
     draw_order: Vec<usize>,
-    id_map: HashMap<i32, usize>, // Object ID <-> Count
+    id_map: HashMap<i32, usize>,     // Object ID <-> Count
+    instid_map: HashMap<i32, usize>, // Instance ID <-> Index
+    deactivated: HashSet<usize>,
 }
 
 chunk_list_derivative!(InstanceList, InstanceListIter, Instance);
@@ -158,22 +167,59 @@ pub struct ObjectIter {
 
 impl ObjectIter {
     pub fn next(&mut self, list: &InstanceList) -> Option<usize> {
-        if self.count > 0 {
-            for (idx, &instance) in list.order.get(self.position..)?.iter().enumerate() {
-                if list.get(instance)?.object_index.get() == self.object_index {
-                    self.count -= 1;
-                    self.position += idx + 1;
-                    return Some(instance)
-                }
+        while self.count > 0 {
+            let (idx, instance) = list
+                .order
+                .get(self.position..)?
+                .iter()
+                .copied()
+                .enumerate()
+                .find(|&(_, instance)| list.get(instance).map(|i| i.object_index.get()) == Some(self.object_index))?;
+            self.count -= 1;
+            self.position += idx + 1;
+
+            // Destroyed and deactivated instances still count towards the total, but are skipped
+            if list.exists(instance) {
+                return Some(instance)
             }
         }
         None
     }
 }
 
+// iterator for iter_by_identity(identities)
+pub struct IdentityIter {
+    // position in the insert-order vec
+    position: usize,
+    // set of object indices to match, usually an object and all its children
+    identities: Rc<RefCell<HashSet<i32>>>,
+}
+
+impl IdentityIter {
+    pub fn next(&mut self, list: &InstanceList) -> Option<usize> {
+        let identities = self.identities.borrow();
+        for (idx, &instance) in list.order.get(self.position..)?.iter().enumerate() {
+            let inst = list.get(instance)?;
+            if list.exists(instance) && identities.contains(&inst.object_index.get()) {
+                self.position += idx + 1;
+                return Some(instance)
+            }
+        }
+        self.position = list.order.len();
+        None
+    }
+}
+
 impl InstanceList {
     pub fn new() -> Self {
-        Self { chunks: ChunkList::new(), order: Vec::new(), draw_order: Vec::new(), id_map: HashMap::new() }
+        Self {
+            chunks: ChunkList::new(),
+            order: Vec::new(),
+            draw_order: Vec::new(),
+            id_map: HashMap::new(),
+            instid_map: HashMap::new(),
+            deactivated: HashSet::new(),
+        }
     }
 
     pub fn draw_sort(&mut self) {
@@ -203,12 +249,60 @@ impl InstanceList {
         }
     }
 
+    pub fn iter_by_identity(&self, identities: Rc<RefCell<HashSet<i32>>>) -> IdentityIter {
+        IdentityIter { position: 0, identities }
+    }
+
+    /// Checks whether there's an instance at this index which hasn't been destroyed or deactivated.
+    pub fn exists(&self, idx: usize) -> bool {
+        matches!(self.chunks.get(idx), Some(instance) if instance.exists.get()) && !self.deactivated.contains(&idx)
+    }
+
+    /// Deactivates an instance. It keeps its place in the list, along with its id and all its state,
+    /// but nothing can see it until it's activated again.
+    pub fn deactivate(&mut self, idx: usize) {
+        if self.exists(idx) {
+            self.deactivated.insert(idx);
+        }
+    }
+
+    /// Reactivates a deactivated instance.
+    pub fn activate(&mut self, idx: usize) {
+        self.deactivated.remove(&idx);
+    }
+
+    /// Lists the deactivated instances in the order they were inserted.
+    pub fn iter_deactivated(&self) -> impl Iterator<Item = usize> + '_ {
+        self.order.iter().copied().filter(move |idx| self.deactivated.contains(idx))
+    }
+
+    /// Finds the instance with the given instance id, if it exists and hasn't been destroyed.
+    pub fn get_by_instid(&self, id: i32) -> Option<usize> {
+        self.instid_map.get(&id).copied().filter(|&idx| self.exists(idx))
+    }
+
+    /// Changes the object of an instance, keeping the per-object counts up to date.
+    pub fn set_object(&mut self, idx: usize, object_index: i32) {
+        if let Some(instance) = self.chunks.get(idx) {
+            let old_object = instance.object_index.replace(object_index);
+            if let Some(n) = self.id_map.get_mut(&old_object) {
+                *n -= 1;
+                if *n == 0 {
+                    self.id_map.remove(&old_object);
+                }
+            }
+            self.id_map.entry(object_index).and_modify(|n| *n += 1).or_insert(1);
+        }
+    }
+
     pub fn insert(&mut self, el: Instance) -> usize {
         let object_id = el.object_index.get();
+        let instance_id = el.id.get() as i32;
         let value = self.chunks.insert(el);
         self.order.push(value);
         self.draw_order.push(value);
         self.id_map.entry(object_id).and_modify(|n| *n += 1).or_insert(1);
+        self.instid_map.insert(instance_id, value);
         value
     }
 
@@ -232,6 +326,9 @@ impl InstanceList {
         });
         let chunks = &self.chunks;
         self.order.retain(|idx| chunks.get(*idx).is_some());
+        self.draw_order.retain(|idx| chunks.get(*idx).is_some());
+        self.deactivated.retain(|idx| chunks.get(*idx).is_some());
+        self.instid_map.retain(|_, idx| chunks.get(*idx).is_some());
     }
 }
 
@@ -268,3 +365,120 @@ impl TileList {
 }
 
 // TODO: Maybe preallocating order/draw_order would increase perf - test this!
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::Object;
+
+    fn object() -> Object {
+        Object {
+            name: "object0".into(),
+            solid: false,
+            visible: true,
+            persistent: false,
+            depth: 0,
+            sprite_index: -1,
+            mask_index: -1,
+            parent_index: -1,
+            events: Default::default(),
+            identities: Default::default(),
+            children: Default::default(),
+        }
+    }
+
+    fn list(objects: &[i32]) -> (InstanceList, Vec<usize>) {
+        let mut list = InstanceList::new();
+        let handles = objects
+            .iter()
+            .enumerate()
+            .map(|(i, &object_index)| list.insert(Instance::new(100001 + i, 0.0, 0.0, object_index, &object())))
+            .collect();
+        (list, handles)
+    }
+
+    fn by_object(list: &InstanceList, object_index: i32) -> Vec<usize> {
+        let mut iter = list.iter_by_object(object_index);
+        let mut handles = Vec::new();
+        while let Some(handle) = iter.next(list) {
+            handles.push(handle);
+        }
+        handles
+    }
+
+    #[test]
+    fn insert() {
+        let (list, handles) = list(&[0, 1, 0]);
+        assert!(handles.iter().all(|&handle| list.exists(handle)));
+        assert_eq!(by_object(&list, 0), [handles[0], handles[2]]);
+        assert_eq!(by_object(&list, 1), [handles[1]]);
+        assert_eq!(by_object(&list, 2), []);
+        assert_eq!(list.get_by_instid(100002), Some(handles[1]));
+        assert_eq!(list.get_by_instid(100004), None);
+    }
+
+    #[test]
+    fn destroy() {
+        let (mut list, handles) = list(&[0, 1, 0]);
+        list.get(handles[0]).unwrap().exists.set(false);
+        assert!(!list.exists(handles[0]));
+        assert_eq!(list.get_by_instid(100001), None);
+        assert_eq!(by_object(&list, 0), [handles[2]]);
+
+        list.remove_with(|instance| !instance.exists.get());
+        assert!(list.get(handles[0]).is_none());
+        assert_eq!(list.get_by_instid(100003), Some(handles[2]));
+        assert_eq!(by_object(&list, 0), [handles[2]]);
+
+        // A new instance can take the freed slot without inheriting the old id
+        let handle = list.insert(Instance::new(100004, 0.0, 0.0, 1, &object()));
+        assert_eq!(list.get_by_instid(100001), None);
+        assert_eq!(list.get_by_instid(100004), Some(handle));
+        assert_eq!(by_object(&list, 1), [handles[1], handle]);
+    }
+
+    #[test]
+    fn deactivate() {
+        let (mut list, handles) = list(&[0, 0]);
+        list.deactivate(handles[0]);
+        assert!(!list.exists(handles[0]));
+        assert_eq!(list.get_by_instid(100001), None);
+        assert_eq!(by_object(&list, 0), [handles[1]]);
+
+        list.activate(handles[0]);
+        assert!(list.exists(handles[0]));
+        assert_eq!(list.get_by_instid(100001), Some(handles[0]));
+    }
+
+    #[test]
+    fn reactivation_order() {
+        let (mut list, handles) = list(&[0, 0, 0, 0]);
+        list.deactivate(handles[0]);
+        list.deactivate(handles[2]);
+        assert_eq!(list.iter_deactivated().collect::<Vec<_>>(), [handles[0], handles[2]]);
+
+        // Reactivated instances go back where they were, not to the end of the list
+        list.activate(handles[2]);
+        list.activate(handles[0]);
+        assert_eq!(by_object(&list, 0), handles);
+        let mut iter = list.iter_inserted();
+        let mut inserted = Vec::new();
+        while let Some(handle) = iter.next(&list) {
+            inserted.push(handle);
+        }
+        assert_eq!(inserted, handles);
+    }
+
+    #[test]
+    fn set_object() {
+        let (mut list, handles) = list(&[0, 0, 1]);
+        list.set_object(handles[0], 1);
+        assert_eq!(list.get(handles[0]).unwrap().object_index.get(), 1);
+        assert_eq!(by_object(&list, 0), [handles[1]]);
+        assert_eq!(by_object(&list, 1), [handles[0], handles[2]]);
+
+        list.set_object(handles[1], 2);
+        assert_eq!(by_object(&list, 0), []);
+        assert_eq!(by_object(&list, 2), [handles[1]]);
+    }
+}
@@ -0,0 +1,18 @@
+#![allow(dead_code)] // Shut up.
+
+mod action;
+mod asset;
+mod atlas;
+mod background;
+mod collision;
+pub mod game;
+pub mod gml;
+pub mod input;
+mod instance;
+mod instancelist;
+mod render;
+mod sandbox;
+mod tile;
+mod types;
+mod util;
+mod view;
@@ -1,20 +1,10 @@
-#![allow(dead_code)] // Shut up.
-
-mod action;
-mod asset;
-mod atlas;
-mod background;
-mod game;
-mod gml;
-mod instance;
-mod instancelist;
-mod render;
-mod tile;
-mod types;
-mod util;
-mod view;
-
-use std::{cmp::Ordering, env, fs, path::Path, process};
+use gm8emulator::{game, gml, input};
+use std::{
+    cmp::Ordering,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
 
 const EXIT_SUCCESS: i32 = 0;
 const EXIT_FAILURE: i32 = 1;
@@ -39,6 +29,10 @@ fn xmain() -> i32 {
 
     let mut opts = getopts::Options::new();
     opts.optflag("h", "help", "prints this help message");
+    opts.optflag("c", "check", "compiles all of the game's code and reports any errors, without running it");
+    opts.optopt("", "dump-code", "writes the game's compiled code to .gml files in a directory and exits", "DIR");
+    opts.optopt("e", "errors", "overrides what happens when the game raises an error", "abort|display|ignore");
+    opts.optopt("r", "import-registry", "imports a .reg file into the emulated registry", "FILE");
     opts.optflag("s", "strict", "enable various data integrity checks");
     opts.optflag("t", "singlethread", "parse gamedata synchronously");
     opts.optflag("v", "verbose", "enables verbose logging");
@@ -63,9 +57,22 @@ fn xmain() -> i32 {
         return EXIT_SUCCESS
     }
 
+    let check = matches.opt_present("c");
+    let dump_directory = matches.opt_str("dump-code").map(PathBuf::from);
     let strict = matches.opt_present("s");
     let multithread = !matches.opt_present("t");
     let verbose = matches.opt_present("v");
+    let registry_import = matches.opt_str("r").map(PathBuf::from);
+    let error_mode = match matches.opt_str("e").as_deref() {
+        Some("abort") => Some(gml::runtime::ErrorMode::Abort),
+        Some("display") => Some(gml::runtime::ErrorMode::Display),
+        Some("ignore") => Some(gml::runtime::ErrorMode::Ignore),
+        Some(mode) => {
+            eprintln!("unknown error mode {}, expected abort, display or ignore", mode);
+            return EXIT_FAILURE
+        },
+        None => None,
+    };
     let input = {
         if matches.free.len() == 1 {
             &matches.free[0]
@@ -109,7 +116,34 @@ fn xmain() -> i32 {
         },
     };
 
-    let mut components = match game::Game::launch(assets) {
+    if check {
+        let diagnostics = game::Game::compile_code(&assets).diagnostics;
+        for error in diagnostics.errors.iter() {
+            println!("{}\n", error);
+        }
+        println!("{} compile error(s) found", diagnostics.errors.len());
+        return if diagnostics.errors.is_empty() { EXIT_SUCCESS } else { EXIT_FAILURE }
+    }
+
+    if let Some(directory) = dump_directory {
+        return match game::Game::compile_code(&assets).dump(&assets, &directory) {
+            Ok(count) => {
+                println!("wrote {} file(s) to {}", count, directory.display());
+                EXIT_SUCCESS
+            },
+            Err(e) => {
+                eprintln!("failed to write code to {}: {}", directory.display(), e);
+                EXIT_FAILURE
+            },
+        }
+    }
+
+    let program_directory = match Path::new(input).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => Path::new(".").to_path_buf(),
+    };
+
+    let (mut components, mut events) = match game::Game::launch(assets, program_directory, registry_import) {
         Ok(g) => g,
         Err(e) => {
             eprintln!("Failed to launch game: {}", e);
@@ -117,19 +151,69 @@ fn xmain() -> i32 {
         },
     };
 
+    if let Some(error_mode) = error_mode {
+        components.error_mode = error_mode;
+    }
+
     while !components.renderer.should_close() {
-        components.glfw.poll_events();
-        for (_, event) in glfw::flush_messages(&components.glfw_events) {
-            println!("Got event {:?}", event);
+        components.input.clear_presses();
+        events.glfw.poll_events();
+        for (_, event) in glfw::flush_messages(&events.receiver) {
             match event {
-                glfw::WindowEvent::Key(glfw::Key::Escape, _, glfw::Action::Press, _) => {
+                glfw::WindowEvent::Key(glfw::Key::Escape, _, glfw::Action::Press, _) if components.esc_close_game => {
                     components.renderer.set_should_close(true);
                     continue // So no draw events are fired while the window should be closing
                 },
+                glfw::WindowEvent::Key(key, _, action, _) => {
+                    if let Some((vk, sided)) = input::vk_from_glfw(key) {
+                        match action {
+                            glfw::Action::Release => components.input.key_release(vk, sided),
+                            glfw::Action::Press | glfw::Action::Repeat => {
+                                components.input.key_press(vk, sided);
+                                // Backspace doesn't come through as a character, but it edits keyboard_string
+                                if vk == input::VK_BACKSPACE {
+                                    components.input.char_typed(char::from(vk));
+                                }
+                            },
+                        }
+                    }
+                },
+                glfw::WindowEvent::Char(ch) => components.input.char_typed(ch),
+                glfw::WindowEvent::MouseButton(button, action, _) => {
+                    if let Some(button) = input::mb_from_glfw(button) {
+                        match action {
+                            glfw::Action::Release => components.input.mouse_release(button),
+                            glfw::Action::Press | glfw::Action::Repeat => components.input.mouse_press(button),
+                        }
+                    }
+                },
+                glfw::WindowEvent::CursorPos(x, y) => {
+                    let (x, y) = components.renderer.unscale_point(x, y);
+                    components.input.mouse_window_x = x;
+                    components.input.mouse_window_y = y;
+                },
+                glfw::WindowEvent::Scroll(_, amount) => components.input.mouse_scroll(amount),
                 _ => {},
             }
         }
 
+        components.update_mouse_position();
+        components.update_joysticks();
+        let mut step = || -> gml::Result<()> {
+            components.run_keyboard_events(gml::ev::KEYBOARD)?;
+            components.run_mouse_events()?;
+            components.run_keyboard_events(gml::ev::KEYPRESS)?;
+            components.run_keyboard_events(gml::ev::KEYRELEASE)?;
+            components.update_motion()
+        };
+        if let Err(e) = step() {
+            eprintln!("{}", e);
+            return EXIT_FAILURE
+        }
+
+        // Instances destroyed during the frame stay in the list until it's over
+        components.instance_list.remove_with(|instance| !instance.exists.get());
+
         components.renderer.set_view(
             0,
             0,
@@ -143,6 +227,9 @@ fn xmain() -> i32 {
         );
 
         fn draw_instance(game: &mut game::Game, idx: usize) {
+            if !game.instance_list.exists(idx) {
+                return // Deactivated instances aren't drawn
+            }
             let instance = game.instance_list.get(idx).expect("OH NO I PANICKE'D");
             if let Some(Some(sprite)) = game.assets.sprites.get(instance.sprite_index.get() as usize) {
                 game.renderer.draw_sprite(
@@ -223,5 +310,9 @@ fn xmain() -> i32 {
         components.renderer.finish();
     }
 
+    // An INI file the game never closed is still saved when it ends
+    if let Some(Err(e)) = components.open_ini.as_mut().map(|ini| ini.flush()) {
+        eprintln!("failed to write INI file: {}", e);
+    }
     EXIT_SUCCESS
 }
@@ -1,5 +1,6 @@
 //! Game rendering functionality
 
+#[cfg(feature = "window")]
 pub mod opengl;
 
 use crate::{atlas::AtlasBuilder, types::Color};
@@ -33,6 +34,9 @@ pub trait Renderer {
     /// Used to resize the window and change its expected (unscaled) size. Usually used after changing rooms.
     fn resize_window(&mut self, width: u32, height: u32);
 
+    /// Converts a point in the window to where it would be if the window was its expected (unscaled) size.
+    fn unscale_point(&self, x: f64, y: f64) -> (f64, f64);
+
     /// Sets the colour (RGB) which will be used to clear the background rectangle after using set_view().
     /// If None is provided, the background will not be cleared at all.
     fn set_background_colour(&mut self, colour: Option<Color>);
@@ -96,3 +100,84 @@ pub struct RendererOptions<'a> {
     pub fullscreen: bool,
     pub vsync: bool,
 }
+
+/// A renderer with no window, which draws nothing. Lets tests run a game without a display.
+#[cfg(test)]
+pub struct NullRenderer;
+
+#[cfg(test)]
+impl Renderer for NullRenderer {
+    fn upload_atlases(&mut self, _atl: AtlasBuilder) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn dump_atlases(&self, _path: fn(usize) -> PathBuf) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn max_gpu_texture_size(&self) -> usize {
+        4096
+    }
+
+    fn should_close(&self) -> bool {
+        false
+    }
+
+    fn set_should_close(&mut self, _b: bool) {}
+
+    fn show_window(&mut self) {}
+
+    fn resize_window(&mut self, _width: u32, _height: u32) {}
+
+    fn unscale_point(&self, x: f64, y: f64) -> (f64, f64) {
+        (x, y)
+    }
+
+    fn set_background_colour(&mut self, _colour: Option<Color>) {}
+
+    fn set_view(
+        &mut self,
+        _src_x: i32,
+        _src_y: i32,
+        _src_w: i32,
+        _src_h: i32,
+        _src_angle: f64,
+        _port_x: i32,
+        _port_y: i32,
+        _port_w: i32,
+        _port_h: i32,
+    ) {
+    }
+
+    fn draw_sprite(
+        &mut self,
+        _texture: &AtlasRef,
+        _x: f64,
+        _y: f64,
+        _xscale: f64,
+        _yscale: f64,
+        _angle: f64,
+        _colour: i32,
+        _alpha: f64,
+    ) {
+    }
+
+    fn draw_sprite_partial(
+        &mut self,
+        _texture: &AtlasRef,
+        _part_x: i32,
+        _part_y: i32,
+        _part_w: i32,
+        _part_h: i32,
+        _x: f64,
+        _y: f64,
+        _xscale: f64,
+        _yscale: f64,
+        _angle: f64,
+        _colour: i32,
+        _alpha: f64,
+    ) {
+    }
+
+    fn finish(&mut self) {}
+}
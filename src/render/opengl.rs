@@ -99,6 +99,10 @@ impl OpenGLRenderer {
         );
 
         window.set_key_polling(true);
+        window.set_char_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
         window.set_framebuffer_size_polling(true);
 
         gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
@@ -672,6 +676,14 @@ impl Renderer for OpenGLRenderer {
         self.window.show()
     }
 
+    fn unscale_point(&self, x: f64, y: f64) -> (f64, f64) {
+        let (width, height) = self.window.get_size();
+        (
+            x * f64::from(self.unscaled_width) / f64::from(width.max(1)),
+            y * f64::from(self.unscaled_height) / f64::from(height.max(1)),
+        )
+    }
+
     fn resize_window(&mut self, width: u32, height: u32) {
         // GameMaker only actually resizes the window if the expected (unscaled) size is changing.
         if self.unscaled_width != width || self.unscaled_height != height {
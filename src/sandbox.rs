@@ -0,0 +1,165 @@
+//! Confines file access made by the game to a single directory on the host.
+//!
+//! GM8 games are written for Windows, so the paths they give us use backslashes, may have drive letters,
+//! and expect the filesystem to be case-insensitive. Everything is resolved relative to the sandbox root,
+//! which is normally the directory the game executable was loaded from.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Windows directory the sandbox root appears as to the game, without its drive letter. Absolute paths on
+/// drive C: inside it resolve to the root, and any other absolute path gets a directory inside the sandbox
+/// named after its drive letter.
+const GAME_DIRECTORY: &str = "Game";
+
+pub struct Sandbox {
+    /// Directory which all file access is confined to
+    root: PathBuf,
+}
+
+impl Sandbox {
+    /// Creates a sandbox rooted at the given directory.
+    pub fn new(root: PathBuf) -> Self {
+        Self { root: fs::canonicalize(&root).unwrap_or(root) }
+    }
+
+    /// Returns the root directory of this sandbox.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the Windows path the root appears as to the game, as used for working_directory.
+    pub fn directory(&self) -> String {
+        format!("C:\\{}", GAME_DIRECTORY)
+    }
+
+    /// Resolves a path given to us by the game into a host path inside the sandbox.
+    /// Returns None if the path would leave the sandbox.
+    ///
+    /// Each component is matched case-insensitively against what already exists on disk, so a game asking for
+    /// "Save\Data.ini" will find "save/data.ini". Components which don't exist yet are kept as the game wrote them.
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let path = path.replace('\\', "/");
+        let (drive, path) = match path.as_bytes() {
+            [letter, b':', ..] if letter.is_ascii_alphabetic() => {
+                (Some(letter.to_ascii_uppercase() as char), &path[2..])
+            },
+            _ => (None, path.as_str()),
+        };
+        let path = Path::new(path);
+
+        // A host path is only allowed if it points inside the root. A path without a drive letter, or one like
+        // "C:file" which is relative to the drive's current directory, is relative to the root.
+        let relative = match drive {
+            None if path.has_root() => path.strip_prefix(&self.root).ok()?,
+            _ => path,
+        };
+
+        let mut components: Vec<&str> = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(c) => components.push(c.to_str()?),
+                Component::CurDir => (),
+                Component::ParentDir => {
+                    components.pop()?;
+                },
+                Component::RootDir => (),
+                Component::Prefix(_) => return None,
+            }
+        }
+
+        let drive_root = match drive {
+            Some(drive) if path.has_root() => {
+                if drive == 'C' && components.first().map(|c| c.eq_ignore_ascii_case(GAME_DIRECTORY)) == Some(true) {
+                    components.remove(0);
+                    None
+                } else {
+                    Some(drive.to_string())
+                }
+            },
+            _ => None,
+        };
+
+        let mut resolved = self.root.clone();
+        for component in drive_root.iter().map(String::as_str).chain(components) {
+            let existing = fs::read_dir(&resolved).ok().and_then(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name())
+                    .find(|name| name.to_str().map(|name| name.eq_ignore_ascii_case(component)) == Some(true))
+            });
+            match existing {
+                Some(name) => resolved.push(name),
+                None => resolved.push(component),
+            }
+        }
+        Some(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes an empty sandbox in the temp directory, which is removed when it's dropped.
+    struct TempSandbox(Sandbox);
+
+    impl TempSandbox {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("gm8emulator-sandbox-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            TempSandbox(Sandbox::new(root))
+        }
+    }
+
+    impl Drop for TempSandbox {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.root());
+        }
+    }
+
+    #[test]
+    fn relative_paths() {
+        let sandbox = TempSandbox::new("relative");
+        let sandbox = &sandbox.0;
+        let root = sandbox.root().to_path_buf();
+        fs::create_dir(root.join("save")).unwrap();
+        fs::write(root.join("save").join("data.ini"), "").unwrap();
+
+        assert_eq!(sandbox.resolve("Save\\Data.ini"), Some(root.join("save").join("data.ini")));
+        assert_eq!(sandbox.resolve("save/new.ini"), Some(root.join("save").join("new.ini")));
+        assert_eq!(sandbox.resolve(".\\a\\..\\b.txt"), Some(root.join("b.txt")));
+        assert_eq!(sandbox.resolve("C:b.txt"), Some(root.join("b.txt")));
+    }
+
+    #[test]
+    fn absolute_paths() {
+        let sandbox = TempSandbox::new("absolute");
+        let sandbox = &sandbox.0;
+        let root = sandbox.root().to_path_buf();
+
+        let in_root = format!("{}\\options.ini", sandbox.directory());
+        assert_eq!(sandbox.resolve(&in_root), Some(root.join("options.ini")));
+        assert_eq!(sandbox.resolve("c:\\GAME\\options.ini"), Some(root.join("options.ini")));
+        assert_eq!(
+            sandbox.resolve("C:\\Users\\Player\\save.dat"),
+            Some(root.join("C").join("Users").join("Player").join("save.dat")),
+        );
+        assert_eq!(sandbox.resolve("D:\\save.dat"), Some(root.join("D").join("save.dat")));
+        assert_eq!(sandbox.resolve(root.join("x.txt").to_str().unwrap()), Some(root.join("x.txt")));
+    }
+
+    #[test]
+    fn escapes() {
+        let sandbox = TempSandbox::new("escapes");
+        let sandbox = &sandbox.0;
+        assert_eq!(sandbox.resolve(".."), None);
+        assert_eq!(sandbox.resolve("a\\..\\..\\b.txt"), None);
+        assert_eq!(sandbox.resolve("C:\\..\\b.txt"), None);
+        assert_eq!(sandbox.resolve("/etc/passwd"), None);
+        assert_eq!(sandbox.resolve("C:..\\b.txt"), None);
+    }
+}
@@ -5,25 +5,81 @@ pub fn bgra2rgba(data: &mut [u8]) {
 }
 
 /// Converts RGBA pixeldata to BGRA pixeldata in-place.
-pub use bgra2rgba as rgba2bgra;
+pub fn rgba2bgra(data: &mut [u8]) {
+    bgra2rgba(data)
+}
+
+// GM8 converts between degrees and radians with TBYTE constants on the x87, so the product is only rounded once,
+// when it's stored back as a double. Splitting each constant into the nearest double and what's left over and
+// adding them in one fused multiply-add gives the same result, where multiplying by PI and dividing by 180 in f64
+// can be off by one in the last place.
+const DEG_TO_RAD: (f64, f64) = (0.017453292519943295, 2.9486522708701687e-19);
+const RAD_TO_DEG: (f64, f64) = (57.29577951308232, -1.9878495670576283e-15);
+
+/// Converts an angle in degrees to radians as GM8 does.
+pub fn degtorad(degrees: f64) -> f64 {
+    degrees.mul_add(DEG_TO_RAD.0, degrees * DEG_TO_RAD.1)
+}
+
+/// Converts an angle in radians to degrees as GM8 does.
+pub fn radtodeg(radians: f64) -> f64 {
+    radians.mul_add(RAD_TO_DEG.0, radians * RAD_TO_DEG.1)
+}
 
 /// The default way to round as defined by IEEE 754 - nearest, ties to even.
 pub fn ieee_round(real: f64) -> i32 {
+    ieee_round_real(real) as i32
+}
+
+/// Rounds the same way as ieee_round, but without narrowing the result, so large values stay as they are.
+pub fn ieee_round_real(real: f64) -> f64 {
     let floor = real.floor();
-    let floori = floor as i32;
     let diff = real - floor;
-    if diff < 0.5 {
-        floori
-    } else if diff > 0.5 {
-        floori + 1
-    } else {
-        floori + (floori & 1)
+    if diff < 0.5 || (diff == 0.5 && floor % 2.0 == 0.0) { floor } else { floor + 1.0 }
+}
+
+/// Formats a real with a fixed number of decimal places, rounding halves away from zero as Delphi does.
+/// Rust's own formatting rounds halves to even, which would give "0.12" for 0.125 where GM8 gives "0.13".
+pub fn format_decimal(real: f64, places: usize) -> String {
+    // Enough digits that the only way to see an exact 5 followed by zeros is if the value really is a tie
+    let exact = format!("{:.*}", places + 25, real.abs());
+    let point = exact.find('.').unwrap_or(exact.len());
+    let mut digits: Vec<u8> = exact.bytes().filter(u8::is_ascii_digit).collect();
+    let keep = point + places;
+    if matches!(digits.get(keep), Some(d) if *d >= b'5') {
+        let mut i = keep;
+        loop {
+            if i == 0 {
+                digits.insert(0, b'1');
+                break
+            }
+            i -= 1;
+            if digits[i] == b'9' {
+                digits[i] = b'0';
+            } else {
+                digits[i] += 1;
+                break
+            }
+        }
+    }
+    let carried = digits.len() - (point + places + 25);
+    digits.truncate(keep + carried);
+
+    let mut output = String::with_capacity(digits.len() + 2);
+    if real < 0.0 && digits.iter().any(|d| *d != b'0') {
+        output.push('-');
     }
+    output.push_str(std::str::from_utf8(&digits[..point + carried]).unwrap());
+    if places > 0 {
+        output.push('.');
+        output.push_str(std::str::from_utf8(&digits[point + carried..]).unwrap());
+    }
+    output
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{bgra2rgba, rgba2bgra};
+    use super::{bgra2rgba, degtorad, format_decimal, ieee_round, ieee_round_real, radtodeg, rgba2bgra};
 
     #[test]
     fn bgra_rgba() {
@@ -36,4 +92,65 @@ mod tests {
         rgba2bgra(&mut cool_pixels);
         assert_eq!(cool_pixels, bgra_pixels);
     }
+
+    #[test]
+    fn angle_conversion() {
+        assert_eq!(degtorad(180.0), std::f64::consts::PI);
+        assert_eq!(radtodeg(std::f64::consts::PI), 180.0);
+        assert_eq!(degtorad(-90.0), -std::f64::consts::FRAC_PI_2);
+
+        // Rounded once from the exact product, where 0.37 * PI / 180.0 gives 0.00645771823237902
+        assert_eq!(degtorad(0.37), 0.006457718232379019);
+        assert_eq!(radtodeg(0.0137), 0.7849521793292278);
+        assert_eq!(radtodeg(0.0411), 2.354856537987683);
+    }
+
+    #[test]
+    fn rounding() {
+        let cases: &[(f64, f64)] = &[
+            (2.5, 2.0),
+            (3.5, 4.0),
+            (-0.5, 0.0),
+            (-2.5, -2.0),
+            (-3.5, -4.0),
+            (1e10, 1e10),
+            (2147483647.7, 2147483648.0),
+            (-2147483648.5, -2147483648.0),
+            (4503599627370497.0, 4503599627370497.0),
+            (f64::INFINITY, f64::INFINITY),
+        ];
+        for &(real, expected) in cases {
+            assert_eq!(ieee_round_real(real), expected, "ieee_round_real({})", real);
+        }
+        assert_eq!(ieee_round(2147483647.7), i32::MAX);
+        assert_eq!(ieee_round(-1e300), i32::MIN);
+        assert_eq!(ieee_round(f64::NAN), 0);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn decimal_formatting() {
+        let cases: &[(f64, usize, &str)] = &[
+            (0.1, 2, "0.10"),
+            (0.125, 2, "0.13"),
+            (-0.125, 2, "-0.13"),
+            (0.375, 2, "0.38"),
+            (1.005, 2, "1.00"),
+            (2.5, 0, "3"),
+            (-2.5, 0, "-3"),
+            (99.995, 2, "100.00"),
+            (2.675, 2, "2.67"),
+            (99.9951, 2, "100.00"),
+            (9.5, 0, "10"),
+            (0.999, 2, "1.00"),
+            (-0.001, 2, "0.00"),
+            (-0.0, 0, "0"),
+            (123456789.0, 0, "123456789"),
+            (1e20, 1, "100000000000000000000.0"),
+            (2.71828, 4, "2.7183"),
+        ];
+        for &(real, places, expected) in cases {
+            assert_eq!(format_decimal(real, places), expected, "format_decimal({}, {})", real, places);
+        }
+    }
 }
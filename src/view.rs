@@ -1,5 +1,8 @@
+use crate::util;
+use std::cell::Cell;
+
 /// An instance of a view in a room
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct View {
     /// Whether to draw this view
     pub visible: bool,
@@ -28,6 +31,9 @@ pub struct View {
     /// Port on screen to draw this view to - height
     pub port_h: u32,
 
+    /// Angle the view is rotated anticlockwise by, in degrees - this can only be set at runtime, with view_angle
+    pub angle: Cell<f64>,
+
     /// Target object ID this view should follow
     pub follow_target: i32,
 
@@ -43,3 +49,88 @@ pub struct View {
     /// Vertical speed with which to follow an instance
     pub follow_vspeed: i32,
 }
+
+impl View {
+    /// Checks whether a point in the window is inside this view's port.
+    pub fn port_contains(&self, x: f64, y: f64) -> bool {
+        x >= f64::from(self.port_x)
+            && y >= f64::from(self.port_y)
+            && x < f64::from(self.port_x) + f64::from(self.port_w)
+            && y < f64::from(self.port_y) + f64::from(self.port_h)
+    }
+
+    /// Converts a point in the window to the point in the room which this view shows there.
+    pub fn port_to_room(&self, x: f64, y: f64) -> (f64, f64) {
+        // Offset from the centre of the port, scaled to the size of the view
+        let dx = (x - f64::from(self.port_x) - f64::from(self.port_w) / 2.0) * f64::from(self.source_w)
+            / f64::from(self.port_w.max(1));
+        let dy = (y - f64::from(self.port_y) - f64::from(self.port_h) / 2.0) * f64::from(self.source_h)
+            / f64::from(self.port_h.max(1));
+
+        // Rotating the view turns the room the other way on screen, so this has to be undone
+        let (sin, cos) = util::degtorad(self.angle.get()).sin_cos();
+        (
+            f64::from(self.source_x) + f64::from(self.source_w) / 2.0 + dx * cos + dy * sin,
+            f64::from(self.source_y) + f64::from(self.source_h) / 2.0 - dx * sin + dy * cos,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(angle: f64) -> View {
+        View {
+            visible: true,
+            source_x: 100,
+            source_y: 50,
+            source_w: 200,
+            source_h: 100,
+            port_x: 10,
+            port_y: 20,
+            port_w: 400,
+            port_h: 200,
+            angle: Cell::new(angle),
+            follow_target: -1,
+            follow_hborder: 0,
+            follow_vborder: 0,
+            follow_hspeed: -1,
+            follow_vspeed: -1,
+        }
+    }
+
+    #[test]
+    fn port_to_room() {
+        let v = view(0.0);
+        assert!(v.port_contains(10.0, 20.0) && v.port_contains(409.0, 219.0) && !v.port_contains(410.0, 20.0));
+        assert_eq!(v.port_to_room(10.0, 20.0), (100.0, 50.0));
+        assert_eq!(v.port_to_room(210.0, 120.0), (200.0, 100.0));
+        assert_eq!(v.port_to_room(410.0, 220.0), (300.0, 150.0));
+    }
+
+    #[test]
+    fn rotated() {
+        // Turning the view anticlockwise makes the room on screen turn clockwise, so a point to the right of
+        // the centre of the port is above the centre of the view
+        let (x, y) = view(90.0).port_to_room(310.0, 120.0);
+        assert!((x - 200.0).abs() < 1e-9 && (y - 50.0).abs() < 1e-9, "{}, {}", x, y);
+    }
+
+    #[test]
+    fn view_angle() {
+        let mut game = crate::game::Game::test();
+        let inst = game.test_instance(0.0, 0.0);
+        game.views = vec![view(0.0)];
+        game.views_enabled = true;
+        game.test_run(inst, "view_angle[0] = 90").unwrap();
+        assert_eq!(game.views[0].angle.get(), 90.0);
+        assert!(game.test_run(inst, "view_angle[1] = 90").is_err());
+
+        // The mouse is mapped into the room through the rotated view
+        game.input.mouse_window_x = 310.0;
+        game.input.mouse_window_y = 120.0;
+        game.update_mouse_position();
+        assert_eq!((game.input.mouse_x, game.input.mouse_y), (200, 50));
+    }
+}